@@ -0,0 +1,104 @@
+//! Entry points of the contract.
+
+use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use near_light_client::{
+    types::{ConsensusState, Header, Height},
+    BasicNearLightClient,
+};
+
+use crate::{
+    error::ContractError,
+    msg::{ExecuteMsg, InstantiateMsg, QueryMsg},
+    state::{save_consensus_state, ContractLightClient, CONSENSUS_STATES},
+};
+
+#[cfg_attr(not(feature = "library"), cosmwasm_std::entry_point)]
+pub fn instantiate(
+    deps: DepsMut<'_>,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let consensus_state: ConsensusState = borsh::from_slice(msg.consensus_state.as_slice())
+        .map_err(|_| ContractError::InvalidEncoding("consensus state"))?;
+    save_consensus_state(deps.storage, &consensus_state)?;
+    Ok(Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute(
+            "height",
+            consensus_state.header.height().revision_height.to_string(),
+        ))
+}
+
+#[cfg_attr(not(feature = "library"), cosmwasm_std::entry_point)]
+pub fn execute(
+    deps: DepsMut<'_>,
+    _env: Env,
+    _info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateState { header } => {
+            let header: Header = borsh::from_slice(header.as_slice())
+                .map_err(|_| ContractError::InvalidEncoding("header"))?;
+            let light_client = ContractLightClient {
+                storage: deps.storage,
+            };
+            light_client
+                .verify_header(&header)
+                .map_err(|err| ContractError::InvalidHeader(format!("{:?}", err)))?;
+            let latest_consensus_state = light_client
+                .get_consensus_state(&light_client.latest_height())
+                .expect("Should not fail as the contract is instantiated.");
+            let height = header.height().revision_height;
+            let consensus_state = latest_consensus_state.next(header);
+            save_consensus_state(deps.storage, &consensus_state)?;
+            Ok(Response::new()
+                .add_attribute("action", "update_state")
+                .add_attribute("height", height.to_string()))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), cosmwasm_std::entry_point)]
+pub fn query(deps: Deps<'_>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    let light_client = ContractLightClient {
+        storage: deps.storage,
+    };
+    match msg {
+        QueryMsg::LatestHeight {} => to_json_binary(&light_client.latest_height().revision_height),
+        QueryMsg::ConsensusState { height } => {
+            to_json_binary(&CONSENSUS_STATES.may_load(deps.storage, height)?)
+        }
+        QueryMsg::VerifyHeader { header } => {
+            let result = match borsh::from_slice::<Header>(header.as_slice()) {
+                Ok(header) => light_client
+                    .verify_header(&header)
+                    .err()
+                    .map(|err| format!("{:?}", err)),
+                Err(_) => Some("Invalid borsh encoding of header.".to_string()),
+            };
+            to_json_binary(&result)
+        }
+        QueryMsg::VerifyMembership {
+            height,
+            key,
+            value,
+            proofs,
+        } => {
+            let result = match light_client.get_consensus_state(&Height::from_block_height(height))
+            {
+                Some(consensus_state) => consensus_state
+                    .verify_membership(
+                        key.as_slice(),
+                        value.as_slice(),
+                        &proofs.into_iter().map(|proof| proof.0).collect(),
+                    )
+                    .err()
+                    .map(|err| format!("{:?}", err)),
+                None => Some(format!("Missing consensus state at height {}.", height)),
+            };
+            to_json_binary(&result)
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Error type of the contract.
+
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+    #[error("Invalid borsh encoding of {0}.")]
+    InvalidEncoding(&'static str),
+    #[error("Failed to verify header: {0}")]
+    InvalidHeader(String),
+}
@@ -0,0 +1,10 @@
+//! An example CosmWasm contract of NEAR light client.
+//!
+//! The contract stores the consensus states of NEAR light client, accepts new headers
+//! through `ExecuteMsg::UpdateState` and verifies the state of NEAR through queries.
+//! Build it with `cargo build --profile cosmwasm --target wasm32-unknown-unknown`.
+
+pub mod contract;
+pub mod error;
+pub mod msg;
+pub mod state;
@@ -0,0 +1,41 @@
+//! Messages of the contract.
+//!
+//! All light client datatypes are passed as their borsh encodings.
+
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Binary;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// The trusted consensus state to start with
+    pub consensus_state: Binary,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Verify the header and store the consensus state of it
+    UpdateState { header: Binary },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Returns the latest height of the light client
+    #[returns(u64)]
+    LatestHeight {},
+    /// Returns the borsh encoded consensus state at the height
+    #[returns(Option<Binary>)]
+    ConsensusState { height: u64 },
+    /// Verify the header with the consensus state of latest height, returns the error if any
+    #[returns(Option<String>)]
+    VerifyHeader { header: Binary },
+    /// Verify the value of a storage key with the consensus state at the height,
+    /// returns the error if any
+    #[returns(Option<String>)]
+    VerifyMembership {
+        height: u64,
+        key: Binary,
+        value: Binary,
+        proofs: Vec<Binary>,
+    },
+}
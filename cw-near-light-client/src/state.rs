@@ -0,0 +1,58 @@
+//! Storage of the contract.
+
+use cosmwasm_std::{Binary, Storage};
+use cw_storage_plus::{Item, Map};
+use near_light_client::{
+    near_types::BlockHeight,
+    types::{ConsensusState, Height},
+    BasicNearLightClient,
+};
+
+/// The latest block height of the light client.
+pub const LATEST_HEIGHT: Item<'_, BlockHeight> = Item::new("latest_height");
+/// The borsh encoded consensus states by block height.
+pub const CONSENSUS_STATES: Map<'_, BlockHeight, Binary> = Map::new("consensus_states");
+
+/// NEAR light client on top of the storage of the contract.
+pub struct ContractLightClient<'a> {
+    pub storage: &'a dyn Storage,
+}
+
+impl BasicNearLightClient for ContractLightClient<'_> {
+    fn latest_height(&self) -> Height {
+        Height::from_block_height(
+            LATEST_HEIGHT
+                .may_load(self.storage)
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+        )
+    }
+
+    fn get_consensus_state(&self, height: &Height) -> Option<ConsensusState> {
+        CONSENSUS_STATES
+            .may_load(self.storage, height.revision_height)
+            .ok()
+            .flatten()
+            .and_then(|bytes| borsh::from_slice(bytes.as_slice()).ok())
+    }
+}
+
+/// Save the consensus state, and update the latest height if it is higher.
+pub fn save_consensus_state(
+    storage: &mut dyn Storage,
+    consensus_state: &ConsensusState,
+) -> cosmwasm_std::StdResult<()> {
+    let height = consensus_state.header.height().revision_height;
+    CONSENSUS_STATES.save(
+        storage,
+        height,
+        &Binary(
+            borsh::to_vec(consensus_state).expect("Should not fail based on borsh serialization."),
+        ),
+    )?;
+    if LATEST_HEIGHT.may_load(storage)?.unwrap_or(0) < height {
+        LATEST_HEIGHT.save(storage, &height)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+//! Generate the gRPC service of `proto/near/lightclient/app/v1/service.proto`, with the
+//! messages of `near-light-client` reused from its `proto` module.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    tonic_build::configure()
+        .extern_path(".near.lightclient.v1", "::near_light_client::proto::v1")
+        .compile(
+            &["proto/near/lightclient/app/v1/service.proto"],
+            &["proto", "../near-light-client/proto"],
+        )?;
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Alerting on the latest verified head falling behind.
+//!
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use abscissa_core::status_err;
+use tokio::sync::watch;
+
+use crate::config::AlertingSection;
+use crate::info_with_time;
+use crate::light_client::near_rpc_client_wrapper::NearRpcClientWrapper;
+use crate::metrics::metrics;
+use crate::shutdown::Shutdown;
+
+/// Progress of the latest verified head, reported to the [`StaleHeadMonitor`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeadProgress {
+    /// Height of the latest verified head
+    pub height: u64,
+    /// Timestamp of the latest verified head in nanoseconds
+    pub timestamp: u64,
+}
+
+/// Monitor checking the progress of the latest verified head periodically, which notifies
+/// once when the head becomes stale, and once when it recovers.
+pub struct StaleHeadMonitor {
+    config: AlertingSection,
+    rpc_client: Arc<NearRpcClientWrapper>,
+    progress: watch::Receiver<HeadProgress>,
+    http_client: reqwest::Client,
+    is_stale: bool,
+}
+
+impl StaleHeadMonitor {
+    /// Create the monitor of the head progress received by `progress`.
+    pub fn new(
+        config: AlertingSection,
+        rpc_client: Arc<NearRpcClientWrapper>,
+        progress: watch::Receiver<HeadProgress>,
+    ) -> Self {
+        Self {
+            config,
+            rpc_client,
+            progress,
+            http_client: reqwest::Client::new(),
+            is_stale: false,
+        }
+    }
+
+    /// Keep checking the head progress until the shutdown is requested.
+    pub async fn run(mut self, shutdown: Shutdown) {
+        let interval = Duration::from_secs(self.config.check_interval_secs.max(1));
+        while !shutdown.sleep(interval).await {
+            self.check().await;
+        }
+    }
+
+    async fn check(&mut self) {
+        let progress = *self.progress.borrow();
+        if progress.height == 0 {
+            return;
+        }
+        match (self.staleness(&progress).await, self.is_stale) {
+            (Some(staleness), false) => {
+                self.is_stale = true;
+                self.notify(format!("NEAR light client is stale: {}.", staleness))
+                    .await;
+            }
+            (None, true) => {
+                self.is_stale = false;
+                self.notify(format!(
+                    "NEAR light client recovered at height {}.",
+                    progress.height
+                ))
+                .await;
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns why the head is stale, `None` if it is not.
+    async fn staleness(&self, progress: &HeadProgress) -> Option<String> {
+        // The lag is always checked, to be reported by the metrics
+        let lag = match self.rpc_client.view_block(&None).await {
+            Ok(chain_head) => {
+                let lag = chain_head.header.height.saturating_sub(progress.height);
+                metrics().chain_tip_lag_heights.set(lag as i64);
+                Some(lag)
+            }
+            Err(err) => {
+                status_err!("Failed to get chain head: {:?}", err);
+                None
+            }
+        };
+        if self.config.max_head_age_secs > 0 {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_nanos() as u64);
+            let age_secs = now.saturating_sub(progress.timestamp) / 1_000_000_000;
+            if age_secs > self.config.max_head_age_secs {
+                return Some(format!(
+                    "the head at height {} is {} seconds old",
+                    progress.height, age_secs
+                ));
+            }
+        }
+        match lag {
+            Some(lag) if self.config.max_lag_heights > 0 && lag > self.config.max_lag_heights => {
+                Some(format!(
+                    "the head at height {} lags {} heights behind the chain head",
+                    progress.height, lag
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Log `message`, and post it to the webhook if configured.
+    async fn notify(&self, message: String) {
+        status_err!("{}", message);
+        let webhook_url = match self.config.webhook_url.as_ref() {
+            Some(webhook_url) => webhook_url,
+            None => return,
+        };
+        // The `text` field is understood by Slack compatible webhooks.
+        let result = self
+            .http_client
+            .post(webhook_url)
+            .json(&serde_json::json!({ "text": message }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => info_with_time!("Notified webhook {}.", webhook_url),
+            Err(err) => status_err!("Failed to notify webhook {}: {:?}", webhook_url, err),
+        }
+    }
+}
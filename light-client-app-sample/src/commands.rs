@@ -10,21 +10,65 @@
 //! See the `impl Configurable` below for how to specify the path to the
 //! application's configuration file.
 
+mod backfill;
+mod benchmark;
+mod check_store;
+mod compare_heads;
+mod export_header;
+mod init;
+mod inspect_failed_head;
+mod keys;
+mod monitor;
+mod prove;
+mod prune;
+mod record_fixtures;
+mod relay;
+mod serve;
+mod snapshot;
 mod start;
+mod status;
+mod verify_event;
 mod verify_membership;
 mod verify_non_membership;
+mod verify_offline;
+mod verify_receipt;
+mod verify_state_batch;
 mod verify_transaction;
+mod view_access_key;
+mod view_account;
+mod view_bps;
+mod view_epochs;
 mod view_head;
+mod view_proof;
+mod watch_tx;
 
 use self::{
-    start::StartCmd, verify_membership::VerifyMembershipCmd,
-    verify_non_membership::VerifyNonMembershipCmd, verify_transaction::VerifyTransactionCmd,
-    view_head::ViewHeadCmd,
+    backfill::BackfillCmd, benchmark::BenchmarkCmd, check_store::CheckStoreCmd,
+    compare_heads::CompareHeadsCmd, export_header::ExportHeaderCmd, init::InitCmd,
+    inspect_failed_head::InspectFailedHeadCmd, keys::KeysCmd, monitor::MonitorCmd, prove::ProveCmd,
+    prune::PruneCmd, record_fixtures::RecordFixturesCmd, relay::RelayCmd, serve::ServeCmd,
+    snapshot::SnapshotCmd, start::StartCmd, status::StatusCmd, verify_event::VerifyEventCmd,
+    verify_membership::VerifyMembershipCmd, verify_non_membership::VerifyNonMembershipCmd,
+    verify_offline::VerifyOfflineCmd, verify_receipt::VerifyReceiptCmd,
+    verify_state_batch::VerifyStateBatchCmd, verify_transaction::VerifyTransactionCmd,
+    view_access_key::ViewAccessKeyCmd, view_account::ViewAccountCmd, view_bps::ViewBpsCmd,
+    view_epochs::ViewEpochsCmd, view_head::ViewHeadCmd, view_proof::ViewProofCmd,
+    watch_tx::WatchTxCmd,
 };
 use crate::config::LightClientAppSampleConfig;
+use crate::output::set_output_format;
 use abscissa_core::{config::Override, Command, Configurable, FrameworkError, Runnable};
 use std::path::PathBuf;
 
+/// Formats of the output of the subcommands printing reports.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable lines
+    Human,
+    /// A JSON object
+    Json,
+}
+
 /// LightClientAppSample Configuration Filename
 pub const CONFIG_FILE: &str = "light_client_app_sample.toml";
 
@@ -32,17 +76,81 @@ pub const CONFIG_FILE: &str = "light_client_app_sample.toml";
 /// Subcommands need to be listed in an enum.
 #[derive(clap::Parser, Command, Debug, Runnable)]
 pub enum LightClientAppSampleCmd {
+    /// Initialize the state data with a trusted checkpoint.
+    Init(InitCmd),
     /// Start an NEAR light instance and keep updating state.
     Start(StartCmd),
+    /// Keep updating state, and relay the verified headers to the configured destination.
+    Relay(RelayCmd),
+    /// Serve a REST API over the light client, while keeping updating state.
+    Serve(ServeCmd),
+    /// Show a live dashboard of the synchronization in the terminal, while synchronizing
+    /// the light client.
+    Monitor(MonitorCmd),
+    /// Print a summary of the sync health of the light client.
+    Status(StatusCmd),
+    /// Compare the latest light client blocks of the configured RPC services with each
+    /// other and with the local verified head.
+    CompareHeads(CompareHeadsCmd),
     /// View head data at the given height.
     ViewHead(ViewHeadCmd),
+    /// View the record of a NEAR account, verified with proof data.
+    ViewAccount(ViewAccountCmd),
+    /// View the permission of an access key of a NEAR account, verified with proof data.
+    ViewAccessKey(ViewAccessKeyCmd),
+    /// View the block producers of the given epoch, or of the epoch of the latest head.
+    ViewBps(ViewBpsCmd),
+    /// List the epochs of the stored heads and block producers, with the heights and the
+    /// block producers held of each.
+    ViewEpochs(ViewEpochsCmd),
     /// Verify the value of a storage key of a NEAR account with proof data.
     VerifyMembership(VerifyMembershipCmd),
     /// Verify that a certain storage key of a NEAR account has NO value with proof data
     /// and optional block height.
     VerifyNonMembership(VerifyNonMembershipCmd),
+    /// Verify the values of a list of storage keys of NEAR accounts, read from a JSON or
+    /// CSV file, with the proof data of all of them at one height.
+    VerifyStateBatch(VerifyStateBatchCmd),
     /// Verify a certain transaction with latest light client head.
     VerifyTransaction(VerifyTransactionCmd),
+    /// Verify the outcome of a certain receipt with latest light client head.
+    VerifyReceipt(VerifyReceiptCmd),
+    /// Wait until a transaction is covered by the light client head, then verify it.
+    WatchTx(WatchTxCmd),
+    /// Verify the outcome of a transaction or receipt, and check that a certain NEP-297
+    /// event is in its proven logs.
+    VerifyEvent(VerifyEventCmd),
+    /// Measure the throughput of the verification of headers and proofs on this machine,
+    /// by replaying the stored heads and recorded proof bundles.
+    Benchmark(BenchmarkCmd),
+    /// Export the header, or the consensus state, of a stored head for a destination chain.
+    ExportHeader(ExportHeaderCmd),
+    /// Record test vectors of the header following the latest head, and of the proofs
+    /// verified with it.
+    RecordFixtures(RecordFixturesCmd),
+    /// Fill the gaps between two heights in the stored heads, starting from a stored head.
+    Backfill(BackfillCmd),
+    /// Check the integrity of the stored heads, and optionally repair the store.
+    CheckStore(CheckStoreCmd),
+    /// Re-run the verification of a failed head, and print the outcome of each check.
+    InspectFailedHead(InspectFailedHeadCmd),
+    /// Prune the stored heads by the configured pruning policy.
+    Prune(PruneCmd),
+    /// Import the signing keys of the relayer into the keystore, and inspect them.
+    #[command(subcommand)]
+    Keys(KeysCmd),
+    /// Write the proof of a storage value or of an outcome, with the head to verify it with,
+    /// into a bundle file.
+    #[command(subcommand)]
+    Prove(ProveCmd),
+    /// Verify a proof bundle file with the stored heads only.
+    VerifyOffline(VerifyOfflineCmd),
+    /// Decode proof data, or the proof of a bundle file, and print the trie nodes and
+    /// merkle paths in it.
+    ViewProof(ViewProofCmd),
+    /// Export the state data to, or import it from, a snapshot archive.
+    #[command(subcommand)]
+    Snapshot(SnapshotCmd),
 }
 
 /// Entry point for the application. It needs to be a struct to allow using subcommands!
@@ -59,10 +167,15 @@ pub struct EntryPoint {
     /// Use the specified config file
     #[arg(short, long)]
     pub config: Option<String>,
+
+    /// Format of the output of `status`, `view-head`, `view-bps` and `verify-*`
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
 }
 
 impl Runnable for EntryPoint {
     fn run(&self) {
+        set_output_format(self.output);
         self.cmd.run()
     }
 }
@@ -98,6 +211,7 @@ impl Configurable<LightClientAppSampleConfig> for EntryPoint {
     ) -> Result<LightClientAppSampleConfig, FrameworkError> {
         match &self.cmd {
             LightClientAppSampleCmd::Start(cmd) => cmd.override_config(config),
+            LightClientAppSampleCmd::Serve(cmd) => cmd.override_config(config),
             //
             // If you don't need special overrides for some
             // subcommands, you can just use a catch all
@@ -0,0 +1,105 @@
+//! `backfill` subcommand - to fill the gaps between two heights in the stored heads.
+
+use crate::light_client::utils::produce_light_client_block;
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::{info_with_time, prelude::*};
+use abscissa_core::{Command, Runnable};
+use near_light_client::types::Height;
+use near_light_client::{BasicNearLightClient, TrustedConsensusState};
+use near_primitives::types::BlockId;
+
+/// `backfill` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct BackfillCmd {
+    /// Height of the stored head to start from
+    #[arg(long)]
+    pub from: u64,
+    /// Height to stop at, the headers above it are not stored
+    #[arg(long)]
+    pub to: u64,
+}
+
+impl Runnable for BackfillCmd {
+    /// Start the application.
+    fn run(&self) {
+        abscissa_tokio::run(&APP, backfill(self)).expect("Failed to backfill NEAR light client.");
+    }
+}
+
+async fn backfill(cmd: &BackfillCmd) {
+    let mut light_client = LightClient::from_config(&APP.config().state_data);
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let mut trusted = match light_client.get_consensus_state(&Height::from_block_height(cmd.from)) {
+        Some(head) => head,
+        None => {
+            status_err!("There is no stored head at height {}.", cmd.from);
+            return;
+        }
+    };
+    let mut count = 0;
+    //
+    // Walk the light client blocks forward, each one verified with the previous one
+    //
+    loop {
+        let trusted_height = trusted.header.height().revision_height;
+        let trusted_block_hash = trusted.header.light_client_block.current_block_hash();
+        let light_client_block_view = match rpc_client
+            .get_next_light_client_block(&near_primitives::hash::CryptoHash(trusted_block_hash.0))
+            .await
+        {
+            Ok(light_client_block_view) => light_client_block_view,
+            Err(err) => {
+                status_err!(
+                    "Failed to get light client block following height {}: {:?}",
+                    trusted_height,
+                    err
+                );
+                break;
+            }
+        };
+        let height = light_client_block_view.inner_lite.height;
+        if height <= trusted_height || height > cmd.to {
+            break;
+        }
+        let block_view = match rpc_client.view_block(&Some(BlockId::Height(height))).await {
+            Ok(block_view) => block_view,
+            Err(err) => {
+                status_err!("Failed to get block at height {}: {:?}", height, err);
+                break;
+            }
+        };
+        let header = produce_light_client_block(&light_client_block_view, &block_view);
+        let client = TrustedConsensusState::new(trusted)
+            .with_fast_finality_check(APP.config().sync.fast_finality_check);
+        if let Err(err) = client.verify_header(&header) {
+            status_err!("Failed to verify header at height {}: {:?}", height, err);
+            break;
+        }
+        // The block producers of the header are in the head it is verified with.
+        trusted = client.consensus_state().next(header);
+        if let Err(err) = light_client.insert_head(trusted.clone()) {
+            status_err!("Failed to save head at height {}: {:?}", height, err);
+            break;
+        }
+        info_with_time!("Backfilled header at height {}.", height);
+        count += 1;
+    }
+    if let Err(err) = light_client.flush() {
+        status_err!("Failed to flush state data: {:?}", err);
+    }
+    status_ok!(
+        "Finished",
+        "Backfilled {} heads between heights {} and {}.",
+        count,
+        cmd.from,
+        cmd.to
+    );
+}
@@ -0,0 +1,300 @@
+//! `benchmark` subcommand - to measure the throughput of the verification of headers and
+//! proofs on the current machine, by replaying the stored heads and recorded proof bundles.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::light_client::bundle::read_bundle;
+use crate::light_client::LightClient;
+use crate::output::{is_json, print_json};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::types::{BundledProof, ConsensusState, Header, Height, ProofBundle};
+use near_light_client::{BasicNearLightClient, TrustedConsensusState};
+use serde::Serialize;
+
+/// `benchmark` subcommand
+///
+/// Each pair of consecutive stored heads is replayed by verifying the later header with
+/// the earlier head, and each proof bundle by verifying its proof with the stored head at
+/// its height. Pairs and bundles which fail to verify, e.g. across a gap left by pruning,
+/// are skipped.
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct BenchmarkCmd {
+    /// Path of a proof bundle file written by `prove`, to replay its proof
+    #[arg(long = "bundle")]
+    pub bundles: Vec<PathBuf>,
+    /// Count of the latest stored heads to replay
+    #[arg(long, default_value_t = 100)]
+    pub max_heads: usize,
+    /// Duration of replaying the headers, and of replaying the proofs, in seconds
+    #[arg(long, default_value_t = 5)]
+    pub duration_secs: u64,
+    /// Stop verifying approval signatures once the approved stake is more than 2/3 of
+    /// the total stake, overriding the configured `sync.fast_finality_check`
+    #[arg(long)]
+    pub fast_finality_check: Option<bool>,
+}
+
+/// Throughput of replaying a set of verifications.
+#[derive(Serialize)]
+struct Throughput {
+    /// Count of the distinct items replayed
+    items: usize,
+    /// Count of the verifications done
+    verifications: u64,
+    seconds: f64,
+    verifications_per_sec: f64,
+    /// Count of the approval signatures of the verified headers
+    signatures: u64,
+    signatures_per_sec: f64,
+}
+
+/// The output of `benchmark`.
+#[derive(Serialize)]
+struct BenchmarkReport {
+    version: &'static str,
+    /// Count of the threads available to the process
+    threads: usize,
+    fast_finality_check: bool,
+    headers: Option<Throughput>,
+    /// Count of the pairs of consecutive stored heads which fail to verify
+    skipped_heads: usize,
+    proofs: Option<Throughput>,
+    /// Count of the bundles which can't be read or fail to verify
+    skipped_bundles: usize,
+}
+
+impl Runnable for BenchmarkCmd {
+    /// Start the application.
+    fn run(&self) {
+        let light_client = LightClient::from_config(&APP.config().state_data);
+        let fast_finality_check = self
+            .fast_finality_check
+            .unwrap_or(APP.config().sync.fast_finality_check);
+        let duration = Duration::from_secs(self.duration_secs.max(1));
+        let (headers, skipped_heads) =
+            load_headers(&light_client, self.max_heads, fast_finality_check);
+        let (proofs, skipped_bundles) = load_proofs(&light_client, &self.bundles);
+        if headers.is_empty() && proofs.is_empty() {
+            status_err!(
+                "Nothing to replay, at least two consecutive stored heads or a proof bundle is required."
+            );
+            std::process::exit(1);
+        }
+        if !is_json() {
+            status_info!(
+                "Info",
+                "Replaying {} headers and {} proofs for {:?} each ...",
+                headers.len(),
+                proofs.len(),
+                duration
+            );
+        }
+        let headers = (!headers.is_empty()).then(|| {
+            measure(duration, &headers, |(client, header)| {
+                client
+                    .verify_header(header)
+                    .expect("Should not fail as verified before.");
+                approval_signatures(header)
+            })
+        });
+        let proofs = (!proofs.is_empty()).then(|| {
+            measure(duration, &proofs, |(head, bundle)| {
+                assert!(
+                    verify_proof(head, bundle),
+                    "Should not fail as verified before."
+                );
+                0
+            })
+        });
+        let report = BenchmarkReport {
+            version: env!("CARGO_PKG_VERSION"),
+            threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+            fast_finality_check,
+            headers,
+            skipped_heads,
+            proofs,
+            skipped_bundles,
+        };
+        if is_json() {
+            print_json(&report);
+        } else {
+            print_report(&report);
+        }
+    }
+}
+
+/// Returns the pairs of consecutive heads among the latest `max_heads` stored heads, whose
+/// later header is verified with the earlier head, and the count of the pairs skipped.
+fn load_headers(
+    light_client: &LightClient,
+    max_heads: usize,
+    fast_finality_check: bool,
+) -> (Vec<(TrustedConsensusState, Header)>, usize) {
+    let heights = light_client.cached_heights();
+    let heights = &heights[heights.len().saturating_sub(max_heads)..];
+    let heads: Vec<ConsensusState> = heights
+        .iter()
+        .filter_map(|height| light_client.get_consensus_state(&Height::from_block_height(*height)))
+        .collect();
+    let mut pairs = Vec::new();
+    let mut skipped = 0;
+    for window in heads.windows(2) {
+        let client = TrustedConsensusState::new(window[0].clone())
+            .with_fast_finality_check(fast_finality_check);
+        match client.verify_header(&window[1].header) {
+            Ok(()) => pairs.push((client, window[1].header.clone())),
+            Err(err) => {
+                if !is_json() {
+                    status_warn!(
+                        "Skip the head at height {}, which fails to verify with the head at height {}: {:?}",
+                        window[1].header.height().revision_height,
+                        window[0].header.height().revision_height,
+                        err
+                    );
+                }
+                skipped += 1;
+            }
+        }
+    }
+    (pairs, skipped)
+}
+
+/// Returns the proof bundles in `paths` with the stored heads at the heights of their
+/// proofs, which are verified with them, and the count of the bundles skipped.
+fn load_proofs(
+    light_client: &LightClient,
+    paths: &[PathBuf],
+) -> (Vec<(ConsensusState, ProofBundle)>, usize) {
+    let mut proofs = Vec::new();
+    let mut skipped = 0;
+    for path in paths.iter() {
+        let loaded = read_bundle(path)
+            .map_err(|err| format!("failed to read it: {:?}", err))
+            .and_then(|(bundle, _)| {
+                let height = bundle.proof.height();
+                let head = light_client.get_consensus_state(&height).ok_or_else(|| {
+                    format!("missing stored head at height {}", height.revision_height)
+                })?;
+                if head.header.light_client_block.current_block_hash()
+                    != bundle
+                        .consensus_state
+                        .header
+                        .light_client_block
+                        .current_block_hash()
+                {
+                    return Err("the bundled head differs from the stored head".to_owned());
+                }
+                if !verify_proof(&head, &bundle) {
+                    return Err("the proof fails to verify".to_owned());
+                }
+                Ok((head, bundle))
+            });
+        match loaded {
+            Ok(proof) => proofs.push(proof),
+            Err(err) => {
+                if !is_json() {
+                    status_warn!("Skip bundle {}, {}.", path.display(), err);
+                }
+                skipped += 1;
+            }
+        }
+    }
+    (proofs, skipped)
+}
+
+/// Returns whether the proof of `bundle` is verified with `head`.
+fn verify_proof(head: &ConsensusState, bundle: &ProofBundle) -> bool {
+    match &bundle.proof {
+        BundledProof::State(proof) => head.verify_state_value_proof(proof).is_ok(),
+        BundledProof::Outcome(proof) => head.verify_outcome_proof(proof).is_ok(),
+    }
+}
+
+/// Returns the count of the approval signatures of `header`.
+fn approval_signatures(header: &Header) -> u64 {
+    header
+        .light_client_block
+        .approvals_after_next
+        .iter()
+        .filter(|approval| approval.is_some())
+        .count() as u64
+}
+
+/// Run `verify` over `items` in rounds, until `duration` elapses after a round, and
+/// return the throughput. `verify` returns the count of the signatures it verifies.
+fn measure<T>(duration: Duration, items: &[T], verify: impl Fn(&T) -> u64) -> Throughput {
+    let started_at = Instant::now();
+    let mut verifications = 0;
+    let mut signatures = 0;
+    while started_at.elapsed() < duration {
+        for item in items.iter() {
+            signatures += verify(item);
+            verifications += 1;
+        }
+    }
+    let seconds = started_at.elapsed().as_secs_f64();
+    Throughput {
+        items: items.len(),
+        verifications,
+        seconds,
+        verifications_per_sec: verifications as f64 / seconds,
+        signatures,
+        signatures_per_sec: signatures as f64 / seconds,
+    }
+}
+
+fn print_report(report: &BenchmarkReport) {
+    status_info!(
+        "Info",
+        "Version {}, {} threads, fast finality check: {}",
+        report.version,
+        report.threads,
+        report.fast_finality_check
+    );
+    match &report.headers {
+        Some(headers) => status_ok!(
+            "Headers",
+            "{:.2} headers/sec, {:.2} signatures/sec ({} verifications of {} headers in {:.2}s)",
+            headers.verifications_per_sec,
+            headers.signatures_per_sec,
+            headers.verifications,
+            headers.items,
+            headers.seconds
+        ),
+        None => status_info!("Headers", "no pair of consecutive stored heads to replay"),
+    }
+    if report.fast_finality_check {
+        status_info!(
+            "Info",
+            "With the fast finality check, not all of the approval signatures counted are verified."
+        );
+    }
+    match &report.proofs {
+        Some(proofs) => status_ok!(
+            "Proofs",
+            "{:.2} proof verifications/sec ({} verifications of {} proofs in {:.2}s)",
+            proofs.verifications_per_sec,
+            proofs.verifications,
+            proofs.items,
+            proofs.seconds
+        ),
+        None => status_info!("Proofs", "no proof bundle to replay, given by `--bundle`"),
+    }
+    if report.skipped_heads > 0 || report.skipped_bundles > 0 {
+        status_warn!(
+            "Skipped {} heads and {} bundles which fail to verify.",
+            report.skipped_heads,
+            report.skipped_bundles
+        );
+    }
+}
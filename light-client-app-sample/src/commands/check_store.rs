@@ -0,0 +1,83 @@
+//! `check-store` subcommand - to check the integrity of the stored heads, and optionally
+//! repair the store.
+
+use crate::light_client::LightClient;
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+
+/// Ways to repair the heads with problems.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum RepairAction {
+    /// Move the heads to the failed heads, or delete them if they can not be decoded
+    Quarantine,
+    /// Delete the heads
+    Delete,
+}
+
+/// `check-store` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct CheckStoreCmd {
+    /// Repair the heads with problems, only report them if omitted
+    #[arg(long, value_enum)]
+    pub repair: Option<RepairAction>,
+}
+
+impl Runnable for CheckStoreCmd {
+    /// Start the application.
+    fn run(&self) {
+        let mut light_client = LightClient::from_config(&APP.config().state_data);
+        let issues = match light_client.check_store() {
+            Ok(issues) => issues,
+            Err(err) => {
+                status_err!("Failed to check store: {:?}", err);
+                return;
+            }
+        };
+        for (height, issue) in issues.iter() {
+            status_err!("Head at height {}: {:?}", height, issue);
+        }
+        if issues.is_empty() {
+            status_ok!(
+                "Finished",
+                "All {} heads are intact.",
+                light_client.cached_heights().len()
+            );
+            return;
+        }
+        let action = match self.repair {
+            Some(action) => action,
+            None => {
+                status_info!(
+                    "Info",
+                    "Found {} heads with problems, use `--repair` to repair them.",
+                    issues.len()
+                );
+                return;
+            }
+        };
+        for (height, _) in issues.iter() {
+            let result = match action {
+                RepairAction::Quarantine => light_client.quarantine_head(*height),
+                RepairAction::Delete => light_client.delete_head(*height),
+            };
+            if let Err(err) = result {
+                status_err!("Failed to repair head at height {}: {:?}", height, err);
+                return;
+            }
+        }
+        status_ok!(
+            "Finished",
+            "Repaired {} heads by {:?}.",
+            issues.len(),
+            action
+        );
+    }
+}
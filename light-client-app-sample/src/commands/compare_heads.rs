@@ -0,0 +1,294 @@
+//! `compare-heads` subcommand - to compare the latest light client blocks of multiple RPC
+//! services with each other and with the local verified head, as a sanity check against
+//! forks and eclipse attacks.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::time::Duration;
+
+use crate::light_client::utils::light_client_block_hash;
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::output::{is_json, print_json};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::{near_types::hash::CryptoHash, BasicNearLightClient};
+use serde::Serialize;
+
+/// Name of the local verified head among the sources of the block hashes.
+const LOCAL_HEAD: &str = "local head";
+
+/// `compare-heads` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct CompareHeadsCmd {
+    /// Endpoint of another RPC service to compare, besides the configured ones
+    #[arg(long = "endpoint")]
+    pub endpoints: Vec<String>,
+    /// Timeout of each query, in seconds
+    #[arg(long, default_value_t = 10)]
+    pub timeout_secs: u64,
+}
+
+/// Hash of the block at a height returned by an RPC service.
+#[derive(Serialize)]
+struct BlockAt {
+    height: u64,
+    hash: Option<CryptoHash>,
+    error: Option<String>,
+}
+
+/// What an RPC service returns.
+#[derive(Serialize)]
+struct EndpointReport {
+    endpoint: String,
+    /// Height and hash of the light client block following the local verified head
+    light_client_block: Option<(u64, CryptoHash)>,
+    error: Option<String>,
+    /// Blocks at the heights of the local verified head and of all light client blocks
+    blocks: Vec<BlockAt>,
+}
+
+/// The output of `compare-heads`.
+#[derive(Serialize)]
+struct CompareReport {
+    local_height: u64,
+    local_hash: CryptoHash,
+    endpoints: Vec<EndpointReport>,
+    /// Heights at which the sources return different block hashes
+    divergences: Vec<String>,
+    consistent: bool,
+}
+
+impl Runnable for CompareHeadsCmd {
+    /// Start the application.
+    fn run(&self) {
+        let report = match abscissa_tokio::run(&APP, compare_heads(self))
+            .expect("Failed to compare heads of RPC services.")
+        {
+            Some(report) => report,
+            None => std::process::exit(1),
+        };
+        if is_json() {
+            print_json(&report);
+        } else {
+            print_report(&report);
+        }
+        if !report.consistent {
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn compare_heads(cmd: &CompareHeadsCmd) -> Option<CompareReport> {
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let head = match light_client.get_consensus_state(&light_client.latest_height()) {
+        Some(head) => head,
+        None => {
+            status_err!("The light client is not initialized.");
+            return None;
+        }
+    };
+    let local_height = head.header.height().revision_height;
+    let local_hash = head.header.light_client_block.current_block_hash();
+    let config = &APP.config().near_rpc;
+    let mut endpoints: Vec<String> = Vec::new();
+    for endpoint in std::iter::once(&config.rpc_endpoint)
+        .chain(config.failover_rpc_endpoints.iter())
+        .chain(config.fallback_rpc_endpoint.iter())
+        .chain(cmd.endpoints.iter())
+    {
+        if !endpoints.contains(endpoint) {
+            endpoints.push(endpoint.clone());
+        }
+    }
+    let timeout = Duration::from_secs(cmd.timeout_secs);
+    let clients: Vec<NearRpcClientWrapper> = endpoints
+        .iter()
+        .map(|endpoint| NearRpcClientWrapper::new(endpoint))
+        .collect();
+    let mut reports = Vec::with_capacity(clients.len());
+    for client in clients.iter() {
+        let result = with_timeout(
+            timeout,
+            client.view_next_light_client_block_once(&near_primitives::hash::CryptoHash(
+                local_hash.0,
+            )),
+        )
+        .await;
+        let (light_client_block, error) = match result {
+            Ok(Some(view)) => (
+                Some((view.inner_lite.height, light_client_block_hash(&view))),
+                None,
+            ),
+            Ok(None) => (None, None),
+            Err(err) => (None, Some(format!("{:?}", err))),
+        };
+        reports.push(EndpointReport {
+            endpoint: client.rpc_addr.clone(),
+            light_client_block,
+            error,
+            blocks: Vec::new(),
+        });
+    }
+    let heights: BTreeSet<u64> = std::iter::once(local_height)
+        .chain(
+            reports
+                .iter()
+                .filter_map(|report| report.light_client_block.map(|(height, _)| height)),
+        )
+        .collect();
+    for (client, report) in clients.iter().zip(reports.iter_mut()) {
+        for height in heights.iter() {
+            let block = match with_timeout(timeout, client.view_block_once(*height)).await {
+                Ok(view) => BlockAt {
+                    height: *height,
+                    hash: Some(CryptoHash(view.header.hash.0)),
+                    error: None,
+                },
+                Err(err) => BlockAt {
+                    height: *height,
+                    hash: None,
+                    error: Some(format!("{:?}", err)),
+                },
+            };
+            report.blocks.push(block);
+        }
+    }
+    let divergences = find_divergences(local_height, local_hash, &reports);
+    let reachable = reports
+        .iter()
+        .any(|report| report.blocks.iter().any(|block| block.hash.is_some()));
+    if !reachable && !is_json() {
+        status_err!("No RPC service returns any block to compare.");
+    }
+    Some(CompareReport {
+        local_height,
+        local_hash,
+        consistent: reachable && divergences.is_empty(),
+        endpoints: reports,
+        divergences,
+    })
+}
+
+/// Returns the result of `future`, or an error if it doesn't complete within `timeout`.
+async fn with_timeout<T>(
+    timeout: Duration,
+    future: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    match tokio::time::timeout(timeout, future).await {
+        Ok(result) => result,
+        Err(_) => anyhow::bail!("Timed out after {:?}.", timeout),
+    }
+}
+
+/// Returns a description of each height at which the local head, the light client blocks
+/// and the blocks of the RPC services have different hashes.
+fn find_divergences(
+    local_height: u64,
+    local_hash: CryptoHash,
+    reports: &[EndpointReport],
+) -> Vec<String> {
+    let mut hashes: BTreeMap<u64, Vec<(String, CryptoHash)>> = BTreeMap::new();
+    hashes
+        .entry(local_height)
+        .or_default()
+        .push((LOCAL_HEAD.to_owned(), local_hash));
+    for report in reports.iter() {
+        if let Some((height, hash)) = report.light_client_block {
+            hashes
+                .entry(height)
+                .or_default()
+                .push((format!("{} (light client block)", report.endpoint), hash));
+        }
+        for block in report.blocks.iter() {
+            if let Some(hash) = block.hash {
+                hashes
+                    .entry(block.height)
+                    .or_default()
+                    .push((report.endpoint.clone(), hash));
+            }
+        }
+    }
+    hashes
+        .iter()
+        .filter(|(_, sources)| {
+            sources
+                .iter()
+                .map(|(_, hash)| hash)
+                .collect::<BTreeSet<_>>()
+                .len()
+                > 1
+        })
+        .map(|(height, sources)| {
+            format!(
+                "Different block hashes at height {}: {}",
+                height,
+                sources
+                    .iter()
+                    .map(|(source, hash)| format!("{} {}", source, hash))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })
+        .collect()
+}
+
+fn print_report(report: &CompareReport) {
+    status_info!(
+        "Info",
+        "Local verified head: height {}, hash {}",
+        report.local_height,
+        report.local_hash
+    );
+    for endpoint in report.endpoints.iter() {
+        match (&endpoint.light_client_block, &endpoint.error) {
+            (Some((height, hash)), _) => status_info!(
+                "Endpoint",
+                "{}: light client block at height {}, hash {}",
+                endpoint.endpoint,
+                height,
+                hash
+            ),
+            (None, Some(error)) => status_warn!(
+                "{}: failed to query the light client block: {}",
+                endpoint.endpoint,
+                error
+            ),
+            (None, None) => status_info!(
+                "Endpoint",
+                "{}: no light client block after the local verified head",
+                endpoint.endpoint
+            ),
+        }
+        for block in endpoint.blocks.iter() {
+            match (&block.hash, &block.error) {
+                (Some(hash), _) => {
+                    status_info!("Info", "block at height {}: {}", block.height, hash)
+                }
+                (None, error) => status_warn!(
+                    "{}: failed to query the block at height {}: {}",
+                    endpoint.endpoint,
+                    block.height,
+                    error.clone().unwrap_or_default()
+                ),
+            }
+        }
+    }
+    for divergence in report.divergences.iter() {
+        status_err!("{}", divergence);
+    }
+    if report.consistent {
+        status_ok!(
+            "Passed",
+            "The {} RPC services agree with each other and with the local verified head.",
+            report.endpoints.len()
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! `export-header` subcommand - to dump the header, or the consensus state, of a stored head
+//! in the formats accepted by the destinations of the relayer.
+
+use std::path::PathBuf;
+
+use crate::light_client::LightClient;
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use crate::relayer::cosmos::{CONSENSUS_STATE_TYPE_URL, HEADER_TYPE_URL};
+use abscissa_core::{Command, Runnable};
+use cosmrs::Any;
+use near_light_client::{proto::v1, types::Height, BasicNearLightClient};
+use prost::Message;
+
+/// Formats of the exported data.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Borsh serialization, as the `update_state` argument of the NEAR verifier contract
+    BorshBase64,
+    /// A JSON object
+    Json,
+    /// The `near.lightclient.v1` protobuf message
+    Protobuf,
+    /// The protobuf message wrapped in `google.protobuf.Any`, as the client message data of
+    /// an ICS-08 wasm light client
+    Any,
+}
+
+/// `export-header` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ExportHeaderCmd {
+    /// Height of the stored head, the latest head if omitted
+    pub height: Option<u64>,
+    /// Format of the exported data
+    #[arg(long, value_enum, default_value_t = ExportFormat::BorshBase64)]
+    pub format: ExportFormat,
+    /// Export the whole consensus state instead of the header
+    #[arg(long)]
+    pub consensus_state: bool,
+    /// Write the data to this file, as raw bytes for binary formats, instead of printing it
+    /// to stdout, base64 encoded for binary formats
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+impl Runnable for ExportHeaderCmd {
+    /// Start the application.
+    fn run(&self) {
+        let light_client = LightClient::from_config(&APP.config().state_data);
+        let height = match self.height {
+            Some(height) => Height::from_block_height(height),
+            None => light_client.latest_height(),
+        };
+        let head = match light_client.get_consensus_state(&height) {
+            Some(head) => head,
+            None => {
+                status_err!("Missing head data at height {}.", height.revision_height);
+                std::process::exit(1);
+            }
+        };
+        let (bytes, binary) = match (self.format, self.consensus_state) {
+            (ExportFormat::BorshBase64, false) => (borsh::to_vec(&head.header).unwrap(), true),
+            (ExportFormat::BorshBase64, true) => (borsh::to_vec(&head).unwrap(), true),
+            (ExportFormat::Json, false) => {
+                (serde_json::to_vec_pretty(&head.header).unwrap(), false)
+            }
+            (ExportFormat::Json, true) => (serde_json::to_vec_pretty(&head).unwrap(), false),
+            (ExportFormat::Protobuf, false) => {
+                (v1::Header::from(head.header).encode_to_vec(), true)
+            }
+            (ExportFormat::Protobuf, true) => {
+                (v1::ConsensusState::from(head).encode_to_vec(), true)
+            }
+            (ExportFormat::Any, false) => (
+                Any {
+                    type_url: HEADER_TYPE_URL.to_owned(),
+                    value: v1::Header::from(head.header).encode_to_vec(),
+                }
+                .encode_to_vec(),
+                true,
+            ),
+            (ExportFormat::Any, true) => (
+                Any {
+                    type_url: CONSENSUS_STATE_TYPE_URL.to_owned(),
+                    value: v1::ConsensusState::from(head).encode_to_vec(),
+                }
+                .encode_to_vec(),
+                true,
+            ),
+        };
+        match self.output.as_ref() {
+            Some(path) => match std::fs::write(path, &bytes) {
+                Ok(()) => status_ok!(
+                    "Finished",
+                    "Exported head at height {} to {}.",
+                    height.revision_height,
+                    path.display()
+                ),
+                Err(err) => {
+                    status_err!("Failed to write {}: {:?}", path.display(), err);
+                    std::process::exit(1);
+                }
+            },
+            None if binary => println!("{}", base64::encode(&bytes)),
+            None => println!("{}", String::from_utf8(bytes).unwrap()),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! `init` subcommand - to initialize NEAR light client with a trusted checkpoint.
+
+use std::str::FromStr;
+
+use crate::light_client::checkpoint::{consensus_state_of_checkpoint, fetch_previous_epoch_header};
+use crate::light_client::utils::{produce_light_client_block, produce_merkle_path};
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::{info_with_time, prelude::*};
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::hash::CryptoHash;
+use near_primitives::types::BlockId;
+
+/// `init` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct InitCmd {
+    /// base58 formatted hash, or height, of the checkpoint block, which must be the last
+    /// final block of an epoch. A hash is trusted as is, while the hash of a height is
+    /// obtained from RPC.
+    pub block: String,
+}
+
+impl Runnable for InitCmd {
+    /// Start the application.
+    fn run(&self) {
+        abscissa_tokio::run(&APP, init_light_client(self))
+            .expect("Failed to initialize NEAR light client.");
+    }
+}
+
+async fn init_light_client(cmd: &InitCmd) {
+    let mut light_client = LightClient::from_config(&APP.config().state_data);
+    if let Some(height) = light_client.oldest_height() {
+        status_err!(
+            "The data folder is already initialized with the head at height {}.",
+            height
+        );
+        return;
+    }
+    let block_id = match cmd.block.parse::<u64>() {
+        Ok(height) => BlockId::Height(height),
+        Err(_) => match near_primitives::hash::CryptoHash::from_str(cmd.block.as_str()) {
+            Ok(hash) => BlockId::Hash(hash),
+            Err(err) => {
+                status_err!("Invalid block hash or height '{}': {:?}", cmd.block, err);
+                return;
+            }
+        },
+    };
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    //
+    // Refuse to mix the data of different chains in the data folder
+    //
+    let chain_id = rpc_client
+        .view_chain_id()
+        .await
+        .expect("Failed to get chain id from RPC.");
+    match light_client.chain_id() {
+        Some(bound_chain_id) if bound_chain_id != chain_id => {
+            status_err!(
+                "The data folder is bound to chain '{}', but the RPC endpoint serves chain '{}'.",
+                bound_chain_id,
+                chain_id
+            );
+            return;
+        }
+        Some(_) => (),
+        None => {
+            if let Err(err) = light_client.set_chain_id(&chain_id) {
+                status_err!(
+                    "Failed to bind the data folder to chain '{}': {:?}",
+                    chain_id,
+                    err
+                );
+                return;
+            }
+        }
+    }
+    //
+    // Fetch the checkpoint, and a header of its previous epoch with its block proof
+    //
+    let block_view = match rpc_client.view_block(&Some(block_id.clone())).await {
+        Ok(block_view) => block_view,
+        Err(err) => {
+            status_err!("Failed to get block {:?}: {:?}", block_id, err);
+            return;
+        }
+    };
+    let trusted_block_hash = match block_id {
+        BlockId::Hash(hash) => CryptoHash(hash.0),
+        BlockId::Height(_) => {
+            status_info!(
+                "Info",
+                "Trust the hash {} of block at height {} returned by RPC.",
+                block_view.header.hash,
+                block_view.header.height
+            );
+            CryptoHash(block_view.header.hash.0)
+        }
+    };
+    let light_client_block_view = match rpc_client
+        .get_next_light_client_block(&block_view.header.prev_hash)
+        .await
+    {
+        Ok(light_client_block_view) => light_client_block_view,
+        Err(err) => {
+            status_err!(
+                "Failed to get light client block of the checkpoint: {:?}",
+                err
+            );
+            return;
+        }
+    };
+    if light_client_block_view.inner_lite.height != block_view.header.height {
+        status_err!(
+            "Block at height {} is not the last final block of its epoch, the light client block following its previous block is at height {}.",
+            block_view.header.height,
+            light_client_block_view.inner_lite.height
+        );
+        return;
+    }
+    let checkpoint = produce_light_client_block(&light_client_block_view, &block_view);
+    let previous_epoch_header =
+        match fetch_previous_epoch_header(&rpc_client, &block_view.header.hash).await {
+            Ok(header) => header,
+            Err(err) => {
+                status_err!("Failed to get header of the previous epoch: {:?}", err);
+                return;
+            }
+        };
+    let block_proof = match rpc_client
+        .get_light_client_block_proof(
+            &near_primitives::hash::CryptoHash(
+                previous_epoch_header
+                    .light_client_block
+                    .current_block_hash()
+                    .0,
+            ),
+            &near_primitives::hash::CryptoHash(trusted_block_hash.0),
+        )
+        .await
+    {
+        Ok(response) => produce_merkle_path(&response.block_proof),
+        Err(err) => {
+            status_err!(
+                "Failed to get block proof of the header of the previous epoch: {:?}",
+                err
+            );
+            return;
+        }
+    };
+    //
+    // Validate the checkpoint and store it as the trust anchor
+    //
+    let head = match consensus_state_of_checkpoint(
+        checkpoint,
+        previous_epoch_header,
+        &block_proof,
+        &trusted_block_hash,
+    ) {
+        Ok(head) => head,
+        Err(err) => {
+            status_err!("Invalid checkpoint: {:?}", err);
+            return;
+        }
+    };
+    let height = head.header.height().revision_height;
+    if let Err(err) = light_client.set_trust_anchor(head) {
+        status_err!("Failed to save the trust anchor: {:?}", err);
+        return;
+    }
+    info_with_time!(
+        "Initialized with the checkpoint {} at height {}.",
+        trusted_block_hash,
+        height
+    );
+}
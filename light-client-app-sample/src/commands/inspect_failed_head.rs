@@ -0,0 +1,102 @@
+//! `inspect-failed-head` subcommand - to re-run the verification of a failed head and
+//! print the outcome of each check.
+
+use crate::light_client::forensics::trace_verification;
+use crate::light_client::LightClient;
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+
+/// `inspect-failed-head` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct InspectFailedHeadCmd {
+    /// Height of the failed head, the failed heights are listed if omitted
+    pub height: Option<u64>,
+    /// Print the approvals of all block producers, not only the invalid ones
+    #[arg(long)]
+    pub approvals: bool,
+}
+
+impl Runnable for InspectFailedHeadCmd {
+    /// Start the application.
+    fn run(&self) {
+        let light_client = LightClient::from_config(&APP.config().state_data);
+        let height = match self.height {
+            Some(height) => height,
+            None => {
+                match light_client.failed_heights() {
+                    Ok(heights) => status_ok!("Finished", "Failed heights: {:?}", heights),
+                    Err(err) => status_err!("Failed to list failed heights: {:?}", err),
+                }
+                return;
+            }
+        };
+        let failed_head = match light_client.get_failed_head(height) {
+            Ok(Some(head)) => head,
+            Ok(None) => {
+                status_err!("There is no failed head at height {}.", height);
+                return;
+            }
+            Err(err) => {
+                status_err!("Failed to read failed head at height {}: {:?}", height, err);
+                return;
+            }
+        };
+        let trusted_head = match light_client.get_head_before(height) {
+            Ok(Some(head)) => head,
+            Ok(None) => {
+                status_err!("There is no stored head below height {}.", height);
+                return;
+            }
+            Err(err) => {
+                status_err!(
+                    "Failed to read stored head below height {}: {:?}",
+                    height,
+                    err
+                );
+                return;
+            }
+        };
+        status_info!(
+            "Info",
+            "Verify failed head at height {} with head at height {}.",
+            height,
+            trusted_head.header.height().revision_height
+        );
+        let trace = trace_verification(&trusted_head, &failed_head.header);
+        for check in trace.checks.iter() {
+            if check.passed {
+                status_ok!("Passed", "{}: {}", check.name, check.detail);
+            } else {
+                status_err!("Check {} failed: {}", check.name, check.detail);
+            }
+        }
+        for approval in trace.approvals.iter() {
+            if self.approvals || approval.valid_signature == Some(false) {
+                status_info!(
+                    "Approval",
+                    "{} with stake {}, signed: {}, valid signature: {:?}",
+                    approval.account_id,
+                    approval.stake,
+                    approval.signed,
+                    approval.valid_signature
+                );
+            }
+        }
+        match trace.result {
+            Ok(()) => status_ok!(
+                "Finished",
+                "Failed head at height {} passes the verification now.",
+                height
+            ),
+            Err(err) => status_err!("Verification of failed head failed: {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! `keys` subcommands - to import the signing keys of the relayer into the keystore, and
+//! to inspect them.
+
+use std::path::PathBuf;
+
+use crate::keystore::{describe_secret, Keystore};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use zeroize::Zeroizing;
+
+/// `keys` subcommands
+#[derive(clap::Subcommand, Command, Debug, Runnable)]
+pub enum KeysCmd {
+    /// Encrypt a key with the passphrase of the keystore, and store it in the keystore.
+    Import(ImportKeyCmd),
+    /// List the keys in the keystore.
+    List(ListKeysCmd),
+    /// Print the public description of a key, and optionally check it can be decrypted.
+    Inspect(InspectKeyCmd),
+}
+
+/// `keys import` subcommand
+#[derive(clap::Parser, Command, Debug)]
+pub struct ImportKeyCmd {
+    /// Name of the key, referred to as `keystore:<name>` in the configuration
+    pub name: String,
+    /// Plaintext file of the secret, e.g. a NEAR credentials file
+    #[arg(
+        long,
+        conflicts_with = "from_env",
+        required_unless_present = "from_env"
+    )]
+    pub from_file: Option<PathBuf>,
+    /// Environment variable holding the secret
+    #[arg(long)]
+    pub from_env: Option<String>,
+    /// Replace the existing key of the same name
+    #[arg(long)]
+    pub overwrite: bool,
+}
+
+/// `keys list` subcommand
+#[derive(clap::Parser, Command, Debug)]
+pub struct ListKeysCmd {}
+
+/// `keys inspect` subcommand
+#[derive(clap::Parser, Command, Debug)]
+pub struct InspectKeyCmd {
+    /// Name of the key
+    pub name: String,
+    /// Decrypt the key with the passphrase, to check it
+    #[arg(long)]
+    pub decrypt: bool,
+}
+
+impl Runnable for ImportKeyCmd {
+    /// Start the application.
+    fn run(&self) {
+        let secret = match (self.from_file.as_ref(), self.from_env.as_ref()) {
+            (Some(path), _) => std::fs::read_to_string(path)
+                .map(Zeroizing::new)
+                .map_err(|err| format!("Failed to read {}: {:?}", path.display(), err)),
+            (None, Some(var)) => std::env::var(var)
+                .map(Zeroizing::new)
+                .map_err(|err| format!("Failed to read ${}: {:?}", var, err)),
+            (None, None) => Err("Either --from-file or --from-env is required.".to_owned()),
+        };
+        let secret = match secret {
+            Ok(secret) => secret,
+            Err(err) => {
+                status_err!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        let keystore = Keystore::from_config(&APP.config().keystore);
+        match keystore.import(&self.name, secret.trim(), self.overwrite) {
+            Ok(path) => status_ok!(
+                "Imported",
+                "key {} to {}, refer to it as `keystore:{}`.",
+                self.name,
+                path.display(),
+                self.name
+            ),
+            Err(err) => {
+                status_err!("Failed to import key {}: {:?}", self.name, err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+impl Runnable for ListKeysCmd {
+    /// Start the application.
+    fn run(&self) {
+        let keystore = Keystore::from_config(&APP.config().keystore);
+        let names = match keystore.names() {
+            Ok(names) => names,
+            Err(err) => {
+                status_err!("Failed to list keys: {:?}", err);
+                std::process::exit(1);
+            }
+        };
+        for name in names.iter() {
+            match keystore.key_file(name) {
+                Ok(key_file) => println!("{}: {}", name, key_file.public),
+                Err(err) => println!("{}: {:?}", name, err),
+            }
+        }
+        status_info!("Info", "{} keys in the keystore.", names.len());
+    }
+}
+
+impl Runnable for InspectKeyCmd {
+    /// Start the application.
+    fn run(&self) {
+        let keystore = Keystore::from_config(&APP.config().keystore);
+        let key_file = match keystore.key_file(&self.name) {
+            Ok(key_file) => key_file,
+            Err(err) => {
+                status_err!("Failed to read key {}: {:?}", self.name, err);
+                std::process::exit(1);
+            }
+        };
+        println!("Name: {}", self.name);
+        println!("Key: {}", key_file.public);
+        if !self.decrypt {
+            return;
+        }
+        match keystore
+            .decrypt(&self.name)
+            .and_then(|secret| describe_secret(&secret))
+        {
+            Ok(public) if public == key_file.public => {
+                status_ok!("Decrypted", "key {} with the passphrase.", self.name)
+            }
+            Ok(public) => {
+                status_err!(
+                    "The decrypted key is {}, different from the description in the key file.",
+                    public
+                );
+                std::process::exit(1);
+            }
+            Err(err) => {
+                status_err!("{:?}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,605 @@
+//! `monitor` subcommand - synchronize the NEAR light client as `start` does, while showing
+//! a live dashboard of the synchronization in the terminal.
+
+use super::start::{prepare_light_client, sync_light_client, StopReason};
+use crate::light_client::near_rpc_client_wrapper::NearRpcClientWrapper;
+use crate::metrics::metrics;
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use crate::server::HeadSummary;
+use crate::shutdown::Shutdown;
+use abscissa_core::{Command, Runnable};
+use gag::BufferRedirect;
+use near_light_client::BasicNearLightClient;
+use prometheus::core::Collector;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+use std::collections::VecDeque;
+use std::io::{IsTerminal, Read, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, broadcast::error::RecvError, Mutex};
+
+/// The count of head summaries buffered for the dashboard lagging behind.
+const HEADS_CAPACITY: usize = 64;
+/// The count of verification timings kept for the chart.
+const MAX_TIMINGS: usize = 120;
+/// The count of log lines and of errors kept.
+const MAX_LINES: usize = 200;
+
+/// `monitor` subcommand
+///
+/// Keys: `q`, `Esc` or `Ctrl-C` to stop the synchronization and quit.
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct MonitorCmd {
+    /// Interval of redrawing the dashboard, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    pub refresh_millis: u64,
+    /// Interval of querying the chain head from RPC, in seconds
+    #[arg(long, default_value_t = 10)]
+    pub rpc_interval_secs: u64,
+}
+
+impl Runnable for MonitorCmd {
+    /// Start the application.
+    fn run(&self) {
+        if !std::io::stdout().is_terminal() {
+            status_err!("The dashboard requires a terminal, use `start` instead.");
+            std::process::exit(1);
+        }
+        let stop_reason =
+            abscissa_tokio::run(&APP, monitor(self)).expect("Failed to monitor NEAR light client.");
+        if stop_reason == StopReason::Error {
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Phase of the synchronization.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Phase {
+    Preparing,
+    Syncing,
+    Stopped(String),
+}
+
+/// What the dashboard shows, updated by the tasks of the command.
+struct Dashboard {
+    phase: Phase,
+    started_at: Instant,
+    /// Height of the latest head when the synchronization started
+    start_height: Option<u64>,
+    latest: Option<HeadSummary>,
+    /// Count of the heads verified since the start
+    verified_heads: u64,
+    chain_tip: Option<u64>,
+    rpc_latency: Option<Duration>,
+    rpc_error: Option<String>,
+    rpc_checks: u64,
+    rpc_check_failures: u64,
+    /// Durations of the latest verifications of headers, in milliseconds
+    timings: VecDeque<u64>,
+    /// Count and sum of the samples of `header_verification_seconds` already charted
+    verification_samples: (u64, f64),
+    logs: VecDeque<String>,
+    errors: VecDeque<String>,
+}
+
+impl Dashboard {
+    fn new() -> Self {
+        let histogram = &metrics().header_verification_seconds;
+        Self {
+            phase: Phase::Preparing,
+            started_at: Instant::now(),
+            start_height: None,
+            latest: None,
+            verified_heads: 0,
+            chain_tip: None,
+            rpc_latency: None,
+            rpc_error: None,
+            rpc_checks: 0,
+            rpc_check_failures: 0,
+            timings: VecDeque::new(),
+            verification_samples: (histogram.get_sample_count(), histogram.get_sample_sum()),
+            logs: VecDeque::new(),
+            errors: VecDeque::new(),
+        }
+    }
+
+    fn lag(&self) -> Option<u64> {
+        match (&self.latest, self.chain_tip) {
+            (Some(latest), Some(tip)) => Some(tip.saturating_sub(latest.height)),
+            _ => None,
+        }
+    }
+
+    /// Chart the verifications done since the last call, by their average duration.
+    fn update_timings(&mut self) {
+        let histogram = &metrics().header_verification_seconds;
+        let (count, sum) = (histogram.get_sample_count(), histogram.get_sample_sum());
+        let (last_count, last_sum) = self.verification_samples;
+        if count > last_count {
+            let millis = (sum - last_sum) * 1000.0 / (count - last_count) as f64;
+            push_bounded(&mut self.timings, millis.max(0.0) as u64, MAX_TIMINGS);
+        }
+        self.verification_samples = (count, sum);
+    }
+
+    /// Add the captured output of the status macros, which would corrupt the dashboard
+    /// if printed to the terminal.
+    fn add_output(&mut self, output: &str) {
+        for line in output.lines() {
+            let line = strip_ansi(line).trim().to_owned();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("error") || line.starts_with("warning") {
+                push_bounded(&mut self.errors, line.clone(), MAX_LINES);
+            }
+            push_bounded(&mut self.logs, line, MAX_LINES);
+        }
+    }
+}
+
+async fn monitor(cmd: &MonitorCmd) -> StopReason {
+    // The status macros print to stderr, which is captured from now on until the
+    // terminal is restored.
+    let mut capture = match BufferRedirect::stderr() {
+        Ok(capture) => capture,
+        Err(err) => {
+            status_err!("Failed to capture the output to stderr: {:?}", err);
+            return StopReason::Error;
+        }
+    };
+    let shutdown = Shutdown::listen();
+    let rpc_client = Arc::new(NearRpcClientWrapper::from_config(&APP.config().near_rpc));
+    let dashboard = Arc::new(std::sync::Mutex::new(Dashboard::new()));
+    let (heads, heads_receiver) = broadcast::channel(HEADS_CAPACITY);
+    let ui = {
+        let dashboard = dashboard.clone();
+        let shutdown = shutdown.clone();
+        let refresh = Duration::from_millis(cmd.refresh_millis.max(50));
+        tokio::task::spawn_blocking(move || {
+            let result = run_ui(&dashboard, &shutdown, &mut capture, refresh);
+            // Stop the synchronization as well if the dashboard fails
+            shutdown.request();
+            (capture, result)
+        })
+    };
+    let (stop_reason, _, _, ui) = tokio::join!(
+        sync(&rpc_client, &dashboard, heads, &shutdown),
+        watch_heads(heads_receiver, &dashboard, &shutdown),
+        watch_chain_tip(
+            &rpc_client,
+            &dashboard,
+            &shutdown,
+            Duration::from_secs(cmd.rpc_interval_secs.max(1))
+        ),
+        ui,
+    );
+    match ui {
+        Ok((mut capture, result)) => {
+            let mut output = String::new();
+            let _ = capture.read_to_string(&mut output);
+            drop(capture);
+            // Re-emit what is not shown on the dashboard since it was left
+            let mut stderr = std::io::stderr();
+            let _ = stderr.write_all(output.as_bytes());
+            if let Err(err) = result {
+                status_err!("The dashboard failed: {:?}", err);
+                return StopReason::Error;
+            }
+        }
+        Err(err) => {
+            status_err!("The dashboard task failed: {:?}", err);
+            return StopReason::Error;
+        }
+    }
+    stop_reason
+}
+
+/// Prepare and synchronize the light client until it stops, then stop the other tasks.
+async fn sync(
+    rpc_client: &Arc<NearRpcClientWrapper>,
+    dashboard: &std::sync::Mutex<Dashboard>,
+    heads: broadcast::Sender<HeadSummary>,
+    shutdown: &Shutdown,
+) -> StopReason {
+    let stop_reason = match prepare_light_client(rpc_client, false, shutdown).await {
+        Some(light_client) => {
+            {
+                let mut dashboard = dashboard.lock().expect("Dashboard lock poisoned.");
+                let latest_height = light_client.latest_height().revision_height;
+                dashboard.start_height = Some(latest_height);
+                dashboard.phase = Phase::Syncing;
+            }
+            sync_light_client(
+                rpc_client.clone(),
+                Arc::new(Mutex::new(light_client)),
+                heads,
+                shutdown.clone(),
+            )
+            .await
+        }
+        None => StopReason::Error,
+    };
+    dashboard.lock().expect("Dashboard lock poisoned.").phase = Phase::Stopped(match stop_reason {
+        StopReason::Shutdown => "shutdown requested".to_owned(),
+        StopReason::Error => "failed, see the errors, press `q` to quit".to_owned(),
+    });
+    // Keep the dashboard showing the error until the user quits
+    if stop_reason == StopReason::Shutdown {
+        shutdown.request();
+    }
+    stop_reason
+}
+
+/// Record each newly verified head.
+async fn watch_heads(
+    mut heads: broadcast::Receiver<HeadSummary>,
+    dashboard: &std::sync::Mutex<Dashboard>,
+    shutdown: &Shutdown,
+) {
+    loop {
+        let received = tokio::select! {
+            received = heads.recv() => received,
+            _ = shutdown.wait() => return,
+        };
+        match received {
+            Ok(head) => {
+                let mut dashboard = dashboard.lock().expect("Dashboard lock poisoned.");
+                dashboard.verified_heads += 1;
+                dashboard.latest = Some(head);
+            }
+            Err(RecvError::Lagged(count)) => {
+                dashboard
+                    .lock()
+                    .expect("Dashboard lock poisoned.")
+                    .verified_heads += count;
+            }
+            Err(RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Query the chain head from RPC every `interval`, as the health check of RPC.
+async fn watch_chain_tip(
+    rpc_client: &NearRpcClientWrapper,
+    dashboard: &std::sync::Mutex<Dashboard>,
+    shutdown: &Shutdown,
+    interval: Duration,
+) {
+    loop {
+        let started_at = Instant::now();
+        let result = tokio::select! {
+            result = tokio::time::timeout(interval, rpc_client.view_block(&None)) => result,
+            _ = shutdown.wait() => return,
+        };
+        {
+            let mut dashboard = dashboard.lock().expect("Dashboard lock poisoned.");
+            dashboard.rpc_checks += 1;
+            match result {
+                Ok(Ok(block)) => {
+                    dashboard.chain_tip = Some(block.header.height);
+                    dashboard.rpc_latency = Some(started_at.elapsed());
+                    dashboard.rpc_error = None;
+                    if let Some(lag) = dashboard.lag() {
+                        metrics().chain_tip_lag_heights.set(lag as i64);
+                    }
+                }
+                Ok(Err(err)) => {
+                    dashboard.rpc_check_failures += 1;
+                    dashboard.rpc_error = Some(format!("{:?}", err));
+                }
+                Err(_) => {
+                    dashboard.rpc_check_failures += 1;
+                    dashboard.rpc_error = Some(format!("Timed out after {:?}.", interval));
+                }
+            }
+        }
+        if shutdown.sleep(interval).await {
+            return;
+        }
+    }
+}
+
+/// Restores the terminal when dropped, even on a panic of drawing.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> std::io::Result<Self> {
+        enable_raw_mode()?;
+        if let Err(err) = execute!(std::io::stdout(), EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(err);
+        }
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Draw the dashboard every `refresh` until the shutdown is requested, which the keys
+/// `q`, `Esc` and `Ctrl-C` do.
+fn run_ui(
+    dashboard: &std::sync::Mutex<Dashboard>,
+    shutdown: &Shutdown,
+    capture: &mut BufferRedirect,
+    refresh: Duration,
+) -> std::io::Result<()> {
+    let _guard = TerminalGuard::enter()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
+    terminal.clear()?;
+    while !shutdown.is_requested() {
+        let mut output = String::new();
+        let _ = capture.read_to_string(&mut output);
+        {
+            let mut dashboard = dashboard.lock().expect("Dashboard lock poisoned.");
+            dashboard.add_output(&output);
+            dashboard.update_timings();
+            terminal.draw(|frame| draw(frame, &dashboard))?;
+        }
+        if event::poll(refresh)? {
+            if let Event::Key(key) = event::read()? {
+                let quit = key.kind == KeyEventKind::Press
+                    && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                        || (key.code == KeyCode::Char('c')
+                            && key.modifiers.contains(KeyModifiers::CONTROL)));
+                if quit {
+                    shutdown.request();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, dashboard: &Dashboard) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(8),
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Min(6),
+        ])
+        .split(frame.size());
+    draw_header(frame, rows[0], dashboard);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[1]);
+    draw_sync(frame, columns[0], dashboard);
+    draw_rpc(frame, columns[1], dashboard);
+    draw_progress(frame, rows[2], dashboard);
+    draw_timings(frame, rows[3], dashboard);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[4]);
+    draw_lines(
+        frame,
+        columns[0],
+        "Recent errors",
+        &dashboard.errors,
+        Color::Red,
+    );
+    draw_lines(frame, columns[1], "Log", &dashboard.logs, Color::Reset);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let (phase, color) = match &dashboard.phase {
+        Phase::Preparing => ("preparing".to_owned(), Color::Yellow),
+        Phase::Syncing => ("synchronizing".to_owned(), Color::Green),
+        Phase::Stopped(reason) => (format!("stopped: {}", reason), Color::Red),
+    };
+    let line = Line::from(format!(
+        "chain {} | {} | up {}s | q to quit",
+        APP.config().near_rpc.rpc_endpoint,
+        phase,
+        dashboard.started_at.elapsed().as_secs()
+    ));
+    frame.render_widget(
+        Paragraph::new(line)
+            .style(Style::default().fg(color))
+            .block(titled("NEAR light client")),
+        area,
+    );
+}
+
+fn draw_sync(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let elapsed = dashboard.started_at.elapsed().as_secs_f64().max(1.0);
+    let mut lines = vec![
+        Line::from(format!(
+            "Latest verified height: {}",
+            or_unknown(dashboard.latest.as_ref().map(|head| head.height))
+        )),
+        Line::from(format!(
+            "Latest block hash: {}",
+            or_unknown(dashboard.latest.as_ref().map(|head| head.block_hash))
+        )),
+        Line::from(format!(
+            "Heads verified: {} ({:.2}/min)",
+            dashboard.verified_heads,
+            dashboard.verified_heads as f64 * 60.0 / elapsed
+        )),
+        Line::from(format!("Stored heads: {}", metrics().stored_heads.get())),
+    ];
+    if let Some(head) = dashboard.latest.as_ref() {
+        let now = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+        lines.push(Line::from(format!(
+            "Head age: {}s",
+            now.saturating_sub(head.timestamp) / 1_000_000_000
+        )));
+        lines.push(Line::from(format!("Epoch: {}", head.epoch_id)));
+    }
+    frame.render_widget(Paragraph::new(lines).block(titled("Sync")), area);
+}
+
+fn draw_rpc(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let (status, color) = match (&dashboard.rpc_error, dashboard.rpc_latency) {
+        (Some(error), _) => (format!("unreachable: {}", error), Color::Red),
+        (None, Some(latency)) => (format!("ok, {} ms", latency.as_millis()), Color::Green),
+        (None, None) => ("checking".to_owned(), Color::Yellow),
+    };
+    let mut lines = vec![
+        Line::styled(format!("Status: {}", status), Style::default().fg(color)),
+        Line::from(format!(
+            "Chain tip height: {}, lag: {}",
+            or_unknown(dashboard.chain_tip),
+            or_unknown(dashboard.lag())
+        )),
+        Line::from(format!(
+            "Checks failed: {} of {}",
+            dashboard.rpc_check_failures, dashboard.rpc_checks
+        )),
+    ];
+    let failures: Vec<String> = metrics()
+        .rpc_call_failures
+        .collect()
+        .iter()
+        .flat_map(|family| family.get_metric().iter())
+        .filter(|metric| metric.get_counter().get_value() > 0.0)
+        .map(|metric| {
+            format!(
+                "{} {}",
+                metric
+                    .get_label()
+                    .first()
+                    .map(|label| label.get_value())
+                    .unwrap_or_default(),
+                metric.get_counter().get_value()
+            )
+        })
+        .collect();
+    lines.push(Line::from(format!(
+        "Failed calls: {}",
+        if failures.is_empty() {
+            "none".to_owned()
+        } else {
+            failures.join(", ")
+        }
+    )));
+    frame.render_widget(Paragraph::new(lines).block(titled("RPC")), area);
+}
+
+fn draw_progress(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let latest = dashboard
+        .latest
+        .as_ref()
+        .map(|head| head.height)
+        .or(dashboard.start_height);
+    let (ratio, label) = match (dashboard.start_height, latest, dashboard.chain_tip) {
+        (Some(start), Some(latest), Some(tip)) if tip > start => (
+            (latest.saturating_sub(start) as f64 / (tip - start) as f64).min(1.0),
+            format!(
+                "{} / {}, lagging {} heights",
+                latest,
+                tip,
+                tip.saturating_sub(latest)
+            ),
+        ),
+        (_, Some(latest), Some(tip)) => (1.0, format!("{} / {}", latest, tip)),
+        _ => (0.0, "unknown".to_owned()),
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(titled("Progress since the start"))
+            .gauge_style(Style::default().fg(Color::Green))
+            .ratio(ratio)
+            .label(label),
+        area,
+    );
+}
+
+fn draw_timings(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let timings: Vec<u64> = dashboard.timings.iter().copied().collect();
+    let title = match timings.last() {
+        Some(last) => format!(
+            "Header verification (ms): last {}, max {}, avg {}",
+            last,
+            timings.iter().max().copied().unwrap_or_default(),
+            timings.iter().sum::<u64>() / timings.len() as u64
+        ),
+        None => "Header verification (ms)".to_owned(),
+    };
+    frame.render_widget(
+        Sparkline::default()
+            .block(titled(&title))
+            .style(Style::default().fg(Color::Cyan))
+            .data(&timings),
+        area,
+    );
+}
+
+fn draw_lines(frame: &mut Frame, area: Rect, title: &str, lines: &VecDeque<String>, color: Color) {
+    // Only the latest lines which fit in the area, without the borders
+    let height = area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = lines
+        .iter()
+        .skip(lines.len().saturating_sub(height))
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines)
+            .style(Style::default().fg(color))
+            .block(titled(title)),
+        area,
+    );
+}
+
+fn titled(title: &str) -> Block<'_> {
+    Block::default().borders(Borders::ALL).title(title)
+}
+
+fn or_unknown<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "unknown".to_owned(), |value| value.to_string())
+}
+
+fn push_bounded<T>(items: &mut VecDeque<T>, item: T, max: usize) {
+    if items.len() == max {
+        items.pop_front();
+    }
+    items.push_back(item);
+}
+
+/// Returns `line` without the ANSI escape sequences of colors.
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip to the final byte of the sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
@@ -0,0 +1,288 @@
+//! `prove` subcommands - to fetch the proof of a storage value or of an outcome, and write
+//! it with the consensus state to verify it with into a bundle file, which can be verified
+//! offline by `verify-offline`.
+
+use std::convert::TryFrom;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::light_client::bundle::{write_bundle, BundleFormat};
+use crate::light_client::utils::{produce_outcome_proof, trie_key_of};
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::hash::CryptoHash;
+use near_light_client::types::{
+    BundledProof, ConsensusState, Height, MembershipProof, NonMembershipProof, ProofBundle,
+    StateValueProof,
+};
+use near_light_client::BasicNearLightClient;
+use near_primitives::types::{AccountId, BlockId, TransactionOrReceiptId};
+
+/// `prove` subcommands
+#[derive(clap::Subcommand, Command, Debug, Runnable)]
+pub enum ProveCmd {
+    /// Prove the value, or the absence, of a storage key of a NEAR account.
+    State(ProveStateCmd),
+    /// Prove the outcome of a transaction.
+    Transaction(ProveTransactionCmd),
+    /// Prove the outcome of a receipt.
+    Receipt(ProveReceiptCmd),
+}
+
+/// Options of the bundle file shared by the `prove` subcommands.
+#[derive(clap::Args, Debug)]
+pub struct BundleOutput {
+    /// Path of the bundle file to write
+    #[arg(long, short)]
+    pub output: PathBuf,
+    /// Serialization of the bundle file
+    #[arg(long, value_enum, default_value_t = BundleFormat::Borsh)]
+    pub format: BundleFormat,
+    /// Height of the stored head to prove with, the latest head if omitted
+    #[arg(long)]
+    pub height: Option<u64>,
+}
+
+/// `prove state` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ProveStateCmd {
+    /// Id of the NEAR account
+    pub near_account: String,
+    /// base64 formatted storage key
+    pub storage_key: String,
+    #[command(flatten)]
+    pub bundle: BundleOutput,
+}
+
+/// `prove transaction` subcommand
+#[derive(clap::Parser, Command, Debug)]
+pub struct ProveTransactionCmd {
+    /// base58 formatted transaction hash
+    pub tx_hash: String,
+    /// Account id of transaction sender
+    pub sender_id: String,
+    #[command(flatten)]
+    pub bundle: BundleOutput,
+}
+
+/// `prove receipt` subcommand
+#[derive(clap::Parser, Command, Debug)]
+pub struct ProveReceiptCmd {
+    /// base58 formatted receipt id
+    pub receipt_id: String,
+    /// Account id of receipt receiver
+    pub receiver_id: String,
+    #[command(flatten)]
+    pub bundle: BundleOutput,
+}
+
+impl Runnable for ProveStateCmd {
+    /// Start the application.
+    fn run(&self) {
+        abscissa_tokio::run(&APP, prove_state(self)).expect("Failed to prove the state.");
+    }
+}
+
+impl Runnable for ProveTransactionCmd {
+    /// Start the application.
+    fn run(&self) {
+        let id = decode_hash(&self.tx_hash).and_then(|transaction_hash| {
+            AccountId::from_str(&self.sender_id).ok().map(|sender_id| {
+                TransactionOrReceiptId::Transaction {
+                    transaction_hash,
+                    sender_id,
+                }
+            })
+        });
+        match id {
+            Some(id) => abscissa_tokio::run(&APP, prove_outcome(id, &self.bundle))
+                .expect("Failed to prove the transaction."),
+            None => status_err!("Invalid transaction hash or sender id."),
+        }
+    }
+}
+
+impl Runnable for ProveReceiptCmd {
+    /// Start the application.
+    fn run(&self) {
+        let id = decode_hash(&self.receipt_id).and_then(|receipt_id| {
+            AccountId::from_str(&self.receiver_id)
+                .ok()
+                .map(|receiver_id| TransactionOrReceiptId::Receipt {
+                    receipt_id,
+                    receiver_id,
+                })
+        });
+        match id {
+            Some(id) => abscissa_tokio::run(&APP, prove_outcome(id, &self.bundle))
+                .expect("Failed to prove the receipt."),
+            None => status_err!("Invalid receipt id or receiver id."),
+        }
+    }
+}
+
+fn decode_hash(hash: &str) -> Option<near_primitives::hash::CryptoHash> {
+    near_primitives::hash::CryptoHash::from_str(hash).ok()
+}
+
+/// Returns the stored head at `height`, or the latest head, with the chain id of the store.
+async fn head_and_chain_id(
+    light_client: &LightClient,
+    rpc_client: &NearRpcClientWrapper,
+    height: Option<u64>,
+) -> Option<(ConsensusState, String)> {
+    let height = height
+        .map(Height::from_block_height)
+        .unwrap_or_else(|| light_client.latest_height());
+    let head = match light_client.get_consensus_state(&height) {
+        Some(head) => head,
+        None => {
+            status_err!("Missing head data at height {}.", height.revision_height);
+            return None;
+        }
+    };
+    let chain_id = match light_client.chain_id() {
+        Some(chain_id) => chain_id,
+        None => match rpc_client.view_chain_id().await {
+            Ok(chain_id) => chain_id,
+            Err(err) => {
+                status_err!("Failed to get chain id from RPC: {:?}", err);
+                return None;
+            }
+        },
+    };
+    Some((head, chain_id))
+}
+
+async fn prove_state(cmd: &ProveStateCmd) {
+    let account_id = match AccountId::try_from(cmd.near_account.clone()) {
+        Ok(account_id) => account_id,
+        Err(err) => {
+            status_err!("Invalid account id '{}': {}", cmd.near_account, err);
+            return;
+        }
+    };
+    let key_bytes = match base64::decode(&cmd.storage_key) {
+        Ok(key_bytes) => key_bytes,
+        Err(err) => {
+            status_err!("Invalid storage key '{}': {}", cmd.storage_key, err);
+            return;
+        }
+    };
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let (head, chain_id) =
+        match head_and_chain_id(&light_client, &rpc_client, cmd.bundle.height).await {
+            Some(head_and_chain_id) => head_and_chain_id,
+            None => return,
+        };
+    let height = head.header.height();
+    // The proofs at `height - 1` are verified with the head at `height`.
+    let result = match rpc_client
+        .view_state_with_proof(
+            account_id,
+            Some(key_bytes.as_ref()),
+            Some(BlockId::Height(height.revision_height - 1)),
+        )
+        .await
+    {
+        Ok(result) => result,
+        Err(err) => {
+            status_err!("Failed to view state: {:?}", err);
+            return;
+        }
+    };
+    let proofs: Vec<Vec<u8>> = result.proof.iter().map(|proof| proof.to_vec()).collect();
+    let key = trie_key_of(&cmd.near_account, &key_bytes, false, None);
+    let proof = match result
+        .values
+        .iter()
+        .find(|item| item.key.deref() == key_bytes.as_slice())
+    {
+        Some(item) => StateValueProof::Membership(MembershipProof::new(
+            height,
+            key,
+            item.value.to_vec(),
+            proofs,
+        )),
+        None => StateValueProof::NonMembership(NonMembershipProof::new(height, key, proofs)),
+    };
+    if let Err(err) = head.verify_state_value_proof(&proof) {
+        status_err!("The proof fails to verify with the stored head: {:?}", err);
+        return;
+    }
+    let kind = match proof {
+        StateValueProof::Membership(_) => "membership",
+        StateValueProof::NonMembership(_) => "non-membership",
+    };
+    save_bundle(
+        ProofBundle::new(chain_id, head, BundledProof::State(proof)),
+        &cmd.bundle,
+        kind,
+    );
+}
+
+async fn prove_outcome(id: TransactionOrReceiptId, output: &BundleOutput) {
+    let outcome_id = match &id {
+        TransactionOrReceiptId::Transaction {
+            transaction_hash, ..
+        } => CryptoHash(transaction_hash.0),
+        TransactionOrReceiptId::Receipt { receipt_id, .. } => CryptoHash(receipt_id.0),
+    };
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let (head, chain_id) = match head_and_chain_id(&light_client, &rpc_client, output.height).await
+    {
+        Some(head_and_chain_id) => head_and_chain_id,
+        None => return,
+    };
+    let head_hash = head.header.light_client_block.current_block_hash();
+    let result = match rpc_client
+        .get_light_client_proof(&id, &near_primitives::hash::CryptoHash(head_hash.0))
+        .await
+    {
+        Ok(result) => result,
+        Err(err) => {
+            status_err!("Failed to get light client proof: {:?}", err);
+            return;
+        }
+    };
+    let proof = produce_outcome_proof(head.header.height(), outcome_id, &result);
+    if let Err(err) = head.verify_outcome_proof(&proof) {
+        status_err!("The proof fails to verify with the stored head: {:?}", err);
+        return;
+    }
+    save_bundle(
+        ProofBundle::new(chain_id, head, BundledProof::Outcome(proof)),
+        output,
+        "outcome",
+    );
+}
+
+fn save_bundle(bundle: ProofBundle, output: &BundleOutput, kind: &str) {
+    let height = bundle.proof.height().revision_height;
+    match write_bundle(&output.output, &bundle, output.format) {
+        Ok(()) => status_ok!(
+            "Finished",
+            "Wrote {} proof at height {} to {}.",
+            kind,
+            height,
+            output.output.display()
+        ),
+        Err(err) => status_err!(
+            "Failed to write bundle file {}: {:?}",
+            output.output.display(),
+            err
+        ),
+    }
+}
@@ -0,0 +1,48 @@
+//! `prune` subcommand - to prune the stored heads by the configured pruning policy.
+
+use crate::light_client::LightClient;
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+
+/// `prune` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct PruneCmd {
+    /// Only print the heights of the heads to prune
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl Runnable for PruneCmd {
+    /// Start the application.
+    fn run(&self) {
+        let mut light_client = LightClient::from_config(&APP.config().state_data);
+        let heights = match light_client.prune(&APP.config().state_data.pruning, self.dry_run) {
+            Ok(heights) => heights,
+            Err(err) => {
+                status_err!("Failed to prune heads: {:?}", err);
+                return;
+            }
+        };
+        if self.dry_run {
+            for height in heights.iter() {
+                status_info!("Info", "Head at height {} would be pruned.", height);
+            }
+            status_ok!("Finished", "{} heads would be pruned.", heights.len());
+        } else {
+            status_ok!(
+                "Finished",
+                "Pruned {} heads, {} heads left.",
+                heights.len(),
+                light_client.cached_heights().len()
+            );
+        }
+    }
+}
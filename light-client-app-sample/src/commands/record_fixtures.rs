@@ -0,0 +1,222 @@
+//! `record-fixtures` subcommand
+//! Record test vectors of the header following the latest light client head, and
+//! optionally of the state of an account and the outcome of a transaction at that header.
+
+use std::convert::TryFrom;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::light_client::utils::{produce_light_client_block, produce_outcome_proof};
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::{info_with_time, prelude::*};
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::{get_raw_prefix_for_contract_data, hash::CryptoHash};
+use near_light_client::test_vectors::{TestCase, TestVector};
+use near_light_client::types::{
+    ConsensusState, MembershipProof, NonMembershipProof, StateValueProof,
+};
+use near_light_client::BasicNearLightClient;
+use near_primitives::types::{AccountId, BlockId};
+
+/// `record-fixtures` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct RecordFixturesCmd {
+    /// The folder to save the test vector files in
+    pub output_folder: String,
+    /// Account to record the state proof of, at the height of the recorded header
+    #[arg(long)]
+    pub account: Option<String>,
+    /// base64 formatted storage key of the account, the empty key if not given
+    #[arg(long)]
+    pub storage_key: Option<String>,
+    /// base58 formatted hash of a transaction to record the outcome proof of
+    #[arg(long, requires = "sender_id")]
+    pub tx_hash: Option<String>,
+    /// Account id of transaction sender
+    #[arg(long, requires = "tx_hash")]
+    pub sender_id: Option<String>,
+}
+
+impl Runnable for RecordFixturesCmd {
+    /// Start the application.
+    fn run(&self) {
+        abscissa_tokio::run(&APP, record_fixtures(self))
+            .expect("Failed to record test vectors of NEAR light client.");
+    }
+}
+
+async fn record_fixtures(cmd: &RecordFixturesCmd) {
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let trusted = match light_client.get_consensus_state(&light_client.latest_height()) {
+        Some(head) => head,
+        None => {
+            status_err!("Uninitialized NEAR light client.");
+            return;
+        }
+    };
+    let chain_id = rpc_client
+        .view_chain_id()
+        .await
+        .expect("Failed to get chain id from RPC.");
+    if let Some(bound_chain_id) = light_client.chain_id() {
+        if bound_chain_id != chain_id {
+            status_err!(
+                "The data folder is bound to chain '{}', but the RPC endpoint serves chain '{}'.",
+                bound_chain_id,
+                chain_id
+            );
+            return;
+        }
+    }
+    std::fs::create_dir_all(&cmd.output_folder).expect("Failed to create output folder.");
+    //
+    // The header following the latest head
+    //
+    let light_client_block_view = rpc_client
+        .get_next_light_client_block(&near_primitives::hash::CryptoHash(
+            trusted.header.light_client_block.current_block_hash().0,
+        ))
+        .await
+        .expect("Failed to get next light client block.");
+    let height = light_client_block_view.inner_lite.height;
+    let block_view = rpc_client
+        .view_block(&Some(BlockId::Height(height)))
+        .await
+        .expect(format!("Failed to get block at height {}.", height).as_str());
+    let header = produce_light_client_block(&light_client_block_view, &block_view);
+    let header_case = TestCase::Header {
+        trusted: trusted.clone(),
+        header: header.clone(),
+    };
+    let accepted = header_case.verify().is_ok();
+    save_vector(
+        &cmd.output_folder,
+        TestVector::new(chain_id.clone(), header_case, accepted),
+    );
+    if !accepted {
+        status_err!(
+            "Header at height {} is rejected, skip recording proofs with it.",
+            height
+        );
+        return;
+    }
+    let consensus_state = ConsensusState::new(
+        trusted.block_producers_of(&header.epoch_id()).cloned(),
+        header,
+    );
+    //
+    // The state proof of the account at the header
+    //
+    if let Some(account) = cmd.account.as_ref() {
+        let key_bytes = cmd
+            .storage_key
+            .as_ref()
+            .map(|key| base64::decode(key).expect("Invalid base64 storage key."))
+            .unwrap_or_default();
+        let result = rpc_client
+            .view_state_with_proof(
+                AccountId::try_from(account.clone()).expect("Invalid account id."),
+                Some(key_bytes.as_ref()),
+                Some(BlockId::Height(height - 1)),
+            )
+            .await
+            .expect("Failed to view state of the given NEAR account.");
+        let proofs: Vec<Vec<u8>> = result.proof.iter().map(|proof| proof.to_vec()).collect();
+        let key = get_raw_prefix_for_contract_data(account, key_bytes.as_ref());
+        let proof = match result
+            .values
+            .iter()
+            .find(|item| item.key.as_ref() == key_bytes.as_slice())
+        {
+            Some(item) => StateValueProof::Membership(MembershipProof::new(
+                consensus_state.header.height(),
+                key,
+                item.value.to_vec(),
+                proofs,
+            )),
+            None => StateValueProof::NonMembership(NonMembershipProof::new(
+                consensus_state.header.height(),
+                key,
+                proofs,
+            )),
+        };
+        save_checked_vector(
+            &cmd.output_folder,
+            &chain_id,
+            TestCase::StateValue {
+                consensus_state: consensus_state.clone(),
+                proof,
+            },
+        );
+    }
+    //
+    // The outcome proof of the transaction at the header
+    //
+    if let (Some(tx_hash), Some(sender_id)) = (cmd.tx_hash.as_ref(), cmd.sender_id.as_ref()) {
+        let transaction_hash =
+            CryptoHash::try_from(bs58::decode(tx_hash).into_vec().unwrap().as_ref()).unwrap();
+        let result = rpc_client
+            .get_light_client_proof(
+                &near_primitives::types::TransactionOrReceiptId::Transaction {
+                    transaction_hash: near_primitives::hash::CryptoHash(transaction_hash.0),
+                    sender_id: AccountId::from_str(sender_id).expect("Invalid sender id."),
+                },
+                &near_primitives::hash::CryptoHash(
+                    consensus_state
+                        .header
+                        .light_client_block
+                        .current_block_hash()
+                        .0,
+                ),
+            )
+            .await
+            .expect("Failed to get light client proof.");
+        let proof =
+            produce_outcome_proof(consensus_state.header.height(), transaction_hash, &result);
+        save_checked_vector(
+            &cmd.output_folder,
+            &chain_id,
+            TestCase::Outcome {
+                consensus_state,
+                proof,
+            },
+        );
+    }
+}
+
+/// Save the vector of a case which is expected to be accepted, report it if it's not.
+fn save_checked_vector(output_folder: &str, chain_id: &str, case: TestCase) {
+    let result = case.verify();
+    if let Err(err) = result.as_ref() {
+        status_err!(
+            "The {} proof at height {} is rejected: {:?}",
+            case.kind(),
+            case.height().revision_height,
+            err
+        );
+    }
+    let accepted = result.is_ok();
+    save_vector(
+        output_folder,
+        TestVector::new(chain_id.to_string(), case, accepted),
+    );
+}
+
+fn save_vector(output_folder: &str, vector: TestVector) {
+    let path = Path::new(output_folder).join(vector.file_name());
+    std::fs::write(&path, vector.to_bytes()).expect("Failed to save test vector to file.");
+    info_with_time!(
+        "Recorded test vector {} (accepted: {}).",
+        path.display(),
+        vector.accepted
+    );
+}
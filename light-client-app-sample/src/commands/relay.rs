@@ -0,0 +1,74 @@
+//! `relay` subcommand - keep updating state and relay the verified headers to a destination.
+
+use super::start::{prepare_light_client, sync_light_client, StopReason};
+use crate::keystore::Keystore;
+use crate::light_client::near_rpc_client_wrapper::NearRpcClientWrapper;
+use crate::prelude::*;
+use crate::relayer::{Destination, Relayer};
+use crate::shutdown::Shutdown;
+use abscissa_core::{Command, Runnable};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+
+/// `relay` subcommand
+///
+/// After each header is verified and saved, the stored heads above the latest height of
+/// the destination are submitted to it in order.
+#[derive(clap::Parser, Command, Debug)]
+pub struct RelayCmd {}
+
+impl Runnable for RelayCmd {
+    /// Start the application.
+    fn run(&self) {
+        let stop_reason =
+            abscissa_tokio::run(&APP, relay()).expect("Failed to relay NEAR light client.");
+        if stop_reason == StopReason::Error {
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn relay() -> StopReason {
+    let config = APP.config().relay.clone();
+    let destination =
+        match Destination::from_config(&config, &Keystore::from_config(&APP.config().keystore)) {
+            Ok(destination) => destination,
+            Err(err) => {
+                status_err!("Failed to set up relay destination: {:?}", err);
+                return StopReason::Error;
+            }
+        };
+    let shutdown = Shutdown::listen();
+    let rpc_client = Arc::new(NearRpcClientWrapper::from_config(&APP.config().near_rpc));
+    let light_client = match prepare_light_client(&rpc_client, false, &shutdown).await {
+        Some(light_client) => Arc::new(Mutex::new(light_client)),
+        None => return StopReason::Error,
+    };
+    let (heads, _) = broadcast::channel(1);
+    let relayer = {
+        let relayer = Relayer::new(destination, config, light_client.clone());
+        let heads = heads.subscribe();
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            let result = relayer.run(heads, shutdown.clone()).await;
+            // Stop synchronizing if the headers can no longer be relayed
+            shutdown.request();
+            result
+        })
+    };
+    let mut stop_reason =
+        sync_light_client(rpc_client, light_client, heads, shutdown.clone()).await;
+    shutdown.request();
+    match relayer.await {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => {
+            status_err!("{:?}", err);
+            stop_reason = StopReason::Error;
+        }
+        Err(err) => {
+            status_err!("The relayer task failed: {:?}", err);
+            stop_reason = StopReason::Error;
+        }
+    }
+    stop_reason
+}
@@ -0,0 +1,130 @@
+//! `serve` subcommand - serve a REST API over the NEAR light client.
+
+use super::start::{prepare_light_client, sync_light_client, StopReason};
+use crate::config::LightClientAppSampleConfig;
+use crate::grpc;
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::server::{self, ServerState};
+use crate::shutdown::Shutdown;
+use crate::{info_with_time, prelude::*};
+use abscissa_core::{config, Command, FrameworkError, Runnable};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+
+/// The count of head summaries buffered for each subscriber lagging behind.
+const HEADS_CAPACITY: usize = 64;
+
+/// `serve` subcommand
+///
+/// Endpoints:
+///
+/// - `GET /healthz`: whether the process is alive and the store is accessible
+/// - `GET /readyz`: whether the latest head is fresh and the RPC services are reachable
+/// - `GET /head`: summary of the latest head
+/// - `GET /consensus_state/{height}`: summary and data of the head at the height
+/// - `GET /metrics`: metrics in the Prometheus text format
+/// - `GET /heads/ws`: WebSocket streaming the summary of each newly verified head as JSON
+/// - `POST /verify/membership`: verify the value of a storage key of a NEAR account
+/// - `POST /verify/transaction`: verify a transaction
+///
+/// The gRPC service `near.lightclient.app.v1.LightClientService` is served alongside, if
+/// its listen address is configured.
+#[derive(clap::Parser, Command, Debug)]
+pub struct ServeCmd {
+    /// The socket address to listen on, overriding the configured one
+    #[arg(long)]
+    pub listen: Option<String>,
+    /// The socket address to listen on for the gRPC service, overriding the configured one
+    #[arg(long)]
+    pub grpc_listen: Option<String>,
+    /// Only serve the stored heads, without synchronizing the light client
+    #[arg(long)]
+    pub no_sync: bool,
+}
+
+impl Runnable for ServeCmd {
+    /// Start the application.
+    fn run(&self) {
+        let stop_reason = abscissa_tokio::run(&APP, serve(self.no_sync))
+            .expect("Failed to serve NEAR light client.");
+        if stop_reason == StopReason::Error {
+            std::process::exit(1);
+        }
+    }
+}
+
+impl config::Override<LightClientAppSampleConfig> for ServeCmd {
+    fn override_config(
+        &self,
+        mut config: LightClientAppSampleConfig,
+    ) -> Result<LightClientAppSampleConfig, FrameworkError> {
+        if let Some(listen) = self.listen.as_ref() {
+            config.server.listen_address = listen.clone();
+        }
+        if let Some(grpc_listen) = self.grpc_listen.as_ref() {
+            config.server.grpc_listen_address = Some(grpc_listen.clone());
+        }
+        Ok(config)
+    }
+}
+
+async fn serve(no_sync: bool) -> StopReason {
+    let shutdown = Shutdown::listen();
+    let rpc_client = Arc::new(NearRpcClientWrapper::from_config(&APP.config().near_rpc));
+    let light_client = if no_sync {
+        LightClient::from_config(&APP.config().state_data)
+    } else {
+        match prepare_light_client(&rpc_client, false, &shutdown).await {
+            Some(light_client) => light_client,
+            None => return StopReason::Error,
+        }
+    };
+    let light_client = Arc::new(Mutex::new(light_client));
+    let (heads, _) = broadcast::channel(HEADS_CAPACITY);
+    let config = APP.config().server.clone();
+    let state = ServerState {
+        light_client: light_client.clone(),
+        rpc_client: rpc_client.clone(),
+        heads: heads.clone(),
+        config: config.clone(),
+    };
+    let mut servers = Vec::new();
+    if let Some(grpc_listen_address) = config.grpc_listen_address {
+        info_with_time!("Serving the gRPC service on {} ...", grpc_listen_address);
+        servers.push(tokio::spawn(grpc::serve(
+            grpc_listen_address,
+            state.clone(),
+            shutdown.clone(),
+        )));
+    }
+    info_with_time!("Serving the REST API on {} ...", config.listen_address);
+    servers.push(tokio::spawn(server::serve(
+        config.listen_address,
+        server::router(state),
+        shutdown.clone(),
+    )));
+    // Without the sync loop, the servers run until the shutdown is requested
+    let mut stop_reason = if no_sync {
+        StopReason::Shutdown
+    } else {
+        let stop_reason =
+            sync_light_client(rpc_client, light_client, heads, shutdown.clone()).await;
+        // Stop serving the heads which are no longer updated
+        shutdown.request();
+        stop_reason
+    };
+    for server in servers {
+        match server.await {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => {
+                status_err!("{}", err);
+                stop_reason = StopReason::Error;
+            }
+            Err(err) => {
+                status_err!("The server task failed: {:?}", err);
+                stop_reason = StopReason::Error;
+            }
+        }
+    }
+    stop_reason
+}
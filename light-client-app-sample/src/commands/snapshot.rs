@@ -0,0 +1,126 @@
+//! `snapshot` subcommands - to export the state data to a snapshot archive, or to import
+//! it from one.
+
+use std::path::PathBuf;
+
+use crate::light_client::{snapshot::Snapshot, LightClient};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+
+/// `snapshot` subcommands
+#[derive(clap::Subcommand, Command, Debug, Runnable)]
+pub enum SnapshotCmd {
+    /// Pack all stored heads, epoch block producers and metadata into a snapshot archive.
+    Export(ExportSnapshotCmd),
+    /// Store all the data of a snapshot archive, to bootstrap a new instance.
+    Import(ImportSnapshotCmd),
+}
+
+/// `snapshot export` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ExportSnapshotCmd {
+    /// Path of the archive file
+    pub file: PathBuf,
+    /// Compress the archive with zstd
+    #[arg(long)]
+    pub zstd: bool,
+}
+
+/// `snapshot import` subcommand
+#[derive(clap::Parser, Command, Debug)]
+pub struct ImportSnapshotCmd {
+    /// Path of the archive file
+    pub file: PathBuf,
+    /// Import even if the data folder already has heads
+    #[arg(long)]
+    pub force: bool,
+}
+
+impl Runnable for ExportSnapshotCmd {
+    /// Start the application.
+    fn run(&self) {
+        let light_client = LightClient::from_config(&APP.config().state_data);
+        let archive = match light_client
+            .export_snapshot()
+            .and_then(|snapshot| snapshot.to_archive(self.zstd))
+        {
+            Ok(archive) => archive,
+            Err(err) => {
+                status_err!("Failed to export snapshot: {:?}", err);
+                return;
+            }
+        };
+        if let Err(err) = std::fs::write(&self.file, archive) {
+            status_err!(
+                "Failed to write snapshot to {}: {:?}",
+                self.file.display(),
+                err
+            );
+            return;
+        }
+        status_ok!(
+            "Exported",
+            "snapshot of {} heads to {}.",
+            light_client.cached_heights().len(),
+            self.file.display()
+        );
+    }
+}
+
+impl Runnable for ImportSnapshotCmd {
+    /// Start the application.
+    fn run(&self) {
+        let snapshot = match std::fs::read(&self.file)
+            .map_err(|err| format!("{:?}", err))
+            .and_then(|archive| {
+                Snapshot::from_archive(&archive).map_err(|err| format!("{:?}", err))
+            }) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                status_err!(
+                    "Failed to read snapshot from {}: {}",
+                    self.file.display(),
+                    err
+                );
+                return;
+            }
+        };
+        let mut light_client = LightClient::from_config(&APP.config().state_data);
+        match (light_client.chain_id(), &snapshot.chain_id) {
+            (Some(bound_chain_id), Some(chain_id)) if bound_chain_id != *chain_id => {
+                status_err!(
+                    "The data folder is bound to chain '{}', but the snapshot is of chain '{}'.",
+                    bound_chain_id,
+                    chain_id
+                );
+                return;
+            }
+            _ => (),
+        }
+        if !self.force && !light_client.cached_heights().is_empty() {
+            status_err!("The data folder already has heads, use `--force` to import anyway.");
+            return;
+        }
+        if let Err(err) = light_client.import_snapshot(&snapshot) {
+            status_err!("Failed to import snapshot: {:?}", err);
+            return;
+        }
+        status_ok!(
+            "Imported",
+            "snapshot of {} heads, latest height of light client: {}",
+            snapshot.heads.len(),
+            light_client
+                .cached_heights()
+                .last()
+                .map_or(0, |height| *height)
+        );
+    }
+}
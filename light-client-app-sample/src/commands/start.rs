@@ -1,15 +1,26 @@
 //! `start` subcommand - start an instance of NEAR light client.
 
-use crate::config::LightClientAppSampleConfig;
-use crate::light_client::utils::produce_light_client_block;
+use crate::alerting::{HeadProgress, StaleHeadMonitor};
+use crate::config::{LightClientAppSampleConfig, SyncSection};
+use crate::light_client::checkpoint::{block_producers_from_ancestor, fetch_previous_epoch_header};
+use crate::light_client::utils::{produce_light_client_block, produce_merkle_path};
 use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::metrics::metrics;
+use crate::prefetch::PrefetchQueue;
+use crate::server::HeadSummary;
+use crate::shutdown::Shutdown;
 /// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
 /// accessors along with logging macros. Customize as you see fit.
 use crate::{info_with_time, prelude::*};
 use abscissa_core::{config, Command, FrameworkError, Runnable};
-use near_light_client::BasicNearLightClient;
+use near_light_client::near_types::hash::CryptoHash;
+use near_light_client::types::{ConsensusState, SealedHeader};
+use near_light_client::{BasicNearLightClient, HeaderVerificationError, TrustedConsensusState};
 use near_primitives::types::BlockId;
 use near_primitives::views::BlockView;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, watch, Mutex};
 
 /// `start` subcommand
 ///
@@ -19,13 +30,20 @@ use near_primitives::views::BlockView;
 ///
 /// <https://docs.rs/clap/>
 #[derive(clap::Parser, Command, Debug)]
-pub struct StartCmd {}
+pub struct StartCmd {
+    /// Verify the headers and report the results, but never write to the store
+    #[arg(long)]
+    pub dry_run: bool,
+}
 
 impl Runnable for StartCmd {
     /// Start the application.
     fn run(&self) {
-        abscissa_tokio::run(&APP, start_light_client())
+        let stop_reason = abscissa_tokio::run(&APP, start_light_client(self.dry_run))
             .expect("Failed to start NEAR light client.");
+        if stop_reason == StopReason::Error {
+            std::process::exit(1);
+        }
     }
 }
 
@@ -41,64 +59,741 @@ impl config::Override<LightClientAppSampleConfig> for StartCmd {
     }
 }
 
-async fn start_light_client() {
-    let rpc_client = NearRpcClientWrapper::new(APP.config().near_rpc.rpc_endpoint.as_str());
-    let mut light_client = LightClient::new(APP.config().state_data.data_folder.clone());
+/// Why the light client stopped.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum StopReason {
+    /// SIGINT or SIGTERM received
+    Shutdown,
+    /// The sync loop failed
+    Error,
+}
+
+async fn start_light_client(dry_run: bool) -> StopReason {
+    let shutdown = Shutdown::listen();
+    let rpc_client = Arc::new(NearRpcClientWrapper::from_config(&APP.config().near_rpc));
+    match prepare_light_client(&rpc_client, dry_run, &shutdown).await {
+        Some(light_client) => {
+            let (heads, _) = broadcast::channel(1);
+            sync_light_client(
+                rpc_client,
+                Arc::new(Mutex::new(light_client)),
+                heads,
+                shutdown,
+            )
+            .await
+        }
+        None => StopReason::Error,
+    }
+}
+
+/// Open the light client, check it against the chain served by RPC, and fast sync it if
+/// it lags far behind. Returns `None` if it fails, which is reported.
+pub(crate) async fn prepare_light_client(
+    rpc_client: &NearRpcClientWrapper,
+    dry_run: bool,
+    shutdown: &Shutdown,
+) -> Option<LightClient> {
+    let mut light_client = LightClient::from_config(&APP.config().state_data)
+        .with_fast_finality_check(APP.config().sync.fast_finality_check);
+    //
+    // Without a trusted head, there is nothing to verify the headers returned by RPC with
+    //
+    if light_client.oldest_height().is_none() {
+        status_err!(
+            "Uninitialized NEAR light client, run `init <block>` with a trusted block first."
+        );
+        return None;
+    }
+    if dry_run {
+        light_client = light_client.dry_run();
+        info_with_time!(
+            "Dry run from height {}, nothing is written to the store.",
+            light_client.latest_height().revision_height
+        );
+    }
+    //
+    // Refuse to mix the data of different chains in the data folder
+    //
+    let chain_id = rpc_client
+        .view_chain_id()
+        .await
+        .expect("Failed to get chain id from RPC.");
+    match light_client.chain_id() {
+        Some(bound_chain_id) if bound_chain_id != chain_id => {
+            status_err!(
+                "The data folder is bound to chain '{}', but the RPC endpoint serves chain '{}'.",
+                bound_chain_id,
+                chain_id
+            );
+            return None;
+        }
+        Some(_) => (),
+        None => {
+            if let Err(err) = light_client.set_chain_id(&chain_id) {
+                status_err!(
+                    "Failed to bind the data folder to chain '{}': {:?}",
+                    chain_id,
+                    err
+                );
+                return None;
+            }
+        }
+    }
+    //
+    // Restore the block producers of the latest head, if they are pruned with older heads
+    //
+    match light_client.restore_block_producers() {
+        Ok(true) => info_with_time!(
+            "Restored block producers of the head at height {}.",
+            light_client.latest_height().revision_height
+        ),
+        Ok(false) => (),
+        Err(err) => {
+            status_err!("Failed to restore block producers: {:?}", err);
+            return None;
+        }
+    }
+    //
+    // Catch up by the epoch boundary headers first, if the light client lags far behind
+    //
+    let sync = APP.config().sync.clone();
+    if sync.fast_sync_lag_heights > 0 {
+        match fast_sync(rpc_client, &mut light_client, &sync, shutdown).await {
+            Ok(0) => (),
+            Ok(count) => info_with_time!("Fast synced {} epoch boundary headers.", count),
+            Err(err) => {
+                status_err!("Failed to fast sync: {:?}", err);
+                return None;
+            }
+        }
+    }
+    Some(light_client)
+}
+
+/// Keep synchronizing the light client until the shutdown is requested or it fails.
+///
+/// The light client is only locked to read the trusted head and to save a verified header,
+/// not while a header is fetched or verified, and the summary of each saved head is sent
+/// to `heads`.
+pub(crate) async fn sync_light_client(
+    rpc_client: Arc<NearRpcClientWrapper>,
+    shared_light_client: Arc<Mutex<LightClient>>,
+    heads: broadcast::Sender<HeadSummary>,
+    shutdown: Shutdown,
+) -> StopReason {
+    let sync = APP.config().sync.clone();
+    let fallback_rpc_client = APP
+        .config()
+        .near_rpc
+        .fallback_rpc_endpoint
+        .as_deref()
+        .map(NearRpcClientWrapper::new);
     //
     // Keep updating state and save state to file
     //
-    let latest_height = match light_client.latest_height() {
+    let latest_height = match shared_light_client
+        .lock()
+        .await
+        .latest_height()
+        .revision_height
+    {
         0 => None,
         height => Some(height),
     };
-    let mut block_view = get_block(&rpc_client, &latest_height).await;
-    let mut should_break = false;
-    while !should_break {
-        let light_client_block_view = rpc_client
-            .get_next_light_client_block(&block_view.header.hash)
-            .await
-            .expect("Failed to get next light client block.");
-        block_view = get_block(
-            &rpc_client,
-            &Some(light_client_block_view.inner_lite.height),
+    metrics()
+        .latest_verified_height
+        .set(latest_height.unwrap_or(0) as i64);
+    metrics()
+        .stored_heads
+        .set(shared_light_client.lock().await.head_count() as i64);
+    let mut backoff = Backoff::new(sync.backoff_ms, sync.max_backoff_ms);
+    let block_view = match get_block(&rpc_client, &latest_height, &mut backoff, &shutdown).await {
+        Some(block_view) => block_view,
+        None => return StopReason::Shutdown,
+    };
+    //
+    // Prefetch the following headers while verifying and saving the current one
+    //
+    let mut prefetched = prefetch_headers(&rpc_client, block_view, &sync, &shutdown);
+    //
+    // Alert if the latest verified head falls behind
+    //
+    let (progress, progress_receiver) =
+        watch::channel(head_progress(&*shared_light_client.lock().await));
+    let monitor = tokio::spawn(
+        StaleHeadMonitor::new(
+            APP.config().alerting.clone(),
+            rpc_client.clone(),
+            progress_receiver,
         )
-        .await;
-        let header = produce_light_client_block(&light_client_block_view, &block_view);
-        let current_cs = light_client.get_consensus_state(&light_client.latest_height());
-        let current_bps = match current_cs {
-            Some(cs) => cs.get_block_producers_of(&header.epoch_id()),
-            None => None,
+        .run(shutdown.clone()),
+    );
+    let mut stop_reason = StopReason::Shutdown;
+    while let Some(fetched) = prefetched.recv().await {
+        if shutdown.is_requested() {
+            break;
+        }
+        let FetchedHeader {
+            prev_block_hash,
+            mut header,
+            block_view,
+        } = fetched;
+        //
+        // Read the trusted head under a short lock, which is released while the header is
+        // fetched and verified, so that the readers of the light client aren't blocked
+        //
+        let (current_cs, fast_finality_check) = {
+            let light_client = shared_light_client.lock().await;
+            (
+                light_client.get_consensus_state(&light_client.latest_height()),
+                light_client.fast_finality_check(),
+            )
         };
-        if current_bps.is_some() {
-            if let Err(err) = light_client.verify_header(&header) {
+        let mut current_cs = match current_cs {
+            Some(current_cs) => current_cs,
+            None => {
                 status_err!(
-                    "Failed to verify header at height {}: {:?}",
-                    header.height(),
-                    err
+                    "Missing the latest head to verify header at height {}.",
+                    header.height().revision_height
                 );
-                should_break = true;
-            } else {
+                stop_reason = StopReason::Error;
+                break;
+            }
+        };
+        if current_cs.block_producers_of(&header.epoch_id()).is_none() {
+            match recover_block_producers(&rpc_client, &shared_light_client, &current_cs, &header)
+                .await
+            {
+                Ok(restored_cs) => {
+                    info_with_time!(
+                        "Recovered block producers of epoch {} from the previous epoch.",
+                        header.epoch_id()
+                    );
+                    if let Some(restored_cs) = restored_cs {
+                        current_cs = restored_cs;
+                    }
+                }
+                Err(err) => status_err!(
+                    "Failed to recover block producers of epoch {}: {:?}",
+                    header.epoch_id(),
+                    err
+                ),
+            }
+        }
+        if current_cs.block_producers_of(&header.epoch_id()).is_none() {
+            status_err!(
+                "Missing block producers of epoch {} to verify header at height {}.",
+                header.epoch_id(),
+                header.height().revision_height
+            );
+            stop_reason = StopReason::Error;
+            break;
+        }
+        let trusted =
+            TrustedConsensusState::new(current_cs).with_fast_finality_check(fast_finality_check);
+        let fetched_block_hash = header.hash();
+        match verify_header_with_retries(
+            &rpc_client,
+            fallback_rpc_client.as_ref(),
+            &trusted,
+            &prev_block_hash,
+            (header, block_view),
+            sync.verify_retries,
+            &mut backoff,
+            &shutdown,
+        )
+        .await
+        {
+            Ok((verified_header, verified_block_view)) => {
+                header = verified_header;
                 info_with_time!(
                     "Successfully verified header at height {}.",
-                    header.height()
+                    header.height().revision_height
                 );
+                // A retry may re-fetch another block, which the prefetched headers don't follow
+                if header.hash() != fetched_block_hash {
+                    let discarded = prefetched.cancel();
+                    info_with_time!(
+                        "Discarded {} prefetched headers following block {}, prefetch again from block {}.",
+                        discarded,
+                        fetched_block_hash,
+                        header.hash()
+                    );
+                    prefetched =
+                        prefetch_headers(&rpc_client, verified_block_view, &sync, &shutdown);
+                }
+            }
+            Err((failed_header, err)) => {
+                let height = failed_header.height().revision_height;
+                status_err!("Failed to verify header at height {}: {:?}", height, err);
+                let discarded = prefetched.cancel();
+                status_err!(
+                    "Discarded {} prefetched headers following the header at height {}.",
+                    discarded,
+                    height
+                );
+                let current_bps = trusted
+                    .consensus_state()
+                    .block_producers_of(&failed_header.epoch_id())
+                    .cloned();
+                let failed_head = ConsensusState::new(current_bps, failed_header.into_header());
+                let saved = shared_light_client
+                    .lock()
+                    .await
+                    .save_failed_head(failed_head);
+                match saved {
+                    Ok(()) => status_err!(
+                        "Quarantined header at height {} to the failed heads.",
+                        height
+                    ),
+                    Err(err) => {
+                        status_err!("Failed to save failed head at height {}: {:?}", height, err)
+                    }
+                }
+                stop_reason = StopReason::Error;
+                break;
             }
-        } else {
-            info_with_time!("Skip verifying header at height {}.", header.height());
         }
-        light_client.update_state(header);
         //
-        while light_client.cached_heights().len()
-            > APP.config().state_data.max_cached_heights as usize
-        {
-            light_client.remove_oldest_head();
+        // Lock the light client again to save the verified header
+        //
+        let mut light_client = shared_light_client.lock().await;
+        let height = header.height().revision_height;
+        if let Err(err) = light_client.update_state(header) {
+            status_err!("Failed to save head at height {}: {:?}", height, err);
+            stop_reason = StopReason::Error;
+            break;
+        }
+        progress.send_replace(head_progress(&light_client));
+        metrics().latest_verified_height.set(height as i64);
+        if let Some(head) = light_client.get_consensus_state(&light_client.latest_height()) {
+            // No receiver is not an error, as there is no subscriber without a server
+            let _ = heads.send(HeadSummary::of(&head));
+        }
+        //
+        match light_client.prune(&APP.config().state_data.pruning, false) {
+            Ok(pruned) => metrics().pruned_heads.inc_by(pruned.len() as u64),
+            Err(err) => status_err!("Failed to prune heads: {:?}", err),
         }
+        metrics().stored_heads.set(light_client.head_count() as i64);
     }
+    prefetched.cancel();
+    monitor.abort();
+    //
+    // Record the last verified height and persist all pending writes
+    //
+    let mut light_client = shared_light_client.lock().await;
+    if let Err(err) = light_client.flush() {
+        status_err!("Failed to flush state data: {:?}", err);
+        return StopReason::Error;
+    }
+    info_with_time!(
+        "Stopped at height {} ({:?}).",
+        light_client.latest_height().revision_height,
+        stop_reason
+    );
+    stop_reason
 }
 
-async fn get_block(rpc_client: &NearRpcClientWrapper, height: &Option<u64>) -> BlockView {
-    rpc_client
-        .view_block(&height.map(|height| BlockId::Height(height)))
+async fn verify_header(
+    rpc_client: &NearRpcClientWrapper,
+    trusted: &TrustedConsensusState,
+    header: &SealedHeader,
+) -> Result<(), HeaderVerificationError> {
+    if !APP.config().sync.verify_chained {
+        return trusted.verify_sealed_header(header);
+    }
+    let trusted_block_hash = trusted
+        .consensus_state()
+        .header
+        .light_client_block
+        .current_block_hash();
+    if header.light_client_block.prev_block_hash == trusted_block_hash {
+        return trusted.verify_sealed_header_chained(header, None);
+    }
+    let block_proof = rpc_client
+        .get_light_client_block_proof(
+            &near_primitives::hash::CryptoHash(trusted_block_hash.0),
+            &near_primitives::hash::CryptoHash(header.hash().0),
+        )
         .await
-        .expect(format!("Failed to get block at height {:?}.", height).as_str())
+        .map(|response| produce_merkle_path(&response.block_proof));
+    match block_proof {
+        Ok(block_proof) => trusted.verify_sealed_header_chained(header, Some(&block_proof)),
+        Err(err) => {
+            status_err!(
+                "Failed to get block proof of {}: {:?}",
+                trusted_block_hash,
+                err
+            );
+            trusted.verify_sealed_header_chained(header, None)
+        }
+    }
+}
+
+/// Verify and save the last header of each epoch following the latest head, while the
+/// light client lags more than `fast_sync_lag_heights` behind the chain head and the
+/// chain head is at least two epochs ahead, returns the count of saved headers.
+///
+/// The last header of an epoch carries the block producers of the next epoch, so the
+/// epochs can be verified one by one without the headers in between.
+async fn fast_sync(
+    rpc_client: &NearRpcClientWrapper,
+    light_client: &mut LightClient,
+    sync: &SyncSection,
+    shutdown: &Shutdown,
+) -> anyhow::Result<u64> {
+    let mut count = 0;
+    while !shutdown.is_requested() {
+        let chain_head = rpc_client.view_block(&None).await?;
+        let latest_head = light_client
+            .get_consensus_state(&light_client.latest_height())
+            .ok_or_else(|| anyhow::anyhow!("Missing the latest head."))?;
+        let latest_header = &latest_head.header;
+        let lag = chain_head
+            .header
+            .height
+            .saturating_sub(latest_header.height().revision_height);
+        let chain_head_epoch_id = CryptoHash(chain_head.header.epoch_id.0);
+        if lag <= sync.fast_sync_lag_heights
+            || chain_head_epoch_id == latest_header.epoch_id()
+            || chain_head_epoch_id == latest_header.next_epoch_id()
+        {
+            break;
+        }
+        // The next light client block of a block, which is two epochs or more behind the
+        // chain head, is the last final block of the next epoch.
+        let latest_block_hash = latest_header.light_client_block.current_block_hash();
+        let (header, _) = fetch_header(
+            rpc_client,
+            &near_primitives::hash::CryptoHash(latest_block_hash.0),
+        )
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("No light client block of the next epoch."))?;
+        if header.epoch_id() != latest_header.next_epoch_id() {
+            info_with_time!(
+                "Header at height {} is not at an epoch boundary, stop fast syncing.",
+                header.height().revision_height
+            );
+            break;
+        }
+        light_client.verify_sealed_header(&header).map_err(|err| {
+            anyhow::anyhow!(
+                "Failed to verify header at height {}: {:?}",
+                header.height().revision_height,
+                err
+            )
+        })?;
+        let height = header.height().revision_height;
+        light_client.update_state(header).map_err(|err| {
+            anyhow::anyhow!("Failed to save head at height {}: {:?}", height, err)
+        })?;
+        light_client
+            .prune(&APP.config().state_data.pruning, false)
+            .map_err(|err| anyhow::anyhow!("Failed to prune heads: {:?}", err))?;
+        info_with_time!("Fast synced epoch boundary header at height {}.", height);
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Returns the progress of the latest head of `light_client`.
+fn head_progress(light_client: &LightClient) -> HeadProgress {
+    light_client
+        .get_consensus_state(&light_client.latest_height())
+        .map_or(HeadProgress::default(), |head| HeadProgress {
+            height: head.header.height().revision_height,
+            timestamp: head.header.light_client_block.inner_lite.timestamp,
+        })
+}
+
+/// Spawn the fetching of the headers following `block_view` ahead of their verification.
+fn prefetch_headers(
+    rpc_client: &Arc<NearRpcClientWrapper>,
+    block_view: BlockView,
+    sync: &SyncSection,
+    shutdown: &Shutdown,
+) -> PrefetchQueue<FetchedHeader> {
+    PrefetchQueue::spawn(sync.prefetch_depth as usize, |sender| {
+        fetch_headers(
+            rpc_client.clone(),
+            block_view,
+            sender,
+            sync.clone(),
+            shutdown.clone(),
+        )
+    })
+}
+
+/// A header fetched ahead of its verification.
+struct FetchedHeader {
+    /// Hash of the block the header is fetched as the next light client block of
+    prev_block_hash: near_primitives::hash::CryptoHash,
+    header: SealedHeader,
+    block_view: BlockView,
+}
+
+/// Keep fetching the headers following `block_view` in batches, and sending them to the
+/// verifier, until the shutdown is requested or the verifier stops receiving.
+///
+/// The sending waits if the verifier lags `prefetch_depth` headers behind.
+async fn fetch_headers(
+    rpc_client: Arc<NearRpcClientWrapper>,
+    mut block_view: BlockView,
+    sender: mpsc::Sender<FetchedHeader>,
+    sync: SyncSection,
+    shutdown: Shutdown,
+) {
+    let mut backoff = Backoff::new(sync.backoff_ms, sync.max_backoff_ms);
+    'fetch: while !shutdown.is_requested() {
+        for _ in 0..sync.batch_size.max(1) {
+            if shutdown.is_requested() {
+                break 'fetch;
+            }
+            let prev_block_hash = block_view.header.hash;
+            match fetch_header(&rpc_client, &prev_block_hash).await {
+                Ok(Some((header, fetched_block_view))) => {
+                    backoff.reset();
+                    block_view = fetched_block_view.clone();
+                    let fetched = FetchedHeader {
+                        prev_block_hash,
+                        header,
+                        block_view: fetched_block_view,
+                    };
+                    if sender.send(fetched).await.is_err() {
+                        break 'fetch;
+                    }
+                }
+                Ok(None) => {
+                    info_with_time!(
+                        "No new light client block, retry in {} ms.",
+                        backoff.current_ms()
+                    );
+                    backoff.wait(&shutdown).await;
+                    continue 'fetch;
+                }
+                Err(err) => {
+                    status_err!(
+                        "Failed to get next light client block, retry in {} ms: {:?}",
+                        backoff.current_ms(),
+                        err
+                    );
+                    backoff.wait(&shutdown).await;
+                    continue 'fetch;
+                }
+            }
+        }
+        //
+        // Fetch the next batch immediately if the fetched headers lag behind too much
+        //
+        match rpc_client.view_block(&None).await {
+            Ok(chain_head) => {
+                let lag = chain_head
+                    .header
+                    .height
+                    .saturating_sub(block_view.header.height);
+                if lag > sync.max_lag_heights {
+                    info_with_time!("Light client lags {} heights behind chain head.", lag);
+                    continue;
+                }
+            }
+            Err(err) => status_err!("Failed to get chain head: {:?}", err),
+        }
+        shutdown
+            .sleep(Duration::from_millis(sync.poll_interval_ms))
+            .await;
+    }
+}
+
+/// Recover the block producers of the epoch of `header` from the last header of the
+/// previous epoch, which is proved to be an ancestor of `latest_head`.
+///
+/// The light client is only locked to save the recovered block producers, returns the
+/// latest head if the block producers are restored to it.
+async fn recover_block_producers(
+    rpc_client: &NearRpcClientWrapper,
+    shared_light_client: &Mutex<LightClient>,
+    latest_head: &ConsensusState,
+    header: &SealedHeader,
+) -> anyhow::Result<Option<ConsensusState>> {
+    let previous_epoch_header = fetch_previous_epoch_header(
+        rpc_client,
+        &near_primitives::hash::CryptoHash(header.hash().0),
+    )
+    .await?;
+    let block_proof = rpc_client
+        .get_light_client_block_proof(
+            &near_primitives::hash::CryptoHash(
+                previous_epoch_header
+                    .light_client_block
+                    .current_block_hash()
+                    .0,
+            ),
+            &near_primitives::hash::CryptoHash(
+                latest_head.header.light_client_block.current_block_hash().0,
+            ),
+        )
+        .await?;
+    let bps = block_producers_from_ancestor(
+        latest_head,
+        &previous_epoch_header,
+        &produce_merkle_path(&block_proof.block_proof),
+    )
+    .map_err(|err| anyhow::anyhow!("Invalid block producers: {:?}", err))?;
+    let mut light_client = shared_light_client.lock().await;
+    let restored = light_client
+        .recover_block_producers(&header.epoch_id(), &bps)
+        .map_err(|err| anyhow::anyhow!("Failed to save block producers: {:?}", err))?;
+    Ok(restored
+        .then(|| light_client.get_consensus_state(&light_client.latest_height()))
+        .flatten())
+}
+
+/// Verify `header`, and re-fetch it and its block from the fallback RPC endpoint (or from
+/// `rpc_client` if there isn't one) to verify again, at most `retries` times, in case
+/// the failure is caused by a stale or bad RPC response.
+///
+/// Returns the verified header and its block, or the last header failed to verify and
+/// the error.
+#[allow(clippy::too_many_arguments)]
+async fn verify_header_with_retries(
+    rpc_client: &NearRpcClientWrapper,
+    fallback_rpc_client: Option<&NearRpcClientWrapper>,
+    trusted: &TrustedConsensusState,
+    prev_block_hash: &near_primitives::hash::CryptoHash,
+    (mut header, mut block_view): (SealedHeader, BlockView),
+    retries: u64,
+    backoff: &mut Backoff,
+    shutdown: &Shutdown,
+) -> Result<(SealedHeader, BlockView), (SealedHeader, HeaderVerificationError)> {
+    let retry_rpc_client = fallback_rpc_client.unwrap_or(rpc_client);
+    let mut current_rpc_client = rpc_client;
+    let mut attempts = 0;
+    loop {
+        let timer = metrics().header_verification_seconds.start_timer();
+        let result = verify_header(current_rpc_client, trusted, &header).await;
+        timer.observe_duration();
+        let err = match result {
+            Ok(()) => {
+                metrics().header_signatures.observe(
+                    header
+                        .light_client_block
+                        .approvals_after_next
+                        .iter()
+                        .filter(|approval| approval.is_some())
+                        .count() as f64,
+                );
+                return Ok((header, block_view));
+            }
+            Err(err) => err,
+        };
+        if attempts >= retries || shutdown.is_requested() {
+            return Err((header, err));
+        }
+        attempts += 1;
+        status_err!(
+            "Failed to verify header at height {}, re-fetch it from {} in {} ms ({}/{}): {:?}",
+            header.height().revision_height,
+            retry_rpc_client.rpc_addr,
+            backoff.current_ms(),
+            attempts,
+            retries,
+            err
+        );
+        backoff.wait(shutdown).await;
+        current_rpc_client = retry_rpc_client;
+        match fetch_header(current_rpc_client, prev_block_hash).await {
+            Ok(Some(fetched)) => (header, block_view) = fetched,
+            Ok(None) => status_err!("No light client block re-fetched, verify the same header."),
+            Err(err) => status_err!(
+                "Failed to re-fetch light client block, verify the same header: {:?}",
+                err
+            ),
+        }
+    }
+}
+
+/// Fetch the next light client block of `prev_block_hash` and its block, returns `None`
+/// if there is no new light client block.
+async fn fetch_header(
+    rpc_client: &NearRpcClientWrapper,
+    prev_block_hash: &near_primitives::hash::CryptoHash,
+) -> anyhow::Result<Option<(SealedHeader, BlockView)>> {
+    let light_client_block_view = match rpc_client
+        .try_get_next_light_client_block(prev_block_hash)
+        .await?
+    {
+        Some(light_client_block_view) => light_client_block_view,
+        None => return Ok(None),
+    };
+    let block_view = rpc_client
+        .view_block(&Some(BlockId::Height(
+            light_client_block_view.inner_lite.height,
+        )))
+        .await?;
+    let header = produce_light_client_block(&light_client_block_view, &block_view).seal();
+    Ok(Some((header, block_view)))
+}
+
+/// Get the block at `height` (or the latest block if it is `None`), retrying with `backoff`
+/// until it succeeds. Returns `None` if the shutdown is requested before that.
+async fn get_block(
+    rpc_client: &NearRpcClientWrapper,
+    height: &Option<u64>,
+    backoff: &mut Backoff,
+    shutdown: &Shutdown,
+) -> Option<BlockView> {
+    while !shutdown.is_requested() {
+        match rpc_client
+            .view_block(&height.map(|height| BlockId::Height(height)))
+            .await
+        {
+            Ok(block_view) => {
+                backoff.reset();
+                return Some(block_view);
+            }
+            Err(err) => {
+                status_err!(
+                    "Failed to get block at height {:?}, retry in {} ms: {:?}",
+                    height,
+                    backoff.current_ms(),
+                    err
+                );
+                backoff.wait(shutdown).await;
+            }
+        }
+    }
+    None
+}
+
+/// Exponential backoff after empty responses or RPC failures.
+struct Backoff {
+    initial_ms: u64,
+    max_ms: u64,
+    current_ms: u64,
+}
+
+impl Backoff {
+    fn new(initial_ms: u64, max_ms: u64) -> Self {
+        Self {
+            initial_ms,
+            max_ms,
+            current_ms: initial_ms,
+        }
+    }
+
+    fn current_ms(&self) -> u64 {
+        self.current_ms
+    }
+
+    fn reset(&mut self) {
+        self.current_ms = self.initial_ms;
+    }
+
+    /// Wait for the current backoff, or until the shutdown is requested, and double it
+    /// for the next time.
+    async fn wait(&mut self, shutdown: &Shutdown) {
+        shutdown.sleep(Duration::from_millis(self.current_ms)).await;
+        self.current_ms = self.current_ms.saturating_mul(2).min(self.max_ms);
+    }
 }
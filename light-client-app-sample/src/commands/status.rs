@@ -0,0 +1,214 @@
+//! `status` subcommand - to print a summary of the sync health of the light client.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::OutputFormat;
+use crate::config::StorageBackend;
+use crate::light_client::forensics::trace_verification;
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::output::{output_format, print_json};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::{near_types::hash::CryptoHash, BasicNearLightClient};
+use serde::Serialize;
+
+/// `status` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct StatusCmd {}
+
+/// Summary of the sync health of the light client.
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    /// Height of the latest verified head, 0 if there is no head
+    pub latest_height: u64,
+    /// Block hash of the latest verified head
+    pub latest_block_hash: Option<CryptoHash>,
+    /// Height of the chain head reported by RPC
+    pub chain_tip_height: Option<u64>,
+    /// Count of heights the latest verified head lags behind the chain head
+    pub lag_heights: Option<u64>,
+    /// Seconds the latest verified head lags behind the chain head by their timestamps
+    pub lag_secs: Option<u64>,
+    /// Age of the latest verified head in seconds
+    pub head_age_secs: Option<u64>,
+    /// Height of the oldest stored head
+    pub oldest_height: Option<u64>,
+    /// Count of the stored heads
+    pub stored_heads: usize,
+    /// Count of the epochs with stored block producers
+    pub cached_epochs: usize,
+    /// The storage backend of state data
+    pub store_backend: StorageBackend,
+    /// Size in bytes of the data folder
+    pub store_size_bytes: u64,
+    /// Height of the last failed head
+    pub last_failed_height: Option<u64>,
+    /// The error of the verification of the last failed head
+    pub last_verification_error: Option<String>,
+    /// The error of querying the chain head from RPC
+    pub rpc_error: Option<String>,
+}
+
+impl Runnable for StatusCmd {
+    /// Start the application.
+    fn run(&self) {
+        let report = abscissa_tokio::run(&APP, collect_status())
+            .expect("Failed to collect status of NEAR light client.");
+        match output_format() {
+            OutputFormat::Human => print_report(&report),
+            OutputFormat::Json => print_json(&report),
+        }
+    }
+}
+
+async fn collect_status() -> StatusReport {
+    let config = APP.config().state_data.clone();
+    let light_client = LightClient::from_config(&config);
+    let latest_head = light_client.get_consensus_state(&light_client.latest_height());
+    let latest_timestamp = latest_head
+        .as_ref()
+        .map(|head| head.header.light_client_block.inner_lite.timestamp);
+    let latest_height = light_client.latest_height().revision_height;
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let (chain_tip, rpc_error) = match rpc_client.view_block(&None).await {
+        Ok(block_view) => (Some(block_view.header), None),
+        Err(err) => (None, Some(format!("{:?}", err))),
+    };
+    let (last_failed_height, last_verification_error) = last_failure(&light_client);
+    StatusReport {
+        latest_height,
+        latest_block_hash: latest_head
+            .as_ref()
+            .map(|head| head.header.light_client_block.current_block_hash()),
+        chain_tip_height: chain_tip.as_ref().map(|tip| tip.height),
+        lag_heights: match (&latest_head, &chain_tip) {
+            (Some(_), Some(tip)) => Some(tip.height.saturating_sub(latest_height)),
+            _ => None,
+        },
+        lag_secs: match (latest_timestamp, &chain_tip) {
+            (Some(timestamp), Some(tip)) => {
+                Some(tip.timestamp_nanosec.saturating_sub(timestamp) / 1_000_000_000)
+            }
+            _ => None,
+        },
+        head_age_secs: latest_timestamp.map(|timestamp| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_nanos() as u64);
+            now.saturating_sub(timestamp) / 1_000_000_000
+        }),
+        oldest_height: light_client.oldest_height(),
+        stored_heads: light_client.head_count(),
+        cached_epochs: light_client.stored_epoch_ids().len(),
+        store_backend: config.backend,
+        store_size_bytes: folder_size(Path::new(&config.data_folder)),
+        last_failed_height,
+        last_verification_error,
+        rpc_error,
+    }
+}
+
+/// Returns the height of the last failed head, and the error of re-verifying it with the
+/// stored head below it.
+fn last_failure(light_client: &LightClient) -> (Option<u64>, Option<String>) {
+    let height = match light_client.failed_heights() {
+        Ok(heights) => match heights.iter().max() {
+            Some(height) => *height,
+            None => return (None, None),
+        },
+        Err(err) => {
+            return (
+                None,
+                Some(format!("Failed to list failed heads: {:?}", err)),
+            )
+        }
+    };
+    let error = match (
+        light_client.get_failed_head(height),
+        light_client.get_head_before(height),
+    ) {
+        (Ok(Some(failed_head)), Ok(Some(trusted_head))) => {
+            match trace_verification(&trusted_head, &failed_head.header).result {
+                Ok(()) => None,
+                Err(err) => Some(format!("{:?}", err)),
+            }
+        }
+        (Ok(None), _) => Some("The failed head is missing.".to_owned()),
+        (_, Ok(None)) => Some("There is no stored head below the failed head.".to_owned()),
+        (Err(err), _) | (_, Err(err)) => Some(format!("Failed to read heads: {:?}", err)),
+    };
+    (Some(height), error)
+}
+
+/// Returns the total size of the files in `path` recursively.
+fn folder_size(path: &Path) -> u64 {
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| folder_size(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+fn print_report(report: &StatusReport) {
+    let or_unknown = |value: Option<u64>| value.map_or("unknown".to_owned(), |v| v.to_string());
+    match report.latest_block_hash.as_ref() {
+        Some(hash) => status_info!(
+            "Head",
+            "latest verified height {} ({}), {} seconds old",
+            report.latest_height,
+            hash,
+            or_unknown(report.head_age_secs)
+        ),
+        None => status_info!("Head", "no verified head"),
+    }
+    match report.rpc_error.as_ref() {
+        Some(err) => status_err!("Failed to get chain head from RPC: {}", err),
+        None => status_info!(
+            "Chain",
+            "tip height {}, lagging {} blocks / {} seconds",
+            or_unknown(report.chain_tip_height),
+            or_unknown(report.lag_heights),
+            or_unknown(report.lag_secs)
+        ),
+    }
+    status_info!(
+        "Store",
+        "{:?} backend, {} bytes, {} heads from height {}, {} epochs",
+        report.store_backend,
+        report.store_size_bytes,
+        report.stored_heads,
+        or_unknown(report.oldest_height),
+        report.cached_epochs
+    );
+    match (
+        report.last_failed_height,
+        report.last_verification_error.as_ref(),
+    ) {
+        (Some(height), Some(err)) => {
+            status_err!("Last failed head at height {}: {}", height, err)
+        }
+        (Some(height), None) => status_info!(
+            "Failed",
+            "last failed head at height {} verifies now",
+            height
+        ),
+        (None, Some(err)) => status_err!("{}", err),
+        (None, None) => status_ok!("Healthy", "no failed head"),
+    }
+}
@@ -0,0 +1,168 @@
+//! `verify-event` subcommand
+//! Verify the outcome of a transaction or receipt with the latest light client head, and
+//! check that a certain NEP-297 event is in its proven logs.
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use super::verify_transaction::verify_outcome;
+use crate::output::{is_json, VerificationReport};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::hash::CryptoHash;
+use near_primitives::types::{AccountId, TransactionOrReceiptId};
+use serde_json::Value;
+
+/// Prefix of the logs of NEP-297 events.
+const EVENT_LOG_PREFIX: &str = "EVENT_JSON:";
+
+/// `verify-event` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct VerifyEventCmd {
+    /// base58 formatted transaction hash, or receipt id with `--receipt`
+    pub id: String,
+    /// Account id of transaction sender, or of receipt receiver with `--receipt`
+    pub account_id: String,
+    /// Take the id as a receipt id, the events of a contract call are in the outcome of
+    /// its receipt
+    #[arg(long)]
+    pub receipt: bool,
+    /// Expected standard of the event, e.g. `nep141`
+    #[arg(long)]
+    pub standard: String,
+    /// Expected name of the event, e.g. `ft_transfer`
+    #[arg(long)]
+    pub event: String,
+    /// JSON which the data of the event must contain: an object matches an object with
+    /// matching values of all its keys, an array matches an array with a matching element
+    /// for each of its elements, and other values match equal values
+    #[arg(long)]
+    pub data: Option<String>,
+    /// base58 formatted block hash of the stored head to verify with, the latest head
+    /// if omitted
+    #[arg(long)]
+    pub head_hash: Option<String>,
+}
+
+impl Runnable for VerifyEventCmd {
+    /// Start the application.
+    fn run(&self) {
+        let verified = abscissa_tokio::run(&APP, verify_event(self))
+            .expect("Failed to print status of NEAR light client.");
+        if !verified {
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn verify_event(cmd: &VerifyEventCmd) -> bool {
+    let id = match bs58::decode(cmd.id.clone())
+        .into_vec()
+        .ok()
+        .and_then(|bytes| CryptoHash::try_from(bytes.as_ref()).ok())
+    {
+        Some(id) => id,
+        None => {
+            VerificationReport::failed(format!(
+                "Invalid transaction hash or receipt id '{}'.",
+                cmd.id
+            ))
+            .print();
+            return false;
+        }
+    };
+    let account_id = match AccountId::from_str(cmd.account_id.as_str()) {
+        Ok(account_id) => account_id,
+        Err(err) => {
+            VerificationReport::failed(format!("Invalid account id '{}': {}", cmd.account_id, err))
+                .print();
+            return false;
+        }
+    };
+    let data = match cmd
+        .data
+        .as_ref()
+        .map(|data| serde_json::from_str::<Value>(data))
+    {
+        Some(Ok(data)) => Some(data),
+        Some(Err(err)) => {
+            VerificationReport::failed(format!("Invalid JSON of the event data: {}", err)).print();
+            return false;
+        }
+        None => None,
+    };
+    let outcome_id = if cmd.receipt {
+        TransactionOrReceiptId::Receipt {
+            receipt_id: near_primitives::hash::CryptoHash(id.0),
+            receiver_id: account_id,
+        }
+    } else {
+        TransactionOrReceiptId::Transaction {
+            transaction_hash: near_primitives::hash::CryptoHash(id.0),
+            sender_id: account_id,
+        }
+    };
+    let (report, logs) = verify_outcome(outcome_id, id, cmd.head_hash.as_ref()).await;
+    if !report.verified {
+        report.print();
+        return false;
+    }
+    let events: Vec<Value> = logs
+        .iter()
+        .filter_map(|log| log.strip_prefix(EVENT_LOG_PREFIX))
+        .filter_map(|event| serde_json::from_str(event).ok())
+        .collect();
+    let found = events.iter().any(|event| {
+        event["standard"] == cmd.standard.as_str()
+            && event["event"] == cmd.event.as_str()
+            && data
+                .as_ref()
+                .map_or(true, |data| json_matches(data, &event["data"]))
+    });
+    let mut report = report.with_detail("events", &events);
+    if !found {
+        report.verified = false;
+        report.error = Some(format!(
+            "Event {} of standard {} is NOT in the proven logs, events found: {}",
+            cmd.event,
+            cmd.standard,
+            serde_json::to_string(&events).unwrap()
+        ));
+    }
+    if is_json() {
+        report.print();
+    } else if found {
+        status_ok!(
+            "VERIFIED",
+            "Event {} of standard {} is in the proven logs.",
+            cmd.event,
+            cmd.standard
+        );
+    } else {
+        report.print();
+    }
+    found
+}
+
+/// Returns whether `actual` contains `expected`.
+fn json_matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected.iter().all(|(key, value)| {
+            actual
+                .get(key)
+                .map_or(false, |actual| json_matches(value, actual))
+        }),
+        (Value::Array(expected), Value::Array(actual)) => expected
+            .iter()
+            .all(|value| actual.iter().any(|actual| json_matches(value, actual))),
+        _ => expected == actual,
+    }
+}
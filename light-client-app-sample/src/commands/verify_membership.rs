@@ -4,14 +4,17 @@
 
 use std::convert::TryFrom;
 use std::ops::Deref;
+use std::path::PathBuf;
 
+use crate::light_client::utils::{print_proof_walk, read_proof_file, trie_key_of};
 use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::output::{is_json, VerificationReport};
 /// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
 /// accessors along with logging macros. Customize as you see fit.
 use crate::{info_with_time, prelude::*};
 use abscissa_core::{Command, Runnable};
-use near_light_client::near_types::get_raw_prefix_for_contract_data;
-use near_light_client::near_types::trie::RawTrieNodeWithSize;
+use near_light_client::near_types::trie::decode_state_proof;
+use near_light_client::types::Height;
 use near_light_client::BasicNearLightClient;
 use near_primitives::types::AccountId;
 
@@ -30,75 +33,101 @@ pub struct VerifyMembershipCmd {
     /// base64 formatted value
     pub value: String,
     pub block_height: Option<u64>,
+    /// Take the storage key as the whole trie key, e.g. of an account record or an access
+    /// key, the account is ignored
+    #[arg(long, conflicts_with = "column", requires = "proof_file")]
+    pub raw_key: bool,
+    /// Take `<column> ++ <account> ++ <storage key>` as the trie key, e.g. 0 for the account
+    /// record, or 2 for an access key with the storage key `0x02 ++ borsh(public key)`
+    #[arg(long, requires = "proof_file")]
+    pub column: Option<u8>,
+    /// JSON file of the array of base64 formatted proof nodes at `block_height - 1`, fetched
+    /// by `view_state` of the account if omitted, which only proves contract data
+    #[arg(long)]
+    pub proof_file: Option<PathBuf>,
 }
 
 impl Runnable for VerifyMembershipCmd {
     /// Start the application.
     fn run(&self) {
-        abscissa_tokio::run(
-            &APP,
-            validate_storage_state(
-                &self.block_height,
-                &self.near_account,
-                &self.storage_key,
-                &self.value,
-            ),
-        )
-        .expect("Failed to print status of NEAR light client.");
+        abscissa_tokio::run(&APP, validate_storage_state(self))
+            .expect("Failed to print status of NEAR light client.");
     }
 }
 
-async fn validate_storage_state(
-    block_height: &Option<u64>,
-    near_account: &String,
-    storage_key: &String,
-    value: &String,
-) {
-    let light_client = LightClient::new(APP.config().state_data.data_folder.clone());
-    let height = match block_height {
-        Some(height) => *height,
-        None => light_client.latest_height(),
+async fn validate_storage_state(cmd: &VerifyMembershipCmd) {
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let height = match cmd.block_height {
+        Some(height) => height,
+        None => light_client.latest_height().revision_height,
     };
-    let head = light_client.get_consensus_state(&height);
+    let head = light_client.get_consensus_state(&Height::from_block_height(height));
     if head.is_none() {
-        status_err!("Missing head data at height {}.", height);
+        VerificationReport::failed(format!("Missing head data at height {}.", height)).print();
         return;
     }
     let head_state = head.unwrap();
-    let rpc_client = NearRpcClientWrapper::new(APP.config().near_rpc.rpc_endpoint.as_str());
-    let key_bytes = base64::decode(storage_key).unwrap();
-    let result = rpc_client
-        .view_state_with_proof(
-            AccountId::try_from(near_account.clone()).unwrap(),
-            Some(key_bytes.as_ref()),
-            Some(near_primitives::types::BlockId::Height(height - 1)),
-        )
-        .await
-        .expect("Failed to view state of the given NEAR account.");
-    assert!(result.values.len() > 0, "Invalid storage key.");
-    assert!(
-        result.values.len() == 1,
-        "The storage key is mapped to multiple values."
-    );
-    let value_bytes = base64::decode(value).unwrap();
-    assert_eq!(
-        result.values[0].value.deref(),
-        value_bytes.deref(),
-        "The value on chain is different from the given value."
-    );
-    let proofs: Vec<Vec<u8>> = result.proof.iter().map(|proof| proof.to_vec()).collect();
+    let key_bytes = base64::decode(&cmd.storage_key).unwrap();
+    let value_bytes = base64::decode(&cmd.value).unwrap();
+    let proofs = match cmd.proof_file.as_ref() {
+        Some(path) => match read_proof_file(path) {
+            Ok(proofs) => proofs,
+            Err(err) => {
+                VerificationReport::failed(format!(
+                    "Failed to read proof file {}: {:?}",
+                    path.display(),
+                    err
+                ))
+                .print();
+                return;
+            }
+        },
+        None => {
+            let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+            let result = rpc_client
+                .view_state_with_proof(
+                    AccountId::try_from(cmd.near_account.clone()).unwrap(),
+                    Some(key_bytes.as_ref()),
+                    Some(near_primitives::types::BlockId::Height(height - 1)),
+                )
+                .await
+                .expect("Failed to view state of the given NEAR account.");
+            let error = if result.values.is_empty() {
+                Some("Invalid storage key.")
+            } else if result.values.len() > 1 {
+                Some("The storage key is mapped to multiple values.")
+            } else if result.values[0].value.deref() != value_bytes.deref() {
+                Some("The value on chain is different from the given value.")
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                VerificationReport::failed(error).print();
+                return;
+            }
+            result.proof.iter().map(|proof| proof.to_vec()).collect()
+        }
+    };
     info_with_time!("Proof data array length: {}", proofs.len());
-    let nodes: Vec<RawTrieNodeWithSize> = proofs
-        .iter()
-        .map(|bytes| RawTrieNodeWithSize::decode(bytes).unwrap())
-        .collect();
-    info_with_time!("Proof data decoded: {:?}", nodes);
-    match head_state.verify_membership(
-        &get_raw_prefix_for_contract_data(&near_account, key_bytes.as_ref()),
-        value_bytes.as_ref(),
-        &proofs,
-    ) {
-        Ok(()) => status_ok!("Finished", "Validation succeeded."),
-        Err(err) => status_err!(format!("{:?}", err)),
+    match decode_state_proof(&proofs) {
+        Ok(nodes) => {
+            info_with_time!("Proof data decoded: {:?}", nodes);
+        }
+        Err(err) => {
+            info_with_time!("Failed to decode proof data: {:?}", err);
+        }
+    }
+    let key = trie_key_of(&cmd.near_account, &key_bytes, cmd.raw_key, cmd.column);
+    let result = head_state.verify_membership(&key, value_bytes.as_ref(), &proofs);
+    let failed = result.is_err();
+    VerificationReport::of_head(
+        height,
+        head_state.header.light_client_block.current_block_hash(),
+    )
+    .with_result(result)
+    .with_detail("key", base64::encode(&key))
+    .print();
+    if failed && !is_json() {
+        print_proof_walk(&key, &proofs);
     }
 }
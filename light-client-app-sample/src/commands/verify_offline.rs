@@ -0,0 +1,51 @@
+//! `verify-offline` subcommand - to verify a proof bundle written by `prove`, or by third
+//! party tooling, with the local store only.
+
+use std::path::PathBuf;
+
+use crate::light_client::bundle::{read_bundle, verify_bundle};
+use crate::light_client::LightClient;
+use crate::output::{is_json, VerificationReport};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+
+/// `verify-offline` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct VerifyOfflineCmd {
+    /// Path of the bundle file, borsh serialized or JSON
+    pub file: PathBuf,
+}
+
+impl Runnable for VerifyOfflineCmd {
+    /// Start the application.
+    fn run(&self) {
+        let report = match read_bundle(&self.file) {
+            Ok((bundle, format)) => {
+                if !is_json() {
+                    status_info!("Info", "Bundle format: {:?}", format);
+                }
+                verify_bundle(&LightClient::from_config(&APP.config().state_data), &bundle)
+                    .with_detail("format", format!("{:?}", format).to_lowercase())
+            }
+            Err(err) => VerificationReport::failed(format!(
+                "Failed to read bundle file {}: {:?}",
+                self.file.display(),
+                err
+            )),
+        };
+        if is_json() || !report.verified {
+            report.print();
+        }
+        if !report.verified {
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! `verify-receipt` subcommand
+//! Verify the outcome of a certain receipt with the latest light client head.
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use super::verify_transaction::verify_outcome;
+use crate::output::VerificationReport;
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::hash::CryptoHash;
+
+/// `verify-receipt` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct VerifyReceiptCmd {
+    /// base58 formatted receipt id
+    pub receipt_id: String,
+    /// Account id of receipt receiver
+    pub receiver_id: String,
+    /// base58 formatted block hash of the stored head to verify with, the latest head
+    /// if omitted
+    #[arg(long)]
+    pub head_hash: Option<String>,
+}
+
+impl Runnable for VerifyReceiptCmd {
+    /// Start the application.
+    fn run(&self) {
+        abscissa_tokio::run(
+            &APP,
+            validate_receipt(&self.receipt_id, &self.receiver_id, self.head_hash.as_ref()),
+        )
+        .expect("Failed to print status of NEAR light client.");
+    }
+}
+
+async fn validate_receipt(receipt_id: &String, receiver_id: &String, head_hash: Option<&String>) {
+    let receipt_id = match bs58::decode(receipt_id.clone())
+        .into_vec()
+        .ok()
+        .and_then(|bytes| CryptoHash::try_from(bytes.as_ref()).ok())
+    {
+        Some(receipt_id) => receipt_id,
+        None => {
+            VerificationReport::failed(format!("Invalid receipt id '{}'.", receipt_id)).print();
+            return;
+        }
+    };
+    let receiver_id = match near_primitives::account::id::AccountId::from_str(receiver_id.as_str())
+    {
+        Ok(receiver_id) => receiver_id,
+        Err(err) => {
+            VerificationReport::failed(format!("Invalid receiver id '{}': {}", receiver_id, err))
+                .print();
+            return;
+        }
+    };
+    verify_outcome(
+        near_primitives::types::TransactionOrReceiptId::Receipt {
+            receipt_id: near_primitives::hash::CryptoHash(receipt_id.0),
+            receiver_id,
+        },
+        receipt_id,
+        head_hash,
+    )
+    .await
+    .0
+    .print();
+}
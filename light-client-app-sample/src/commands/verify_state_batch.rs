@@ -0,0 +1,261 @@
+//! `verify-state-batch` subcommand - to verify the values of a list of storage keys of NEAR
+//! accounts with the proof data of all of them at one height.
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+use crate::light_client::utils::trie_key_of;
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::output::{is_json, VerificationReport};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::{info_with_time, prelude::*};
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::trie::StateProof;
+use near_light_client::types::Height;
+use near_light_client::BasicNearLightClient;
+use near_primitives::types::{AccountId, BlockId};
+use serde::{Deserialize, Serialize};
+
+/// Formats of the file of the items to verify.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchFileFormat {
+    /// A JSON array of objects with `account`, `key` and `value`
+    Json,
+    /// Lines of `account,key,value`, with an optional header line
+    Csv,
+}
+
+/// `verify-state-batch` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct VerifyStateBatchCmd {
+    /// Path of the file of the items to verify, each of which is an account id with
+    /// a base64 formatted storage key and expected value
+    pub file: PathBuf,
+    /// Format of the file, by its extension if omitted
+    #[arg(long, value_enum)]
+    pub format: Option<BatchFileFormat>,
+    /// Height of the stored head to verify with, the latest head if omitted
+    #[arg(long)]
+    pub height: Option<u64>,
+}
+
+/// An item to verify.
+#[derive(Deserialize)]
+struct BatchItem {
+    account: String,
+    /// base64 formatted storage key
+    key: String,
+    /// base64 formatted expected value
+    value: String,
+}
+
+/// The result of an item.
+#[derive(Serialize)]
+struct ItemReport {
+    account: String,
+    key: String,
+    verified: bool,
+    error: Option<String>,
+}
+
+impl Runnable for VerifyStateBatchCmd {
+    /// Start the application.
+    fn run(&self) {
+        let verified = abscissa_tokio::run(&APP, verify_state_batch(self))
+            .expect("Failed to verify the state batch.");
+        if !verified {
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn verify_state_batch(cmd: &VerifyStateBatchCmd) -> bool {
+    let format = cmd.format.unwrap_or_else(|| {
+        match cmd
+            .file
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => BatchFileFormat::Csv,
+            _ => BatchFileFormat::Json,
+        }
+    });
+    let items = match read_batch_file(&cmd.file, format) {
+        Ok(items) => items,
+        Err(err) => {
+            VerificationReport::failed(format!(
+                "Failed to read batch file {}: {:?}",
+                cmd.file.display(),
+                err
+            ))
+            .print();
+            return false;
+        }
+    };
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let height = cmd
+        .height
+        .unwrap_or_else(|| light_client.latest_height().revision_height);
+    let head = match light_client.get_consensus_state(&Height::from_block_height(height)) {
+        Some(head) => head,
+        None => {
+            VerificationReport::failed(format!("Missing head data at height {}.", height)).print();
+            return false;
+        }
+    };
+    // The results of the items, `None` for the items to verify with the merged proof data.
+    let mut errors: Vec<Option<String>> = Vec::with_capacity(items.len());
+    let mut pairs = Vec::with_capacity(items.len());
+    let mut proofs = Vec::new();
+    let mut known_proofs = HashSet::new();
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    for (index, item) in items.iter().enumerate() {
+        let account_id = match AccountId::try_from(item.account.clone()) {
+            Ok(account_id) => account_id,
+            Err(err) => {
+                errors.push(Some(format!("Invalid account id: {}", err)));
+                continue;
+            }
+        };
+        let (key, value) = match (base64::decode(&item.key), base64::decode(&item.value)) {
+            (Ok(key), Ok(value)) => (key, value),
+            (Err(err), _) | (_, Err(err)) => {
+                errors.push(Some(format!("Invalid key or value: {}", err)));
+                continue;
+            }
+        };
+        // The proofs at `height - 1` are verified with the head at `height`.
+        match rpc_client
+            .view_state_with_proof(account_id, Some(&key), Some(BlockId::Height(height - 1)))
+            .await
+        {
+            Ok(result) => {
+                info_with_time!(
+                    "Fetched proof data of item {}: {} nodes",
+                    index,
+                    result.proof.len()
+                );
+                for proof in result.proof.iter() {
+                    if known_proofs.insert(proof.to_vec()) {
+                        proofs.push(proof.to_vec());
+                    }
+                }
+                errors.push(None);
+                pairs.push((trie_key_of(&item.account, &key, false, None), value));
+            }
+            Err(err) => errors.push(Some(format!("Failed to view state: {:?}", err))),
+        }
+    }
+    let results = if pairs.is_empty() {
+        Vec::new()
+    } else {
+        match StateProof::new(&proofs) {
+            Ok(proof) => head.verify_membership_batch(
+                &pairs
+                    .iter()
+                    .map(|(key, value)| (key.as_slice(), value.as_slice()))
+                    .collect::<Vec<_>>(),
+                &proof,
+            ),
+            Err(err) => pairs.iter().map(|_| Err(err.clone())).collect(),
+        }
+    };
+    let mut results = results.into_iter();
+    let item_reports: Vec<ItemReport> = items
+        .iter()
+        .zip(errors)
+        .map(|(item, error)| {
+            let error = error.or_else(|| {
+                results
+                    .next()
+                    .and_then(|result| result.err())
+                    .map(|err| format!("{:?}", err))
+            });
+            ItemReport {
+                account: item.account.clone(),
+                key: item.key.clone(),
+                verified: error.is_none(),
+                error,
+            }
+        })
+        .collect();
+    let passed = item_reports.iter().filter(|item| item.verified).count();
+    let failed = item_reports.len() - passed;
+    if !is_json() {
+        for item in item_reports.iter() {
+            match item.error.as_ref() {
+                None => status_ok!("Passed", "{} {}", item.account, item.key),
+                Some(error) => status_err!("{} {}: {}", item.account, item.key, error),
+            }
+        }
+    }
+    let mut report =
+        VerificationReport::of_head(height, head.header.light_client_block.current_block_hash())
+            .with_detail("total", item_reports.len())
+            .with_detail("passed", passed)
+            .with_detail("failed", failed)
+            .with_detail("items", &item_reports);
+    report.verified = failed == 0;
+    if failed > 0 {
+        report.error = Some(format!(
+            "{} of {} items failed to verify.",
+            failed,
+            item_reports.len()
+        ));
+    }
+    if is_json() {
+        report.print();
+    } else if failed == 0 {
+        status_ok!(
+            "VERIFIED",
+            "All {} items are verified with the head at height {}.",
+            passed,
+            height
+        );
+    } else {
+        status_err!(
+            "{} of {} items failed to verify with the head at height {}.",
+            failed,
+            item_reports.len(),
+            height
+        );
+    }
+    failed == 0
+}
+
+/// Read the items to verify from `path` in `format`.
+fn read_batch_file(path: &Path, format: BatchFileFormat) -> anyhow::Result<Vec<BatchItem>> {
+    let content = std::fs::read_to_string(path)?;
+    match format {
+        BatchFileFormat::Json => Ok(serde_json::from_str(&content)?),
+        BatchFileFormat::Csv => content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .enumerate()
+            .filter(|(index, line)| !(*index == 0 && line.starts_with("account,")))
+            .map(|(_, line)| {
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                match fields.as_slice() {
+                    [account, key, value] => Ok(BatchItem {
+                        account: account.to_string(),
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    }),
+                    _ => Err(anyhow::anyhow!(
+                        "Expected 3 fields of `account,key,value` in line '{}'.",
+                        line
+                    )),
+                }
+            })
+            .collect(),
+    }
+}
@@ -4,19 +4,15 @@
 use std::convert::TryFrom;
 use std::str::FromStr;
 
-use crate::light_client::utils::produce_light_client_block_lite_view;
+use crate::light_client::utils::produce_outcome_proof;
 use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::output::VerificationReport;
 /// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
 /// accessors along with logging macros. Customize as you see fit.
 use crate::{info_with_time, prelude::*};
 use abscissa_core::{Command, Runnable};
-use borsh::BorshDeserialize;
 use near_light_client::near_types::hash::CryptoHash;
-use near_light_client::near_types::merkle::MerklePathItem;
-use near_light_client::near_types::transaction::{
-    ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus,
-};
-use near_light_client::BasicNearLightClient;
+use near_light_client::{types::Height, BasicNearLightClient};
 
 /// `validate-tx` subcommand
 ///
@@ -31,90 +27,142 @@ pub struct VerifyTransactionCmd {
     pub tx_hash: String,
     /// Account id of transaction sender
     pub sender_id: String,
+    /// base58 formatted block hash of the stored head to verify with, the latest head
+    /// if omitted
+    #[arg(long)]
+    pub head_hash: Option<String>,
 }
 
 impl Runnable for VerifyTransactionCmd {
     /// Start the application.
     fn run(&self) {
-        abscissa_tokio::run(&APP, validate_transaction(&self.tx_hash, &self.sender_id))
-            .expect("Failed to print status of NEAR light client.");
+        abscissa_tokio::run(
+            &APP,
+            validate_transaction(&self.tx_hash, &self.sender_id, self.head_hash.as_ref()),
+        )
+        .expect("Failed to print status of NEAR light client.");
     }
 }
 
-async fn validate_transaction(tx_hash: &String, sender_id: &String) {
-    let light_client = LightClient::new(APP.config().state_data.data_folder.clone());
-    let transaction_hash =
-        CryptoHash::try_from(bs58::decode(tx_hash.clone()).into_vec().unwrap().as_ref()).unwrap();
-    let sender_id = near_primitives::account::id::AccountId::from_str(sender_id.as_str()).unwrap();
-    let rpc_client = NearRpcClientWrapper::new(APP.config().near_rpc.rpc_endpoint.as_str());
-    let head = light_client.get_consensus_state(&light_client.latest_height());
+async fn validate_transaction(tx_hash: &String, sender_id: &String, head_hash: Option<&String>) {
+    let transaction_hash = match bs58::decode(tx_hash.clone())
+        .into_vec()
+        .ok()
+        .and_then(|bytes| CryptoHash::try_from(bytes.as_ref()).ok())
+    {
+        Some(transaction_hash) => transaction_hash,
+        None => {
+            VerificationReport::failed(format!("Invalid transaction hash '{}'.", tx_hash)).print();
+            return;
+        }
+    };
+    let sender_id = match near_primitives::account::id::AccountId::from_str(sender_id.as_str()) {
+        Ok(sender_id) => sender_id,
+        Err(err) => {
+            VerificationReport::failed(format!("Invalid sender id '{}': {}", sender_id, err))
+                .print();
+            return;
+        }
+    };
+    verify_outcome(
+        near_primitives::types::TransactionOrReceiptId::Transaction {
+            transaction_hash: near_primitives::hash::CryptoHash(transaction_hash.0),
+            sender_id,
+        },
+        transaction_hash,
+        head_hash,
+    )
+    .await
+    .0
+    .print();
+}
+
+/// Verify the outcome `outcome_id` of transaction or receipt `id` with the stored head of
+/// block hash `head_hash`, or the latest head if `head_hash` is `None`.
+///
+/// Returns the report of the verification, to be printed by the caller, with the proven
+/// logs of the outcome, which are empty if the verification failed.
+pub(super) async fn verify_outcome(
+    id: near_primitives::types::TransactionOrReceiptId,
+    outcome_id: CryptoHash,
+    head_hash: Option<&String>,
+) -> (VerificationReport, Vec<String>) {
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let head_height = match head_hash {
+        Some(head_hash) => {
+            let head_hash = match bs58::decode(head_hash)
+                .into_vec()
+                .ok()
+                .and_then(|bytes| CryptoHash::try_from(bytes.as_ref()).ok())
+            {
+                Some(head_hash) => head_hash,
+                None => {
+                    return (
+                        VerificationReport::failed(format!("Invalid head hash '{}'.", head_hash)),
+                        Vec::new(),
+                    );
+                }
+            };
+            match light_client
+                .height_of_block_hash(&head_hash)
+                .expect("Failed to read block hash index.")
+            {
+                Some(height) => Height::from_block_height(height),
+                None => {
+                    return (
+                        VerificationReport::failed(format!(
+                            "Missing head with block hash {}.",
+                            head_hash
+                        )),
+                        Vec::new(),
+                    );
+                }
+            }
+        }
+        None => light_client.latest_height(),
+    };
+    let head = light_client.get_consensus_state(&head_height);
     if head.is_none() {
-        status_err!("Uninitialized NEAR light client.");
-        return;
+        return (
+            VerificationReport::failed("Uninitialized NEAR light client."),
+            Vec::new(),
+        );
     }
     let head_state = head.unwrap();
     let head_hash = head_state.header.light_client_block.current_block_hash();
     let result = rpc_client
-        .get_light_client_proof(
-            &near_primitives::types::TransactionOrReceiptId::Transaction {
-                transaction_hash: near_primitives::hash::CryptoHash(transaction_hash.0),
-                sender_id,
-            },
-            &near_primitives::hash::CryptoHash(head_hash.clone().0),
-        )
+        .get_light_client_proof(&id, &near_primitives::hash::CryptoHash(head_hash.clone().0))
         .await
         .expect("Failed to get light client proof.");
     info_with_time!("Header of block proof: {:?}", result.block_header_lite);
+    let block_hash = CryptoHash(result.block_header_lite.hash().0);
+    if let Some(height) = light_client
+        .height_of_block_hash(&block_hash)
+        .expect("Failed to read block hash index.")
+    {
+        info_with_time!(
+            "Block {} of the outcome is the stored head at height {}.",
+            block_hash,
+            height
+        );
+    }
     info_with_time!("Block proof length: {}", result.block_proof.len());
     info_with_time!("Block proof data: {:?}", result.block_proof);
-    match head_state.verify_transaction_or_receipt(
-        &ExecutionOutcomeWithId {
-            id: transaction_hash,
-            outcome: ExecutionOutcome {
-                logs: result.outcome_proof.outcome.logs,
-                receipt_ids: result
-                    .outcome_proof
-                    .outcome
-                    .receipt_ids
-                    .iter()
-                    .map(|h| CryptoHash(h.clone().0))
-                    .collect(),
-                gas_burnt: result.outcome_proof.outcome.gas_burnt,
-                tokens_burnt: result.outcome_proof.outcome.tokens_burnt,
-                executor_id: result.outcome_proof.outcome.executor_id.to_string(),
-                status: ExecutionStatus::try_from_slice(
-                    borsh::to_vec(&result.outcome_proof.outcome.status)
-                        .unwrap()
-                        .as_ref(),
-                )
-                .unwrap(),
-            },
-        },
-        &result
-            .outcome_proof
-            .proof
-            .iter()
-            .map(|proof| {
-                MerklePathItem::try_from_slice(borsh::to_vec(&proof).unwrap().as_ref()).unwrap()
-            })
-            .collect(),
-        &result
-            .outcome_root_proof
-            .iter()
-            .map(|proof| {
-                MerklePathItem::try_from_slice(borsh::to_vec(&proof).unwrap().as_ref()).unwrap()
-            })
-            .collect(),
-        &produce_light_client_block_lite_view(&result.block_header_lite),
-        &result
-            .block_proof
-            .iter()
-            .map(|proof| {
-                MerklePathItem::try_from_slice(borsh::to_vec(&proof).unwrap().as_ref()).unwrap()
-            })
-            .collect(),
-    ) {
-        Ok(()) => status_ok!("Finished", "Validation succeeded."),
-        Err(err) => status_err!(format!("{:?}", err)),
-    }
+    let outcome_proof = produce_outcome_proof(head_state.header.height(), outcome_id, &result);
+    let verification = head_state.verify_outcome_proof(&outcome_proof);
+    let logs = match verification {
+        Ok(()) => result.outcome_proof.outcome.logs.clone(),
+        Err(_) => Vec::new(),
+    };
+    let report = VerificationReport::of_head(head_state.header.height().revision_height, head_hash)
+        .with_result(verification)
+        .with_detail("outcome_id", outcome_id)
+        .with_detail("block_hash", block_hash)
+        .with_detail("block_height", result.block_header_lite.inner_lite.height)
+        .with_detail(
+            "executor_id",
+            result.outcome_proof.outcome.executor_id.to_string(),
+        );
+    (report, logs)
 }
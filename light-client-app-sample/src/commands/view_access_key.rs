@@ -0,0 +1,150 @@
+//! `view-access-key` subcommand - to print the permission of an access key of a NEAR
+//! account, verified with proof data against the light client head at the given height.
+
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+use crate::light_client::utils::{print_proof_walk, read_proof_file, trie_key_of};
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_crypto::PublicKey;
+use near_light_client::near_types::trie_key::{col, ACCESS_KEY_SEPARATOR};
+use near_light_client::types::Height;
+use near_light_client::BasicNearLightClient;
+use near_primitives::account::AccessKey;
+use near_primitives::types::{AccountId, BlockId};
+use near_primitives::views::AccessKeyPermissionView;
+
+/// `view-access-key` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ViewAccessKeyCmd {
+    /// Id of the NEAR account
+    pub near_account: String,
+    /// Public key of the access key, e.g. `ed25519:<base58 key>`
+    pub public_key: String,
+    /// Height of the head to verify against, the latest head if omitted
+    pub block_height: Option<u64>,
+    /// JSON file of the array of base64 formatted proof nodes of the access key at
+    /// `block_height - 1`, as NEAR RPC only proves contract data
+    #[arg(long)]
+    pub proof_file: PathBuf,
+}
+
+impl Runnable for ViewAccessKeyCmd {
+    /// Start the application.
+    fn run(&self) {
+        abscissa_tokio::run(&APP, view_access_key(self))
+            .expect("Failed to print status of NEAR light client.");
+    }
+}
+
+async fn view_access_key(cmd: &ViewAccessKeyCmd) {
+    let account_id = match AccountId::try_from(cmd.near_account.clone()) {
+        Ok(account_id) => account_id,
+        Err(err) => {
+            status_err!("Invalid account id '{}': {}", cmd.near_account, err);
+            return;
+        }
+    };
+    let public_key: PublicKey = match cmd.public_key.parse() {
+        Ok(public_key) => public_key,
+        Err(err) => {
+            status_err!("Invalid public key '{}': {}", cmd.public_key, err);
+            return;
+        }
+    };
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let height = match cmd.block_height {
+        Some(height) => height,
+        None => light_client.latest_height().revision_height,
+    };
+    let head_state = match light_client.get_consensus_state(&Height::from_block_height(height)) {
+        Some(head_state) => head_state,
+        None => {
+            status_err!("Missing head data at height {}.", height);
+            return;
+        }
+    };
+    let proofs = match read_proof_file(&cmd.proof_file) {
+        Ok(proofs) => proofs,
+        Err(err) => {
+            status_err!(
+                "Failed to read proof file {}: {:?}",
+                cmd.proof_file.display(),
+                err
+            );
+            return;
+        }
+    };
+    // The state root of a head is the one after applying the chunks of the previous block.
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let access_key_view = match rpc_client
+        .view_access_key(&account_id, &public_key, Some(BlockId::Height(height - 1)))
+        .await
+    {
+        Ok(access_key_view) => access_key_view,
+        Err(err) => {
+            status_err!(
+                "Failed to view access key {} of {}: {:?}",
+                public_key,
+                account_id,
+                err
+            );
+            return;
+        }
+    };
+    let value = borsh::to_vec(&AccessKey::from(access_key_view.clone())).unwrap();
+    let mut storage_key = vec![ACCESS_KEY_SEPARATOR];
+    storage_key.extend(borsh::to_vec(&public_key).unwrap());
+    let key = trie_key_of(
+        &cmd.near_account,
+        &storage_key,
+        false,
+        Some(col::ACCESS_KEY),
+    );
+    if let Err(err) = head_state.verify_membership(&key, &value, &proofs) {
+        status_err!(format!("{:?}", err));
+        print_proof_walk(&key, &proofs);
+        return;
+    }
+    status_info!("Info", "Account: {}", account_id);
+    status_info!("Info", "Public key: {}", public_key);
+    status_info!("Info", "Height: {}", height);
+    status_info!("Info", "Nonce: {}", access_key_view.nonce);
+    match access_key_view.permission {
+        AccessKeyPermissionView::FullAccess => {
+            status_info!("Info", "Permission: full access");
+        }
+        AccessKeyPermissionView::FunctionCall {
+            allowance,
+            receiver_id,
+            method_names,
+        } => {
+            status_info!("Info", "Permission: function call");
+            match allowance {
+                Some(allowance) => status_info!("Info", "Allowance: {} yoctoNEAR", allowance),
+                None => status_info!("Info", "Allowance: unlimited"),
+            }
+            status_info!("Info", "Receiver: {}", receiver_id);
+            if method_names.is_empty() {
+                status_info!("Info", "Methods: any");
+            } else {
+                status_info!("Info", "Methods: {}", method_names.join(", "));
+            }
+        }
+    }
+    status_ok!(
+        "VERIFIED",
+        "The access key is proven by head at height {}.",
+        height
+    );
+}
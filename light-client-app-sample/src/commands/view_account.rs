@@ -0,0 +1,114 @@
+//! `view-account` subcommand - to print the record of a NEAR account, verified with
+//! proof data against the light client head at the given height.
+
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+use crate::light_client::utils::{print_proof_walk, read_proof_file};
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::trie_key::TrieKey;
+use near_light_client::types::Height;
+use near_light_client::BasicNearLightClient;
+use near_primitives::account::Account;
+use near_primitives::types::{AccountId, BlockId};
+
+/// `view-account` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ViewAccountCmd {
+    /// Id of the NEAR account
+    pub near_account: String,
+    /// Height of the head to verify against, the latest head if omitted
+    pub block_height: Option<u64>,
+    /// JSON file of the array of base64 formatted proof nodes of the account record at
+    /// `block_height - 1`, as NEAR RPC only proves contract data
+    #[arg(long)]
+    pub proof_file: PathBuf,
+}
+
+impl Runnable for ViewAccountCmd {
+    /// Start the application.
+    fn run(&self) {
+        abscissa_tokio::run(&APP, view_account(self))
+            .expect("Failed to print status of NEAR light client.");
+    }
+}
+
+async fn view_account(cmd: &ViewAccountCmd) {
+    let account_id = match AccountId::try_from(cmd.near_account.clone()) {
+        Ok(account_id) => account_id,
+        Err(err) => {
+            status_err!("Invalid account id '{}': {}", cmd.near_account, err);
+            return;
+        }
+    };
+    let light_client = LightClient::from_config(&APP.config().state_data);
+    let height = match cmd.block_height {
+        Some(height) => height,
+        None => light_client.latest_height().revision_height,
+    };
+    let head_state = match light_client.get_consensus_state(&Height::from_block_height(height)) {
+        Some(head_state) => head_state,
+        None => {
+            status_err!("Missing head data at height {}.", height);
+            return;
+        }
+    };
+    let proofs = match read_proof_file(&cmd.proof_file) {
+        Ok(proofs) => proofs,
+        Err(err) => {
+            status_err!(
+                "Failed to read proof file {}: {:?}",
+                cmd.proof_file.display(),
+                err
+            );
+            return;
+        }
+    };
+    // The state root of a head is the one after applying the chunks of the previous block.
+    let rpc_client = NearRpcClientWrapper::from_config(&APP.config().near_rpc);
+    let account_view = match rpc_client
+        .view_account(&account_id, Some(BlockId::Height(height - 1)))
+        .await
+    {
+        Ok(account_view) => account_view,
+        Err(err) => {
+            status_err!("Failed to view account {}: {:?}", account_id, err);
+            return;
+        }
+    };
+    let value = borsh::to_vec(&Account::from(account_view.clone())).unwrap();
+    let key = TrieKey::Account {
+        account_id: cmd.near_account.clone(),
+    }
+    .to_vec();
+    if let Err(err) = head_state.verify_membership(&key, &value, &proofs) {
+        status_err!(format!("{:?}", err));
+        print_proof_walk(&key, &proofs);
+        return;
+    }
+    status_info!("Info", "Account: {}", account_id);
+    status_info!("Info", "Height: {}", height);
+    status_info!("Info", "Balance: {} yoctoNEAR", account_view.amount);
+    status_info!("Info", "Locked: {} yoctoNEAR", account_view.locked);
+    status_info!(
+        "Info",
+        "Storage usage: {} bytes",
+        account_view.storage_usage
+    );
+    status_info!("Info", "Code hash: {}", account_view.code_hash);
+    status_ok!(
+        "VERIFIED",
+        "The account record is proven by head at height {}.",
+        height
+    );
+}
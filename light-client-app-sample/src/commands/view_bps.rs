@@ -0,0 +1,102 @@
+//! `view-bps` subcommand - to print the block producers of a certain epoch.
+
+use crate::light_client::{store::epoch_id_of_name, LightClient};
+use crate::output::{is_json, print_json};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::{near_types::ValidatorStakeView, BasicNearLightClient};
+
+/// `view-bps` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ViewBpsCmd {
+    /// Epoch id in base58, the epoch of the latest head if omitted
+    pub epoch_id: Option<String>,
+    /// Only list the ids of the epochs with stored block producers
+    #[arg(long)]
+    pub list: bool,
+}
+
+impl Runnable for ViewBpsCmd {
+    /// Start the application.
+    fn run(&self) {
+        let light_client = LightClient::from_config(&APP.config().state_data);
+        if self.list {
+            let epoch_ids = light_client.stored_epoch_ids();
+            if is_json() {
+                print_json(&serde_json::json!({ "epoch_ids": epoch_ids }));
+                return;
+            }
+            for epoch_id in epoch_ids {
+                status_info!("Info", "Stored block producers of epoch {}", epoch_id);
+            }
+            return;
+        }
+        let epoch_id = match self.epoch_id.as_ref() {
+            Some(name) => match epoch_id_of_name(name) {
+                Ok(epoch_id) => epoch_id,
+                Err(_) => {
+                    status_err!("Invalid epoch id '{}'.", name);
+                    return;
+                }
+            },
+            None => match light_client.get_consensus_state(&light_client.latest_height()) {
+                Some(head) => head.header.epoch_id(),
+                None => {
+                    status_err!("The light client is not initialized.");
+                    return;
+                }
+            },
+        };
+        let bps = match light_client.get_epoch_block_producers(&epoch_id) {
+            Ok(Some(bps)) => bps,
+            Ok(None) => {
+                status_err!("Missing block producers of epoch {}.", epoch_id);
+                return;
+            }
+            Err(err) => {
+                status_err!(
+                    "Failed to read block producers of epoch {}: {:?}",
+                    epoch_id,
+                    err
+                );
+                return;
+            }
+        };
+        if is_json() {
+            let bps: Vec<serde_json::Value> = bps
+                .decoded()
+                .iter()
+                .map(|bp| {
+                    let ValidatorStakeView::V1(bp) = bp;
+                    serde_json::json!({
+                        "account_id": bp.account_id,
+                        "stake": bp.stake.to_string(),
+                    })
+                })
+                .collect();
+            print_json(&serde_json::json!({
+                "epoch_id": epoch_id,
+                "block_producers": bps,
+            }));
+            return;
+        }
+        status_info!(
+            "Info",
+            "Block producers of epoch {}, count: {}",
+            epoch_id,
+            bps.len()
+        );
+        for bp in bps.decoded().iter() {
+            let ValidatorStakeView::V1(bp) = bp;
+            status_info!("Info", "{}: {}", bp.account_id, bp.stake);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! `view-epochs` subcommand - to list the epochs of the stored heads and of the stored
+//! block producers.
+
+use std::collections::BTreeMap;
+
+use crate::light_client::LightClient;
+use crate::output::{is_json, print_json};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::{near_types::hash::CryptoHash, types::Height, BasicNearLightClient};
+use serde::Serialize;
+
+/// `view-epochs` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ViewEpochsCmd {}
+
+/// An epoch represented in the store.
+#[derive(Serialize)]
+struct EpochEntry {
+    epoch_id: CryptoHash,
+    /// Height of the first stored head of the epoch
+    first_height: Option<u64>,
+    /// Height of the last stored head of the epoch
+    last_height: Option<u64>,
+    /// Count of the stored heads of the epoch
+    heads: usize,
+    /// Count of the block producers, `None` if they are not held
+    validators: Option<usize>,
+    /// Total stake of the block producers
+    total_stake: Option<String>,
+    /// The error of reading the block producers
+    error: Option<String>,
+    /// Whether it's the epoch of the latest head
+    is_current: bool,
+    /// Whether it's the next epoch of the latest head
+    is_next: bool,
+}
+
+impl Runnable for ViewEpochsCmd {
+    /// Start the application.
+    fn run(&self) {
+        let light_client = LightClient::from_config(&APP.config().state_data);
+        let latest_head = light_client.get_consensus_state(&light_client.latest_height());
+        let current_epoch_id = latest_head.as_ref().map(|head| head.header.epoch_id());
+        let next_epoch_id = latest_head.as_ref().map(|head| head.header.next_epoch_id());
+        // Heights of the first and the last stored heads, and the count of them, by epoch.
+        let mut heights: BTreeMap<CryptoHash, (u64, u64, usize)> = BTreeMap::new();
+        for height in light_client.cached_heights() {
+            if let Some(head) = light_client.get_consensus_state(&Height::from_block_height(height))
+            {
+                let entry = heights
+                    .entry(head.header.epoch_id())
+                    .or_insert((height, height, 0));
+                entry.0 = entry.0.min(height);
+                entry.1 = entry.1.max(height);
+                entry.2 += 1;
+            }
+        }
+        let mut epoch_ids: Vec<CryptoHash> = heights.keys().copied().collect();
+        epoch_ids.sort_by_key(|epoch_id| heights[epoch_id].0);
+        for epoch_id in light_client
+            .stored_epoch_ids()
+            .into_iter()
+            .chain(next_epoch_id)
+        {
+            if !epoch_ids.contains(&epoch_id) {
+                epoch_ids.push(epoch_id);
+            }
+        }
+        let epochs: Vec<EpochEntry> = epoch_ids
+            .into_iter()
+            .map(|epoch_id| {
+                let (validators, total_stake, error) = match light_client
+                    .get_epoch_block_producers(&epoch_id)
+                {
+                    Ok(Some(bps)) => (Some(bps.len()), Some(bps.total_stake().to_string()), None),
+                    Ok(None) => (None, None, None),
+                    Err(err) => (None, None, Some(format!("{:?}", err))),
+                };
+                let heights = heights.get(&epoch_id);
+                EpochEntry {
+                    epoch_id,
+                    first_height: heights.map(|heights| heights.0),
+                    last_height: heights.map(|heights| heights.1),
+                    heads: heights.map_or(0, |heights| heights.2),
+                    validators,
+                    total_stake,
+                    error,
+                    is_current: Some(epoch_id) == current_epoch_id,
+                    is_next: Some(epoch_id) == next_epoch_id,
+                }
+            })
+            .collect();
+        let next_epoch_bps_held = epochs
+            .iter()
+            .any(|epoch| epoch.is_next && epoch.validators.is_some());
+        if is_json() {
+            print_json(&serde_json::json!({
+                "epochs": epochs,
+                "next_epoch_bps_held": next_epoch_bps_held,
+            }));
+            return;
+        }
+        if latest_head.is_none() {
+            status_err!("The light client is not initialized.");
+            return;
+        }
+        for epoch in epochs.iter() {
+            let role = match (epoch.is_current, epoch.is_next) {
+                (true, _) => " (current)",
+                (_, true) => " (next)",
+                _ => "",
+            };
+            let heads = match (epoch.first_height, epoch.last_height) {
+                (Some(first_height), Some(last_height)) => format!(
+                    "heights {} to {} ({} heads)",
+                    first_height, last_height, epoch.heads
+                ),
+                _ => "no stored heads".to_owned(),
+            };
+            match (&epoch.validators, &epoch.total_stake, &epoch.error) {
+                (Some(validators), Some(total_stake), _) => status_info!(
+                    "Epoch",
+                    "{}{}: {}, {} block producers, total stake {}",
+                    epoch.epoch_id,
+                    role,
+                    heads,
+                    validators,
+                    total_stake
+                ),
+                (_, _, Some(error)) => status_warn!(
+                    "Epoch {}{}: {}, failed to read the block producers: {}",
+                    epoch.epoch_id,
+                    role,
+                    heads,
+                    error
+                ),
+                _ => status_info!(
+                    "Epoch",
+                    "{}{}: {}, block producers NOT held",
+                    epoch.epoch_id,
+                    role,
+                    heads
+                ),
+            }
+        }
+        match next_epoch_id {
+            Some(_) if next_epoch_bps_held => {
+                status_ok!("Passed", "The block producers of the next epoch are held.")
+            }
+            Some(next_epoch_id) => status_warn!(
+                "The block producers of the next epoch {} are NOT held yet, the headers of it can only be verified once the last header of the current epoch, carrying them, is verified.",
+                next_epoch_id
+            ),
+            None => (),
+        }
+    }
+}
@@ -1,11 +1,13 @@
 //! `view-head` subcommand - to print the head data at a certain height.
 
 use crate::light_client::{utils::print_light_client_consensus_state, LightClient};
+use crate::output::{is_json, print_json};
 /// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
 /// accessors along with logging macros. Customize as you see fit.
 use crate::prelude::*;
+use crate::server::HeadSummary;
 use abscissa_core::{Command, Runnable};
-use near_light_client::BasicNearLightClient;
+use near_light_client::{types::Height, BasicNearLightClient};
 
 /// `view-head` subcommand
 ///
@@ -23,18 +25,25 @@ pub struct ViewHeadCmd {
 impl Runnable for ViewHeadCmd {
     /// Start the application.
     fn run(&self) {
-        let light_client = LightClient::new(APP.config().state_data.data_folder.clone());
-        status_info!(
-            "Info",
-            "Latest height of light client: {}",
-            light_client.latest_height()
-        );
+        let light_client = LightClient::from_config(&APP.config().state_data);
+        let latest_height = light_client.latest_height().revision_height;
         let height = match self.height {
             Some(height) => height,
-            None => light_client.latest_height(),
+            None => latest_height,
         };
-        if let Some(head) = light_client.get_consensus_state(&height) {
-            if self.with_detail.map_or(false, |w| w) {
+        let head = light_client.get_consensus_state(&Height::from_block_height(height));
+        let with_detail = self.with_detail.map_or(false, |w| w);
+        if is_json() {
+            print_json(&serde_json::json!({
+                "latest_height": latest_height,
+                "head": head.as_ref().map(HeadSummary::of),
+                "consensus_state": head.filter(|_| with_detail),
+            }));
+            return;
+        }
+        status_info!("Info", "Latest height of light client: {}", latest_height);
+        if let Some(head) = head {
+            if with_detail {
                 status_info!("Info", "Head data at height {}: {:?}", height, head);
             } else {
                 print_light_client_consensus_state(&head);
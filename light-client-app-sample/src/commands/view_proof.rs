@@ -0,0 +1,499 @@
+//! `view-proof` subcommand - to decode proof data, or the proof of a bundle file, and print
+//! the trie nodes and merkle paths in it, along with how a given key traverses the nodes.
+
+use std::path::PathBuf;
+
+use crate::light_client::bundle::read_bundle;
+use crate::light_client::utils::{read_proof_file, trie_key_of};
+use crate::output::{is_json, print_json};
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::prelude::*;
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::{
+    hash::{combine_hash, CryptoHash},
+    merkle::{Direction, MerklePath},
+    trie::{
+        decode_state_proof, nibble_slice::NibbleSlice, walk_proof, RawTrieNode, RawTrieNodeWithSize,
+    },
+};
+use near_light_client::types::{BundledProof, OutcomeProof, StateValueProof};
+use serde::Serialize;
+
+/// `view-proof` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct ViewProofCmd {
+    /// base64 formatted proof data items, e.g. the `proof` of the results of `view_state` RPC
+    pub proofs: Vec<String>,
+    /// Read the proof data from a JSON file of an array of base64 formatted items
+    #[arg(long, conflicts_with_all = ["proofs", "bundle"])]
+    pub proof_file: Option<PathBuf>,
+    /// Read the proof from a bundle file written by `prove`
+    #[arg(long, conflicts_with = "proofs")]
+    pub bundle: Option<PathBuf>,
+    /// base64 formatted key to walk the trie nodes along, the key of the bundled proof
+    /// if omitted
+    #[arg(long)]
+    pub key: Option<String>,
+    /// Take the key as a storage key of the contract data of this account, instead of
+    /// a raw trie key
+    #[arg(long, requires = "key")]
+    pub account: Option<String>,
+}
+
+/// A decoded trie node.
+#[derive(Serialize)]
+struct NodeView {
+    index: usize,
+    hash: CryptoHash,
+    node_type: String,
+    /// Key nibbles of a leaf or extension node, in hex digits
+    key_nibbles: Option<String>,
+    /// Children of a branch node, one hex digit per present child and `.` per absent one
+    children_bitmap: Option<String>,
+    /// Hashes of the present children of a branch node, by their nibbles
+    children: Vec<(u8, CryptoHash)>,
+    /// Hash of the child of an extension node
+    child_hash: Option<CryptoHash>,
+    value_length: Option<u32>,
+    value_hash: Option<CryptoHash>,
+    memory_usage: u64,
+    /// Whether the key traverses this node
+    on_key_path: bool,
+}
+
+/// A node visited by walking the trie nodes along the key.
+#[derive(Serialize)]
+struct StepView {
+    node_index: u16,
+    expected_hash: CryptoHash,
+    hash_matched: bool,
+    /// Key nibbles consumed before and after visiting the node
+    consumed_nibbles: (usize, usize),
+    next_expected_hash: Option<CryptoHash>,
+}
+
+/// The walk of the trie nodes along the key.
+#[derive(Serialize)]
+struct KeyWalkView {
+    key: String,
+    key_nibbles: String,
+    steps: Vec<StepView>,
+    /// Where and why the walk stops
+    result: String,
+}
+
+/// An item of a merkle path, with the hash computed by combining it.
+#[derive(Serialize)]
+struct MerkleStepView {
+    direction: Direction,
+    hash: CryptoHash,
+    combined_hash: CryptoHash,
+}
+
+/// A merkle path from an item hash to a root.
+#[derive(Serialize)]
+struct MerklePathView {
+    name: &'static str,
+    item_hash: CryptoHash,
+    steps: Vec<MerkleStepView>,
+    root: CryptoHash,
+    /// The root which the path is checked against, if it is checked directly
+    expected_root: Option<CryptoHash>,
+}
+
+/// The output of `view-proof` in JSON.
+#[derive(Serialize, Default)]
+struct ProofView {
+    proof_items: usize,
+    nodes: Vec<NodeView>,
+    key_walk: Option<KeyWalkView>,
+    /// Whether the root node is a state root in the bundled head
+    root_in_bundled_head: Option<bool>,
+    merkle_paths: Vec<MerklePathView>,
+}
+
+impl Runnable for ViewProofCmd {
+    /// Start the application.
+    fn run(&self) {
+        match self.view() {
+            Ok(view) if is_json() => print_json(&view),
+            Ok(view) => print_proof_view(&view),
+            Err(err) => {
+                status_err!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+impl ViewProofCmd {
+    fn view(&self) -> Result<ProofView, String> {
+        let key = match self.key.as_ref() {
+            Some(key) => {
+                let key_bytes =
+                    base64::decode(key).map_err(|err| format!("Invalid key '{}': {}", key, err))?;
+                Some(match self.account.as_ref() {
+                    Some(account) => trie_key_of(account, &key_bytes, false, None),
+                    None => key_bytes,
+                })
+            }
+            None => None,
+        };
+        if let Some(path) = self.bundle.as_ref() {
+            let (bundle, _) = read_bundle(path).map_err(|err| {
+                format!("Failed to read bundle file {}: {:?}", path.display(), err)
+            })?;
+            return match &bundle.proof {
+                BundledProof::State(proof) => {
+                    let (proofs, value) = match proof {
+                        StateValueProof::Membership(proof) => (&proof.proof, Some(&proof.value)),
+                        StateValueProof::NonMembership(proof) => (&proof.proof, None),
+                    };
+                    // The bundled value is only checked along the bundled key.
+                    let mut view = match key.as_ref() {
+                        Some(key) => view_state_proof(proofs, key, None)?,
+                        None => view_state_proof(
+                            proofs,
+                            proof.key(),
+                            value.map(|value| value.as_slice()),
+                        )?,
+                    };
+                    view.root_in_bundled_head = view.nodes.first().map(|root| {
+                        bundle
+                            .consensus_state
+                            .header
+                            .prev_state_root_of_chunks
+                            .contains(&root.hash)
+                    });
+                    Ok(view)
+                }
+                BundledProof::Outcome(proof) => Ok(ProofView {
+                    merkle_paths: view_outcome_proof(
+                        proof,
+                        bundle
+                            .consensus_state
+                            .header
+                            .light_client_block
+                            .inner_lite
+                            .block_merkle_root,
+                    ),
+                    ..Default::default()
+                }),
+            };
+        }
+        let proofs = match self.proof_file.as_ref() {
+            Some(path) => read_proof_file(path).map_err(|err| {
+                format!("Failed to read proof file {}: {:?}", path.display(), err)
+            })?,
+            None => self
+                .proofs
+                .iter()
+                .map(|proof| {
+                    base64::decode(proof)
+                        .map_err(|err| format!("Invalid proof data '{}': {}", proof, err))
+                })
+                .collect::<Result<Vec<Vec<u8>>, String>>()?,
+        };
+        if proofs.is_empty() {
+            return Err("No proof data given.".to_owned());
+        }
+        match key {
+            Some(key) => view_state_proof(&proofs, &key, None),
+            None => Ok(ProofView {
+                proof_items: proofs.len(),
+                nodes: decode_nodes(&proofs)?
+                    .iter()
+                    .enumerate()
+                    .map(node_view)
+                    .collect(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+fn decode_nodes(proofs: &[Vec<u8>]) -> Result<Vec<RawTrieNodeWithSize>, String> {
+    decode_state_proof(proofs).map_err(|err| format!("Failed to decode proof data: {:?}", err))
+}
+
+/// Decode `proofs` and walk the nodes along `key`, checking the value hash at the end of
+/// the walk against `value` if given.
+fn view_state_proof(
+    proofs: &[Vec<u8>],
+    key: &[u8],
+    value: Option<&[u8]>,
+) -> Result<ProofView, String> {
+    let nodes = decode_nodes(proofs)?;
+    let mut node_views: Vec<NodeView> = nodes.iter().enumerate().map(node_view).collect();
+    let key_nibbles = NibbleSlice::new(key).to_nibbles();
+    let mut steps = Vec::new();
+    let mut consumed_nibbles = 0;
+    let mut last_value_hash = None;
+    if let Some(root) = node_views.first().map(|root| root.hash) {
+        for step in walk_proof(key, &nodes, &root) {
+            node_views[step.node_index as usize].on_key_path = step.hash_matched;
+            last_value_hash = step.node.value_hash();
+            steps.push(StepView {
+                node_index: step.node_index,
+                expected_hash: step.expected_hash,
+                hash_matched: step.hash_matched,
+                consumed_nibbles: (consumed_nibbles, step.consumed_nibbles),
+                next_expected_hash: step.next_expected_hash,
+            });
+            consumed_nibbles = step.consumed_nibbles;
+        }
+    }
+    let result = match steps.last() {
+        None => "There is no trie node in the proof data.".to_owned(),
+        Some(step) if !step.hash_matched => format!(
+            "Node #{} does not have the expected hash {}.",
+            step.node_index, step.expected_hash
+        ),
+        Some(step) if step.next_expected_hash.is_some() => format!(
+            "The proof data ends before node {}, which the key continues to.",
+            step.next_expected_hash.unwrap()
+        ),
+        Some(step) if consumed_nibbles == key_nibbles.len() => match last_value_hash {
+            Some(value_hash) => match value {
+                Some(value) if CryptoHash::hash_bytes(value) == value_hash => format!(
+                    "The key ends at node #{} with value hash {}, which matches the value.",
+                    step.node_index, value_hash
+                ),
+                Some(_) => format!(
+                    "The key ends at node #{} with value hash {}, which does NOT match the value.",
+                    step.node_index, value_hash
+                ),
+                None => format!(
+                    "The key ends at node #{} with value hash {}.",
+                    step.node_index, value_hash
+                ),
+            },
+            None => format!(
+                "The key ends at node #{} without a value, so it has NO value.",
+                step.node_index
+            ),
+        },
+        Some(step) => format!(
+            "The key diverges from the trie at node #{} after {} of {} nibbles, so it has NO value.",
+            step.node_index,
+            consumed_nibbles,
+            key_nibbles.len()
+        ),
+    };
+    Ok(ProofView {
+        proof_items: proofs.len(),
+        nodes: node_views,
+        key_walk: Some(KeyWalkView {
+            key: base64::encode(key),
+            key_nibbles: hex_nibbles(&key_nibbles),
+            steps,
+            result,
+        }),
+        ..Default::default()
+    })
+}
+
+fn node_view((index, node): (usize, &RawTrieNodeWithSize)) -> NodeView {
+    let children: Vec<(u8, CryptoHash)> = node
+        .children()
+        .map(|children| {
+            children
+                .iter()
+                .enumerate()
+                .filter_map(|(nibble, child)| child.map(|child| (nibble as u8, child)))
+                .collect()
+        })
+        .unwrap_or_default();
+    NodeView {
+        index,
+        hash: node.hash(),
+        node_type: format!("{:?}", node.node().node_type()),
+        key_nibbles: node
+            .key_nibbles()
+            .map(|nibbles| hex_nibbles(&nibbles.to_nibbles())),
+        children_bitmap: node.children().map(|children| {
+            children
+                .iter()
+                .enumerate()
+                .map(|(nibble, child)| match child {
+                    Some(_) => format!("{:x}", nibble),
+                    None => ".".to_owned(),
+                })
+                .collect()
+        }),
+        children,
+        child_hash: match node.node() {
+            RawTrieNode::Extension(_, child_hash) => Some(*child_hash),
+            _ => None,
+        },
+        value_length: node.node().value_length(),
+        value_hash: node.value_hash(),
+        memory_usage: node.memory_usage(),
+        on_key_path: false,
+    }
+}
+
+/// Returns the merkle paths of `proof`, in the order they are verified.
+fn view_outcome_proof(proof: &OutcomeProof, block_merkle_root: CryptoHash) -> Vec<MerklePathView> {
+    let outcome_path = view_merkle_path(
+        "outcome_proof",
+        &proof.outcome_proof,
+        CryptoHash::hash_borsh(&proof.outcome_with_id.to_hashes()),
+        // The chunk outcome root is only checked through the outcome root of the block.
+        None,
+    );
+    let outcome_root_path = view_merkle_path(
+        "outcome_root_proof",
+        &proof.outcome_root_proof,
+        CryptoHash::hash_borsh(&outcome_path.root),
+        Some(proof.block_lite_view.inner_lite.outcome_root),
+    );
+    let block_path = view_merkle_path(
+        "block_proof",
+        &proof.block_proof,
+        proof.block_lite_view.current_block_hash(),
+        Some(block_merkle_root),
+    );
+    vec![outcome_path, outcome_root_path, block_path]
+}
+
+fn view_merkle_path(
+    name: &'static str,
+    path: &MerklePath,
+    item_hash: CryptoHash,
+    expected_root: Option<CryptoHash>,
+) -> MerklePathView {
+    let mut combined_hash = item_hash;
+    let steps: Vec<MerkleStepView> = path
+        .iter()
+        .map(|item| {
+            combined_hash = match item.direction {
+                Direction::Left => combine_hash(&item.hash, &combined_hash),
+                Direction::Right => combine_hash(&combined_hash, &item.hash),
+            };
+            MerkleStepView {
+                direction: item.direction.clone(),
+                hash: item.hash,
+                combined_hash,
+            }
+        })
+        .collect();
+    MerklePathView {
+        name,
+        item_hash,
+        steps,
+        root: combined_hash,
+        expected_root,
+    }
+}
+
+fn hex_nibbles(nibbles: &[u8]) -> String {
+    nibbles
+        .iter()
+        .map(|nibble| format!("{:x}", nibble))
+        .collect()
+}
+
+fn print_proof_view(view: &ProofView) {
+    if !view.nodes.is_empty() {
+        status_info!(
+            "Info",
+            "Proof data: {} items, {} trie nodes",
+            view.proof_items,
+            view.nodes.len()
+        );
+    }
+    if let Some(in_head) = view.root_in_bundled_head {
+        if in_head {
+            status_ok!(
+                "Passed",
+                "The root node is a state root in the bundled head."
+            );
+        } else {
+            status_warn!("The root node is NOT a state root in the bundled head.");
+        }
+    }
+    for node in view.nodes.iter() {
+        status_info!(
+            "Node",
+            "{}#{} {}: hash {}, memory usage {}",
+            if node.on_key_path { "> " } else { "" },
+            node.index,
+            node.node_type,
+            node.hash,
+            node.memory_usage
+        );
+        if let Some(key_nibbles) = node.key_nibbles.as_ref() {
+            status_info!("Info", "key nibbles: {}", key_nibbles);
+        }
+        if let Some(children_bitmap) = node.children_bitmap.as_ref() {
+            status_info!("Info", "children: {}", children_bitmap);
+            for (nibble, hash) in node.children.iter() {
+                status_info!("Info", "  {:x}: {}", nibble, hash);
+            }
+        }
+        if let Some(child_hash) = node.child_hash {
+            status_info!("Info", "child: {}", child_hash);
+        }
+        if let (Some(value_length), Some(value_hash)) = (node.value_length, node.value_hash) {
+            status_info!(
+                "Info",
+                "value: length {}, hash {}",
+                value_length,
+                value_hash
+            );
+        }
+    }
+    if let Some(key_walk) = view.key_walk.as_ref() {
+        status_info!("Key", "{} (nibbles {})", key_walk.key, key_walk.key_nibbles);
+        for step in key_walk.steps.iter() {
+            let (before, after) = step.consumed_nibbles;
+            status_info!(
+                "Walk",
+                "#{} {}: consumed {} [{}] remaining {}",
+                step.node_index,
+                if step.hash_matched {
+                    "hash matched"
+                } else {
+                    "hash MISMATCHED"
+                },
+                key_walk.key_nibbles.get(..before).unwrap_or_default(),
+                key_walk.key_nibbles.get(before..after).unwrap_or_default(),
+                key_walk.key_nibbles.get(after..).unwrap_or_default(),
+            );
+        }
+        status_info!("Result", "{}", key_walk.result);
+    }
+    for path in view.merkle_paths.iter() {
+        status_info!("Merkle", "{} from item hash {}", path.name, path.item_hash);
+        for (index, step) in path.steps.iter().enumerate() {
+            status_info!(
+                "Info",
+                "#{} {:?} sibling {} -> {}",
+                index,
+                step.direction,
+                step.hash,
+                step.combined_hash
+            );
+        }
+        match path.expected_root {
+            Some(expected_root) if expected_root == path.root => {
+                status_ok!("Passed", "{} computes root {}.", path.name, path.root)
+            }
+            Some(expected_root) => status_err!(
+                "{} computes root {}, but {} is expected.",
+                path.name,
+                path.root,
+                expected_root
+            ),
+            None => status_info!("Info", "{} computes root {}.", path.name, path.root),
+        }
+    }
+}
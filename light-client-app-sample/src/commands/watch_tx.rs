@@ -0,0 +1,248 @@
+//! `watch-tx` subcommand - wait until a transaction is covered by the light client head,
+//! then verify it, exiting with a non-zero code if it can not be verified.
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::start::{prepare_light_client, sync_light_client, StopReason};
+use super::verify_transaction::verify_outcome;
+use crate::light_client::{near_rpc_client_wrapper::NearRpcClientWrapper, LightClient};
+use crate::shutdown::Shutdown;
+/// App-local prelude includes `app_reader()`/`app_writer()`/`app_config()`
+/// accessors along with logging macros. Customize as you see fit.
+use crate::{info_with_time, prelude::*};
+use abscissa_core::{Command, Runnable};
+use near_light_client::near_types::hash::CryptoHash;
+use near_light_client::BasicNearLightClient;
+use near_primitives::types::{AccountId, BlockHeight, BlockId, TransactionOrReceiptId};
+use tokio::sync::{broadcast, Mutex};
+
+/// `watch-tx` subcommand
+///
+/// The `Parser` proc macro generates an option parser based on the struct
+/// definition, and is defined in the `clap` crate. See their documentation
+/// for a more comprehensive example:
+///
+/// <https://docs.rs/clap/>
+#[derive(clap::Parser, Command, Debug)]
+pub struct WatchTxCmd {
+    /// base58 formatted transaction hash
+    pub tx_hash: String,
+    /// Account id of transaction sender
+    pub sender_id: String,
+    /// Advance the light client head in this process, use it if `start` (or `serve`)
+    /// is not running on the same data folder
+    #[arg(long)]
+    pub advance: bool,
+    /// Interval of polling the transaction and the stored heads, in seconds
+    #[arg(long, default_value_t = 5)]
+    pub poll_interval_secs: u64,
+    /// Give up after waiting for this many seconds, never if omitted
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl Runnable for WatchTxCmd {
+    /// Start the application.
+    fn run(&self) {
+        let verified =
+            abscissa_tokio::run(&APP, watch_tx(self)).expect("Failed to watch the transaction.");
+        if !verified {
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn watch_tx(cmd: &WatchTxCmd) -> bool {
+    let transaction_hash = match bs58::decode(cmd.tx_hash.clone())
+        .into_vec()
+        .ok()
+        .and_then(|bytes| CryptoHash::try_from(bytes.as_ref()).ok())
+    {
+        Some(transaction_hash) => transaction_hash,
+        None => {
+            status_err!("Invalid transaction hash '{}'.", cmd.tx_hash);
+            return false;
+        }
+    };
+    let sender_id = match AccountId::from_str(cmd.sender_id.as_str()) {
+        Ok(sender_id) => sender_id,
+        Err(err) => {
+            status_err!("Invalid sender id '{}': {}", cmd.sender_id, err);
+            return false;
+        }
+    };
+    let shutdown = Shutdown::listen();
+    if let Some(timeout_secs) = cmd.timeout_secs {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if !shutdown.sleep(Duration::from_secs(timeout_secs)).await {
+                status_err!("Timed out after {} seconds.", timeout_secs);
+                shutdown.request();
+            }
+        });
+    }
+    let poll_interval = Duration::from_secs(cmd.poll_interval_secs);
+    let rpc_client = Arc::new(NearRpcClientWrapper::from_config(&APP.config().near_rpc));
+    let block_height = match wait_for_inclusion(
+        &rpc_client,
+        &transaction_hash,
+        &sender_id,
+        poll_interval,
+        &shutdown,
+    )
+    .await
+    {
+        Some(block_height) => block_height,
+        None => return false,
+    };
+    let started_at = Instant::now();
+    let covered = if cmd.advance {
+        advance_head(rpc_client, block_height, &shutdown).await
+    } else {
+        wait_for_head(block_height, poll_interval, &shutdown).await
+    };
+    if !covered {
+        return false;
+    }
+    info_with_time!(
+        "Block {} is covered by the light client head after {:?}.",
+        block_height,
+        started_at.elapsed()
+    );
+    let report = verify_outcome(
+        TransactionOrReceiptId::Transaction {
+            transaction_hash: near_primitives::hash::CryptoHash(transaction_hash.0),
+            sender_id,
+        },
+        transaction_hash,
+        None,
+    )
+    .await
+    .0;
+    report.print();
+    report.verified
+}
+
+/// Poll the transaction until it is executed, and returns the height of the block
+/// including it, or `None` if the shutdown is requested first.
+async fn wait_for_inclusion(
+    rpc_client: &NearRpcClientWrapper,
+    transaction_hash: &CryptoHash,
+    sender_id: &AccountId,
+    poll_interval: Duration,
+    shutdown: &Shutdown,
+) -> Option<BlockHeight> {
+    let tx_hash = near_primitives::hash::CryptoHash(transaction_hash.0);
+    loop {
+        match rpc_client.view_tx_status(&tx_hash, sender_id).await {
+            Ok(outcome) => {
+                let block_hash = outcome.transaction_outcome.block_hash;
+                match rpc_client
+                    .view_block(&Some(BlockId::Hash(block_hash)))
+                    .await
+                {
+                    Ok(block) => {
+                        info_with_time!(
+                            "Transaction {} is included in block {} at height {}.",
+                            transaction_hash,
+                            block_hash,
+                            block.header.height
+                        );
+                        return Some(block.header.height);
+                    }
+                    Err(err) => info_with_time!("Failed to view block {}: {:?}", block_hash, err),
+                }
+            }
+            Err(err) => info_with_time!(
+                "Transaction {} is not executed yet: {:?}",
+                transaction_hash,
+                err
+            ),
+        }
+        if shutdown.sleep(poll_interval).await {
+            return None;
+        }
+    }
+}
+
+/// Wait until the latest stored head, advanced by another process, is above `block_height`.
+///
+/// The block proof of an outcome is against the block merkle root of the head, which
+/// only covers the blocks before the head.
+async fn wait_for_head(
+    block_height: BlockHeight,
+    poll_interval: Duration,
+    shutdown: &Shutdown,
+) -> bool {
+    loop {
+        let latest_height = LightClient::from_config(&APP.config().state_data)
+            .latest_height()
+            .revision_height;
+        if latest_height > block_height {
+            return true;
+        }
+        info_with_time!(
+            "Waiting for a head above height {}, the latest head is at height {}.",
+            block_height,
+            latest_height
+        );
+        if shutdown.sleep(poll_interval).await {
+            return false;
+        }
+    }
+}
+
+/// Synchronize the light client in this process until the latest head is above
+/// `block_height`.
+async fn advance_head(
+    rpc_client: Arc<NearRpcClientWrapper>,
+    block_height: BlockHeight,
+    shutdown: &Shutdown,
+) -> bool {
+    let light_client = match prepare_light_client(&rpc_client, false, shutdown).await {
+        Some(light_client) => Arc::new(Mutex::new(light_client)),
+        None => return false,
+    };
+    if light_client.lock().await.latest_height().revision_height > block_height {
+        return true;
+    }
+    // Stop the sync loop by its own shutdown handle once the head is high enough.
+    let sync_shutdown = Shutdown::default();
+    let (heads, mut heads_rx) = broadcast::channel(1);
+    let watch = async {
+        let covered = loop {
+            tokio::select! {
+                head = heads_rx.recv() => match head {
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                        if light_client.lock().await.latest_height().revision_height
+                            > block_height
+                        {
+                            break true;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break false,
+                },
+                _ = shutdown.wait() => break false,
+            }
+        };
+        sync_shutdown.request();
+        covered
+    };
+    let (stop_reason, covered) = tokio::join!(
+        sync_light_client(
+            rpc_client,
+            light_client.clone(),
+            heads,
+            sync_shutdown.clone()
+        ),
+        watch
+    );
+    if stop_reason == StopReason::Error {
+        status_err!("The light client stopped synchronizing due to an error.");
+        return false;
+    }
+    covered
+}
@@ -14,6 +14,21 @@ pub struct LightClientAppSampleConfig {
     pub near_rpc: NearRpcSection,
     /// Configuration for state data of NEAR light client.
     pub state_data: StateDataSection,
+    /// Configuration for synchronizing headers from NEAR rpc.
+    #[serde(default)]
+    pub sync: SyncSection,
+    /// Configuration for alerting on the latest verified head falling behind.
+    #[serde(default)]
+    pub alerting: AlertingSection,
+    /// Configuration for the API server of the `serve` subcommand.
+    #[serde(default)]
+    pub server: ServerSection,
+    /// Configuration for relaying the verified headers by the `relay` subcommand.
+    #[serde(default)]
+    pub relay: RelaySection,
+    /// Configuration for the keystore of the signing keys of the relayer.
+    #[serde(default)]
+    pub keystore: KeystoreSection,
 }
 
 /// Default configuration settings.
@@ -25,6 +40,11 @@ impl Default for LightClientAppSampleConfig {
         Self {
             near_rpc: NearRpcSection::default(),
             state_data: StateDataSection::default(),
+            sync: SyncSection::default(),
+            alerting: AlertingSection::default(),
+            server: ServerSection::default(),
+            relay: RelaySection::default(),
+            keystore: KeystoreSection::default(),
         }
     }
 }
@@ -35,12 +55,25 @@ impl Default for LightClientAppSampleConfig {
 pub struct NearRpcSection {
     /// Endpoint of the RPC service. Should be a valid URL.
     pub rpc_endpoint: String,
+    /// Endpoint of the RPC service to re-fetch the headers failed to verify from.
+    #[serde(default)]
+    pub fallback_rpc_endpoint: Option<String>,
+    /// Endpoints of the RPC services to fail over to in order, when the above one fails.
+    #[serde(default)]
+    pub failover_rpc_endpoints: Vec<String>,
+    /// The count of RPC services which must know the block of a light client block before
+    /// it is accepted, 0 or 1 to accept it from a single RPC service.
+    #[serde(default)]
+    pub light_client_block_quorum: u64,
 }
 
 impl Default for NearRpcSection {
     fn default() -> Self {
         Self {
             rpc_endpoint: "https://rpc.testnet.near.org".to_owned(),
+            fallback_rpc_endpoint: None,
+            failover_rpc_endpoints: Vec::new(),
+            light_client_block_quorum: 0,
         }
     }
 }
@@ -51,15 +84,345 @@ impl Default for NearRpcSection {
 pub struct StateDataSection {
     /// The folder which stores state data files.
     pub data_folder: String,
-    /// The max height count of cached head data.
-    pub max_cached_heights: u64,
+    /// The pruning policy of the stored heads.
+    #[serde(default)]
+    pub pruning: PruningSection,
+    /// The storage backend of state data.
+    #[serde(default)]
+    pub backend: StorageBackend,
+    /// The compression of the stored consensus states and block producers.
+    #[serde(default)]
+    pub compression: StorageCompression,
+    /// Options of the in-memory cache of the stored heads.
+    #[serde(default)]
+    pub cache: CacheSection,
+    /// Options of the RocksDB backend.
+    #[serde(default)]
+    pub rocksdb: RocksDbSection,
 }
 
 impl Default for StateDataSection {
     fn default() -> Self {
         Self {
             data_folder: "./tmp/chain_data/testnet".to_owned(),
-            max_cached_heights: 100,
+            pruning: PruningSection::default(),
+            backend: StorageBackend::default(),
+            compression: StorageCompression::default(),
+            cache: CacheSection::default(),
+            rocksdb: RocksDbSection::default(),
+        }
+    }
+}
+
+/// Configuration settings for pruning the stored heads.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PruningSection {
+    /// The count of the most recent heads to keep.
+    pub keep_recent_heights: u64,
+    /// The count of the most recent epochs to keep the first stored head of.
+    pub keep_epochs: u64,
+}
+
+impl Default for PruningSection {
+    fn default() -> Self {
+        Self {
+            keep_recent_heights: 100,
+            keep_epochs: 2,
+        }
+    }
+}
+
+/// Configuration settings for the in-memory LRU cache of the stored heads.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheSection {
+    /// The max count of cached heads, and of cached epoch block producers, 0 to disable
+    /// the cache.
+    pub capacity: u64,
+}
+
+impl Default for CacheSection {
+    fn default() -> Self {
+        Self { capacity: 256 }
+    }
+}
+
+/// Storage backends of state data.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// One file per head in sub folders of the data folder.
+    #[default]
+    File,
+    /// A sled database in sub folder `sled` of the data folder.
+    Sled,
+    /// A RocksDB database in sub folder `rocksdb` of the data folder, requires the
+    /// `rocksdb` feature.
+    Rocksdb,
+    /// An SQLite database in file `sqlite.db` of the data folder, requires the `sqlite`
+    /// feature.
+    Sqlite,
+}
+
+/// Compressions of the stored blobs of state data, the stored blobs are decompressed
+/// transparently on reading whatever the configured compression is.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageCompression {
+    /// Store the borsh serialization as is.
+    #[default]
+    None,
+    /// Compress the borsh serialization by zstd.
+    Zstd,
+}
+
+/// Configuration settings for the RocksDB backend.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RocksDbSection {
+    /// The size of the block cache in MiB.
+    pub cache_size_mb: u64,
+    /// The compaction style of the column families.
+    pub compaction_style: RocksDbCompactionStyle,
+}
+
+impl Default for RocksDbSection {
+    fn default() -> Self {
+        Self {
+            cache_size_mb: 128,
+            compaction_style: RocksDbCompactionStyle::Level,
+        }
+    }
+}
+
+/// Compaction styles of RocksDB.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RocksDbCompactionStyle {
+    /// Suited to read heavy workloads.
+    Level,
+    /// Suited to write heavy workloads, such as syncing a lot of headers.
+    Universal,
+}
+
+/// Configuration settings for synchronizing headers from NEAR rpc.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SyncSection {
+    /// Whether to check that each new header descends from the latest trusted header.
+    pub verify_chained: bool,
+    /// Whether to stop verifying approval signatures once the approved stake is enough.
+    pub fast_finality_check: bool,
+    /// The interval in milliseconds to wait after each batch of headers, once the light
+    /// client has caught up with the chain.
+    pub poll_interval_ms: u64,
+    /// The max count of headers to synchronize in each iteration of the sync loop.
+    pub batch_size: u64,
+    /// The initial backoff in milliseconds after an empty response or an RPC failure,
+    /// which is doubled on each consecutive one.
+    pub backoff_ms: u64,
+    /// The max backoff in milliseconds.
+    pub max_backoff_ms: u64,
+    /// The max count of heights the light client may lag behind the chain head without
+    /// synchronizing immediately, instead of waiting `poll_interval_ms`.
+    pub max_lag_heights: u64,
+    /// The count of times to re-fetch and verify a header again after it failed to
+    /// verify, before quarantining it to the failed heads and stopping.
+    pub verify_retries: u64,
+    /// The max count of headers fetched ahead of the header being verified and saved.
+    pub prefetch_depth: u64,
+    /// The count of heights the light client must lag behind the chain head to catch up
+    /// by only the last header of each epoch first, 0 to disable.
+    pub fast_sync_lag_heights: u64,
+}
+
+impl Default for SyncSection {
+    fn default() -> Self {
+        Self {
+            verify_chained: false,
+            fast_finality_check: false,
+            poll_interval_ms: 1000,
+            batch_size: 10,
+            backoff_ms: 1000,
+            max_backoff_ms: 60000,
+            max_lag_heights: 100,
+            verify_retries: 3,
+            prefetch_depth: 4,
+            fast_sync_lag_heights: 43200,
+        }
+    }
+}
+
+/// Configuration settings for alerting on the latest verified head falling behind.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct AlertingSection {
+    /// URL of the webhook to post the alerts to, as the `text` of a JSON object, the alerts
+    /// are only logged if omitted.
+    pub webhook_url: Option<String>,
+    /// The interval in seconds to check the latest verified head.
+    pub check_interval_secs: u64,
+    /// The max age in seconds of the latest verified head by its timestamp, 0 to disable.
+    pub max_head_age_secs: u64,
+    /// The max count of heights the latest verified head may lag behind the chain head
+    /// reported by RPC, 0 to disable.
+    pub max_lag_heights: u64,
+}
+
+impl Default for AlertingSection {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            check_interval_secs: 60,
+            max_head_age_secs: 600,
+            max_lag_heights: 0,
+        }
+    }
+}
+
+/// Configuration settings for the API server of the `serve` subcommand.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ServerSection {
+    /// The socket address to listen on for the REST API.
+    pub listen_address: String,
+    /// The socket address to listen on for the gRPC service, not served if `None`.
+    pub grpc_listen_address: Option<String>,
+    /// The maximum age in seconds of the latest head for `/readyz` to report ready,
+    /// 0 to disable the check.
+    pub ready_max_head_age_secs: u64,
+}
+
+impl Default for ServerSection {
+    fn default() -> Self {
+        Self {
+            listen_address: "127.0.0.1:8080".to_owned(),
+            grpc_listen_address: None,
+            ready_max_head_age_secs: 600,
+        }
+    }
+}
+
+/// Configuration settings for relaying the verified headers to a destination.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RelaySection {
+    /// The destination to submit the verified headers to.
+    pub destination: RelayDestination,
+    /// The count of retries of a failed submission, before the relayer stops.
+    pub retries: u64,
+    /// The interval in milliseconds between the retries of a failed submission.
+    pub retry_interval_ms: u64,
+    /// Configuration of the NEAR verifier contract destination.
+    pub near: NearDestinationSection,
+    /// Configuration of the ICS-08 wasm client destination on a Cosmos chain.
+    pub cosmos: CosmosDestinationSection,
+}
+
+impl Default for RelaySection {
+    fn default() -> Self {
+        Self {
+            destination: RelayDestination::default(),
+            retries: 3,
+            retry_interval_ms: 3000,
+            near: NearDestinationSection::default(),
+            cosmos: CosmosDestinationSection::default(),
+        }
+    }
+}
+
+/// Destinations to relay the verified headers to.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RelayDestination {
+    /// A NEAR verifier contract, i.e. `near-light-client-contract`.
+    #[default]
+    Near,
+    /// An ICS-08 wasm light client on a Cosmos chain.
+    Cosmos,
+}
+
+/// Configuration settings for relaying to a NEAR verifier contract.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct NearDestinationSection {
+    /// Endpoint of the RPC service of the NEAR network the contract is deployed on.
+    pub rpc_endpoint: String,
+    /// Account id of the verifier contract.
+    pub contract_id: String,
+    /// Reference of the key of the account signing the transactions, whose secret is the
+    /// content of a NEAR credentials file, e.g. `keystore:<name>`, `env:<VAR>` or
+    /// `file:<path>`.
+    pub signer_key: String,
+    /// Gas attached to each `update_state` call.
+    pub gas: u64,
+}
+
+impl Default for NearDestinationSection {
+    fn default() -> Self {
+        Self {
+            rpc_endpoint: String::new(),
+            contract_id: String::new(),
+            signer_key: String::new(),
+            gas: 300_000_000_000_000,
+        }
+    }
+}
+
+/// Configuration settings for relaying to an ICS-08 wasm light client on a Cosmos chain.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct CosmosDestinationSection {
+    /// Endpoint of the Tendermint RPC service of the Cosmos chain.
+    pub rpc_endpoint: String,
+    /// Chain id of the Cosmos chain.
+    pub chain_id: String,
+    /// Id of the ICS-08 wasm light client, e.g. `08-wasm-0`.
+    pub client_id: String,
+    /// Bech32 prefix of the account addresses of the Cosmos chain.
+    pub account_prefix: String,
+    /// Reference of the hex formatted secp256k1 secret key of the account signing the
+    /// transactions, e.g. `keystore:<name>`, `env:<VAR>` or `file:<path>`.
+    pub signer_key: String,
+    /// Gas limit of each `MsgUpdateClient` transaction.
+    pub gas_limit: u64,
+    /// Denomination of the fee.
+    pub fee_denom: String,
+    /// Amount of the fee of each transaction.
+    pub fee_amount: u128,
+}
+
+impl Default for CosmosDestinationSection {
+    fn default() -> Self {
+        Self {
+            rpc_endpoint: String::new(),
+            chain_id: String::new(),
+            client_id: String::new(),
+            account_prefix: "cosmos".to_owned(),
+            signer_key: String::new(),
+            gas_limit: 2_000_000,
+            fee_denom: "stake".to_owned(),
+            fee_amount: 5000,
+        }
+    }
+}
+
+/// Configuration settings for the keystore of the signing keys.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct KeystoreSection {
+    /// The folder of the encrypted key files.
+    pub folder: String,
+    /// The environment variable holding the passphrase of the encrypted key files.
+    pub passphrase_env: String,
+}
+
+impl Default for KeystoreSection {
+    fn default() -> Self {
+        Self {
+            folder: "./keys".to_owned(),
+            passphrase_env: "LIGHT_CLIENT_KEYSTORE_PASSPHRASE".to_owned(),
         }
     }
 }
@@ -0,0 +1,161 @@
+//! gRPC server of NEAR light client, defined in `proto/near/lightclient/app/v1/service.proto`.
+//!
+//! The service shares the state and the verifications of the REST API in [`crate::server`].
+
+use std::{net::SocketAddr, pin::Pin, str::FromStr};
+
+use axum::http::StatusCode;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+use crate::server::{self, ApiError, HeadSummary, ServerState, VerificationResponse};
+use crate::shutdown::Shutdown;
+
+/// Messages and service traits generated from the proto files.
+#[allow(
+    missing_docs,
+    unused_qualifications,
+    clippy::derive_partial_eq_without_eq
+)]
+pub mod v1 {
+    tonic::include_proto!("near.lightclient.app.v1");
+}
+
+use v1::light_client_service_server::{LightClientService, LightClientServiceServer};
+
+/// Implementation of the gRPC service over the shared state of the server.
+pub struct GrpcService {
+    state: ServerState,
+}
+
+impl GrpcService {
+    /// Create the service over `state`.
+    pub fn new(state: ServerState) -> Self {
+        Self { state }
+    }
+}
+
+/// Serve the gRPC service on `listen_address` until the shutdown is requested.
+pub async fn serve(
+    listen_address: String,
+    state: ServerState,
+    shutdown: Shutdown,
+) -> Result<(), String> {
+    let address = SocketAddr::from_str(listen_address.as_str())
+        .map_err(|err| format!("Invalid listen address '{}': {:?}", listen_address, err))?;
+    tonic::transport::Server::builder()
+        .add_service(LightClientServiceServer::new(GrpcService::new(state)))
+        .serve_with_shutdown(address, async move { shutdown.wait().await })
+        .await
+        .map_err(|err| format!("gRPC server failed: {:?}", err))
+}
+
+#[tonic::async_trait]
+impl LightClientService for GrpcService {
+    async fn get_head(
+        &self,
+        _request: Request<v1::GetHeadRequest>,
+    ) -> Result<Response<v1::HeadSummary>, Status> {
+        let head = server::head_at(&self.state, None).await?;
+        Ok(Response::new(HeadSummary::of(&head).into()))
+    }
+
+    async fn get_consensus_state(
+        &self,
+        request: Request<v1::GetConsensusStateRequest>,
+    ) -> Result<Response<v1::GetConsensusStateResponse>, Status> {
+        let head = server::head_at(&self.state, Some(request.into_inner().height)).await?;
+        Ok(Response::new(v1::GetConsensusStateResponse {
+            summary: Some(HeadSummary::of(&head).into()),
+            consensus_state: Some(head.into()),
+        }))
+    }
+
+    type SubscribeHeadsStream =
+        Pin<Box<dyn Stream<Item = Result<v1::HeadSummary, Status>> + Send + 'static>>;
+
+    async fn subscribe_heads(
+        &self,
+        _request: Request<v1::SubscribeHeadsRequest>,
+    ) -> Result<Response<Self::SubscribeHeadsStream>, Status> {
+        let heads = BroadcastStream::new(self.state.heads.subscribe()).map(|head| match head {
+            Ok(head) => Ok(head.into()),
+            Err(err) => Err(Status::data_loss(format!(
+                "The subscriber lags behind: {}",
+                err
+            ))),
+        });
+        Ok(Response::new(Box::pin(heads)))
+    }
+
+    async fn verify_membership(
+        &self,
+        request: Request<v1::VerifyMembershipRequest>,
+    ) -> Result<Response<v1::VerificationResult>, Status> {
+        let request = request.into_inner();
+        let response = server::verify_membership_at(
+            &self.state,
+            &request.account_id,
+            &request.storage_key,
+            &request.value,
+            latest_if_zero(request.height),
+        )
+        .await?;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn verify_transaction(
+        &self,
+        request: Request<v1::VerifyTransactionRequest>,
+    ) -> Result<Response<v1::VerificationResult>, Status> {
+        let request = request.into_inner();
+        let response = server::verify_transaction_at(
+            &self.state,
+            &request.tx_hash,
+            &request.sender_id,
+            latest_if_zero(request.height),
+        )
+        .await?;
+        Ok(Response::new(response.into()))
+    }
+}
+
+/// Height 0 in requests stands for the latest head.
+fn latest_if_zero(height: u64) -> Option<u64> {
+    match height {
+        0 => None,
+        height => Some(height),
+    }
+}
+
+impl From<HeadSummary> for v1::HeadSummary {
+    fn from(summary: HeadSummary) -> Self {
+        Self {
+            height: summary.height,
+            block_hash: summary.block_hash.0.to_vec(),
+            epoch_id: summary.epoch_id.0.to_vec(),
+            next_epoch_id: summary.next_epoch_id.0.to_vec(),
+            timestamp: summary.timestamp,
+        }
+    }
+}
+
+impl From<VerificationResponse> for v1::VerificationResult {
+    fn from(response: VerificationResponse) -> Self {
+        Self {
+            height: response.height,
+            verified: response.verified,
+            error: response.error.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ApiError> for Status {
+    fn from(err: ApiError) -> Self {
+        match err.status() {
+            StatusCode::BAD_REQUEST => Status::invalid_argument(err.message()),
+            StatusCode::NOT_FOUND => Status::not_found(err.message()),
+            _ => Status::internal(err.message()),
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Keystore of the signing keys of the relayer.
+//!
+//! A signing key is referred to in the configuration by one of:
+//!
+//! - `keystore:<name>`: file `<name>.json` in the keystore folder, encrypted by a key derived
+//!   from the passphrase in the environment variable `keystore.passphrase_env`
+//! - `env:<VAR>`: the secret injected by environment variable `VAR`
+//! - `file:<path>`: a plaintext file, e.g. a NEAR credentials file
+//!
+//! The secret of a key is the content of a NEAR credentials file (JSON with `account_id`,
+//! `public_key` and `secret_key`) for NEAR, or a hex formatted secp256k1 secret key for
+//! Cosmos chains.
+
+use std::path::{Path, PathBuf};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::config::KeystoreSection;
+
+const KEY_FILE_VERSION: u32 = 1;
+const SALT_LENGTH: usize = 16;
+
+/// An encrypted key file of the keystore.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KeyFile {
+    /// Version of the format
+    pub version: u32,
+    /// Public description of the key, e.g. the account id and the public key, which is
+    /// authenticated as the associated data of the encryption
+    pub public: String,
+    /// base64 formatted salt of the argon2id key derivation
+    pub salt: String,
+    /// base64 formatted nonce of the ChaCha20-Poly1305 encryption
+    pub nonce: String,
+    /// base64 formatted encrypted secret
+    pub ciphertext: String,
+}
+
+/// Keystore of the signing keys, in the folder and with the passphrase configured.
+pub struct Keystore {
+    folder: PathBuf,
+    passphrase_env: String,
+}
+
+impl Keystore {
+    /// Create the keystore in the given configuration.
+    pub fn from_config(config: &KeystoreSection) -> Self {
+        Self {
+            folder: PathBuf::from(&config.folder),
+            passphrase_env: config.passphrase_env.clone(),
+        }
+    }
+
+    /// Returns the secret of the key referred to by `reference`.
+    pub fn resolve(&self, reference: &str) -> anyhow::Result<Zeroizing<String>> {
+        match reference.split_once(':') {
+            Some(("keystore", name)) => self.decrypt(name),
+            Some(("env", var)) => std::env::var(var)
+                .map(Zeroizing::new)
+                .map_err(|err| anyhow::anyhow!("Failed to read secret from ${}: {:?}", var, err)),
+            Some(("file", path)) => std::fs::read_to_string(path)
+                .map(Zeroizing::new)
+                .map_err(|err| anyhow::anyhow!("Failed to read key file {}: {:?}", path, err)),
+            _ => anyhow::bail!(
+                "Invalid key reference '{}', expected `keystore:<name>`, `env:<VAR>` or `file:<path>`.",
+                reference
+            ),
+        }
+    }
+
+    /// Returns the names of the keys in the keystore.
+    pub fn names(&self) -> anyhow::Result<Vec<String>> {
+        if !self.folder.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = std::fs::read_dir(&self.folder)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                match path.extension().and_then(|extension| extension.to_str()) {
+                    Some("json") => path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .map(str::to_owned),
+                    _ => None,
+                }
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the key file of key `name`, without decrypting it.
+    pub fn key_file(&self, name: &str) -> anyhow::Result<KeyFile> {
+        let path = self.path_of(name)?;
+        let content = std::fs::read_to_string(&path)
+            .map_err(|err| anyhow::anyhow!("Failed to read {}: {:?}", path.display(), err))?;
+        let key_file: KeyFile = serde_json::from_str(&content)?;
+        if key_file.version != KEY_FILE_VERSION {
+            anyhow::bail!(
+                "Unsupported version {} of key file {}.",
+                key_file.version,
+                path.display()
+            );
+        }
+        Ok(key_file)
+    }
+
+    /// Encrypt `secret` with the passphrase, and store it as key `name`.
+    ///
+    /// Returns the path of the key file.
+    pub fn import(&self, name: &str, secret: &str, overwrite: bool) -> anyhow::Result<PathBuf> {
+        let path = self.path_of(name)?;
+        if path.exists() && !overwrite {
+            anyhow::bail!("Key {} already exists in {}.", name, path.display());
+        }
+        let public = describe_secret(secret)?;
+        let passphrase = self.passphrase()?;
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = ChaCha20Poly1305::new(&derive_key(&passphrase, &salt)?);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: secret.as_bytes(),
+            aad: public.as_bytes(),
+        };
+        let ciphertext = cipher
+            .encrypt(&nonce, payload)
+            .map_err(|err| anyhow::anyhow!("Failed to encrypt key: {:?}", err))?;
+        let key_file = KeyFile {
+            version: KEY_FILE_VERSION,
+            public,
+            salt: base64::encode(salt),
+            nonce: base64::encode(nonce),
+            ciphertext: base64::encode(ciphertext),
+        };
+        std::fs::create_dir_all(&self.folder)?;
+        write_private(&path, serde_json::to_string_pretty(&key_file)?.as_bytes())?;
+        Ok(path)
+    }
+
+    /// Returns the decrypted secret of key `name`.
+    pub fn decrypt(&self, name: &str) -> anyhow::Result<Zeroizing<String>> {
+        let key_file = self.key_file(name)?;
+        let passphrase = self.passphrase()?;
+        let salt = base64::decode(&key_file.salt)?;
+        let nonce = base64::decode(&key_file.nonce)?;
+        let ciphertext = base64::decode(&key_file.ciphertext)?;
+        if nonce.len() != 12 {
+            anyhow::bail!("Invalid nonce of key {}.", name);
+        }
+        let cipher = ChaCha20Poly1305::new(&derive_key(&passphrase, &salt)?);
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: key_file.public.as_bytes(),
+        };
+        let secret = cipher
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Failed to decrypt key {}, wrong passphrase or tampered key file?",
+                    name
+                )
+            })?;
+        Ok(Zeroizing::new(String::from_utf8(secret)?))
+    }
+
+    fn passphrase(&self) -> anyhow::Result<Zeroizing<String>> {
+        match std::env::var(&self.passphrase_env) {
+            Ok(passphrase) if !passphrase.is_empty() => Ok(Zeroizing::new(passphrase)),
+            _ => anyhow::bail!(
+                "Missing the passphrase of the keystore in ${}.",
+                self.passphrase_env
+            ),
+        }
+    }
+
+    fn path_of(&self, name: &str) -> anyhow::Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !name.starts_with('.');
+        if !valid {
+            anyhow::bail!("Invalid key name '{}'.", name);
+        }
+        Ok(self.folder.join(format!("{}.json", name)))
+    }
+}
+
+/// Returns the public description of `secret`, which also checks its format.
+pub fn describe_secret(secret: &str) -> anyhow::Result<String> {
+    if let Ok(credentials) = serde_json::from_str::<NearCredentials>(secret) {
+        return Ok(format!(
+            "NEAR account {} ({})",
+            credentials.account_id,
+            credentials.secret_key.public_key()
+        ));
+    }
+    let key_bytes = hex::decode(secret.trim()).map_err(|_| {
+        anyhow::anyhow!("The secret is neither NEAR credentials nor a hex formatted secp256k1 key.")
+    })?;
+    let signing_key = cosmrs::crypto::secp256k1::SigningKey::from_slice(&key_bytes)
+        .map_err(|err| anyhow::anyhow!("Invalid secp256k1 key: {:?}", err))?;
+    Ok(format!(
+        "secp256k1 key {}",
+        hex::encode(signing_key.public_key().to_bytes())
+    ))
+}
+
+/// Content of a NEAR credentials file.
+#[derive(Deserialize)]
+pub struct NearCredentials {
+    /// The account the key belongs to
+    pub account_id: near_primitives::types::AccountId,
+    /// The secret key, named `private_key` in some tools
+    #[serde(alias = "private_key")]
+    pub secret_key: near_crypto::SecretKey,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> anyhow::Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| anyhow::anyhow!("Failed to derive key: {:?}", err))?;
+    Ok(key)
+}
+
+/// Write `content` to `path`, readable and writable by the owner only on unix.
+///
+/// The content is written to a new temporary file first, which is renamed to `path`, as
+/// the mode only applies to the files created, not to an existing file at `path`.
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let temp_path = path.with_extension("json.tmp");
+    match std::fs::remove_file(&temp_path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+        _ => (),
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&temp_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&temp_path, path)
+}
@@ -15,10 +15,19 @@
     unused_qualifications
 )]
 
+pub mod alerting;
 pub mod application;
 pub mod commands;
 pub mod config;
 pub mod error;
+pub mod grpc;
+pub mod keystore;
 pub mod light_client;
+pub mod metrics;
+pub mod output;
+pub mod prefetch;
 pub mod prelude;
+pub mod relayer;
+pub mod server;
+pub mod shutdown;
 pub mod utils;
@@ -1,130 +1,431 @@
 //! LightClient implementation
 //!
 
+pub mod bundle;
+pub mod check;
+pub mod checkpoint;
+pub mod forensics;
 pub mod near_rpc_client_wrapper;
+pub mod pruning;
+pub mod snapshot;
+pub mod store;
 pub mod utils;
 
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    num::NonZeroUsize,
+};
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use near_light_client::{
-    near_types::{hash::CryptoHash, BlockHeight, ValidatorStakeView},
-    types::{ConsensusState, Header, Height},
-    BasicNearLightClient,
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::{ConsensusState, Height, SealedHeader},
+    BasicNearLightClient, StorageError,
 };
 
-const HEAD_DATA_SUB_FOLDER: &str = "head";
+use self::check::HeadIssue;
+use self::snapshot::Snapshot;
+use self::store::{
+    cached_store::CachedHeadStore, overlay_store::OverlayHeadStore, sled_store::SledHeadStore,
+    CacheStats, FileHeadStore, HeadStore,
+};
+use crate::config::{PruningSection, StateDataSection, StorageBackend};
 
-#[derive(BorshDeserialize, BorshSerialize)]
-struct BlockProducers(Vec<ValidatorStakeView>);
+const SLED_SUB_FOLDER: &str = "sled";
+#[cfg(feature = "rocksdb")]
+const ROCKSDB_SUB_FOLDER: &str = "rocksdb";
+#[cfg(feature = "sqlite")]
+const SQLITE_FILE: &str = "sqlite.db";
 
 ///
 pub struct LightClient {
-    base_folder: String,
+    store: Box<dyn HeadStore>,
     cached_heights: VecDeque<BlockHeight>,
+    stored_epoch_ids: BTreeSet<CryptoHash>,
+    /// Epoch ids of the stored heads by their heights, loaded on the first pruning
+    head_epoch_ids: Option<BTreeMap<BlockHeight, CryptoHash>>,
+    fast_finality_check: bool,
 }
 
 impl BasicNearLightClient for LightClient {
     fn latest_height(&self) -> Height {
-        self.cached_heights.back().map_or(0, |h| *h)
+        Height::from_block_height(self.cached_heights.back().map_or(0, |h| *h))
     }
 
+    /// Returns `None` if the head at `height` fails to be read, which is logged, use
+    /// [`LightClient::get_head`] to handle the failure.
     fn get_consensus_state(&self, height: &Height) -> Option<ConsensusState> {
-        let file_name = format!("{}/{}/{}", self.base_folder, HEAD_DATA_SUB_FOLDER, height);
-        if let Ok(bytes) = std::fs::read(file_name) {
-            return Some(
-                ConsensusState::try_from_slice(&bytes)
-                    .expect(format!("Invalid head data file for height {}.", height).as_str()),
-            );
-        }
-        None
+        let height = height.revision_height;
+        self.get_head(height).unwrap_or_else(|err| {
+            tracing::error!("Failed to read head at height {}: {:?}", height, err);
+            None
+        })
+    }
+
+    fn fast_finality_check(&self) -> bool {
+        self.fast_finality_check
     }
 }
 
 impl LightClient {
-    /// Create light client from a trusted head
+    /// Create light client with the heads stored in files in `base_folder`
     pub fn new(base_folder: String) -> Self {
-        let (queue, _map) = get_cached_heights(&base_folder);
+        Self::with_store(Box::new(
+            FileHeadStore::open(base_folder).expect("Failed to open head data folder."),
+        ))
+    }
+    /// Create light client with the storage backend in the given configuration
+    pub fn from_config(config: &StateDataSection) -> Self {
+        let store: Box<dyn HeadStore> = match config.backend {
+            StorageBackend::File => Box::new(
+                FileHeadStore::open(&config.data_folder)
+                    .expect("Failed to open head data folder.")
+                    .with_compression(config.compression),
+            ),
+            StorageBackend::Sled => Box::new(
+                SledHeadStore::open(format!("{}/{}", config.data_folder, SLED_SUB_FOLDER))
+                    .expect("Failed to open sled database.")
+                    .with_compression(config.compression),
+            ),
+            #[cfg(feature = "rocksdb")]
+            StorageBackend::Rocksdb => Box::new(
+                store::rocksdb_store::RocksDbHeadStore::open(
+                    format!("{}/{}", config.data_folder, ROCKSDB_SUB_FOLDER),
+                    &config.rocksdb,
+                )
+                .expect("Failed to open RocksDB database.")
+                .with_compression(config.compression),
+            ),
+            #[cfg(not(feature = "rocksdb"))]
+            StorageBackend::Rocksdb => {
+                panic!("Storage backend `rocksdb` requires the `rocksdb` feature.")
+            }
+            #[cfg(feature = "sqlite")]
+            StorageBackend::Sqlite => Box::new(
+                store::sqlite_store::SqliteHeadStore::open(format!(
+                    "{}/{}",
+                    config.data_folder, SQLITE_FILE
+                ))
+                .expect("Failed to open SQLite database.")
+                .with_compression(config.compression),
+            ),
+            #[cfg(not(feature = "sqlite"))]
+            StorageBackend::Sqlite => {
+                panic!("Storage backend `sqlite` requires the `sqlite` feature.")
+            }
+        };
+        match NonZeroUsize::new(config.cache.capacity as usize) {
+            Some(capacity) => Self::with_store(Box::new(CachedHeadStore::new(store, capacity))),
+            None => Self::with_store(store),
+        }
+    }
+    /// Create light client with the heads in the given store
+    pub fn with_store(store: Box<dyn HeadStore>) -> Self {
+        let heights = store
+            .heights()
+            .expect("Failed to list heights of head data.");
+        let epoch_ids = store
+            .epoch_ids()
+            .expect("Failed to list epoch ids of block producers.");
         LightClient {
-            base_folder: base_folder.clone(),
-            cached_heights: queue,
+            store,
+            cached_heights: heights.into(),
+            stored_epoch_ids: epoch_ids.into_iter().collect(),
+            head_epoch_ids: None,
+            fast_finality_check: false,
         }
     }
+    /// Keep all the following writes in memory, so that the stored state is never changed
+    pub fn dry_run(self) -> Self {
+        let fast_finality_check = self.fast_finality_check;
+        Self::with_store(Box::new(OverlayHeadStore::new(self.store)))
+            .with_fast_finality_check(fast_finality_check)
+    }
+    /// Stop verifying approval signatures once the block is final
+    pub fn with_fast_finality_check(mut self, fast_finality_check: bool) -> Self {
+        self.fast_finality_check = fast_finality_check;
+        self
+    }
+    /// Returns the identifier of the chain the data folder is bound to,
+    /// `None` if the data folder is not bound yet
+    pub fn chain_id(&self) -> Option<String> {
+        self.store.chain_id().expect("Failed to read chain id.")
+    }
+    /// Bind the data folder to the chain `chain_id`
+    pub fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError> {
+        self.store.set_chain_id(chain_id)
+    }
+    /// Returns the stored head at `height`
+    pub fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        self.store.get_head(height)
+    }
     ///
     pub fn oldest_height(&self) -> Option<u64> {
         self.cached_heights.front().map(|h| *h)
     }
+    /// Returns the count of the stored heads.
+    pub fn head_count(&self) -> usize {
+        self.cached_heights.len()
+    }
     ///
     pub fn cached_heights(&self) -> Vec<u64> {
         self.cached_heights.iter().map(|h| *h).collect()
     }
     ///
-    pub fn set_consensus_state(&mut self, height: &Height, consensus_state: ConsensusState) {
-        let file_name = format!("{}/{}/{}", self.base_folder, HEAD_DATA_SUB_FOLDER, height);
-        std::fs::write(file_name, borsh::to_vec(&consensus_state).unwrap())
-            .expect("Failed to save light client state to file.");
+    pub fn set_consensus_state(
+        &mut self,
+        height: &Height,
+        consensus_state: ConsensusState,
+    ) -> Result<(), StorageError> {
+        let block_hash = consensus_state
+            .header
+            .light_client_block
+            .current_block_hash();
+        self.store
+            .put_head(height.revision_height, &consensus_state, &block_hash)
     }
-    ///
-    pub fn remove_oldest_head(&mut self) {
-        if let Some(height) = self.cached_heights.pop_front() {
-            let file_name = format!("{}/{}/{}", self.base_folder, HEAD_DATA_SUB_FOLDER, height);
-            std::fs::remove_file(file_name)
-                .expect(format!("Failed to remove head data file for height {}.", height).as_str());
+    /// Remove the oldest head, which is kept in cache if it fails to be removed from store
+    pub fn remove_oldest_head(&mut self) -> Result<(), StorageError> {
+        if let Some(height) = self.cached_heights.front().copied() {
+            self.store.delete_head(height)?;
+            self.cached_heights.pop_front();
+            if let Some(head_epoch_ids) = self.head_epoch_ids.as_mut() {
+                head_epoch_ids.remove(&height);
+            }
+        }
+        Ok(())
+    }
+    /// Check all the stored heads, returns the heads with problems and the problems
+    pub fn check_store(&self) -> Result<Vec<(BlockHeight, HeadIssue)>, StorageError> {
+        check::check_heads(self.store.as_ref())
+    }
+    /// Move the head at `height` to the failed heads if it can be decoded, otherwise
+    /// delete it
+    pub fn quarantine_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        if let Some(head) = store::tolerate_corrupted(self.store.get_head(height))? {
+            self.store.put_failed_head(&head)?;
+        }
+        self.delete_head(height)
+    }
+    /// Delete the head at `height`, even if it can not be decoded
+    pub fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.store.delete_head(height)?;
+        self.cached_heights.retain(|h| *h != height);
+        if let Some(head_epoch_ids) = self.head_epoch_ids.as_mut() {
+            head_epoch_ids.remove(&height);
+        }
+        Ok(())
+    }
+    /// Returns the heights of the heads to prune by `policy`, and removes them if
+    /// `dry_run` is false
+    pub fn prune(
+        &mut self,
+        policy: &PruningSection,
+        dry_run: bool,
+    ) -> Result<Vec<BlockHeight>, StorageError> {
+        if self.head_epoch_ids.is_none() {
+            let mut head_epoch_ids = BTreeMap::new();
+            for height in self.cached_heights.iter() {
+                if let Some(head) = self.store.get_head(*height)? {
+                    head_epoch_ids.insert(*height, head.header.epoch_id());
+                }
+            }
+            self.head_epoch_ids = Some(head_epoch_ids);
+        }
+        let head_epoch_ids = self
+            .head_epoch_ids
+            .as_mut()
+            .expect("Should not fail based on the loading above.");
+        let heads: Vec<(BlockHeight, CryptoHash)> = head_epoch_ids
+            .iter()
+            .map(|(height, epoch_id)| (*height, *epoch_id))
+            .collect();
+        let heights = pruning::heights_to_prune(&heads, policy);
+        if !dry_run {
+            for height in heights.iter() {
+                self.store.delete_head(*height)?;
+                head_epoch_ids.remove(height);
+                self.cached_heights.retain(|h| h != height);
+            }
+        }
+        Ok(heights)
+    }
+    /// Store `head` at its height, which may be lower than the latest height, along with
+    /// the block producers it carries
+    pub fn insert_head(&mut self, head: ConsensusState) -> Result<(), StorageError> {
+        let height = head.header.height();
+        if let Some(bps) = head.current_bps.as_ref() {
+            self.save_epoch_block_producers(&head.header.epoch_id(), bps)?;
+        }
+        if let Some(next_bps) = head.header.light_client_block.next_bps.as_ref() {
+            self.save_epoch_block_producers(&head.header.next_epoch_id(), next_bps)?;
+        }
+        let epoch_id = head.header.epoch_id();
+        self.set_consensus_state(&height, head)?;
+        if let Err(index) = self.cached_heights.binary_search(&height.revision_height) {
+            self.cached_heights.insert(index, height.revision_height);
+        }
+        if let Some(head_epoch_ids) = self.head_epoch_ids.as_mut() {
+            head_epoch_ids.insert(height.revision_height, epoch_id);
+        }
+        Ok(())
+    }
+    /// Store `head` as the trust anchor, along with the block producers it carries
+    pub fn set_trust_anchor(&mut self, head: ConsensusState) -> Result<(), StorageError> {
+        self.insert_head(head)?;
+        self.flush()
+    }
+    /// Returns the heights of the failed heads
+    pub fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        self.store.failed_heights()
+    }
+    /// Returns the failed head at `height`
+    pub fn get_failed_head(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<ConsensusState>, StorageError> {
+        self.store.get_failed_head(height)
+    }
+    /// Returns the stored head with the highest height below `height`
+    pub fn get_head_before(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<ConsensusState>, StorageError> {
+        match self.cached_heights.iter().rev().find(|h| **h < height) {
+            Some(h) => self.store.get_head(*h),
+            None => Ok(None),
         }
     }
     ///
-    pub fn save_failed_head(&self, head: ConsensusState) {
-        let file_name = format!(
-            "{}/failed_head/{}",
-            self.base_folder, head.header.light_client_block.inner_lite.height
-        );
-        std::fs::write(file_name, borsh::to_vec(&head).unwrap())
-            .expect("Failed to save failed light client head to file.");
+    pub fn save_failed_head(&mut self, head: ConsensusState) -> Result<(), StorageError> {
+        self.store.put_failed_head(&head)
+    }
+    /// Returns the block producers of epoch `epoch_id`, from the latest head if it carries
+    /// them, otherwise from the epoch block producers store
+    pub fn get_epoch_block_producers(
+        &self,
+        epoch_id: &CryptoHash,
+    ) -> Result<Option<BlockProducers>, StorageError> {
+        if let Some(bps) = self
+            .get_consensus_state(&self.latest_height())
+            .and_then(|cs| cs.block_producers_of(epoch_id).cloned())
+        {
+            return Ok(Some(bps));
+        }
+        self.store.get_epoch_bps(epoch_id)
+    }
+    /// Returns the height of the last header verified before the last shutdown
+    pub fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError> {
+        self.store.last_verified_height()
+    }
+    /// Record the height of the last verified header, and persist all pending writes,
+    /// before shutting down
+    pub fn flush(&mut self) -> Result<(), StorageError> {
+        let latest_height = self.latest_height().revision_height;
+        if latest_height > 0 {
+            self.store.set_last_verified_height(latest_height)?;
+        }
+        self.store.flush()
+    }
+    /// Returns the counters of the in-memory cache of the stored heads, `None` if the
+    /// cache is disabled
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.store.cache_stats()
     }
+    /// Returns the height of the stored head with the given block hash
+    pub fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError> {
+        self.store.height_of_block_hash(block_hash)
+    }
+    /// Returns the ids of the epochs with stored block producers
+    pub fn stored_epoch_ids(&self) -> Vec<CryptoHash> {
+        self.stored_epoch_ids.iter().copied().collect()
+    }
+    /// Fill the block producers of the latest head from the epoch block producers store,
+    /// if the head doesn't carry them, so that the following headers can be verified.
     ///
-    pub fn update_state(&mut self, header: Header) {
-        let current_bps = match self.get_consensus_state(&self.latest_height()) {
-            Some(cs) => cs.get_block_producers_of(&header.epoch_id()),
-            None => None,
+    /// Returns whether the latest head is updated.
+    pub fn restore_block_producers(&mut self) -> Result<bool, StorageError> {
+        let latest_height = self.latest_height();
+        let head = match self.get_consensus_state(&latest_height) {
+            Some(head) if head.current_bps.is_none() => head,
+            _ => return Ok(false),
         };
-        if self.latest_height() < header.height() {
-            self.cached_heights.push_back(header.height());
+        match self.store.get_epoch_bps(&head.header.epoch_id())? {
+            Some(bps) => {
+                self.set_consensus_state(
+                    &latest_height,
+                    ConsensusState::new(Some(bps), head.header),
+                )?;
+                Ok(true)
+            }
+            None => Ok(false),
         }
-        self.set_consensus_state(
-            &header.height(),
-            ConsensusState {
-                current_bps,
-                header,
-            },
-        );
     }
-}
+    /// Store the recovered block producers `bps` of epoch `epoch_id`, and restore them to
+    /// the latest head if it is in that epoch.
+    ///
+    /// Returns whether the latest head is updated.
+    pub fn recover_block_producers(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<bool, StorageError> {
+        self.save_epoch_block_producers(epoch_id, bps)?;
+        self.restore_block_producers()
+    }
+    /// Returns a snapshot of all the stored data
+    pub fn export_snapshot(&self) -> Result<Snapshot, StorageError> {
+        Snapshot::read_from(self.store.as_ref())
+    }
+    /// Store all the data of `snapshot`, and cache the heights of the stored heads
+    pub fn import_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), StorageError> {
+        snapshot.write_to(self.store.as_mut())?;
+        self.cached_heights = self.store.heights()?.into();
+        self.head_epoch_ids = None;
+        self.stored_epoch_ids = self.store.epoch_ids()?.into_iter().collect();
+        Ok(())
+    }
+    /// Save the consensus state of `header`, the height is cached only if it is saved.
+    ///
+    /// The block producers of the epoch of `header` and of the next epoch are also saved
+    /// in the epoch block producers store, if they are known and not stored yet. The head
+    /// is indexed by the block hash `header` is sealed with.
+    pub fn update_state(&mut self, header: SealedHeader) -> Result<(), StorageError> {
+        let current_bps = self.get_epoch_block_producers(&header.epoch_id())?;
+        if let Some(bps) = current_bps.as_ref() {
+            self.save_epoch_block_producers(&header.epoch_id(), bps)?;
+        }
+        if let Some(next_bps) = header.light_client_block.next_bps.as_ref() {
+            self.save_epoch_block_producers(&header.next_epoch_id(), next_bps)?;
+        }
+        let height = header.height();
+        let epoch_id = header.epoch_id();
+        let block_hash = header.hash();
+        self.store.put_head(
+            height.revision_height,
+            &ConsensusState::new(current_bps, header.into_header()),
+            &block_hash,
+        )?;
+        if self.latest_height() < height {
+            self.cached_heights.push_back(height.revision_height);
+        }
+        if let Some(head_epoch_ids) = self.head_epoch_ids.as_mut() {
+            head_epoch_ids.insert(height.revision_height, epoch_id);
+        }
+        Ok(())
+    }
 
-//
-fn get_cached_heights(
-    base_folder: &String,
-) -> (VecDeque<BlockHeight>, HashMap<CryptoHash, BlockHeight>) {
-    let head_data_path = format!("{}/{}", base_folder, HEAD_DATA_SUB_FOLDER);
-    let mut heights = Vec::new();
-    let mut result_map = HashMap::new();
-    for entry in std::fs::read_dir(head_data_path).expect("Failed to access head data folder.") {
-        let dir_entry = entry.expect("Invalid file entry.");
-        let path = dir_entry.path();
-        if path.is_file() {
-            if let Ok(bytes) = std::fs::read(path.as_os_str()) {
-                let head = ConsensusState::try_from_slice(&bytes)
-                    .expect(format!("Invalid head data file {}.", path.display()).as_str());
-                heights.push(head.header.light_client_block.inner_lite.height);
-                let current_block_hash = head.header.light_client_block.current_block_hash();
-                result_map.insert(
-                    current_block_hash,
-                    head.header.light_client_block.inner_lite.height,
-                );
-            }
+    fn save_epoch_block_producers(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError> {
+        if !self.stored_epoch_ids.contains(epoch_id) {
+            self.store.put_epoch_bps(epoch_id, bps)?;
+            self.stored_epoch_ids.insert(*epoch_id);
         }
+        Ok(())
     }
-    heights.sort();
-    let mut result = VecDeque::new();
-    heights.iter().for_each(|h| result.push_back(*h));
-    (result, result_map)
 }
@@ -0,0 +1,155 @@
+//! Files of proof bundles, which can be verified offline with the local store only.
+//!
+//! A bundle file holds a [`ProofBundle`], either borsh serialized or as a JSON object. The
+//! format is detected when reading, a JSON bundle starts with `{`.
+
+use std::path::Path;
+
+use borsh::BorshDeserialize;
+use near_light_client::types::{BundledProof, ProofBundle, StateValueProof, PROOF_FORMAT_VERSION};
+use near_light_client::BasicNearLightClient;
+
+use super::LightClient;
+use crate::output::{is_json, VerificationReport};
+use crate::prelude::*;
+
+/// Serialization of bundle files.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BundleFormat {
+    /// Borsh serialization, which can be submitted to a contract as is
+    Borsh,
+    /// A JSON object, for inspection and third party tooling
+    Json,
+}
+
+/// Write `bundle` to the file at `path` in `format`.
+pub fn write_bundle(path: &Path, bundle: &ProofBundle, format: BundleFormat) -> anyhow::Result<()> {
+    let bytes = match format {
+        BundleFormat::Borsh => borsh::to_vec(bundle)?,
+        BundleFormat::Json => serde_json::to_vec_pretty(bundle)?,
+    };
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Read a bundle from the file at `path`, in either format.
+pub fn read_bundle(path: &Path) -> anyhow::Result<(ProofBundle, BundleFormat)> {
+    let bytes = std::fs::read(path)?;
+    match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'{') => Ok((serde_json::from_slice(&bytes)?, BundleFormat::Json)),
+        _ => Ok((ProofBundle::try_from_slice(&bytes)?, BundleFormat::Borsh)),
+    }
+}
+
+/// Verify `bundle` with the stored head at the height of its proof, printing each check
+/// unless the output is JSON.
+///
+/// The bundled consensus state is only trusted if it is the stored head, and the proof is
+/// verified with the stored head.
+pub fn verify_bundle(light_client: &LightClient, bundle: &ProofBundle) -> VerificationReport {
+    let human = !is_json();
+    if human {
+        status_info!("Info", "Bundle version: {}", bundle.version);
+    }
+    if bundle.version != PROOF_FORMAT_VERSION {
+        return VerificationReport::failed(format!(
+            "Unsupported bundle version {}, expected {}.",
+            bundle.version, PROOF_FORMAT_VERSION
+        ));
+    }
+    if human {
+        status_info!("Info", "Chain id: {}", bundle.chain_id);
+    }
+    match light_client.chain_id() {
+        Some(chain_id) if chain_id != bundle.chain_id => {
+            return VerificationReport::failed(format!(
+                "The bundle is of chain '{}', but the data folder is bound to chain '{}'.",
+                bundle.chain_id, chain_id
+            ));
+        }
+        Some(_) if human => status_ok!("Passed", "The chain id matches the data folder."),
+        None if human => {
+            status_warn!("The data folder is not bound to a chain, the chain id is unchecked.")
+        }
+        _ => (),
+    }
+    let height = bundle.proof.height();
+    let bundled_head_hash = bundle
+        .consensus_state
+        .header
+        .light_client_block
+        .current_block_hash();
+    if human {
+        status_info!(
+            "Info",
+            "Bundled head: height {}, block hash {}",
+            bundle.consensus_state.header.height().revision_height,
+            bundled_head_hash
+        );
+    }
+    if bundle.consensus_state.header.height() != height {
+        return VerificationReport::failed(format!(
+            "The bundled head is not at the height {} of the proof.",
+            height.revision_height
+        ));
+    }
+    let head = match light_client.get_consensus_state(&height) {
+        Some(head) => head,
+        None => {
+            return VerificationReport::failed(format!(
+                "Missing stored head at height {}, the bundled head can not be trusted.",
+                height.revision_height
+            ));
+        }
+    };
+    let head_hash = head.header.light_client_block.current_block_hash();
+    if head_hash != bundled_head_hash {
+        return VerificationReport::failed(format!(
+            "The bundled head differs from the stored head with block hash {}.",
+            head_hash
+        ));
+    }
+    if human {
+        status_ok!("Passed", "The bundled head is the stored head.");
+    }
+    let report = VerificationReport::of_head(height.revision_height, head_hash)
+        .with_detail("chain_id", &bundle.chain_id);
+    let report = match &bundle.proof {
+        BundledProof::State(proof) => {
+            let report = report.with_detail("key", base64::encode(proof.key()));
+            let report = match proof {
+                StateValueProof::Membership(proof) => report
+                    .with_detail("proof", "membership")
+                    .with_detail("value", base64::encode(&proof.value)),
+                StateValueProof::NonMembership(_) => report.with_detail("proof", "non-membership"),
+            };
+            report.with_result(head.verify_state_value_proof(proof))
+        }
+        BundledProof::Outcome(proof) => {
+            let outcome = &proof.outcome_with_id.outcome;
+            report
+                .with_detail("proof", "outcome")
+                .with_detail("outcome_id", proof.outcome_with_id.id)
+                .with_detail("block_height", proof.block_lite_view.inner_lite.height)
+                .with_detail("block_hash", proof.block_lite_view.current_block_hash())
+                .with_detail("executor_id", &outcome.executor_id)
+                .with_detail("status", format!("{:?}", outcome.status))
+                .with_detail("gas_burnt", outcome.gas_burnt)
+                .with_detail("logs", &outcome.logs)
+                .with_result(head.verify_outcome_proof(proof))
+        }
+    };
+    if human {
+        for (key, value) in report.details.iter() {
+            status_info!("Info", "{}: {}", key, value);
+        }
+        if report.verified {
+            status_ok!(
+                "VERIFIED",
+                "The proof is verified with the stored head at height {}.",
+                height.revision_height
+            );
+        }
+    }
+    report
+}
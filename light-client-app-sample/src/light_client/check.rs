@@ -0,0 +1,92 @@
+//! Integrity checking of the heads stored by NEAR light client.
+//!
+
+use near_light_client::{
+    near_types::{hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+
+use super::store::HeadStore;
+
+/// Problems of a stored head found by [`check_heads`].
+#[derive(Debug)]
+pub enum HeadIssue {
+    /// The stored data can not be decoded
+    Undecodable,
+    /// The height of the header differs from the height the head is stored at
+    HeightMismatch { header_height: BlockHeight },
+    /// The block hash index doesn't map the recomputed block hash of the header to the
+    /// height the head is stored at
+    BlockHashIndexMismatch {
+        block_hash: CryptoHash,
+        indexed_height: Option<BlockHeight>,
+    },
+    /// The `next_bps` of the header don't match its `next_bp_hash`
+    NextBpsHashMismatch,
+    /// The `current_bps` don't match the `next_bp_hash` of the previous stored head,
+    /// which is in the previous epoch
+    CurrentBpsHashMismatch { previous_height: BlockHeight },
+}
+
+/// Check all the heads in `store`, returns the heights of the heads with problems and
+/// the problems, in ascending order of heights.
+pub fn check_heads(store: &dyn HeadStore) -> Result<Vec<(BlockHeight, HeadIssue)>, StorageError> {
+    let mut issues = Vec::new();
+    let mut previous: Option<(BlockHeight, ConsensusState)> = None;
+    for height in store.heights()? {
+        let head = match store.get_head(height) {
+            Ok(Some(head)) => head,
+            Ok(None) => continue,
+            Err(StorageError::CorruptedData { .. }) => {
+                issues.push((height, HeadIssue::Undecodable));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if let Some(issue) = check_head(store, height, &head, previous.as_ref())? {
+            issues.push((height, issue));
+            continue;
+        }
+        previous = Some((height, head));
+    }
+    Ok(issues)
+}
+
+fn check_head(
+    store: &dyn HeadStore,
+    height: BlockHeight,
+    head: &ConsensusState,
+    previous: Option<&(BlockHeight, ConsensusState)>,
+) -> Result<Option<HeadIssue>, StorageError> {
+    let header_height = head.header.height().revision_height;
+    if header_height != height {
+        return Ok(Some(HeadIssue::HeightMismatch { header_height }));
+    }
+    let block_hash = head.header.light_client_block.current_block_hash();
+    let indexed_height = store.height_of_block_hash(&block_hash)?;
+    if indexed_height != Some(height) {
+        return Ok(Some(HeadIssue::BlockHashIndexMismatch {
+            block_hash,
+            indexed_height,
+        }));
+    }
+    let light_client_block = &head.header.light_client_block;
+    if let Some(next_bps) = light_client_block.next_bps.as_ref() {
+        if next_bps.hash() != light_client_block.inner_lite.next_bp_hash {
+            return Ok(Some(HeadIssue::NextBpsHashMismatch));
+        }
+    }
+    if let (Some((previous_height, previous)), Some(current_bps)) =
+        (previous, head.current_bps.as_ref())
+    {
+        if previous.header.next_epoch_id() == head.header.epoch_id()
+            && current_bps.hash() != previous.header.light_client_block.inner_lite.next_bp_hash
+        {
+            return Ok(Some(HeadIssue::CurrentBpsHashMismatch {
+                previous_height: *previous_height,
+            }));
+        }
+    }
+    Ok(None)
+}
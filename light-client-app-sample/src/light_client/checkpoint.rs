@@ -0,0 +1,107 @@
+//! Validation of the trusted checkpoints to initialize NEAR light client with, and of
+//! the block producers recovered from the previous epochs.
+//!
+
+use near_light_client::{
+    near_types::{
+        block_producers::BlockProducers,
+        hash::CryptoHash,
+        merkle::{compute_root_from_path, MerklePath},
+    },
+    types::{ConsensusState, Header},
+    validate_block_producers, BasicNearLightClient, HeaderVerificationError, TrustedConsensusState,
+};
+use near_primitives::types::BlockId;
+
+use super::near_rpc_client_wrapper::NearRpcClientWrapper;
+use super::utils::produce_light_client_block;
+
+/// Create the consensus state of the header `checkpoint` as the trust anchor, whose block
+/// hash is `trusted_block_hash`.
+///
+/// `previous_epoch_header` is a header of the epoch before the checkpoint, which is proved
+/// to be an ancestor of the checkpoint by `block_proof`. Its `next_bps` are the block
+/// producers of the epoch of the checkpoint, which must approve the checkpoint and become
+/// its `current_bps`.
+pub fn consensus_state_of_checkpoint(
+    checkpoint: Header,
+    previous_epoch_header: Header,
+    block_proof: &MerklePath,
+    trusted_block_hash: &CryptoHash,
+) -> Result<ConsensusState, HeaderVerificationError> {
+    if previous_epoch_header.next_epoch_id() != checkpoint.epoch_id() {
+        return Err(HeaderVerificationError::InvalidEpochId);
+    }
+    // The `block_merkle_root` of the checkpoint is trusted by its block hash, so it anchors
+    // the previous epoch header, whose `next_bps` are then checked against its `next_bp_hash`.
+    let mut head = ConsensusState::from_trusted_block(
+        checkpoint.light_client_block,
+        checkpoint.prev_state_root_of_chunks,
+        None,
+        trusted_block_hash,
+    )?;
+    let current_bps = block_producers_from_ancestor(&head, &previous_epoch_header, block_proof)?;
+    TrustedConsensusState::new(ConsensusState::new(None, previous_epoch_header))
+        .verify_header(&head.header)?;
+    head.current_bps = Some(current_bps);
+    Ok(head)
+}
+
+/// Returns the block producers of the epoch following `previous_epoch_header`, which is
+/// proved to be an ancestor of `trusted_head` by `block_proof`, i.e. its block hash is
+/// included in the `block_merkle_root` of `trusted_head`.
+pub fn block_producers_from_ancestor(
+    trusted_head: &ConsensusState,
+    previous_epoch_header: &Header,
+    block_proof: &MerklePath,
+) -> Result<BlockProducers, HeaderVerificationError> {
+    let trusted_block = &trusted_head.header.light_client_block;
+    let block_hash = previous_epoch_header
+        .light_client_block
+        .current_block_hash();
+    if compute_root_from_path(block_proof, block_hash) != trusted_block.inner_lite.block_merkle_root
+    {
+        return Err(HeaderVerificationError::BrokenChain {
+            trusted_block_hash: trusted_block.current_block_hash(),
+        });
+    }
+    let next_bps = previous_epoch_header
+        .light_client_block
+        .next_bps
+        .clone()
+        .ok_or(HeaderVerificationError::MissingNextBlockProducersInHead)?;
+    if next_bps.hash()
+        != previous_epoch_header
+            .light_client_block
+            .inner_lite
+            .next_bp_hash
+    {
+        return Err(HeaderVerificationError::InvalidNextBlockProducersHash);
+    }
+    validate_block_producers(&next_bps.decoded())?;
+    Ok(next_bps)
+}
+
+/// Returns the header of the last final block of the epoch before the epoch of block
+/// `block_hash`, which carries the block producers of the epoch of the block.
+pub async fn fetch_previous_epoch_header(
+    rpc_client: &NearRpcClientWrapper,
+    block_hash: &near_primitives::hash::CryptoHash,
+) -> anyhow::Result<Header> {
+    let epoch_start_height = rpc_client.view_epoch_start_height(block_hash).await?;
+    let epoch_start_block = rpc_client
+        .view_block(&Some(BlockId::Height(epoch_start_height)))
+        .await?;
+    let light_client_block_view = rpc_client
+        .get_next_light_client_block(&epoch_start_block.header.prev_hash)
+        .await?;
+    let block_view = rpc_client
+        .view_block(&Some(BlockId::Height(
+            light_client_block_view.inner_lite.height,
+        )))
+        .await?;
+    Ok(produce_light_client_block(
+        &light_client_block_view,
+        &block_view,
+    ))
+}
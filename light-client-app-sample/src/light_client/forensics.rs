@@ -0,0 +1,181 @@
+//! Forensics of the failed heads, by re-running the checks of the header verification
+//! one by one and tracing their outcomes.
+//!
+
+use near_light_client::{
+    near_types::{
+        merkle::merklize,
+        signature::{Ed25519DalekVerifier, SignatureVerifier},
+    },
+    types::{ConsensusState, Header},
+    BasicNearLightClient, HeaderVerificationError, TrustedConsensusState,
+};
+
+/// Outcome of a check of the header verification.
+#[derive(Debug)]
+pub struct CheckOutcome {
+    /// Name of the check
+    pub name: &'static str,
+    /// Whether the check passed
+    pub passed: bool,
+    /// The values the check compared
+    pub detail: String,
+}
+
+/// Approval of a block producer in the `approvals_after_next` of a header.
+#[derive(Debug)]
+pub struct ApprovalTrace {
+    /// Account id of the block producer
+    pub account_id: String,
+    /// Stake of the block producer
+    pub stake: u128,
+    /// Whether the block producer signed the approval
+    pub signed: bool,
+    /// Whether the signature is valid, `None` if not signed
+    pub valid_signature: Option<bool>,
+}
+
+/// Trace of the verification of a header with a trusted head.
+#[derive(Debug)]
+pub struct VerificationTrace {
+    /// Outcomes of the checks in the order of the verification
+    pub checks: Vec<CheckOutcome>,
+    /// Approvals of the block producers of the epoch of the header
+    pub approvals: Vec<ApprovalTrace>,
+    /// Total stake of the block producers of the epoch of the header
+    pub total_stake: u128,
+    /// Stake of the block producers which signed the approval
+    pub approved_stake: u128,
+    /// Result of the verification as a whole
+    pub result: Result<(), HeaderVerificationError>,
+}
+
+/// Verify `header` with `trusted_head`, tracing the outcome of each check. All approval
+/// signatures are checked, and the checks go on after a failure, so that every problem
+/// of the header is reported.
+pub fn trace_verification(trusted_head: &ConsensusState, header: &Header) -> VerificationTrace {
+    let trusted_header = &trusted_head.header;
+    let light_client_block = &header.light_client_block;
+    let mut checks = Vec::new();
+    checks.push(CheckOutcome {
+        name: "block height",
+        passed: header.height() > trusted_header.height(),
+        detail: format!(
+            "header at height {}, trusted head at height {}",
+            header.height().revision_height,
+            trusted_header.height().revision_height
+        ),
+    });
+    let in_next_epoch = header.epoch_id() == trusted_header.next_epoch_id();
+    checks.push(CheckOutcome {
+        name: "epoch id",
+        passed: header.epoch_id() == trusted_header.epoch_id() || in_next_epoch,
+        detail: format!(
+            "header in epoch {}, trusted head in epoch {} followed by epoch {}",
+            header.epoch_id(),
+            trusted_header.epoch_id(),
+            trusted_header.next_epoch_id()
+        ),
+    });
+    checks.push(CheckOutcome {
+        name: "next block producers in header",
+        passed: !in_next_epoch || light_client_block.next_bps.is_some(),
+        detail: format!(
+            "header in the next epoch: {}, carries next block producers: {}",
+            in_next_epoch,
+            light_client_block.next_bps.is_some()
+        ),
+    });
+    let mut approvals = Vec::new();
+    let mut total_stake = 0;
+    let mut approved_stake = 0;
+    match trusted_head.get_block_producers_of(&header.epoch_id()) {
+        Some(bps) => {
+            checks.push(CheckOutcome {
+                name: "epoch block producers",
+                passed: true,
+                detail: format!(
+                    "{} block producers, {} approvals",
+                    bps.len(),
+                    light_client_block.approvals_after_next.len()
+                ),
+            });
+            let approval_message = light_client_block.approval_message();
+            for (maybe_signature, bp) in light_client_block
+                .approvals_after_next
+                .iter()
+                .zip(bps.iter())
+            {
+                let bp = bp.as_validator_stake();
+                total_stake += bp.stake;
+                let valid_signature = maybe_signature.as_ref().map(|signature| {
+                    Ed25519DalekVerifier.verify(signature, &approval_message, &bp.public_key)
+                });
+                if maybe_signature.is_some() {
+                    approved_stake += bp.stake;
+                }
+                approvals.push(ApprovalTrace {
+                    account_id: bp.account_id.clone(),
+                    stake: bp.stake,
+                    signed: maybe_signature.is_some(),
+                    valid_signature,
+                });
+            }
+            let invalid: Vec<&str> = approvals
+                .iter()
+                .filter(|approval| approval.valid_signature == Some(false))
+                .map(|approval| approval.account_id.as_str())
+                .collect();
+            checks.push(CheckOutcome {
+                name: "approval signatures",
+                passed: invalid.is_empty(),
+                detail: format!("invalid signatures of {:?}", invalid),
+            });
+            checks.push(CheckOutcome {
+                name: "approved stake",
+                passed: approved_stake * 3 > total_stake * 2,
+                detail: format!(
+                    "approved stake {} of total stake {}, more than 2/3 required",
+                    approved_stake, total_stake
+                ),
+            });
+        }
+        None => checks.push(CheckOutcome {
+            name: "epoch block producers",
+            passed: false,
+            detail: format!(
+                "block producers of epoch {} unknown to the trusted head",
+                header.epoch_id()
+            ),
+        }),
+    }
+    if let Some(next_bps) = light_client_block.next_bps.as_ref() {
+        checks.push(CheckOutcome {
+            name: "next block producers hash",
+            passed: next_bps.hash() == light_client_block.inner_lite.next_bp_hash,
+            detail: format!(
+                "hash of next block producers {}, next_bp_hash {}",
+                next_bps.hash(),
+                light_client_block.inner_lite.next_bp_hash
+            ),
+        });
+    }
+    let prev_state_root = merklize(&header.prev_state_root_of_chunks).0;
+    checks.push(CheckOutcome {
+        name: "previous state root",
+        passed: prev_state_root == light_client_block.inner_lite.prev_state_root,
+        detail: format!(
+            "merkle root of {} chunk state roots {}, prev_state_root {}",
+            header.prev_state_root_of_chunks.len(),
+            prev_state_root,
+            light_client_block.inner_lite.prev_state_root
+        ),
+    });
+    VerificationTrace {
+        checks,
+        approvals,
+        total_stake,
+        approved_stake,
+        result: TrustedConsensusState::new(trusted_head.clone()).verify_header(header),
+    }
+}
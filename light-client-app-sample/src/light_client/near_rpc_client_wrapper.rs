@@ -3,17 +3,31 @@
 
 use std::fmt::Debug;
 
+use near_crypto::PublicKey;
 use near_jsonrpc_client::methods::light_client_proof::RpcLightClientExecutionProofResponse;
 use near_jsonrpc_client::methods::query::RpcQueryRequest;
 use near_jsonrpc_client::{methods, JsonRpcClient, MethodCallResult};
+use near_jsonrpc_primitives::types::light_client::{
+    RpcLightClientBlockProofResponse, RpcLightClientProofError,
+};
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
+use near_jsonrpc_primitives::types::transactions::TransactionInfo;
 use near_primitives::hash::CryptoHash;
-use near_primitives::types::{AccountId, BlockId, Finality, StoreKey, TransactionOrReceiptId};
-use near_primitives::views::{BlockView, QueryRequest};
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{
+    AccountId, BlockHeight, BlockId, EpochReference, Finality, FunctionArgs, StoreKey,
+    TransactionOrReceiptId,
+};
+use near_primitives::views::{
+    AccessKeyView, AccountView, BlockView, FinalExecutionOutcomeView, QueryRequest,
+};
 use tokio_retry::strategy::{jitter, ExponentialBackoff, FixedInterval};
 use tokio_retry::Retry;
 
+use super::utils::light_client_block_hash;
+use crate::config::NearRpcSection;
 use crate::info_with_time;
+use crate::metrics::metrics;
 
 enum RetryStrategy {
     ExponentialBackoff,
@@ -30,6 +44,10 @@ pub struct NearRpcClientWrapper {
     pub rpc_addr: String,
     ///
     pub rpc_client: JsonRpcClient,
+    /// Addresses and clients of the RPC services to fail over to in order
+    failover_clients: Vec<(String, JsonRpcClient)>,
+    /// The count of RPC services which must know the block of a light client block
+    quorum: u64,
 }
 
 impl NearRpcClientWrapper {
@@ -40,30 +58,109 @@ impl NearRpcClientWrapper {
         Self {
             rpc_client,
             rpc_addr: rpc_addr.into(),
+            failover_clients: Vec::new(),
+            quorum: 0,
         }
     }
 
+    /// Create the wrapper with the RPC services in the given configuration
+    pub(crate) fn from_config(config: &NearRpcSection) -> Self {
+        Self::new(config.rpc_endpoint.as_str())
+            .with_failover(&config.failover_rpc_endpoints)
+            .with_quorum(config.light_client_block_quorum)
+    }
+
+    /// Fail over to the RPC services `rpc_addrs` in order, when the queries fail
+    pub(crate) fn with_failover(mut self, rpc_addrs: &[String]) -> Self {
+        let connector = JsonRpcClient::new_client();
+        self.failover_clients = rpc_addrs
+            .iter()
+            .map(|rpc_addr| (rpc_addr.clone(), connector.connect(rpc_addr)))
+            .collect();
+        self
+    }
+
+    /// Require `quorum` RPC services to know the block of a light client block
+    pub(crate) fn with_quorum(mut self, quorum: u64) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    /// Returns the addresses and clients of all RPC services, the primary one first
+    fn clients(&self) -> impl Iterator<Item = (&String, &JsonRpcClient)> {
+        std::iter::once((&self.rpc_addr, &self.rpc_client)).chain(
+            self.failover_clients
+                .iter()
+                .map(|(rpc_addr, rpc_client)| (rpc_addr, rpc_client)),
+        )
+    }
+
     pub(crate) async fn query<M>(&self, method: &M) -> MethodCallResult<M::Response, M::Error>
     where
         M: methods::RpcMethod + Debug,
         M::Response: Debug,
         M::Error: Debug,
     {
-        retry(
-            || async {
-                info_with_time!("Try querying {:?} ...", method);
-                let result = self.rpc_client.call(method).await;
-                tracing::info!(
-                    target: "workspaces",
-                    "Querying RPC with {:?} resulted in {:?}",
-                    method,
-                    result
-                );
-                result
-            },
-            RetryStrategy::FixedInterval,
+        let mut result = query_with(&self.rpc_client, method).await;
+        for (rpc_addr, rpc_client) in self.failover_clients.iter() {
+            if result.is_ok() {
+                break;
+            }
+            info_with_time!("Fail over to {} ...", rpc_addr);
+            result = query_with(rpc_client, method).await;
+        }
+        result
+    }
+
+    /// Check that at least `quorum` RPC services, including the one which returned
+    /// `light_client_block`, know the block of `light_client_block` at its height.
+    ///
+    /// The block is viewed by its hash, as the next light client block of a block moves
+    /// forward while the chain grows, so the services may return different but valid ones.
+    async fn check_quorum(
+        &self,
+        light_client_block: &near_primitives::views::LightClientBlockView,
+    ) -> anyhow::Result<()> {
+        if self.quorum <= 1 {
+            return Ok(());
+        }
+        let expected_hash = CryptoHash(light_client_block_hash(light_client_block).0);
+        let expected_height = light_client_block.inner_lite.height;
+        let mut agreed = 0;
+        for (rpc_addr, rpc_client) in self.clients() {
+            let result = query_with(
+                rpc_client,
+                &methods::block::RpcBlockRequest {
+                    block_reference: BlockId::Hash(expected_hash).into(),
+                },
+            )
+            .await;
+            match result {
+                Ok(view)
+                    if view.header.hash == expected_hash
+                        && view.header.height == expected_height =>
+                {
+                    agreed += 1
+                }
+                Ok(view) => info_with_time!(
+                    "{} returned a different block {} at height {}.",
+                    rpc_addr,
+                    view.header.hash,
+                    view.header.height
+                ),
+                Err(err) => info_with_time!("Failed to query {}: {:?}", rpc_addr, err),
+            }
+            if agreed >= self.quorum {
+                return Ok(());
+            }
+        }
+        anyhow::bail!(
+            "Only {} RPC services returned the block {} at height {}, less than the quorum {}.",
+            agreed,
+            expected_hash,
+            expected_height,
+            self.quorum
         )
-        .await
     }
 
     pub(crate) async fn get_next_light_client_block(
@@ -79,8 +176,9 @@ impl NearRpcClientWrapper {
                         },
                     )
                     .await?;
-                if query_resp.is_some() {
-                    anyhow::Ok(query_resp.unwrap())
+                if let Some(light_client_block) = query_resp {
+                    self.check_quorum(&light_client_block).await?;
+                    anyhow::Ok(light_client_block)
                 } else {
                     anyhow::bail!("Failed to get next light client block. Response is empty.")
                 }
@@ -90,6 +188,25 @@ impl NearRpcClientWrapper {
         .await
     }
 
+    /// Returns the light client block following `last_block_hash`, `None` if there is
+    /// no new light client block yet.
+    pub(crate) async fn try_get_next_light_client_block(
+        &self,
+        last_block_hash: &CryptoHash,
+    ) -> anyhow::Result<Option<near_primitives::views::LightClientBlockView>> {
+        let query_resp = self
+            .query(
+                &methods::next_light_client_block::RpcLightClientNextBlockRequest {
+                    last_block_hash: last_block_hash.clone(),
+                },
+            )
+            .await?;
+        if let Some(light_client_block) = query_resp.as_ref() {
+            self.check_quorum(light_client_block).await?;
+        }
+        anyhow::Ok(query_resp)
+    }
+
     pub(crate) async fn view_state_with_proof(
         &self,
         contract_id: AccountId,
@@ -146,6 +263,31 @@ impl NearRpcClientWrapper {
         .await
     }
 
+    pub(crate) async fn get_light_client_block_proof(
+        &self,
+        block_hash: &CryptoHash,
+        light_client_head: &CryptoHash,
+    ) -> anyhow::Result<RpcLightClientBlockProofResponse> {
+        retry(
+            || async {
+                let query_resp = self
+                    .query(&methods::any::<
+                        Result<RpcLightClientBlockProofResponse, RpcLightClientProofError>,
+                    >(
+                        "EXPERIMENTAL_light_client_block_proof",
+                        serde_json::json!({
+                            "block_hash": block_hash,
+                            "light_client_head": light_client_head,
+                        }),
+                    ))
+                    .await?;
+                anyhow::Ok(query_resp)
+            },
+            RetryStrategy::ExponentialBackoff,
+        )
+        .await
+    }
+
     pub(crate) async fn view_block(&self, block_id: &Option<BlockId>) -> anyhow::Result<BlockView> {
         retry(
             || async {
@@ -164,6 +306,273 @@ impl NearRpcClientWrapper {
         )
         .await
     }
+
+    /// Returns the height of the first block of the epoch of block `block_hash`.
+    pub(crate) async fn view_epoch_start_height(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> anyhow::Result<u64> {
+        retry(
+            || async {
+                let validators = self
+                    .query(&methods::validators::RpcValidatorRequest {
+                        epoch_reference: EpochReference::BlockId(BlockId::Hash(*block_hash)),
+                    })
+                    .await?;
+
+                Ok(validators.epoch_start_height)
+            },
+            RetryStrategy::ExponentialBackoff,
+        )
+        .await
+    }
+
+    /// Returns the record of `account_id` at block `block_id`, or of the latest block.
+    pub(crate) async fn view_account(
+        &self,
+        account_id: &AccountId,
+        block_id: Option<BlockId>,
+    ) -> anyhow::Result<AccountView> {
+        retry(
+            || async {
+                let block_reference = block_id
+                    .clone()
+                    .map(Into::into)
+                    .unwrap_or_else(|| Finality::None.into());
+
+                let query_resp = self
+                    .query(&RpcQueryRequest {
+                        block_reference,
+                        request: QueryRequest::ViewAccount {
+                            account_id: account_id.clone(),
+                        },
+                    })
+                    .await?;
+
+                match query_resp.kind {
+                    QueryResponseKind::ViewAccount(account) => anyhow::Ok(account),
+                    _ => anyhow::bail!(ERR_INVALID_VARIANT),
+                }
+            },
+            RetryStrategy::ExponentialBackoff,
+        )
+        .await
+    }
+
+    /// Returns access key `public_key` of `account_id` at block `block_id`, or of the latest
+    /// block.
+    pub(crate) async fn view_access_key(
+        &self,
+        account_id: &AccountId,
+        public_key: &PublicKey,
+        block_id: Option<BlockId>,
+    ) -> anyhow::Result<AccessKeyView> {
+        retry(
+            || async {
+                let block_reference = block_id
+                    .clone()
+                    .map(Into::into)
+                    .unwrap_or_else(|| Finality::None.into());
+
+                let query_resp = self
+                    .query(&RpcQueryRequest {
+                        block_reference,
+                        request: QueryRequest::ViewAccessKey {
+                            account_id: account_id.clone(),
+                            public_key: public_key.clone(),
+                        },
+                    })
+                    .await?;
+
+                match query_resp.kind {
+                    QueryResponseKind::AccessKey(access_key) => anyhow::Ok(access_key),
+                    _ => anyhow::bail!(ERR_INVALID_VARIANT),
+                }
+            },
+            RetryStrategy::ExponentialBackoff,
+        )
+        .await
+    }
+
+    /// Returns the nonce of access key `public_key` of `account_id`.
+    pub(crate) async fn view_access_key_nonce(
+        &self,
+        account_id: &AccountId,
+        public_key: &PublicKey,
+    ) -> anyhow::Result<u64> {
+        retry(
+            || async {
+                let query_resp = self
+                    .query(&RpcQueryRequest {
+                        block_reference: Finality::Final.into(),
+                        request: QueryRequest::ViewAccessKey {
+                            account_id: account_id.clone(),
+                            public_key: public_key.clone(),
+                        },
+                    })
+                    .await?;
+
+                match query_resp.kind {
+                    QueryResponseKind::AccessKey(access_key) => anyhow::Ok(access_key.nonce),
+                    _ => anyhow::bail!(ERR_INVALID_VARIANT),
+                }
+            },
+            RetryStrategy::ExponentialBackoff,
+        )
+        .await
+    }
+
+    /// Returns the result of view function `method_name` of `contract_id` with `args`.
+    pub(crate) async fn call_view_function(
+        &self,
+        contract_id: &AccountId,
+        method_name: &str,
+        args: Vec<u8>,
+    ) -> anyhow::Result<Vec<u8>> {
+        retry(
+            || async {
+                let query_resp = self
+                    .query(&RpcQueryRequest {
+                        block_reference: Finality::Final.into(),
+                        request: QueryRequest::CallFunction {
+                            account_id: contract_id.clone(),
+                            method_name: method_name.to_owned(),
+                            args: FunctionArgs::from(args.clone()),
+                        },
+                    })
+                    .await?;
+
+                match query_resp.kind {
+                    QueryResponseKind::CallResult(result) => anyhow::Ok(result.result),
+                    _ => anyhow::bail!(ERR_INVALID_VARIANT),
+                }
+            },
+            RetryStrategy::ExponentialBackoff,
+        )
+        .await
+    }
+
+    /// Broadcast `signed_transaction`, and wait until it is executed.
+    pub(crate) async fn broadcast_tx_commit(
+        &self,
+        signed_transaction: &SignedTransaction,
+    ) -> anyhow::Result<FinalExecutionOutcomeView> {
+        let outcome = self
+            .query(&methods::broadcast_tx_commit::RpcBroadcastTxCommitRequest {
+                signed_transaction: signed_transaction.clone(),
+            })
+            .await?;
+        anyhow::Ok(outcome)
+    }
+
+    /// Returns the final outcome of transaction `tx_hash` sent by `sender_id`, without
+    /// retries, as it fails until the transaction is known by the RPC service.
+    pub(crate) async fn view_tx_status(
+        &self,
+        tx_hash: &CryptoHash,
+        sender_id: &AccountId,
+    ) -> anyhow::Result<FinalExecutionOutcomeView> {
+        let outcome = self
+            .query(&methods::tx::RpcTransactionStatusRequest {
+                transaction_info: TransactionInfo::TransactionId {
+                    tx_hash: *tx_hash,
+                    sender_account_id: sender_id.clone(),
+                },
+            })
+            .await?;
+        anyhow::Ok(outcome)
+    }
+
+    /// Check that any of the RPC services responds to a status query, without retries.
+    pub(crate) async fn ping(&self) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+        for (rpc_addr, rpc_client) in self.clients() {
+            match rpc_client.call(methods::status::RpcStatusRequest).await {
+                Ok(_) => return Ok(()),
+                Err(err) => errors.push(format!("{}: {:?}", rpc_addr, err)),
+            }
+        }
+        anyhow::bail!("No RPC service is reachable: {}", errors.join(", "))
+    }
+
+    /// Returns the light client block following `last_block_hash` from the primary RPC
+    /// service only, without retries, fail over or quorum check.
+    pub(crate) async fn view_next_light_client_block_once(
+        &self,
+        last_block_hash: &CryptoHash,
+    ) -> anyhow::Result<Option<near_primitives::views::LightClientBlockView>> {
+        let light_client_block = self
+            .rpc_client
+            .call(
+                &methods::next_light_client_block::RpcLightClientNextBlockRequest {
+                    last_block_hash: last_block_hash.clone(),
+                },
+            )
+            .await?;
+        anyhow::Ok(light_client_block)
+    }
+
+    /// Returns the block at `height` from the primary RPC service only, without retries
+    /// or fail over.
+    pub(crate) async fn view_block_once(&self, height: BlockHeight) -> anyhow::Result<BlockView> {
+        let block_view = self
+            .rpc_client
+            .call(&methods::block::RpcBlockRequest {
+                block_reference: BlockId::Height(height).into(),
+            })
+            .await?;
+        anyhow::Ok(block_view)
+    }
+
+    pub(crate) async fn view_chain_id(&self) -> anyhow::Result<String> {
+        retry(
+            || async {
+                let status = self.query(&methods::status::RpcStatusRequest).await?;
+
+                Ok(status.chain_id)
+            },
+            RetryStrategy::ExponentialBackoff,
+        )
+        .await
+    }
+}
+
+/// Query `method` from `rpc_client`, with retries.
+async fn query_with<M>(
+    rpc_client: &JsonRpcClient,
+    method: &M,
+) -> MethodCallResult<M::Response, M::Error>
+where
+    M: methods::RpcMethod + Debug,
+    M::Response: Debug,
+    M::Error: Debug,
+{
+    retry(
+        || async {
+            info_with_time!("Try querying {:?} ...", method);
+            let timer = metrics()
+                .rpc_call_seconds
+                .with_label_values(&[method.method_name()])
+                .start_timer();
+            let result = rpc_client.call(method).await;
+            timer.observe_duration();
+            if result.is_err() {
+                metrics()
+                    .rpc_call_failures
+                    .with_label_values(&[method.method_name()])
+                    .inc();
+            }
+            tracing::info!(
+                target: "workspaces",
+                "Querying RPC with {:?} resulted in {:?}",
+                method,
+                result
+            );
+            result
+        },
+        RetryStrategy::FixedInterval,
+    )
+    .await
 }
 
 async fn retry<R, E, T, F>(task: F, strategy: RetryStrategy) -> T::Output
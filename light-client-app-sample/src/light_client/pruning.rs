@@ -0,0 +1,48 @@
+//! Pruning policy of the heads stored by NEAR light client.
+//!
+
+use std::collections::BTreeSet;
+
+use near_light_client::near_types::{hash::CryptoHash, BlockHeight};
+
+use crate::config::PruningSection;
+
+/// Returns the heights of the heads to prune by `policy`, in ascending order.
+///
+/// The `heads` are the heights of the stored heads with their epoch ids, in ascending order
+/// of heights. A head is kept if it is:
+///
+/// - one of the `keep_recent_heights` most recent heads
+/// - the first stored head of one of the `keep_epochs` most recent epochs
+/// - the oldest stored head, which is the trust anchor of all the following heads
+pub fn heights_to_prune(
+    heads: &[(BlockHeight, CryptoHash)],
+    policy: &PruningSection,
+) -> Vec<BlockHeight> {
+    let recent_start = heads
+        .len()
+        .saturating_sub(policy.keep_recent_heights as usize);
+    let mut epoch_first_heights = Vec::new();
+    let mut last_epoch_id = None;
+    for (height, epoch_id) in heads {
+        if last_epoch_id != Some(epoch_id) {
+            epoch_first_heights.push(*height);
+            last_epoch_id = Some(epoch_id);
+        }
+    }
+    let kept_epoch_first_heights: BTreeSet<BlockHeight> = epoch_first_heights
+        .iter()
+        .rev()
+        .take(policy.keep_epochs as usize)
+        .copied()
+        .collect();
+    heads
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(index, (height, _))| {
+            *index < recent_start && !kept_epoch_first_heights.contains(height)
+        })
+        .map(|(_, (height, _))| *height)
+        .collect()
+}
@@ -0,0 +1,137 @@
+//! Snapshots of the state data of NEAR light client, to bootstrap new instances.
+//!
+//! A snapshot archive is laid out as:
+//!
+//! - the magic bytes [`SNAPSHOT_MAGIC`]
+//! - the format version, as little endian `u32`
+//! - the compression of the payload, `0` for none and `1` for zstd
+//! - the payload, the borsh serialization of [`Snapshot`]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_light_client::{
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+
+use super::store::{backend_error, decode, HeadStore};
+
+/// Magic bytes at the start of snapshot archives.
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"NEARLCSS";
+/// Version of the snapshot format written by this app.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_ZSTD: u8 = 1;
+const HEADER_SIZE: usize = SNAPSHOT_MAGIC.len() + 4 + 1;
+const ZSTD_LEVEL: i32 = 3;
+
+/// All the data of a store.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Snapshot {
+    /// Identifier of the chain the store is bound to
+    pub chain_id: Option<String>,
+    pub heads: Vec<(BlockHeight, ConsensusState)>,
+    pub epoch_bps: Vec<(CryptoHash, BlockProducers)>,
+    pub failed_heads: Vec<ConsensusState>,
+}
+
+impl Snapshot {
+    /// Read all the data in `store`.
+    pub fn read_from(store: &dyn HeadStore) -> Result<Self, StorageError> {
+        let mut heads = Vec::new();
+        for height in store.heights()? {
+            if let Some(head) = store.get_head(height)? {
+                heads.push((height, head));
+            }
+        }
+        let mut epoch_bps = Vec::new();
+        for epoch_id in store.epoch_ids()? {
+            if let Some(bps) = store.get_epoch_bps(&epoch_id)? {
+                epoch_bps.push((epoch_id, bps));
+            }
+        }
+        let mut failed_heads = Vec::new();
+        for height in store.failed_heights()? {
+            if let Some(head) = store.get_failed_head(height)? {
+                failed_heads.push(head);
+            }
+        }
+        Ok(Self {
+            chain_id: store.chain_id()?,
+            heads,
+            epoch_bps,
+            failed_heads,
+        })
+    }
+
+    /// Write all the data of the snapshot to `store`.
+    pub fn write_to(&self, store: &mut dyn HeadStore) -> Result<(), StorageError> {
+        if let Some(chain_id) = &self.chain_id {
+            store.set_chain_id(chain_id)?;
+        }
+        for (epoch_id, bps) in &self.epoch_bps {
+            store.put_epoch_bps(epoch_id, bps)?;
+        }
+        for head in &self.failed_heads {
+            store.put_failed_head(head)?;
+        }
+        for (height, head) in &self.heads {
+            let block_hash = head.header.light_client_block.current_block_hash();
+            store.put_head(*height, head, &block_hash)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the archive of the snapshot, with the payload compressed by zstd if
+    /// `compress` is true.
+    pub fn to_archive(&self, compress: bool) -> Result<Vec<u8>, StorageError> {
+        let payload = borsh::to_vec(self).map_err(backend_error)?;
+        let mut archive = Vec::with_capacity(HEADER_SIZE + payload.len());
+        archive.extend_from_slice(SNAPSHOT_MAGIC);
+        archive.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        if compress {
+            archive.push(COMPRESSION_ZSTD);
+            archive
+                .extend(zstd::encode_all(payload.as_slice(), ZSTD_LEVEL).map_err(backend_error)?);
+        } else {
+            archive.push(COMPRESSION_NONE);
+            archive.extend(payload);
+        }
+        Ok(archive)
+    }
+
+    /// Decode a snapshot from its archive.
+    pub fn from_archive(archive: &[u8]) -> Result<Self, StorageError> {
+        if archive.len() < HEADER_SIZE || !archive.starts_with(SNAPSHOT_MAGIC) {
+            return Err(StorageError::CorruptedData {
+                key: "snapshot".to_string(),
+            });
+        }
+        let version = u32::from_le_bytes(
+            archive[SNAPSHOT_MAGIC.len()..HEADER_SIZE - 1]
+                .try_into()
+                .expect("Should not fail based on the length of the archive header."),
+        );
+        if version != SNAPSHOT_VERSION {
+            return Err(backend_error(format!(
+                "Unsupported snapshot version {}, expected {}.",
+                version, SNAPSHOT_VERSION
+            )));
+        }
+        let payload = &archive[HEADER_SIZE..];
+        match archive[HEADER_SIZE - 1] {
+            COMPRESSION_NONE => decode("snapshot", payload),
+            COMPRESSION_ZSTD => decode(
+                "snapshot",
+                &zstd::decode_all(payload).map_err(|_| StorageError::CorruptedData {
+                    key: "snapshot".to_string(),
+                })?,
+            ),
+            compression => Err(backend_error(format!(
+                "Unsupported snapshot compression {}.",
+                compression
+            ))),
+        }
+    }
+}
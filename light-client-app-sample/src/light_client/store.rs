@@ -0,0 +1,503 @@
+//! Storage of the state data of NEAR light client.
+//!
+
+pub mod cached_store;
+pub mod overlay_store;
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb_store;
+pub mod sled_store;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_store;
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_light_client::{
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+
+use crate::config::StorageCompression;
+
+const HEAD_DATA_SUB_FOLDER: &str = "head";
+const FAILED_HEAD_SUB_FOLDER: &str = "failed_head";
+const EPOCH_BPS_SUB_FOLDER: &str = "epoch_bps";
+const BLOCK_HASH_INDEX_SUB_FOLDER: &str = "block_hash_index";
+const CHAIN_ID_FILE: &str = "chain_id";
+const LAST_VERIFIED_HEIGHT_FILE: &str = "last_verified_height";
+const QUARANTINE_SUB_FOLDER: &str = "quarantine";
+const TEMP_FILE_EXTENSION: &str = "tmp";
+/// Header byte of the stored blobs compressed by zstd.
+///
+/// Uncompressed blobs may start with it too, e.g. the block producers of 197 validators,
+/// as their length prefix is 197 in a little endian u32. Such a blob is not followed by the
+/// magic number of a zstd frame, so that it fails to decompress and is decoded as is.
+const ZSTD_BLOB_FLAG: u8 = 0xc5;
+const ZSTD_LEVEL: i32 = 3;
+
+/// Persistence of the consensus states (heads) of the light client, the block producers
+/// of epochs and the heads failed to verify.
+///
+/// Stores are `Send`, so that a light client can be shared by the sync loop and the
+/// server.
+pub trait HeadStore: Send {
+    /// Returns the heights of all stored heads in ascending order.
+    fn heights(&self) -> Result<Vec<BlockHeight>, StorageError>;
+    ///
+    fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError>;
+    /// Store `head` at `height`, indexed by `block_hash`, which is the hash of the block of
+    /// the head, e.g. of the sealed header it is created from.
+    fn put_head(
+        &mut self,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError>;
+    /// Delete the head at `height`, even if it can not be decoded.
+    fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError>;
+    /// Returns the height of the stored head with the given block hash.
+    fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError>;
+    /// Returns the ids of all epochs with stored block producers.
+    fn epoch_ids(&self) -> Result<Vec<CryptoHash>, StorageError>;
+    ///
+    fn get_epoch_bps(&self, epoch_id: &CryptoHash) -> Result<Option<BlockProducers>, StorageError>;
+    ///
+    fn put_epoch_bps(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError>;
+    /// Returns the heights of all stored failed heads in ascending order.
+    fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError>;
+    ///
+    fn get_failed_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError>;
+    ///
+    fn put_failed_head(&mut self, head: &ConsensusState) -> Result<(), StorageError>;
+    /// Returns the identifier of the chain the store is bound to, `None` if not bound yet.
+    fn chain_id(&self) -> Result<Option<String>, StorageError>;
+    ///
+    fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError>;
+    /// Returns the height of the last header verified before the last shutdown.
+    fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError>;
+    ///
+    fn set_last_verified_height(&mut self, height: BlockHeight) -> Result<(), StorageError>;
+    /// Persist the writes buffered by the backend, if any.
+    fn flush(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+    /// Returns the counters of the in-memory cache, `None` if the store is not cached.
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+}
+
+/// Counters of the lookups in the in-memory cache of a store.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Count of the lookups served from the cache
+    pub hits: u64,
+    /// Count of the lookups served from the underlying store
+    pub misses: u64,
+}
+
+/// Store with one file per head in a data folder, in the following layout:
+///
+/// - `head/<height>`: borsh serialized consensus states
+/// - `failed_head/<height>`: borsh serialized consensus states failed to verify
+/// - `epoch_bps/<epoch id>`: borsh serialized block producers of epochs
+/// - `block_hash_index/<block hash>`: borsh serialized heights of the heads
+/// - `chain_id`: identifier of the chain the data folder is bound to
+/// - `last_verified_height`: height of the last header verified before the last shutdown
+///
+/// Files are written to a temporary file which is synced and then renamed to the target,
+/// so that a crash never leaves a partially written head. Head files which can not be
+/// decoded are moved to `quarantine/<sub folder>/` on opening.
+pub struct FileHeadStore {
+    base_folder: PathBuf,
+    compression: StorageCompression,
+}
+
+impl FileHeadStore {
+    /// Open the store in `base_folder`, creating the sub folders if they don't exist.
+    ///
+    /// Temporary files left by interrupted writes are removed, and the heads which can
+    /// not be decoded are quarantined.
+    pub fn open(base_folder: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let base_folder = base_folder.as_ref().to_path_buf();
+        for sub_folder in [
+            HEAD_DATA_SUB_FOLDER,
+            FAILED_HEAD_SUB_FOLDER,
+            EPOCH_BPS_SUB_FOLDER,
+            BLOCK_HASH_INDEX_SUB_FOLDER,
+        ] {
+            std::fs::create_dir_all(base_folder.join(sub_folder)).map_err(backend_error)?;
+            remove_temp_files(&base_folder.join(sub_folder))?;
+        }
+        let store = Self {
+            base_folder,
+            compression: StorageCompression::None,
+        };
+        for sub_folder in [HEAD_DATA_SUB_FOLDER, FAILED_HEAD_SUB_FOLDER] {
+            store.quarantine_unreadable_heads(sub_folder)?;
+        }
+        Ok(store)
+    }
+
+    /// Compress the stored consensus states and block producers with `compression`
+    pub fn with_compression(mut self, compression: StorageCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns the paths of the quarantined files, in the layout of the store.
+    pub fn quarantined_files(&self) -> Result<Vec<PathBuf>, StorageError> {
+        let mut files = Vec::new();
+        for sub_folder in [HEAD_DATA_SUB_FOLDER, FAILED_HEAD_SUB_FOLDER] {
+            let folder = self
+                .base_folder
+                .join(QUARANTINE_SUB_FOLDER)
+                .join(sub_folder);
+            if !folder.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(folder).map_err(backend_error)? {
+                files.push(entry.map_err(backend_error)?.path());
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn quarantine_unreadable_heads(&self, sub_folder: &str) -> Result<(), StorageError> {
+        for height in list_heights(&self.base_folder.join(sub_folder))? {
+            let path = self.path_of(sub_folder, height);
+            if let Err(StorageError::CorruptedData { .. }) = read_file::<ConsensusState>(&path) {
+                let quarantine_folder = self
+                    .base_folder
+                    .join(QUARANTINE_SUB_FOLDER)
+                    .join(sub_folder);
+                std::fs::create_dir_all(&quarantine_folder).map_err(backend_error)?;
+                std::fs::rename(&path, quarantine_folder.join(height.to_string()))
+                    .map_err(backend_error)?;
+                tracing::warn!(
+                    "Quarantined unreadable head file {} to {}.",
+                    path.display(),
+                    quarantine_folder.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the paths of the entries of the block hash index pointing to `height`.
+    fn block_hash_index_entries_of(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Vec<PathBuf>, StorageError> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(self.base_folder.join(BLOCK_HASH_INDEX_SUB_FOLDER))
+            .map_err(backend_error)?
+        {
+            let path = entry.map_err(backend_error)?.path();
+            if path.is_file() && tolerate_corrupted(read_file(&path))? == Some(height) {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    fn path_of(&self, sub_folder: &str, name: impl ToString) -> PathBuf {
+        self.base_folder.join(sub_folder).join(name.to_string())
+    }
+}
+
+impl HeadStore for FileHeadStore {
+    fn heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        list_heights(&self.base_folder.join(HEAD_DATA_SUB_FOLDER))
+    }
+
+    fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        read_file(&self.path_of(HEAD_DATA_SUB_FOLDER, height))
+    }
+
+    fn put_head(
+        &mut self,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError> {
+        write_file(
+            &self.path_of(HEAD_DATA_SUB_FOLDER, height),
+            head,
+            self.compression,
+        )?;
+        write_file(
+            &self.path_of(BLOCK_HASH_INDEX_SUB_FOLDER, block_hash),
+            &height,
+            StorageCompression::None,
+        )
+    }
+
+    fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        match tolerate_corrupted(self.get_head(height))? {
+            Some(head) => remove_file(&self.path_of(
+                BLOCK_HASH_INDEX_SUB_FOLDER,
+                head.header.light_client_block.current_block_hash(),
+            ))?,
+            // The block hash of a corrupted head is unknown, so its index entries are found
+            // by the height.
+            None => {
+                for path in self.block_hash_index_entries_of(height)? {
+                    remove_file(&path)?;
+                }
+            }
+        }
+        remove_file(&self.path_of(HEAD_DATA_SUB_FOLDER, height))
+    }
+
+    fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError> {
+        read_file(&self.path_of(BLOCK_HASH_INDEX_SUB_FOLDER, block_hash))
+    }
+
+    fn epoch_ids(&self) -> Result<Vec<CryptoHash>, StorageError> {
+        let mut epoch_ids = Vec::new();
+        for entry in
+            std::fs::read_dir(self.base_folder.join(EPOCH_BPS_SUB_FOLDER)).map_err(backend_error)?
+        {
+            let path = entry.map_err(backend_error)?.path();
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                if path.is_file() {
+                    epoch_ids.push(epoch_id_of_name(name)?);
+                }
+            }
+        }
+        epoch_ids.sort_by_key(|epoch_id| epoch_id.0);
+        Ok(epoch_ids)
+    }
+
+    fn get_epoch_bps(&self, epoch_id: &CryptoHash) -> Result<Option<BlockProducers>, StorageError> {
+        read_file(&self.path_of(EPOCH_BPS_SUB_FOLDER, epoch_id))
+    }
+
+    fn put_epoch_bps(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError> {
+        write_file(
+            &self.path_of(EPOCH_BPS_SUB_FOLDER, epoch_id),
+            bps,
+            self.compression,
+        )
+    }
+
+    fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        list_heights(&self.base_folder.join(FAILED_HEAD_SUB_FOLDER))
+    }
+
+    fn get_failed_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        read_file(&self.path_of(FAILED_HEAD_SUB_FOLDER, height))
+    }
+
+    fn put_failed_head(&mut self, head: &ConsensusState) -> Result<(), StorageError> {
+        write_file(
+            &self.path_of(FAILED_HEAD_SUB_FOLDER, head.header.height().revision_height),
+            head,
+            self.compression,
+        )
+    }
+
+    fn chain_id(&self) -> Result<Option<String>, StorageError> {
+        match std::fs::read_to_string(self.base_folder.join(CHAIN_ID_FILE)) {
+            Ok(chain_id) => Ok(Some(chain_id.trim().to_string())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(backend_error(err)),
+        }
+    }
+
+    fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError> {
+        write_atomically(&self.base_folder.join(CHAIN_ID_FILE), chain_id.as_bytes())
+    }
+
+    fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError> {
+        let path = self.base_folder.join(LAST_VERIFIED_HEIGHT_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(height) => {
+                height
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| StorageError::CorruptedData {
+                        key: path.display().to_string(),
+                    })
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(backend_error(err)),
+        }
+    }
+
+    fn set_last_verified_height(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        write_atomically(
+            &self.base_folder.join(LAST_VERIFIED_HEIGHT_FILE),
+            height.to_string().as_bytes(),
+        )
+    }
+}
+
+/// Convert an error of the storage backend to `StorageError`.
+pub fn backend_error(err: impl std::fmt::Display) -> StorageError {
+    StorageError::Backend {
+        message: err.to_string(),
+    }
+}
+
+/// Returns the borsh serialization of `value` to store, compressed by `compression`.
+pub fn encode<T: BorshSerialize>(
+    value: &T,
+    compression: StorageCompression,
+) -> Result<Vec<u8>, StorageError> {
+    let bytes = borsh::to_vec(value).map_err(backend_error)?;
+    match compression {
+        StorageCompression::None => Ok(bytes),
+        StorageCompression::Zstd => {
+            let mut blob = vec![ZSTD_BLOB_FLAG];
+            blob.extend(zstd::encode_all(bytes.as_slice(), ZSTD_LEVEL).map_err(backend_error)?);
+            Ok(blob)
+        }
+    }
+}
+
+/// Decode borsh serialized `bytes` stored for `key`, which are decompressed first if
+/// they are compressed by [`encode`].
+pub fn decode<T: BorshDeserialize>(key: impl ToString, bytes: &[u8]) -> Result<T, StorageError> {
+    let decompressed = match bytes.split_first() {
+        Some((&ZSTD_BLOB_FLAG, compressed)) => zstd::decode_all(compressed).ok(),
+        _ => None,
+    };
+    T::try_from_slice(decompressed.as_deref().unwrap_or(bytes)).map_err(|_| {
+        StorageError::CorruptedData {
+            key: key.to_string(),
+        }
+    })
+}
+
+/// Returns `None` for the data which can not be decoded, to be able to delete it.
+pub fn tolerate_corrupted<T>(
+    result: Result<Option<T>, StorageError>,
+) -> Result<Option<T>, StorageError> {
+    match result {
+        Err(StorageError::CorruptedData { .. }) => Ok(None),
+        result => result,
+    }
+}
+
+/// Decode a height stored as the big endian bytes.
+pub fn height_of_key(bytes: &[u8]) -> Result<BlockHeight, StorageError> {
+    bytes
+        .try_into()
+        .map(BlockHeight::from_be_bytes)
+        .map_err(|_| StorageError::CorruptedData {
+            key: format!("{:?}", bytes),
+        })
+}
+
+/// Decode an epoch id stored as its bytes.
+pub fn epoch_id_of_key(bytes: &[u8]) -> Result<CryptoHash, StorageError> {
+    CryptoHash::try_from(bytes).map_err(|_| StorageError::CorruptedData {
+        key: format!("{:?}", bytes),
+    })
+}
+
+/// Decode an epoch id stored as its base58 string.
+pub fn epoch_id_of_name(name: &str) -> Result<CryptoHash, StorageError> {
+    bs58::decode(name)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| CryptoHash::try_from(bytes.as_slice()).ok())
+        .ok_or_else(|| StorageError::CorruptedData {
+            key: name.to_string(),
+        })
+}
+
+fn read_file<T: BorshDeserialize>(path: &Path) -> Result<Option<T>, StorageError> {
+    match std::fs::read(path) {
+        Ok(bytes) => decode(path.display(), &bytes).map(Some),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(backend_error(err)),
+    }
+}
+
+fn remove_file(path: &Path) -> Result<(), StorageError> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(backend_error(err)),
+        _ => Ok(()),
+    }
+}
+
+fn write_file<T: BorshSerialize>(
+    path: &Path,
+    value: &T,
+    compression: StorageCompression,
+) -> Result<(), StorageError> {
+    write_atomically(path, &encode(value, compression)?)
+}
+
+/// Write `bytes` to a temporary file beside `path`, sync it and rename it to `path`,
+/// then sync the folder to persist the rename.
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), StorageError> {
+    let temp_path = path.with_extension(TEMP_FILE_EXTENSION);
+    let mut file = std::fs::File::create(&temp_path).map_err(backend_error)?;
+    file.write_all(bytes).map_err(backend_error)?;
+    file.sync_all().map_err(backend_error)?;
+    std::fs::rename(&temp_path, path).map_err(backend_error)?;
+    #[cfg(unix)]
+    if let Some(folder) = path.parent() {
+        std::fs::File::open(folder)
+            .and_then(|folder| folder.sync_all())
+            .map_err(backend_error)?;
+    }
+    Ok(())
+}
+
+/// Remove the temporary files left in `folder` by interrupted writes.
+fn remove_temp_files(folder: &Path) -> Result<(), StorageError> {
+    for entry in std::fs::read_dir(folder).map_err(backend_error)? {
+        let path = entry.map_err(backend_error)?.path();
+        if path.is_file()
+            && path.extension().and_then(|ext| ext.to_str()) == Some(TEMP_FILE_EXTENSION)
+        {
+            std::fs::remove_file(&path).map_err(backend_error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the heights of the files named by heights in `folder`, in ascending order.
+fn list_heights(folder: &Path) -> Result<Vec<BlockHeight>, StorageError> {
+    let mut heights = Vec::new();
+    for entry in std::fs::read_dir(folder).map_err(backend_error)? {
+        let path = entry.map_err(backend_error)?.path();
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(height) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<BlockHeight>().ok())
+        {
+            heights.push(height);
+        }
+    }
+    heights.sort();
+    Ok(heights)
+}
@@ -0,0 +1,159 @@
+//! In-memory LRU cache over a `HeadStore`.
+//!
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use lru::LruCache;
+use near_light_client::{
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+
+use super::{CacheStats, HeadStore};
+
+/// Store caching the most recently used heads and epoch block producers of another store
+/// in memory, the failed heads and metadata are not cached.
+pub struct CachedHeadStore {
+    inner: Box<dyn HeadStore>,
+    heads: Mutex<LruCache<BlockHeight, ConsensusState>>,
+    epoch_bps: Mutex<LruCache<CryptoHash, BlockProducers>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedHeadStore {
+    /// Cache at most `capacity` heads and `capacity` epoch block producers of `inner`.
+    pub fn new(inner: Box<dyn HeadStore>, capacity: NonZeroUsize) -> Self {
+        Self {
+            inner,
+            heads: Mutex::new(LruCache::new(capacity)),
+            epoch_bps: Mutex::new(LruCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn get_cached<K: std::hash::Hash + Eq + Clone, V: Clone>(
+        &self,
+        cache: &Mutex<LruCache<K, V>>,
+        key: &K,
+        load: impl FnOnce() -> Result<Option<V>, StorageError>,
+    ) -> Result<Option<V>, StorageError> {
+        if let Some(value) = lock(cache).get(key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(value.clone()));
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = load()?;
+        if let Some(value) = value.as_ref() {
+            lock(cache).put(key.clone(), value.clone());
+        }
+        Ok(value)
+    }
+}
+
+impl HeadStore for CachedHeadStore {
+    fn heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        self.inner.heights()
+    }
+
+    fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        self.get_cached(&self.heads, &height, || self.inner.get_head(height))
+    }
+
+    fn put_head(
+        &mut self,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError> {
+        self.inner.put_head(height, head, block_hash)?;
+        lock(&self.heads).put(height, head.clone());
+        Ok(())
+    }
+
+    fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.inner.delete_head(height)?;
+        lock(&self.heads).pop(&height);
+        Ok(())
+    }
+
+    fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError> {
+        self.inner.height_of_block_hash(block_hash)
+    }
+
+    fn epoch_ids(&self) -> Result<Vec<CryptoHash>, StorageError> {
+        self.inner.epoch_ids()
+    }
+
+    fn get_epoch_bps(&self, epoch_id: &CryptoHash) -> Result<Option<BlockProducers>, StorageError> {
+        self.get_cached(&self.epoch_bps, epoch_id, || {
+            self.inner.get_epoch_bps(epoch_id)
+        })
+    }
+
+    fn put_epoch_bps(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError> {
+        self.inner.put_epoch_bps(epoch_id, bps)?;
+        lock(&self.epoch_bps).put(*epoch_id, bps.clone());
+        Ok(())
+    }
+
+    fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        self.inner.failed_heights()
+    }
+
+    fn get_failed_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        self.inner.get_failed_head(height)
+    }
+
+    fn put_failed_head(&mut self, head: &ConsensusState) -> Result<(), StorageError> {
+        self.inner.put_failed_head(head)
+    }
+
+    fn chain_id(&self) -> Result<Option<String>, StorageError> {
+        self.inner.chain_id()
+    }
+
+    fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError> {
+        self.inner.set_chain_id(chain_id)
+    }
+
+    fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError> {
+        self.inner.last_verified_height()
+    }
+
+    fn set_last_verified_height(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.inner.set_last_verified_height(height)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        self.inner.flush()
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        })
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
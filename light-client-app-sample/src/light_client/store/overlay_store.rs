@@ -0,0 +1,161 @@
+//! In-memory overlay over a `HeadStore`, which never writes to the store beneath.
+//!
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use near_light_client::{
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+
+use super::{CacheStats, HeadStore};
+
+/// Store keeping all writes in memory on top of another store, which is only read, so
+/// that the light client can run against the stored state without changing it.
+pub struct OverlayHeadStore {
+    inner: Box<dyn HeadStore>,
+    /// Heads written to the overlay, `None` for the deleted ones
+    heads: BTreeMap<BlockHeight, Option<ConsensusState>>,
+    /// Heights of the heads written to the overlay by their block hashes
+    block_hash_index: BTreeMap<CryptoHash, BlockHeight>,
+    epoch_bps: BTreeMap<CryptoHash, BlockProducers>,
+    failed_heads: BTreeMap<BlockHeight, ConsensusState>,
+    chain_id: Option<String>,
+    last_verified_height: Option<BlockHeight>,
+}
+
+impl OverlayHeadStore {
+    /// Keep the writes to `inner` in memory.
+    pub fn new(inner: Box<dyn HeadStore>) -> Self {
+        Self {
+            inner,
+            heads: BTreeMap::new(),
+            block_hash_index: BTreeMap::new(),
+            epoch_bps: BTreeMap::new(),
+            failed_heads: BTreeMap::new(),
+            chain_id: None,
+            last_verified_height: None,
+        }
+    }
+}
+
+impl HeadStore for OverlayHeadStore {
+    fn heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        let mut heights: BTreeSet<BlockHeight> = self.inner.heights()?.into_iter().collect();
+        for (height, head) in self.heads.iter() {
+            match head {
+                Some(_) => heights.insert(*height),
+                None => heights.remove(height),
+            };
+        }
+        Ok(heights.into_iter().collect())
+    }
+
+    fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        match self.heads.get(&height) {
+            Some(head) => Ok(head.clone()),
+            None => self.inner.get_head(height),
+        }
+    }
+
+    fn put_head(
+        &mut self,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError> {
+        self.block_hash_index.retain(|_, h| *h != height);
+        self.block_hash_index.insert(*block_hash, height);
+        self.heads.insert(height, Some(head.clone()));
+        Ok(())
+    }
+
+    fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.block_hash_index.retain(|_, h| *h != height);
+        self.heads.insert(height, None);
+        Ok(())
+    }
+
+    fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError> {
+        if let Some(height) = self.block_hash_index.get(block_hash) {
+            return Ok(Some(*height));
+        }
+        match self.inner.height_of_block_hash(block_hash)? {
+            Some(height) if self.heads.contains_key(&height) => Ok(None),
+            height => Ok(height),
+        }
+    }
+
+    fn epoch_ids(&self) -> Result<Vec<CryptoHash>, StorageError> {
+        let mut epoch_ids: BTreeSet<CryptoHash> = self.inner.epoch_ids()?.into_iter().collect();
+        epoch_ids.extend(self.epoch_bps.keys().copied());
+        Ok(epoch_ids.into_iter().collect())
+    }
+
+    fn get_epoch_bps(&self, epoch_id: &CryptoHash) -> Result<Option<BlockProducers>, StorageError> {
+        match self.epoch_bps.get(epoch_id) {
+            Some(bps) => Ok(Some(bps.clone())),
+            None => self.inner.get_epoch_bps(epoch_id),
+        }
+    }
+
+    fn put_epoch_bps(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError> {
+        self.epoch_bps.insert(*epoch_id, bps.clone());
+        Ok(())
+    }
+
+    fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        let mut heights: BTreeSet<BlockHeight> = self.inner.failed_heights()?.into_iter().collect();
+        heights.extend(self.failed_heads.keys().copied());
+        Ok(heights.into_iter().collect())
+    }
+
+    fn get_failed_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        match self.failed_heads.get(&height) {
+            Some(head) => Ok(Some(head.clone())),
+            None => self.inner.get_failed_head(height),
+        }
+    }
+
+    fn put_failed_head(&mut self, head: &ConsensusState) -> Result<(), StorageError> {
+        self.failed_heads
+            .insert(head.header.height().revision_height, head.clone());
+        Ok(())
+    }
+
+    fn chain_id(&self) -> Result<Option<String>, StorageError> {
+        match self.chain_id.as_ref() {
+            Some(chain_id) => Ok(Some(chain_id.clone())),
+            None => self.inner.chain_id(),
+        }
+    }
+
+    fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError> {
+        self.chain_id = Some(chain_id.to_owned());
+        Ok(())
+    }
+
+    fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError> {
+        match self.last_verified_height {
+            Some(height) => Ok(Some(height)),
+            None => self.inner.last_verified_height(),
+        }
+    }
+
+    fn set_last_verified_height(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.last_verified_height = Some(height);
+        Ok(())
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        self.inner.cache_stats()
+    }
+}
@@ -0,0 +1,254 @@
+//! Implementation of `HeadStore` based on RocksDB.
+//!
+
+use std::path::Path;
+
+use near_light_client::{
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+use rocksdb::{
+    BlockBasedOptions, Cache, ColumnFamily, DBCompactionStyle, IteratorMode, Options, WriteBatch,
+    DB,
+};
+
+use super::{
+    backend_error, decode, encode, epoch_id_of_key, height_of_key, tolerate_corrupted, HeadStore,
+};
+use crate::config::{RocksDbCompactionStyle, RocksDbSection, StorageCompression};
+
+const HEADS_CF: &str = "heads";
+const EPOCH_BPS_CF: &str = "epoch_bps";
+const BLOCK_HASH_INDEX_CF: &str = "block_hash_index";
+const FAILED_HEADS_CF: &str = "failed_heads";
+const CHAIN_ID_KEY: &[u8] = b"chain_id";
+const LAST_VERIFIED_HEIGHT_KEY: &[u8] = b"last_verified_height";
+
+/// Store in a RocksDB database, with a column family for each kind of data:
+///
+/// - `heads`: consensus states keyed by the big endian bytes of their heights
+/// - `epoch_bps`: block producers keyed by epoch ids
+/// - `block_hash_index`: heights of the heads keyed by their block hashes
+/// - `failed_heads`: consensus states failed to verify, keyed the same as `heads`
+///
+/// The metadata, such as the chain id, is stored in the default column family.
+pub struct RocksDbHeadStore {
+    db: DB,
+    compression: StorageCompression,
+}
+
+impl RocksDbHeadStore {
+    /// Open the database in `path` with the options in `config`, creating it if it
+    /// doesn't exist.
+    pub fn open(path: impl AsRef<Path>, config: &RocksDbSection) -> Result<Self, StorageError> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let mut block_options = BlockBasedOptions::default();
+        block_options.set_block_cache(&Cache::new_lru_cache(
+            config.cache_size_mb as usize * 1024 * 1024,
+        ));
+        options.set_block_based_table_factory(&block_options);
+        options.set_compaction_style(match config.compaction_style {
+            RocksDbCompactionStyle::Level => DBCompactionStyle::Level,
+            RocksDbCompactionStyle::Universal => DBCompactionStyle::Universal,
+        });
+        let db = DB::open_cf(
+            &options,
+            path,
+            [HEADS_CF, EPOCH_BPS_CF, BLOCK_HASH_INDEX_CF, FAILED_HEADS_CF],
+        )
+        .map_err(backend_error)?;
+        Ok(Self {
+            db,
+            compression: StorageCompression::None,
+        })
+    }
+
+    /// Compress the stored consensus states and block producers with `compression`
+    pub fn with_compression(mut self, compression: StorageCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Compact all the column families, to reclaim the space of pruned heads.
+    pub fn compact(&self) {
+        for name in [HEADS_CF, EPOCH_BPS_CF, BLOCK_HASH_INDEX_CF, FAILED_HEADS_CF] {
+            self.db
+                .compact_range_cf(self.cf(name), None::<&[u8]>, None::<&[u8]>);
+        }
+    }
+
+    fn cf(&self, name: &str) -> &ColumnFamily {
+        self.db
+            .cf_handle(name)
+            .expect("Should not fail based on the column families created on opening.")
+    }
+
+    fn heights_of(&self, name: &str) -> Result<Vec<BlockHeight>, StorageError> {
+        self.db
+            .iterator_cf(self.cf(name), IteratorMode::Start)
+            .map(|item| height_of_key(&item.map_err(backend_error)?.0))
+            .collect()
+    }
+
+    fn get_value<T: borsh::BorshDeserialize>(
+        &self,
+        cf_name: &str,
+        key: impl AsRef<[u8]>,
+        name: impl ToString,
+    ) -> Result<Option<T>, StorageError> {
+        self.db
+            .get_cf(self.cf(cf_name), key)
+            .map_err(backend_error)?
+            .map(|bytes| decode(name, &bytes))
+            .transpose()
+    }
+}
+
+impl HeadStore for RocksDbHeadStore {
+    fn heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        self.heights_of(HEADS_CF)
+    }
+
+    fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        self.get_value(HEADS_CF, height.to_be_bytes(), height)
+    }
+
+    fn put_head(
+        &mut self,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError> {
+        let mut batch = WriteBatch::default();
+        batch.put_cf(
+            self.cf(HEADS_CF),
+            height.to_be_bytes(),
+            encode(head, self.compression)?,
+        );
+        batch.put_cf(
+            self.cf(BLOCK_HASH_INDEX_CF),
+            block_hash.0,
+            height.to_be_bytes(),
+        );
+        self.db.write(batch).map_err(backend_error)
+    }
+
+    fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        let mut batch = WriteBatch::default();
+        batch.delete_cf(self.cf(HEADS_CF), height.to_be_bytes());
+        match tolerate_corrupted(self.get_head(height))? {
+            Some(head) => batch.delete_cf(
+                self.cf(BLOCK_HASH_INDEX_CF),
+                head.header.light_client_block.current_block_hash().0,
+            ),
+            // The block hash of a corrupted head is unknown, so its index entries are found
+            // by the height.
+            None => {
+                for item in self
+                    .db
+                    .iterator_cf(self.cf(BLOCK_HASH_INDEX_CF), IteratorMode::Start)
+                {
+                    let (block_hash, value) = item.map_err(backend_error)?;
+                    if *value == height.to_be_bytes() {
+                        batch.delete_cf(self.cf(BLOCK_HASH_INDEX_CF), block_hash);
+                    }
+                }
+            }
+        }
+        self.db.write(batch).map_err(backend_error)
+    }
+
+    fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError> {
+        self.db
+            .get_cf(self.cf(BLOCK_HASH_INDEX_CF), block_hash.0)
+            .map_err(backend_error)?
+            .map(|bytes| height_of_key(&bytes))
+            .transpose()
+    }
+
+    fn epoch_ids(&self) -> Result<Vec<CryptoHash>, StorageError> {
+        self.db
+            .iterator_cf(self.cf(EPOCH_BPS_CF), IteratorMode::Start)
+            .map(|item| epoch_id_of_key(&item.map_err(backend_error)?.0))
+            .collect()
+    }
+
+    fn get_epoch_bps(&self, epoch_id: &CryptoHash) -> Result<Option<BlockProducers>, StorageError> {
+        self.get_value(EPOCH_BPS_CF, epoch_id.0, epoch_id)
+    }
+
+    fn put_epoch_bps(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError> {
+        self.db
+            .put_cf(
+                self.cf(EPOCH_BPS_CF),
+                epoch_id.0,
+                encode(bps, self.compression)?,
+            )
+            .map_err(backend_error)
+    }
+
+    fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        self.heights_of(FAILED_HEADS_CF)
+    }
+
+    fn get_failed_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        self.get_value(FAILED_HEADS_CF, height.to_be_bytes(), height)
+    }
+
+    fn put_failed_head(&mut self, head: &ConsensusState) -> Result<(), StorageError> {
+        let height = head.header.height().revision_height;
+        self.db
+            .put_cf(
+                self.cf(FAILED_HEADS_CF),
+                height.to_be_bytes(),
+                encode(head, self.compression)?,
+            )
+            .map_err(backend_error)
+    }
+
+    fn chain_id(&self) -> Result<Option<String>, StorageError> {
+        self.db
+            .get(CHAIN_ID_KEY)
+            .map_err(backend_error)?
+            .map(|bytes| {
+                String::from_utf8(bytes).map_err(|_| StorageError::CorruptedData {
+                    key: "chain_id".to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError> {
+        self.db
+            .put(CHAIN_ID_KEY, chain_id.as_bytes())
+            .map_err(backend_error)
+    }
+
+    fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError> {
+        self.db
+            .get(LAST_VERIFIED_HEIGHT_KEY)
+            .map_err(backend_error)?
+            .map(|bytes| height_of_key(&bytes))
+            .transpose()
+    }
+
+    fn set_last_verified_height(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.db
+            .put(LAST_VERIFIED_HEIGHT_KEY, height.to_be_bytes())
+            .map_err(backend_error)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        self.db.flush().map_err(backend_error)
+    }
+}
@@ -0,0 +1,271 @@
+//! Implementation of `HeadStore` based on sled.
+//!
+
+use std::path::Path;
+
+use sled::{transaction::TransactionError, Transactional};
+
+use near_light_client::{
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+
+use super::{
+    backend_error, decode, encode, epoch_id_of_key, height_of_key, tolerate_corrupted, HeadStore,
+};
+use crate::config::StorageCompression;
+
+const HEADS_TREE: &str = "heads";
+const FAILED_HEADS_TREE: &str = "failed_heads";
+const EPOCH_BPS_TREE: &str = "epoch_bps";
+const BLOCK_HASH_INDEX_TREE: &str = "block_hash_index";
+const EPOCH_INDEX_TREE: &str = "epoch_index";
+const CHAIN_ID_KEY: &[u8] = b"chain_id";
+const LAST_VERIFIED_HEIGHT_KEY: &[u8] = b"last_verified_height";
+/// Length of the keys of the epoch index, an epoch id followed by a height.
+const EPOCH_KEY_LENGTH: usize = 32 + 8;
+
+/// Store in a sled database, with the heads keyed by the big endian bytes of their heights,
+/// so that they are iterated in ascending order.
+///
+/// Secondary indexes are maintained for the heads, from block hash to height and from
+/// epoch id followed by height to nothing.
+pub struct SledHeadStore {
+    db: sled::Db,
+    heads: sled::Tree,
+    failed_heads: sled::Tree,
+    epoch_bps: sled::Tree,
+    block_hash_index: sled::Tree,
+    epoch_index: sled::Tree,
+    compression: StorageCompression,
+}
+
+impl SledHeadStore {
+    /// Open the database in `path`, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let db = sled::open(path).map_err(backend_error)?;
+        let open_tree = |name: &str| db.open_tree(name).map_err(backend_error);
+        Ok(Self {
+            heads: open_tree(HEADS_TREE)?,
+            failed_heads: open_tree(FAILED_HEADS_TREE)?,
+            epoch_bps: open_tree(EPOCH_BPS_TREE)?,
+            block_hash_index: open_tree(BLOCK_HASH_INDEX_TREE)?,
+            epoch_index: open_tree(EPOCH_INDEX_TREE)?,
+            db,
+            compression: StorageCompression::None,
+        })
+    }
+
+    /// Compress the stored consensus states and block producers with `compression`
+    pub fn with_compression(mut self, compression: StorageCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns the heights of the stored heads in the given epoch, in ascending order.
+    pub fn heights_of_epoch(
+        &self,
+        epoch_id: &CryptoHash,
+    ) -> Result<Vec<BlockHeight>, StorageError> {
+        self.epoch_index
+            .scan_prefix(epoch_id.0)
+            .keys()
+            .map(|key| height_of_key(&key.map_err(backend_error)?[epoch_id.0.len()..]))
+            .collect()
+    }
+}
+
+impl HeadStore for SledHeadStore {
+    fn heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        heights_of(&self.heads)
+    }
+
+    fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        get_value(&self.heads, height.to_be_bytes(), height)
+    }
+
+    fn put_head(
+        &mut self,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError> {
+        let bytes = encode(head, self.compression)?;
+        let epoch_key = epoch_key(&head.header.epoch_id(), height);
+        (&self.heads, &self.block_hash_index, &self.epoch_index)
+            .transaction(|(heads, block_hash_index, epoch_index)| {
+                heads.insert(height.to_be_bytes().as_slice(), bytes.as_slice())?;
+                block_hash_index
+                    .insert(block_hash.0.as_slice(), height.to_be_bytes().as_slice())?;
+                epoch_index.insert(epoch_key.as_slice(), sled::IVec::default())?;
+                Ok(())
+            })
+            .map_err(transaction_error)
+    }
+
+    fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        let height_bytes = height.to_be_bytes();
+        let (block_hash_keys, epoch_keys) = match tolerate_corrupted(self.get_head(height))? {
+            Some(head) => {
+                let block_hash = head.header.light_client_block.current_block_hash();
+                (
+                    vec![block_hash.0.to_vec()],
+                    vec![epoch_key(&head.header.epoch_id(), height)],
+                )
+            }
+            // The block hash and the epoch of a corrupted head are unknown, so its index
+            // entries are found by the height.
+            None => (
+                find_keys(&self.block_hash_index, |_, value| value == height_bytes)?,
+                find_keys(&self.epoch_index, |key, _| {
+                    key.len() == EPOCH_KEY_LENGTH && key[32..] == height_bytes
+                })?,
+            ),
+        };
+        (&self.heads, &self.block_hash_index, &self.epoch_index)
+            .transaction(|(heads, block_hash_index, epoch_index)| {
+                heads.remove(height_bytes.as_slice())?;
+                for key in block_hash_keys.iter() {
+                    block_hash_index.remove(key.as_slice())?;
+                }
+                for key in epoch_keys.iter() {
+                    epoch_index.remove(key.as_slice())?;
+                }
+                Ok(())
+            })
+            .map_err(transaction_error)
+    }
+
+    fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError> {
+        self.block_hash_index
+            .get(block_hash.0)
+            .map_err(backend_error)?
+            .map(|bytes| height_of_key(&bytes))
+            .transpose()
+    }
+
+    fn epoch_ids(&self) -> Result<Vec<CryptoHash>, StorageError> {
+        self.epoch_bps
+            .iter()
+            .keys()
+            .map(|key| epoch_id_of_key(&key.map_err(backend_error)?))
+            .collect()
+    }
+
+    fn get_epoch_bps(&self, epoch_id: &CryptoHash) -> Result<Option<BlockProducers>, StorageError> {
+        get_value(&self.epoch_bps, epoch_id.0, epoch_id)
+    }
+
+    fn put_epoch_bps(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError> {
+        self.epoch_bps
+            .insert(epoch_id.0, encode(bps, self.compression)?)
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+
+    fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        heights_of(&self.failed_heads)
+    }
+
+    fn get_failed_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        get_value(&self.failed_heads, height.to_be_bytes(), height)
+    }
+
+    fn put_failed_head(&mut self, head: &ConsensusState) -> Result<(), StorageError> {
+        let height = head.header.height().revision_height;
+        self.failed_heads
+            .insert(height.to_be_bytes(), encode(head, self.compression)?)
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+
+    fn chain_id(&self) -> Result<Option<String>, StorageError> {
+        self.db
+            .get(CHAIN_ID_KEY)
+            .map_err(backend_error)?
+            .map(|bytes| {
+                String::from_utf8(bytes.to_vec()).map_err(|_| StorageError::CorruptedData {
+                    key: "chain_id".to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError> {
+        self.db
+            .insert(CHAIN_ID_KEY, chain_id.as_bytes())
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+
+    fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError> {
+        self.db
+            .get(LAST_VERIFIED_HEIGHT_KEY)
+            .map_err(backend_error)?
+            .map(|bytes| height_of_key(&bytes))
+            .transpose()
+    }
+
+    fn set_last_verified_height(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.db
+            .insert(LAST_VERIFIED_HEIGHT_KEY, &height.to_be_bytes())
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        self.db.flush().map(|_| ()).map_err(backend_error)
+    }
+}
+
+fn epoch_key(epoch_id: &CryptoHash, height: BlockHeight) -> Vec<u8> {
+    [epoch_id.0.as_slice(), &height.to_be_bytes()].concat()
+}
+
+/// Returns the keys of the entries of `tree` accepted by `matches`.
+fn find_keys(
+    tree: &sled::Tree,
+    matches: impl Fn(&[u8], &[u8]) -> bool,
+) -> Result<Vec<Vec<u8>>, StorageError> {
+    let mut keys = Vec::new();
+    for item in tree.iter() {
+        let (key, value) = item.map_err(backend_error)?;
+        if matches(&key, &value) {
+            keys.push(key.to_vec());
+        }
+    }
+    Ok(keys)
+}
+
+fn heights_of(tree: &sled::Tree) -> Result<Vec<BlockHeight>, StorageError> {
+    tree.iter()
+        .keys()
+        .map(|key| height_of_key(&key.map_err(backend_error)?))
+        .collect()
+}
+
+fn get_value<T: borsh::BorshDeserialize>(
+    tree: &sled::Tree,
+    key: impl AsRef<[u8]>,
+    name: impl ToString,
+) -> Result<Option<T>, StorageError> {
+    tree.get(key)
+        .map_err(backend_error)?
+        .map(|bytes| decode(name, &bytes))
+        .transpose()
+}
+
+fn transaction_error(err: TransactionError) -> StorageError {
+    match err {
+        TransactionError::Storage(err) => backend_error(err),
+        TransactionError::Abort(()) => backend_error("Transaction aborted."),
+    }
+}
@@ -0,0 +1,297 @@
+//! Implementation of `HeadStore` based on SQLite.
+//!
+
+use std::path::Path;
+
+use near_light_client::{
+    near_types::{block_producers::BlockProducers, hash::CryptoHash, BlockHeight},
+    types::ConsensusState,
+    StorageError,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{backend_error, decode, encode, epoch_id_of_name, HeadStore};
+use crate::config::StorageCompression;
+
+/// The heads and failed heads have their heights, epoch ids, block hashes (in base58) and
+/// timestamps (in nanoseconds) as indexed columns, so that the verification history can be
+/// queried with SQL, and the borsh serialized consensus states in column `data`.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS heads (
+    height INTEGER PRIMARY KEY,
+    epoch_id TEXT NOT NULL,
+    block_hash TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS heads_epoch_id ON heads (epoch_id);
+CREATE UNIQUE INDEX IF NOT EXISTS heads_block_hash ON heads (block_hash);
+CREATE INDEX IF NOT EXISTS heads_timestamp ON heads (timestamp);
+CREATE TABLE IF NOT EXISTS failed_heads (
+    height INTEGER PRIMARY KEY,
+    epoch_id TEXT NOT NULL,
+    block_hash TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS epoch_bps (
+    epoch_id TEXT PRIMARY KEY,
+    data BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS metadata (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+const CHAIN_ID_KEY: &str = "chain_id";
+const LAST_VERIFIED_HEIGHT_KEY: &str = "last_verified_height";
+
+/// Store in an SQLite database, see [`SCHEMA`] for the tables.
+pub struct SqliteHeadStore {
+    conn: Connection,
+    compression: StorageCompression,
+}
+
+impl SqliteHeadStore {
+    /// Open the database file `path`, creating it and the tables if they don't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        if let Some(folder) = path.as_ref().parent() {
+            std::fs::create_dir_all(folder).map_err(backend_error)?;
+        }
+        let conn = Connection::open(path).map_err(backend_error)?;
+        conn.execute_batch(SCHEMA).map_err(backend_error)?;
+        Ok(Self {
+            conn,
+            compression: StorageCompression::None,
+        })
+    }
+
+    /// Compress the stored consensus states and block producers with `compression`
+    pub fn with_compression(mut self, compression: StorageCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns the heights of the stored heads in the given epoch, in ascending order.
+    pub fn heights_of_epoch(
+        &self,
+        epoch_id: &CryptoHash,
+    ) -> Result<Vec<BlockHeight>, StorageError> {
+        self.query_heights(
+            "SELECT height FROM heads WHERE epoch_id = ?1 ORDER BY height",
+            params![epoch_id.to_string()],
+        )
+    }
+
+    /// Returns the heights of the stored heads with timestamps in `[from, to)`, in ascending
+    /// order.
+    pub fn heights_between(&self, from: u64, to: u64) -> Result<Vec<BlockHeight>, StorageError> {
+        self.query_heights(
+            "SELECT height FROM heads WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY height",
+            params![from as i64, to as i64],
+        )
+    }
+
+    fn query_heights(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<BlockHeight>, StorageError> {
+        let mut statement = self.conn.prepare_cached(sql).map_err(backend_error)?;
+        let heights = statement
+            .query_map(params, |row| row.get::<_, i64>(0))
+            .map_err(backend_error)?
+            .map(|height| height.map(|height| height as BlockHeight))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(backend_error);
+        heights
+    }
+
+    fn get_metadata(&self, key: &str) -> Result<Option<String>, StorageError> {
+        self.conn
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![key],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(backend_error)
+    }
+
+    fn put_metadata(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+
+    fn get_head_in(
+        &self,
+        table: &str,
+        height: BlockHeight,
+    ) -> Result<Option<ConsensusState>, StorageError> {
+        self.conn
+            .query_row(
+                &format!("SELECT data FROM {} WHERE height = ?1", table),
+                params![height as i64],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()
+            .map_err(backend_error)?
+            .map(|bytes| decode(format!("{}/{}", table, height), &bytes))
+            .transpose()
+    }
+
+    fn put_head_in(
+        &self,
+        table: &str,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                &format!(
+                    "INSERT OR REPLACE INTO {} (height, epoch_id, block_hash, timestamp, data) \
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    table
+                ),
+                params![
+                    height as i64,
+                    head.header.epoch_id().to_string(),
+                    block_hash.to_string(),
+                    head.header.light_client_block.inner_lite.timestamp as i64,
+                    encode(head, self.compression)?,
+                ],
+            )
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+}
+
+impl HeadStore for SqliteHeadStore {
+    fn heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        self.query_heights("SELECT height FROM heads ORDER BY height", [])
+    }
+
+    fn get_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        self.get_head_in("heads", height)
+    }
+
+    fn put_head(
+        &mut self,
+        height: BlockHeight,
+        head: &ConsensusState,
+        block_hash: &CryptoHash,
+    ) -> Result<(), StorageError> {
+        self.put_head_in("heads", height, head, block_hash)
+    }
+
+    fn delete_head(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                "DELETE FROM heads WHERE height = ?1",
+                params![height as i64],
+            )
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+
+    fn height_of_block_hash(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Option<BlockHeight>, StorageError> {
+        self.conn
+            .query_row(
+                "SELECT height FROM heads WHERE block_hash = ?1",
+                params![block_hash.to_string()],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map(|height| height.map(|height| height as BlockHeight))
+            .map_err(backend_error)
+    }
+
+    fn epoch_ids(&self) -> Result<Vec<CryptoHash>, StorageError> {
+        let mut statement = self
+            .conn
+            .prepare_cached("SELECT epoch_id FROM epoch_bps ORDER BY epoch_id")
+            .map_err(backend_error)?;
+        let epoch_ids = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(backend_error)?
+            .map(|name| epoch_id_of_name(&name.map_err(backend_error)?))
+            .collect();
+        epoch_ids
+    }
+
+    fn get_epoch_bps(&self, epoch_id: &CryptoHash) -> Result<Option<BlockProducers>, StorageError> {
+        self.conn
+            .query_row(
+                "SELECT data FROM epoch_bps WHERE epoch_id = ?1",
+                params![epoch_id.to_string()],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()
+            .map_err(backend_error)?
+            .map(|bytes| decode(epoch_id, &bytes))
+            .transpose()
+    }
+
+    fn put_epoch_bps(
+        &mut self,
+        epoch_id: &CryptoHash,
+        bps: &BlockProducers,
+    ) -> Result<(), StorageError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO epoch_bps (epoch_id, data) VALUES (?1, ?2)",
+                params![epoch_id.to_string(), encode(bps, self.compression)?],
+            )
+            .map(|_| ())
+            .map_err(backend_error)
+    }
+
+    fn failed_heights(&self) -> Result<Vec<BlockHeight>, StorageError> {
+        self.query_heights("SELECT height FROM failed_heads ORDER BY height", [])
+    }
+
+    fn get_failed_head(&self, height: BlockHeight) -> Result<Option<ConsensusState>, StorageError> {
+        self.get_head_in("failed_heads", height)
+    }
+
+    fn put_failed_head(&mut self, head: &ConsensusState) -> Result<(), StorageError> {
+        self.put_head_in(
+            "failed_heads",
+            head.header.height().revision_height,
+            head,
+            &head.header.light_client_block.current_block_hash(),
+        )
+    }
+
+    fn chain_id(&self) -> Result<Option<String>, StorageError> {
+        self.get_metadata(CHAIN_ID_KEY)
+    }
+
+    fn set_chain_id(&mut self, chain_id: &str) -> Result<(), StorageError> {
+        self.put_metadata(CHAIN_ID_KEY, chain_id)
+    }
+
+    fn last_verified_height(&self) -> Result<Option<BlockHeight>, StorageError> {
+        self.get_metadata(LAST_VERIFIED_HEIGHT_KEY)?
+            .map(|height| {
+                height.parse().map_err(|_| StorageError::CorruptedData {
+                    key: LAST_VERIFIED_HEIGHT_KEY.to_string(),
+                })
+            })
+            .transpose()
+    }
+
+    fn set_last_verified_height(&mut self, height: BlockHeight) -> Result<(), StorageError> {
+        self.put_metadata(LAST_VERIFIED_HEIGHT_KEY, &height.to_string())
+    }
+}
@@ -1,16 +1,23 @@
 //! Some util functions related to NEAR light client.
 //!
-use abscissa_core::status_info;
+use abscissa_core::{status_err, status_info};
+use borsh::BorshDeserialize;
+use near_jsonrpc_client::methods::light_client_proof::RpcLightClientExecutionProofResponse;
 use near_light_client::{
     near_types::{
+        get_raw_prefix_for_contract_data,
         hash::CryptoHash,
+        merkle::{Direction, MerklePath, MerklePathItem},
         signature::{ED25519PublicKey, PublicKey, Signature},
+        transaction::{ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus},
+        trie::{decode_state_proof, walk_proof},
         BlockHeaderInnerLite, EpochId, LightClientBlock, LightClientBlockLite, ValidatorStakeView,
         ValidatorStakeViewV1,
     },
-    types::{ConsensusState, Header},
+    types::{ConsensusState, Header, Height, OutcomeProof},
 };
 use near_primitives::views::BlockView;
+use std::path::Path;
 
 /// Produce `BlockHeaderInnerLiteView` by its NEAR version
 pub fn produce_block_header_inner_light(
@@ -96,13 +103,74 @@ pub fn produce_light_client_block_lite_view(
     }
 }
 
+/// Returns the block hash of NEAR version of `LightClientBlockView`.
+pub fn light_client_block_hash(view: &near_primitives::views::LightClientBlockView) -> CryptoHash {
+    LightClientBlockLite {
+        inner_lite: produce_block_header_inner_light(&view.inner_lite),
+        inner_rest_hash: CryptoHash(view.inner_rest_hash.0),
+        prev_block_hash: CryptoHash(view.prev_block_hash.0),
+    }
+    .current_block_hash()
+}
+
+/// Produce `MerklePathItem` by its NEAR version
+pub fn produce_merkle_path_item(item: &near_primitives::merkle::MerklePathItem) -> MerklePathItem {
+    MerklePathItem {
+        hash: CryptoHash(item.hash.0),
+        direction: match item.direction {
+            near_primitives::merkle::Direction::Left => Direction::Left,
+            near_primitives::merkle::Direction::Right => Direction::Right,
+        },
+    }
+}
+
+/// Produce `MerklePath` by its NEAR version
+pub fn produce_merkle_path(path: &[near_primitives::merkle::MerklePathItem]) -> MerklePath {
+    path.iter().map(produce_merkle_path_item).collect()
+}
+
+/// Produce `OutcomeProof` of the outcome of a transaction or receipt `id`, by the response of
+/// rpc `light_client_proof` with the light client head at `height`.
+pub fn produce_outcome_proof(
+    height: Height,
+    id: CryptoHash,
+    response: &RpcLightClientExecutionProofResponse,
+) -> OutcomeProof {
+    let outcome = &response.outcome_proof.outcome;
+    OutcomeProof::new(
+        height,
+        ExecutionOutcomeWithId {
+            id,
+            outcome: ExecutionOutcome {
+                logs: outcome.logs.clone(),
+                receipt_ids: outcome
+                    .receipt_ids
+                    .iter()
+                    .map(|h| CryptoHash(h.clone().0))
+                    .collect(),
+                gas_burnt: outcome.gas_burnt,
+                tokens_burnt: outcome.tokens_burnt,
+                executor_id: outcome.executor_id.to_string(),
+                status: ExecutionStatus::try_from_slice(
+                    borsh::to_vec(&outcome.status).unwrap().as_ref(),
+                )
+                .unwrap(),
+            },
+        },
+        produce_merkle_path(&response.outcome_proof.proof),
+        produce_merkle_path(&response.outcome_root_proof),
+        produce_light_client_block_lite_view(&response.block_header_lite),
+        produce_merkle_path(&response.block_proof),
+    )
+}
+
 /// Print general info of `LightClientBlockView` with macro `status_info`.
 pub fn print_light_client_consensus_state(view: &ConsensusState) {
     status_info!(
         "Info",
         "ConsensusState: {{ prev_block_hash: {}, height: {}, prev_state_root: {}, epoch_id: {}, next_epoch_id: {}, signature_count: {}, current_bps_count: {}, next_bps_count: {} }}",
         view.header.light_client_block.prev_block_hash,
-        view.header.height(),
+        view.header.height().revision_height,
         view.header.light_client_block.inner_lite.prev_state_root,
         view.header.epoch_id(),
         view.header.next_epoch_id(),
@@ -127,3 +195,63 @@ pub fn print_block_view(view: &BlockView) {
         view.chunks.iter().map(|h| h.prev_state_root).collect::<Vec<near_primitives::hash::CryptoHash>>(),
     );
 }
+
+/// Print the nodes visited by walking the proof data along the given key with macro `status_info`.
+pub fn print_proof_walk(key: &[u8], proofs: &[Vec<u8>]) {
+    let nodes = match decode_state_proof(proofs) {
+        Ok(nodes) => nodes,
+        Err(err) => {
+            status_err!("Failed to decode proof data: {:?}", err);
+            return;
+        }
+    };
+    let root = match nodes.first() {
+        Some(node) => node.hash(),
+        None => return,
+    };
+    for step in walk_proof(key, &nodes, &root) {
+        status_info!(
+            "Info",
+            "ProofStep: {{ node_index: {}, expected_hash: {}, hash_matched: {}, consumed_nibbles: {}, next_expected_hash: {:?}, node: {:?} }}",
+            step.node_index,
+            step.expected_hash,
+            step.hash_matched,
+            step.consumed_nibbles,
+            step.next_expected_hash,
+            step.node,
+        );
+    }
+}
+
+/// Returns the trie key of `storage_key`: the key itself if `raw_key`,
+/// `column ++ account_id ++ storage_key` if `column` is given, or the key of the contract
+/// data of `account_id` otherwise.
+pub fn trie_key_of(
+    account_id: &String,
+    storage_key: &[u8],
+    raw_key: bool,
+    column: Option<u8>,
+) -> Vec<u8> {
+    match (raw_key, column) {
+        (true, _) => storage_key.to_vec(),
+        (false, Some(column)) => {
+            let mut key = Vec::with_capacity(1 + account_id.len() + storage_key.len());
+            key.push(column);
+            key.extend_from_slice(account_id.as_bytes());
+            key.extend_from_slice(storage_key);
+            key
+        }
+        (false, None) => get_raw_prefix_for_contract_data(account_id, storage_key),
+    }
+}
+
+/// Read proof data from a JSON file of an array of base64 formatted trie nodes, the same
+/// as the `proof` of the results of `view_state` RPC.
+pub fn read_proof_file(path: &Path) -> anyhow::Result<Vec<Vec<u8>>> {
+    let content = std::fs::read_to_string(path)?;
+    let nodes: Vec<String> = serde_json::from_str(&content)?;
+    nodes
+        .iter()
+        .map(|node| base64::decode(node).map_err(anyhow::Error::from))
+        .collect()
+}
@@ -0,0 +1,129 @@
+//! Prometheus metrics of NEAR light client, exposed by the `/metrics` endpoint of the server.
+//!
+
+use std::sync::OnceLock;
+
+use prometheus::{
+    exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+use crate::light_client::store::CacheStats;
+
+/// Metrics of the sync loop, the RPC calls and the store.
+pub struct Metrics {
+    registry: Registry,
+    /// Height of the latest verified head
+    pub latest_verified_height: IntGauge,
+    /// Count of heights the latest verified head lags behind the chain head
+    pub chain_tip_lag_heights: IntGauge,
+    /// Duration of the verification of a header, including fetching its block proof
+    pub header_verification_seconds: Histogram,
+    /// Count of approval signatures of a verified header
+    pub header_signatures: Histogram,
+    /// Latency of a RPC call by method
+    pub rpc_call_seconds: HistogramVec,
+    /// Count of failed RPC calls by method
+    pub rpc_call_failures: IntCounterVec,
+    /// Count of the stored heads
+    pub stored_heads: IntGauge,
+    /// Count of the heads pruned
+    pub pruned_heads: IntCounter,
+    /// Count of the lookups served from the in-memory cache of the store
+    pub store_cache_hits: IntCounter,
+    /// Count of the lookups served from the store beneath the in-memory cache
+    pub store_cache_misses: IntCounter,
+}
+
+/// Returns the metrics of the process.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| Metrics::new().expect("Failed to register metrics."))
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some("near_light_client".to_owned()), None)?;
+        let latest_verified_height = IntGauge::new(
+            "latest_verified_height",
+            "Height of the latest verified head.",
+        )?;
+        let chain_tip_lag_heights = IntGauge::new(
+            "chain_tip_lag_heights",
+            "Count of heights the latest verified head lags behind the chain head.",
+        )?;
+        let header_verification_seconds = Histogram::with_opts(
+            HistogramOpts::new(
+                "header_verification_seconds",
+                "Duration of the verification of a header.",
+            )
+            .buckets(exponential_buckets(0.005, 2.0, 12)?),
+        )?;
+        let header_signatures = Histogram::with_opts(
+            HistogramOpts::new(
+                "header_signatures",
+                "Count of approval signatures of a verified header.",
+            )
+            .buckets(exponential_buckets(1.0, 2.0, 10)?),
+        )?;
+        let rpc_call_seconds = HistogramVec::new(
+            HistogramOpts::new("rpc_call_seconds", "Latency of a RPC call."),
+            &["method"],
+        )?;
+        let rpc_call_failures = IntCounterVec::new(
+            Opts::new("rpc_call_failures_total", "Count of failed RPC calls."),
+            &["method"],
+        )?;
+        let stored_heads = IntGauge::new("stored_heads", "Count of the stored heads.")?;
+        let pruned_heads = IntCounter::new("pruned_heads_total", "Count of the heads pruned.")?;
+        let store_cache_hits = IntCounter::new(
+            "store_cache_hits_total",
+            "Count of the lookups served from the in-memory cache of the store.",
+        )?;
+        let store_cache_misses = IntCounter::new(
+            "store_cache_misses_total",
+            "Count of the lookups served from the store beneath the in-memory cache.",
+        )?;
+        registry.register(Box::new(latest_verified_height.clone()))?;
+        registry.register(Box::new(chain_tip_lag_heights.clone()))?;
+        registry.register(Box::new(header_verification_seconds.clone()))?;
+        registry.register(Box::new(header_signatures.clone()))?;
+        registry.register(Box::new(rpc_call_seconds.clone()))?;
+        registry.register(Box::new(rpc_call_failures.clone()))?;
+        registry.register(Box::new(stored_heads.clone()))?;
+        registry.register(Box::new(pruned_heads.clone()))?;
+        registry.register(Box::new(store_cache_hits.clone()))?;
+        registry.register(Box::new(store_cache_misses.clone()))?;
+        Ok(Self {
+            registry,
+            latest_verified_height,
+            chain_tip_lag_heights,
+            header_verification_seconds,
+            header_signatures,
+            rpc_call_seconds,
+            rpc_call_failures,
+            stored_heads,
+            pruned_heads,
+            store_cache_hits,
+            store_cache_misses,
+        })
+    }
+
+    /// Catch the cache counters up with `stats`, the counters of the store since it is
+    /// opened.
+    pub fn update_store_cache(&self, stats: CacheStats) {
+        self.store_cache_hits
+            .inc_by(stats.hits.saturating_sub(self.store_cache_hits.get()));
+        self.store_cache_misses
+            .inc_by(stats.misses.saturating_sub(self.store_cache_misses.get()));
+    }
+
+    /// Returns all metrics in the Prometheus text format.
+    pub fn encode(&self) -> Result<String, String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|err| format!("Failed to encode metrics: {:?}", err))?;
+        String::from_utf8(buffer).map_err(|err| format!("Invalid metrics text: {:?}", err))
+    }
+}
@@ -0,0 +1,127 @@
+//! Output of the subcommands printing reports, in the format selected by the global
+//! `--output` flag.
+//!
+//! With `--output json`, the reporting subcommands print a single JSON object to stdout
+//! instead of status lines, and the progress lines of [`info_with_time`] are omitted, so
+//! that scripts can parse the output as is.
+//!
+//! [`info_with_time`]: crate::info_with_time
+
+use std::fmt::Debug;
+use std::sync::OnceLock;
+
+use abscissa_core::{status_err, status_ok};
+use near_light_client::near_types::hash::CryptoHash;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::commands::OutputFormat;
+
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Select the output format, which can only be selected once.
+pub fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+/// Returns the selected output format, [`OutputFormat::Human`] if none is selected.
+pub fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().unwrap_or(OutputFormat::Human)
+}
+
+/// Returns whether the subcommands print JSON instead of human readable lines.
+pub fn is_json() -> bool {
+    output_format() == OutputFormat::Json
+}
+
+/// Print `value` as a JSON object to stdout.
+pub fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(err) => status_err!("Failed to serialize output: {:?}", err),
+    }
+}
+
+/// Result of a `verify-*` subcommand.
+#[derive(Debug, Default, Serialize)]
+pub struct VerificationReport {
+    /// Whether the verification succeeded
+    pub verified: bool,
+    /// Height of the head verified with
+    pub height: Option<u64>,
+    /// Block hash of the head verified with
+    pub head_hash: Option<CryptoHash>,
+    /// The error if the verification failed, or could not be done
+    pub error: Option<String>,
+    /// Name of the variant of the verification error, e.g. `InvalidBlockProof`
+    pub error_variant: Option<String>,
+    /// Details specific to the subcommand
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl VerificationReport {
+    /// Returns a report of a verification with the head at `height` of block `head_hash`.
+    pub fn of_head(height: u64, head_hash: CryptoHash) -> Self {
+        Self {
+            height: Some(height),
+            head_hash: Some(head_hash),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a report of a verification which could not be done due to `error`.
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Record the result of the verification.
+    pub fn with_result<E: Debug>(mut self, result: Result<(), E>) -> Self {
+        match result {
+            Ok(()) => {
+                self.verified = true;
+                self.error = None;
+                self.error_variant = None;
+            }
+            Err(err) => {
+                let error = format!("{:?}", err);
+                self.verified = false;
+                self.error_variant = Some(variant_name(&error));
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+
+    /// Add a detail specific to the subcommand.
+    pub fn with_detail(mut self, key: &str, value: impl Serialize) -> Self {
+        self.details.insert(
+            key.to_owned(),
+            serde_json::to_value(value).unwrap_or(Value::Null),
+        );
+        self
+    }
+
+    /// Print the report as JSON, or as a status line.
+    pub fn print(&self) {
+        if is_json() {
+            print_json(self);
+        } else if self.verified {
+            status_ok!("Finished", "Validation succeeded.");
+        } else {
+            status_err!(self.error.clone().unwrap_or_default());
+        }
+    }
+}
+
+/// Returns the name of the enum variant of the `Debug` output of an error.
+fn variant_name(debug: &str) -> String {
+    debug
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
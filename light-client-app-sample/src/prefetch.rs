@@ -0,0 +1,57 @@
+//! Queue of the items fetched ahead of their processing, such as the headers fetched while
+//! the previous header is verified.
+//!
+
+use std::future::Future;
+
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Bounded queue filled by a spawned fetcher, which waits while the queue is full.
+///
+/// The fetcher is aborted when the queue is cancelled or dropped.
+pub struct PrefetchQueue<T> {
+    receiver: mpsc::Receiver<T>,
+    fetcher: JoinHandle<()>,
+}
+
+impl<T: Send + 'static> PrefetchQueue<T> {
+    /// Spawn the future returned by `fetch` to send at most `depth` items ahead of
+    /// [`PrefetchQueue::recv`].
+    ///
+    /// Must be called in a tokio runtime.
+    pub fn spawn<F, Fut>(depth: usize, fetch: F) -> Self
+    where
+        F: FnOnce(mpsc::Sender<T>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(depth.max(1));
+        Self {
+            receiver,
+            fetcher: tokio::spawn(fetch(sender)),
+        }
+    }
+
+    /// Returns the next fetched item, `None` if the fetcher stops and the queue is empty,
+    /// or the queue is cancelled.
+    pub async fn recv(&mut self) -> Option<T> {
+        self.receiver.recv().await
+    }
+
+    /// Abort the fetcher and discard the items in the queue, as they follow an item which
+    /// failed to process. Returns the count of the discarded items.
+    pub fn cancel(&mut self) -> usize {
+        self.fetcher.abort();
+        self.receiver.close();
+        let mut discarded = 0;
+        while self.receiver.try_recv().is_ok() {
+            discarded += 1;
+        }
+        discarded
+    }
+}
+
+impl<T> Drop for PrefetchQueue<T> {
+    fn drop(&mut self) {
+        self.fetcher.abort();
+    }
+}
@@ -0,0 +1,179 @@
+//! Relayer of the verified headers to a destination verifier.
+//!
+
+pub mod cosmos;
+pub mod near;
+
+use std::{sync::Arc, time::Duration};
+
+use abscissa_core::status_err;
+use near_light_client::{
+    types::{Header, Height},
+    BasicNearLightClient,
+};
+use tokio::sync::{broadcast, broadcast::error::RecvError, Mutex};
+
+use self::{cosmos::CosmosDestination, near::NearDestination};
+use crate::config::{RelayDestination, RelaySection};
+use crate::info_with_time;
+use crate::keystore::Keystore;
+use crate::light_client::LightClient;
+use crate::server::HeadSummary;
+use crate::shutdown::Shutdown;
+
+/// A verifier the headers are relayed to.
+pub enum Destination {
+    /// A NEAR verifier contract
+    Near(NearDestination),
+    /// An ICS-08 wasm light client on a Cosmos chain
+    Cosmos(CosmosDestination),
+}
+
+impl Destination {
+    /// Create the destination in the given configuration.
+    pub fn from_config(config: &RelaySection, keystore: &Keystore) -> anyhow::Result<Self> {
+        Ok(match config.destination {
+            RelayDestination::Near => {
+                Self::Near(NearDestination::from_config(&config.near, keystore)?)
+            }
+            RelayDestination::Cosmos => {
+                Self::Cosmos(CosmosDestination::from_config(&config.cosmos, keystore)?)
+            }
+        })
+    }
+
+    /// Returns the latest height of the headers accepted by the destination.
+    pub async fn latest_height(&self) -> anyhow::Result<u64> {
+        match self {
+            Self::Near(destination) => destination.latest_height().await,
+            Self::Cosmos(destination) => destination.latest_height().await,
+        }
+    }
+
+    /// Submit `header`, and wait until it is accepted.
+    pub async fn submit(&mut self, header: &Header) -> anyhow::Result<()> {
+        match self {
+            Self::Near(destination) => destination.submit(header).await,
+            Self::Cosmos(destination) => destination.submit(header).await,
+        }
+    }
+
+    /// Drop the locally tracked nonce, to re-query it before the next submission.
+    pub fn reset_nonce(&mut self) {
+        match self {
+            Self::Near(destination) => destination.reset_nonce(),
+            Self::Cosmos(destination) => destination.reset_nonce(),
+        }
+    }
+}
+
+/// Relayer submitting the stored heads above the latest height of the destination in order,
+/// whenever a new head is verified.
+pub struct Relayer {
+    destination: Destination,
+    config: RelaySection,
+    light_client: Arc<Mutex<LightClient>>,
+}
+
+impl Relayer {
+    /// Create the relayer of the heads of `light_client` to `destination`.
+    pub fn new(
+        destination: Destination,
+        config: RelaySection,
+        light_client: Arc<Mutex<LightClient>>,
+    ) -> Self {
+        Self {
+            destination,
+            config,
+            light_client,
+        }
+    }
+
+    /// Keep relaying until the shutdown is requested, or a submission fails after retries.
+    pub async fn run(
+        mut self,
+        mut heads: broadcast::Receiver<HeadSummary>,
+        shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
+        loop {
+            self.relay_stored_heads(&shutdown).await?;
+            tokio::select! {
+                head = heads.recv() => match head {
+                    // Lagging behind is fine, as all the stored heads are relayed anyway
+                    Ok(_) | Err(RecvError::Lagged(_)) => (),
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = shutdown.wait() => return Ok(()),
+            }
+        }
+    }
+
+    /// Submit the stored heads above the latest height of the destination in order.
+    async fn relay_stored_heads(&mut self, shutdown: &Shutdown) -> anyhow::Result<()> {
+        let destination_height = self.destination.latest_height().await?;
+        let heights: Vec<u64> = self
+            .light_client
+            .lock()
+            .await
+            .cached_heights()
+            .into_iter()
+            .filter(|height| *height > destination_height)
+            .collect();
+        for height in heights {
+            if shutdown.is_requested() {
+                break;
+            }
+            let head = self
+                .light_client
+                .lock()
+                .await
+                .get_consensus_state(&Height::from_block_height(height));
+            match head {
+                Some(head) => self.submit_with_retries(&head.header, shutdown).await?,
+                None => status_err!("Missing head at height {}, skip relaying it.", height),
+            }
+        }
+        Ok(())
+    }
+
+    async fn submit_with_retries(
+        &mut self,
+        header: &Header,
+        shutdown: &Shutdown,
+    ) -> anyhow::Result<()> {
+        let height = header.height().revision_height;
+        let mut attempts = 0;
+        loop {
+            let err = match self.destination.submit(header).await {
+                Ok(()) => {
+                    info_with_time!("Relayed header at height {}.", height);
+                    return Ok(());
+                }
+                Err(err) => err,
+            };
+            if attempts >= self.config.retries || shutdown.is_requested() {
+                anyhow::bail!("Failed to relay header at height {}: {:?}", height, err);
+            }
+            attempts += 1;
+            status_err!(
+                "Failed to relay header at height {}, retry in {} ms ({}/{}): {:?}",
+                height,
+                self.config.retry_interval_ms,
+                attempts,
+                self.config.retries,
+                err
+            );
+            // The nonce may be out of sync, e.g. if the transaction is rejected
+            self.destination.reset_nonce();
+            if shutdown
+                .sleep(Duration::from_millis(self.config.retry_interval_ms))
+                .await
+            {
+                anyhow::bail!(
+                    "Shutdown requested while relaying header at height {}.",
+                    height
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Destination of an ICS-08 wasm light client on a Cosmos chain.
+//!
+
+use cosmrs::{
+    crypto::secp256k1::SigningKey,
+    proto::cosmos::auth::v1beta1::{BaseAccount, QueryAccountRequest, QueryAccountResponse},
+    rpc::{Client, HttpClient},
+    tendermint::chain::Id as ChainId,
+    tx::{Body, Fee, SignDoc, SignerInfo},
+    Any, Coin,
+};
+use near_light_client::{proto::v1, types::Header};
+use prost::Message;
+use zeroize::Zeroizing;
+
+use crate::config::CosmosDestinationSection;
+use crate::keystore::Keystore;
+
+/// The same as `near_light_client::ibc_client::HEADER_TYPE_URL`, without the `ibc` feature
+pub(crate) const HEADER_TYPE_URL: &str = "/near.lightclient.v1.Header";
+/// The same as `near_light_client::ibc_client::CONSENSUS_STATE_TYPE_URL`, without the `ibc`
+/// feature
+pub(crate) const CONSENSUS_STATE_TYPE_URL: &str = "/near.lightclient.v1.ConsensusState";
+const MSG_UPDATE_CLIENT_TYPE_URL: &str = "/ibc.core.client.v1.MsgUpdateClient";
+const WASM_CLIENT_MESSAGE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ClientMessage";
+const QUERY_ACCOUNT_PATH: &str = "/cosmos.auth.v1beta1.Query/Account";
+const QUERY_CLIENT_STATE_PATH: &str = "/ibc.core.client.v1.Query/ClientState";
+
+/// `ibc.core.client.v1.Height`
+#[derive(Clone, PartialEq, Message)]
+struct IbcHeight {
+    #[prost(uint64, tag = "1")]
+    revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    revision_height: u64,
+}
+
+/// `ibc.core.client.v1.MsgUpdateClient`
+#[derive(Clone, PartialEq, Message)]
+struct MsgUpdateClient {
+    #[prost(string, tag = "1")]
+    client_id: String,
+    #[prost(message, optional, tag = "2")]
+    client_message: Option<Any>,
+    #[prost(string, tag = "3")]
+    signer: String,
+}
+
+/// `ibc.lightclients.wasm.v1.ClientMessage`
+#[derive(Clone, PartialEq, Message)]
+struct WasmClientMessage {
+    #[prost(bytes = "vec", tag = "1")]
+    data: Vec<u8>,
+}
+
+/// `ibc.lightclients.wasm.v1.ClientState`
+#[derive(Clone, PartialEq, Message)]
+struct WasmClientState {
+    #[prost(bytes = "vec", tag = "1")]
+    data: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    checksum: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    latest_height: Option<IbcHeight>,
+}
+
+/// `ibc.core.client.v1.QueryClientStateRequest`
+#[derive(Clone, PartialEq, Message)]
+struct QueryClientStateRequest {
+    #[prost(string, tag = "1")]
+    client_id: String,
+}
+
+/// `ibc.core.client.v1.QueryClientStateResponse`, without the proof
+#[derive(Clone, PartialEq, Message)]
+struct QueryClientStateResponse {
+    #[prost(message, optional, tag = "1")]
+    client_state: Option<Any>,
+}
+
+/// An ICS-08 wasm light client, which the headers are submitted to by `MsgUpdateClient`
+/// transactions, with the headers wrapped as `Any` of `near.lightclient.v1.Header` in the
+/// client messages.
+pub struct CosmosDestination {
+    rpc_client: HttpClient,
+    config: CosmosDestinationSection,
+    chain_id: ChainId,
+    signing_key: SigningKey,
+    /// The account number and the sequence of the next transaction, `None` to query
+    /// them from RPC
+    sequence: Option<(u64, u64)>,
+}
+
+impl CosmosDestination {
+    /// Create the destination in the given configuration.
+    pub fn from_config(
+        config: &CosmosDestinationSection,
+        keystore: &Keystore,
+    ) -> anyhow::Result<Self> {
+        let rpc_client = HttpClient::new(config.rpc_endpoint.as_str())?;
+        let chain_id = config
+            .chain_id
+            .parse()
+            .map_err(|err| anyhow::anyhow!("Invalid chain id: {:?}", err))?;
+        let key_hex = keystore.resolve(&config.signer_key)?;
+        let key_bytes = Zeroizing::new(hex::decode(key_hex.trim())?);
+        let signing_key = SigningKey::from_slice(&key_bytes)
+            .map_err(|err| anyhow::anyhow!("Invalid signer key: {:?}", err))?;
+        Ok(Self {
+            rpc_client,
+            config: config.clone(),
+            chain_id,
+            signing_key,
+            sequence: None,
+        })
+    }
+
+    /// Returns the latest height of the client.
+    pub async fn latest_height(&self) -> anyhow::Result<u64> {
+        let response: QueryClientStateResponse = self
+            .abci_query(
+                QUERY_CLIENT_STATE_PATH,
+                QueryClientStateRequest {
+                    client_id: self.config.client_id.clone(),
+                }
+                .encode_to_vec(),
+            )
+            .await?;
+        let client_state = response
+            .client_state
+            .ok_or_else(|| anyhow::anyhow!("Missing client {}.", self.config.client_id))?;
+        let client_state = WasmClientState::decode(client_state.value.as_slice())?;
+        Ok(client_state
+            .latest_height
+            .map_or(0, |height| height.revision_height))
+    }
+
+    /// Submit `header` by a `MsgUpdateClient` transaction, and wait until it is committed.
+    pub async fn submit(&mut self, header: &Header) -> anyhow::Result<()> {
+        let public_key = self.signing_key.public_key();
+        let signer = public_key
+            .account_id(&self.config.account_prefix)
+            .map_err(|err| anyhow::anyhow!("Invalid account prefix: {:?}", err))?;
+        let (account_number, sequence) = match self.sequence {
+            Some(sequence) => sequence,
+            None => self.query_account(signer.as_ref()).await?,
+        };
+        let header = Any {
+            type_url: HEADER_TYPE_URL.to_owned(),
+            value: v1::Header::from(header.clone()).encode_to_vec(),
+        };
+        let message = MsgUpdateClient {
+            client_id: self.config.client_id.clone(),
+            client_message: Some(Any {
+                type_url: WASM_CLIENT_MESSAGE_TYPE_URL.to_owned(),
+                value: WasmClientMessage {
+                    data: header.encode_to_vec(),
+                }
+                .encode_to_vec(),
+            }),
+            signer: signer.to_string(),
+        };
+        let body = Body::new(
+            vec![Any {
+                type_url: MSG_UPDATE_CLIENT_TYPE_URL.to_owned(),
+                value: message.encode_to_vec(),
+            }],
+            "",
+            0u32,
+        );
+        let fee = Fee::from_amount_and_gas(
+            Coin {
+                denom: self
+                    .config
+                    .fee_denom
+                    .parse()
+                    .map_err(|err| anyhow::anyhow!("Invalid fee denom: {:?}", err))?,
+                amount: self.config.fee_amount,
+            },
+            self.config.gas_limit,
+        );
+        let auth_info = SignerInfo::single_direct(Some(public_key), sequence).auth_info(fee);
+        let tx_bytes = SignDoc::new(&body, &auth_info, &self.chain_id, account_number)
+            .and_then(|sign_doc| sign_doc.sign(&self.signing_key))
+            .and_then(|tx_raw| tx_raw.to_bytes())
+            .map_err(|err| anyhow::anyhow!("Failed to sign transaction: {:?}", err))?;
+        let response = self.rpc_client.broadcast_tx_commit(tx_bytes).await?;
+        if response.check_tx.code.is_err() {
+            anyhow::bail!(
+                "Transaction {} is rejected: {}",
+                response.hash,
+                response.check_tx.log
+            );
+        }
+        // The sequence is used once the transaction passes the check, whatever its outcome is
+        self.sequence = Some((account_number, sequence + 1));
+        if response.tx_result.code.is_err() {
+            anyhow::bail!(
+                "Transaction {} of MsgUpdateClient failed: {}",
+                response.hash,
+                response.tx_result.log
+            );
+        }
+        Ok(())
+    }
+
+    /// Drop the locally tracked sequence, to re-query it before the next submission.
+    pub fn reset_nonce(&mut self) {
+        self.sequence = None;
+    }
+
+    /// Returns the account number and the sequence of `address`.
+    async fn query_account(&self, address: &str) -> anyhow::Result<(u64, u64)> {
+        let response: QueryAccountResponse = self
+            .abci_query(
+                QUERY_ACCOUNT_PATH,
+                QueryAccountRequest {
+                    address: address.to_owned(),
+                }
+                .encode_to_vec(),
+            )
+            .await?;
+        let account = response
+            .account
+            .ok_or_else(|| anyhow::anyhow!("Missing account {}.", address))?;
+        let account = BaseAccount::decode(account.value.as_slice())?;
+        Ok((account.account_number, account.sequence))
+    }
+
+    async fn abci_query<R: Message + Default>(
+        &self,
+        path: &str,
+        data: Vec<u8>,
+    ) -> anyhow::Result<R> {
+        let response = self
+            .rpc_client
+            .abci_query(Some(path.to_owned()), data, None, false)
+            .await?;
+        if response.code.is_err() {
+            anyhow::bail!("Query {} failed: {}", path, response.log);
+        }
+        Ok(R::decode(response.value.as_slice())?)
+    }
+}
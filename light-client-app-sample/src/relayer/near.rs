@@ -0,0 +1,104 @@
+//! Destination of a NEAR verifier contract, i.e. `near-light-client-contract`.
+//!
+
+use near_crypto::{InMemorySigner, Signer};
+use near_light_client::types::Header;
+use near_primitives::{
+    transaction::{Action, FunctionCallAction, Transaction},
+    types::AccountId,
+    views::FinalExecutionStatus,
+};
+
+use crate::config::NearDestinationSection;
+use crate::keystore::{Keystore, NearCredentials};
+use crate::light_client::near_rpc_client_wrapper::NearRpcClientWrapper;
+
+/// A NEAR verifier contract, which the headers are submitted to by calling `update_state`.
+pub struct NearDestination {
+    rpc_client: NearRpcClientWrapper,
+    contract_id: AccountId,
+    signer: InMemorySigner,
+    gas: u64,
+    /// The nonce of the last transaction sent, `None` to query it from RPC
+    nonce: Option<u64>,
+}
+
+impl NearDestination {
+    /// Create the destination in the given configuration.
+    pub fn from_config(
+        config: &NearDestinationSection,
+        keystore: &Keystore,
+    ) -> anyhow::Result<Self> {
+        let contract_id = AccountId::try_from(config.contract_id.clone())
+            .map_err(|err| anyhow::anyhow!("Invalid contract id: {:?}", err))?;
+        let secret = keystore.resolve(&config.signer_key)?;
+        let credentials: NearCredentials = serde_json::from_str(&secret)
+            .map_err(|err| anyhow::anyhow!("Invalid NEAR credentials of signer: {:?}", err))?;
+        let signer =
+            InMemorySigner::from_secret_key(credentials.account_id, credentials.secret_key);
+        Ok(Self {
+            rpc_client: NearRpcClientWrapper::new(config.rpc_endpoint.as_str()),
+            contract_id,
+            signer,
+            gas: config.gas,
+            nonce: None,
+        })
+    }
+
+    /// Returns the latest height of the contract.
+    pub async fn latest_height(&self) -> anyhow::Result<u64> {
+        let result = self
+            .rpc_client
+            .call_view_function(&self.contract_id, "latest_height", b"{}".to_vec())
+            .await?;
+        Ok(serde_json::from_slice(&result)?)
+    }
+
+    /// Call `update_state` of the contract with `header`, and wait until it is executed.
+    pub async fn submit(&mut self, header: &Header) -> anyhow::Result<()> {
+        let nonce = match self.nonce {
+            Some(nonce) => nonce,
+            None => {
+                self.rpc_client
+                    .view_access_key_nonce(&self.signer.account_id, &self.signer.public_key)
+                    .await?
+            }
+        } + 1;
+        let block_hash = self.rpc_client.view_block(&None).await?.header.hash;
+        let args = serde_json::to_vec(&serde_json::json!({
+            "header": base64::encode(borsh::to_vec(header)?),
+        }))?;
+        let transaction = Transaction {
+            signer_id: self.signer.account_id.clone(),
+            public_key: self.signer.public_key(),
+            nonce,
+            receiver_id: self.contract_id.clone(),
+            block_hash,
+            actions: vec![Action::FunctionCall(Box::new(FunctionCallAction {
+                method_name: "update_state".to_owned(),
+                args,
+                gas: self.gas,
+                deposit: 0,
+            }))],
+        };
+        let outcome = self
+            .rpc_client
+            .broadcast_tx_commit(&transaction.sign(&self.signer))
+            .await?;
+        // The nonce is used once the transaction is included, whatever its outcome is
+        self.nonce = Some(nonce);
+        match outcome.status {
+            FinalExecutionStatus::SuccessValue(_) => Ok(()),
+            status => anyhow::bail!(
+                "Transaction {} of update_state failed: {:?}",
+                outcome.transaction.hash,
+                status
+            ),
+        }
+    }
+
+    /// Drop the locally tracked nonce, to re-query it before the next submission.
+    pub fn reset_nonce(&mut self) {
+        self.nonce = None;
+    }
+}
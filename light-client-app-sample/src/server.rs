@@ -0,0 +1,493 @@
+//! REST API server of NEAR light client, running the same verifications as the CLI.
+//!
+
+use std::{
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use near_light_client::{
+    near_types::{get_raw_prefix_for_contract_data, hash::CryptoHash},
+    types::{ConsensusState, Height},
+    BasicNearLightClient,
+};
+use near_primitives::types::{AccountId, BlockId, TransactionOrReceiptId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, broadcast::error::RecvError, Mutex};
+
+use crate::config::ServerSection;
+use crate::light_client::{
+    near_rpc_client_wrapper::NearRpcClientWrapper, utils::produce_outcome_proof, LightClient,
+};
+use crate::metrics::metrics;
+use crate::shutdown::Shutdown;
+
+/// State shared by the handlers of the server.
+#[derive(Clone)]
+pub struct ServerState {
+    /// The light client, shared with the sync loop if any
+    pub light_client: Arc<Mutex<LightClient>>,
+    /// The client of the RPC services to fetch the proofs from
+    pub rpc_client: Arc<NearRpcClientWrapper>,
+    /// The summaries of the newly verified heads, sent by the sync loop if any
+    pub heads: broadcast::Sender<HeadSummary>,
+    /// The configuration of the server
+    pub config: ServerSection,
+}
+
+/// Summary of a head.
+#[derive(Clone, Debug, Serialize)]
+pub struct HeadSummary {
+    /// Height of the head
+    pub height: u64,
+    /// Block hash of the head, base58 formatted in JSON
+    pub block_hash: CryptoHash,
+    /// Epoch id of the head, base58 formatted in JSON
+    pub epoch_id: CryptoHash,
+    /// Id of the next epoch of the head, base58 formatted in JSON
+    pub next_epoch_id: CryptoHash,
+    /// Timestamp of the head in nanoseconds
+    pub timestamp: u64,
+}
+
+impl HeadSummary {
+    /// Returns the summary of `head`.
+    pub fn of(head: &ConsensusState) -> Self {
+        Self {
+            height: head.header.height().revision_height,
+            block_hash: head.header.light_client_block.current_block_hash(),
+            epoch_id: head.header.epoch_id(),
+            next_epoch_id: head.header.next_epoch_id(),
+            timestamp: head.header.light_client_block.inner_lite.timestamp,
+        }
+    }
+}
+
+/// A head with its data.
+#[derive(Debug, Serialize)]
+pub struct ConsensusStateResponse {
+    /// Summary of the head
+    #[serde(flatten)]
+    pub summary: HeadSummary,
+    /// base64 formatted borsh serialization of the consensus state
+    pub data: String,
+}
+
+/// Request to verify the value of a storage key of a NEAR account.
+#[derive(Debug, Deserialize)]
+pub struct VerifyMembershipRequest {
+    /// The NEAR account
+    pub account_id: String,
+    /// base64 formatted storage key
+    pub storage_key: String,
+    /// base64 formatted value
+    pub value: String,
+    /// Height of the stored head to verify with, the latest head if omitted
+    pub height: Option<u64>,
+}
+
+/// Request to verify a transaction.
+#[derive(Debug, Deserialize)]
+pub struct VerifyTransactionRequest {
+    /// base58 formatted transaction hash
+    pub tx_hash: String,
+    /// Account id of transaction sender
+    pub sender_id: String,
+    /// Height of the stored head to verify with, the latest head if omitted
+    pub height: Option<u64>,
+}
+
+/// Result of a verification.
+#[derive(Debug, Serialize)]
+pub struct VerificationResponse {
+    /// Height of the head verified with
+    pub height: u64,
+    /// Whether the verification succeeded
+    pub verified: bool,
+    /// The error of the verification if it failed
+    pub error: Option<String>,
+}
+
+/// Result of the liveness check.
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    /// Whether the store is accessible
+    pub healthy: bool,
+    /// Why the process is not healthy
+    pub error: Option<String>,
+}
+
+/// Result of the readiness check.
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// Whether the latest head is fresh and the RPC services are reachable
+    pub ready: bool,
+    /// Height of the latest head, 0 if there is no head
+    pub height: u64,
+    /// Age of the latest head in seconds
+    pub head_age_secs: Option<u64>,
+    /// Whether any of the RPC services is reachable
+    pub rpc_reachable: bool,
+    /// Why the light client is not ready
+    pub errors: Vec<String>,
+}
+
+/// Error of a request, responded as `{"error": <message>}`.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: message.into(),
+        }
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the HTTP status of the error.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Returns the message of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(serde_json::json!({ "error": self.message })),
+        )
+            .into_response()
+    }
+}
+
+/// Returns the routes of the REST API.
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/healthz", get(get_healthz))
+        .route("/readyz", get(get_readyz))
+        .route("/head", get(get_head))
+        .route("/consensus_state/:height", get(get_consensus_state))
+        .route("/heads/ws", get(subscribe_heads))
+        .route("/metrics", get(get_metrics))
+        .route("/verify/membership", post(verify_membership))
+        .route("/verify/transaction", post(verify_transaction))
+        .with_state(state)
+}
+
+/// Serve the REST API on `listen_address` until the shutdown is requested.
+pub async fn serve(
+    listen_address: String,
+    router: Router,
+    shutdown: Shutdown,
+) -> Result<(), String> {
+    let address = SocketAddr::from_str(listen_address.as_str())
+        .map_err(|err| format!("Invalid listen address '{}': {:?}", listen_address, err))?;
+    axum::Server::try_bind(&address)
+        .map_err(|err| format!("Failed to bind {}: {:?}", address, err))?
+        .serve(router.into_make_service())
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await
+        .map_err(|err| format!("Server failed: {:?}", err))
+}
+
+async fn get_healthz(State(state): State<ServerState>) -> (StatusCode, Json<HealthResponse>) {
+    let result = state.light_client.lock().await.last_verified_height();
+    let status = match result {
+        Ok(_) => StatusCode::OK,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (
+        status,
+        Json(HealthResponse {
+            healthy: result.is_ok(),
+            error: result
+                .err()
+                .map(|err| format!("Store is not accessible: {:?}", err)),
+        }),
+    )
+}
+
+async fn get_readyz(State(state): State<ServerState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let mut errors = Vec::new();
+    let head = head_at(&state, None).await.ok();
+    let height = head
+        .as_ref()
+        .map_or(0, |head| head.header.height().revision_height);
+    let head_age_secs = head.as_ref().map(|head| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64);
+        now.saturating_sub(head.header.light_client_block.inner_lite.timestamp) / 1_000_000_000
+    });
+    match head_age_secs {
+        None => errors.push("There is no head.".to_owned()),
+        Some(age_secs)
+            if state.config.ready_max_head_age_secs > 0
+                && age_secs > state.config.ready_max_head_age_secs =>
+        {
+            errors.push(format!(
+                "The head at height {} is {} seconds old.",
+                height, age_secs
+            ))
+        }
+        Some(_) => (),
+    }
+    let rpc_reachable = match state.rpc_client.ping().await {
+        Ok(()) => true,
+        Err(err) => {
+            errors.push(err.to_string());
+            false
+        }
+    };
+    let ready = errors.is_empty();
+    (
+        if ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        },
+        Json(ReadinessResponse {
+            ready,
+            height,
+            head_age_secs,
+            rpc_reachable,
+            errors,
+        }),
+    )
+}
+
+async fn get_head(State(state): State<ServerState>) -> Result<Json<HeadSummary>, ApiError> {
+    let head = head_at(&state, None).await?;
+    Ok(Json(HeadSummary::of(&head)))
+}
+
+async fn get_consensus_state(
+    State(state): State<ServerState>,
+    Path(height): Path<u64>,
+) -> Result<Json<ConsensusStateResponse>, ApiError> {
+    let head = head_at(&state, Some(height)).await?;
+    let data = borsh::to_vec(&head)
+        .map_err(|err| ApiError::internal(format!("Failed to serialize head: {:?}", err)))?;
+    Ok(Json(ConsensusStateResponse {
+        summary: HeadSummary::of(&head),
+        data: base64::encode(data),
+    }))
+}
+
+async fn get_metrics(State(state): State<ServerState>) -> Result<String, ApiError> {
+    if let Some(stats) = state.light_client.lock().await.cache_stats() {
+        metrics().update_store_cache(stats);
+    }
+    metrics().encode().map_err(ApiError::internal)
+}
+
+async fn subscribe_heads(State(state): State<ServerState>, ws: WebSocketUpgrade) -> Response {
+    let heads = state.heads.subscribe();
+    ws.on_upgrade(move |socket| push_heads(socket, heads))
+}
+
+/// Send the summary of each newly verified head to `socket` as a JSON text message, until
+/// the peer goes away or lags too far behind.
+async fn push_heads(mut socket: WebSocket, mut heads: broadcast::Receiver<HeadSummary>) {
+    let (code, reason) = loop {
+        match heads.recv().await {
+            Ok(head) => {
+                let text = match serde_json::to_string(&head) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        break (
+                            close_code::ERROR,
+                            format!("Failed to serialize head: {:?}", err),
+                        )
+                    }
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    return;
+                }
+            }
+            Err(RecvError::Lagged(count)) => {
+                break (
+                    close_code::AGAIN,
+                    format!("The subscriber lags behind by {} heads.", count),
+                )
+            }
+            Err(RecvError::Closed) => {
+                break (close_code::AWAY, "The light client stopped.".to_owned())
+            }
+        }
+    };
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        })))
+        .await;
+}
+
+async fn verify_membership(
+    State(state): State<ServerState>,
+    Json(request): Json<VerifyMembershipRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    let key_bytes = base64::decode(&request.storage_key)
+        .map_err(|err| ApiError::bad_request(format!("Invalid storage key: {:?}", err)))?;
+    let value_bytes = base64::decode(&request.value)
+        .map_err(|err| ApiError::bad_request(format!("Invalid value: {:?}", err)))?;
+    verify_membership_at(
+        &state,
+        &request.account_id,
+        &key_bytes,
+        &value_bytes,
+        request.height,
+    )
+    .await
+    .map(Json)
+}
+
+async fn verify_transaction(
+    State(state): State<ServerState>,
+    Json(request): Json<VerifyTransactionRequest>,
+) -> Result<Json<VerificationResponse>, ApiError> {
+    let tx_hash = bs58::decode(&request.tx_hash)
+        .into_vec()
+        .map_err(|err| ApiError::bad_request(format!("Invalid transaction hash: {:?}", err)))?;
+    verify_transaction_at(&state, &tx_hash, &request.sender_id, request.height)
+        .await
+        .map(Json)
+}
+
+/// Returns the stored head at `height`, or the latest head if `height` is `None`.
+pub(crate) async fn head_at(
+    state: &ServerState,
+    height: Option<u64>,
+) -> Result<ConsensusState, ApiError> {
+    let light_client = state.light_client.lock().await;
+    let height = match height {
+        Some(height) => Height::from_block_height(height),
+        None => light_client.latest_height(),
+    };
+    light_client
+        .get_head(height.revision_height)
+        .map_err(|err| {
+            ApiError::internal(format!(
+                "Failed to read head at height {}: {:?}",
+                height.revision_height, err
+            ))
+        })?
+        .ok_or_else(|| {
+            ApiError::not_found(format!(
+                "Missing head at height {}.",
+                height.revision_height
+            ))
+        })
+}
+
+/// Verify the value of storage key `key` of `account_id` with the stored head at `height`,
+/// or the latest head if `height` is `None`.
+pub(crate) async fn verify_membership_at(
+    state: &ServerState,
+    account_id: &str,
+    key: &[u8],
+    value: &[u8],
+    height: Option<u64>,
+) -> Result<VerificationResponse, ApiError> {
+    let head = head_at(state, height).await?;
+    let height = head.header.height().revision_height;
+    let near_account_id = AccountId::try_from(account_id.to_string())
+        .map_err(|err| ApiError::bad_request(format!("Invalid account id: {:?}", err)))?;
+    // The proofs at `height - 1` are verified with the head at `height`.
+    let result = state
+        .rpc_client
+        .view_state_with_proof(
+            near_account_id,
+            Some(key),
+            Some(BlockId::Height(height - 1)),
+        )
+        .await
+        .map_err(|err| ApiError::internal(format!("Failed to view state: {:?}", err)))?;
+    let proofs: Vec<Vec<u8>> = result.proof.iter().map(|proof| proof.to_vec()).collect();
+    let key = get_raw_prefix_for_contract_data(&account_id.to_string(), key);
+    Ok(verification_response(
+        height,
+        head.verify_membership(&key, value, &proofs),
+    ))
+}
+
+/// Verify transaction `tx_hash` sent by `sender_id` with the stored head at `height`,
+/// or the latest head if `height` is `None`.
+pub(crate) async fn verify_transaction_at(
+    state: &ServerState,
+    tx_hash: &[u8],
+    sender_id: &str,
+    height: Option<u64>,
+) -> Result<VerificationResponse, ApiError> {
+    let head = head_at(state, height).await?;
+    let height = head.header.height();
+    let transaction_hash = CryptoHash::try_from(tx_hash)
+        .map_err(|_| ApiError::bad_request("Invalid transaction hash."))?;
+    let sender_id = AccountId::from_str(sender_id)
+        .map_err(|err| ApiError::bad_request(format!("Invalid sender id: {:?}", err)))?;
+    let head_hash = head.header.light_client_block.current_block_hash();
+    let result = state
+        .rpc_client
+        .get_light_client_proof(
+            &TransactionOrReceiptId::Transaction {
+                transaction_hash: near_primitives::hash::CryptoHash(transaction_hash.0),
+                sender_id,
+            },
+            &near_primitives::hash::CryptoHash(head_hash.0),
+        )
+        .await
+        .map_err(|err| {
+            ApiError::internal(format!("Failed to get light client proof: {:?}", err))
+        })?;
+    let outcome_proof = produce_outcome_proof(height, transaction_hash, &result);
+    Ok(verification_response(
+        height.revision_height,
+        head.verify_outcome_proof(&outcome_proof),
+    ))
+}
+
+fn verification_response<E: std::fmt::Debug>(
+    height: u64,
+    result: Result<(), E>,
+) -> VerificationResponse {
+    VerificationResponse {
+        height,
+        verified: result.is_ok(),
+        error: result.err().map(|err| format!("{:?}", err)),
+    }
+}
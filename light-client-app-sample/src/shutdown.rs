@@ -0,0 +1,82 @@
+//! Graceful shutdown on SIGINT/SIGTERM.
+//!
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::sync::Notify;
+
+/// Handle of a shutdown request, which long running loops check between units of work
+/// so that they stop at a consistent state.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    /// Returns a handle which is requested on SIGINT, or SIGTERM on unix.
+    ///
+    /// Must be called in a tokio runtime.
+    pub fn listen() -> Self {
+        let shutdown = Self::default();
+        let handle = shutdown.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            handle.request();
+        });
+        shutdown
+    }
+
+    /// Request the shutdown, and wake up the waiting [`Shutdown::sleep`].
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Returns whether the shutdown is requested.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the shutdown is requested.
+    pub async fn wait(&self) {
+        while !self.sleep(Duration::from_secs(3600)).await {}
+    }
+
+    /// Sleep for `duration`, or until the shutdown is requested.
+    ///
+    /// Returns whether the shutdown is requested.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        let notified = self.notify.notified();
+        if self.is_requested() {
+            return true;
+        }
+        tokio::select! {
+            _ = notified => (),
+            _ = tokio::time::sleep(duration) => (),
+        }
+        self.is_requested()
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate =
+        signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler.");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => (),
+        _ = terminate.recv() => (),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -1,14 +1,16 @@
 //! Util functions/macors
 
-/// Print info message with current time formatted.
+/// Print info message with current time formatted, unless the output is JSON.
 #[macro_export]
 macro_rules! info_with_time {
     ($fmt:expr, $($arg:tt)+) => {
-        abscissa_core::status_info!("Info",
-            format!("{}\t{}",
-                chrono::Local::now().naive_local().format("%m-%d %H:%M:%S").to_string(),
-                format!($fmt, $($arg)+)
-            )
-        );
+        if !$crate::output::is_json() {
+            abscissa_core::status_info!("Info",
+                format!("{}\t{}",
+                    chrono::Local::now().naive_local().format("%m-%d %H:%M:%S").to_string(),
+                    format!($fmt, $($arg)+)
+                )
+            );
+        }
     };
 }
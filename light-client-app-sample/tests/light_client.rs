@@ -0,0 +1,536 @@
+//! Tests of the stores, the pruning, the snapshots, the offline verification of bundles and
+//! the checkpoints of the light client, with the fixtures of `near-light-client`.
+
+use borsh::BorshSerialize;
+use light_client_app_sample::{
+    commands::OutputFormat,
+    config::{PruningSection, StorageCompression},
+    light_client::{
+        bundle::verify_bundle,
+        checkpoint::consensus_state_of_checkpoint,
+        pruning::heights_to_prune,
+        snapshot::{Snapshot, SNAPSHOT_MAGIC},
+        store::{
+            cached_store::CachedHeadStore, decode, encode, overlay_store::OverlayHeadStore,
+            sled_store::SledHeadStore, CacheStats, FileHeadStore, HeadStore,
+        },
+        LightClient,
+    },
+    output::set_output_format,
+};
+use near_light_client::{
+    near_types::{
+        block_producers::BlockProducers,
+        hash::CryptoHash,
+        merkle::{compute_block_merkle_path, compute_block_merkle_root, MerklePath},
+        BlockHeight, EpochId, ValidatorStakeView, ValidatorStakeViewV1,
+    },
+    testing::{
+        ed25519_public_key, fixture_secret_keys, header_fixture, sign_approvals,
+        state_proof_fixture,
+    },
+    types::{BundledProof, ConsensusState, Header, MembershipProof, ProofBundle, StateValueProof},
+    HeaderVerificationError, StorageError,
+};
+use std::num::NonZeroUsize;
+
+/// Returns the fixture consensus state moved to `height` in the epoch named `epoch`.
+fn head_at(height: BlockHeight, epoch: &str) -> ConsensusState {
+    let mut head = header_fixture(4, 2).consensus_state;
+    let inner_lite = &mut head.header.light_client_block.inner_lite;
+    inner_lite.height = height;
+    inner_lite.epoch_id = EpochId(CryptoHash::hash_bytes(epoch.as_bytes()));
+    head
+}
+
+fn bytes_of<T: BorshSerialize>(value: &T) -> Vec<u8> {
+    borsh::to_vec(value).unwrap()
+}
+
+fn block_hash_of(head: &ConsensusState) -> CryptoHash {
+    head.header.light_client_block.current_block_hash()
+}
+
+fn put_head(store: &mut dyn HeadStore, height: BlockHeight, head: &ConsensusState) {
+    store.put_head(height, head, &block_hash_of(head)).unwrap();
+}
+
+/// Write heads, block producers, a failed head and the metadata to the empty `store`, and
+/// check they are read back, including after deleting a head.
+fn check_round_trip(store: &mut dyn HeadStore) {
+    let heads = [head_at(10, "a"), head_at(11, "a"), head_at(20, "b")];
+    for head in heads.iter() {
+        put_head(store, head.header.height().revision_height, head);
+    }
+    assert_eq!(store.heights().unwrap(), vec![10, 11, 20]);
+    for head in heads.iter() {
+        let height = head.header.height().revision_height;
+        assert_eq!(
+            bytes_of(&store.get_head(height).unwrap().unwrap()),
+            bytes_of(head)
+        );
+        assert_eq!(
+            store.height_of_block_hash(&block_hash_of(head)).unwrap(),
+            Some(height)
+        );
+    }
+
+    store.delete_head(11).unwrap();
+    assert_eq!(store.heights().unwrap(), vec![10, 20]);
+    assert!(store.get_head(11).unwrap().is_none());
+    assert_eq!(
+        store
+            .height_of_block_hash(&block_hash_of(&heads[1]))
+            .unwrap(),
+        None
+    );
+    store.delete_head(11).unwrap();
+
+    let epoch_id = heads[0].header.epoch_id();
+    let bps = heads[0].current_bps.clone().unwrap();
+    assert!(store.get_epoch_bps(&epoch_id).unwrap().is_none());
+    store.put_epoch_bps(&epoch_id, &bps).unwrap();
+    assert_eq!(store.epoch_ids().unwrap(), vec![epoch_id]);
+    assert_eq!(
+        bytes_of(&store.get_epoch_bps(&epoch_id).unwrap().unwrap()),
+        bytes_of(&bps)
+    );
+
+    store.put_failed_head(&heads[1]).unwrap();
+    assert_eq!(store.failed_heights().unwrap(), vec![11]);
+    assert_eq!(
+        bytes_of(&store.get_failed_head(11).unwrap().unwrap()),
+        bytes_of(&heads[1])
+    );
+    assert!(store.get_head(11).unwrap().is_none());
+
+    assert_eq!(store.chain_id().unwrap(), None);
+    store.set_chain_id("testnet").unwrap();
+    assert_eq!(store.chain_id().unwrap().as_deref(), Some("testnet"));
+    assert_eq!(store.last_verified_height().unwrap(), None);
+    store.set_last_verified_height(20).unwrap();
+    assert_eq!(store.last_verified_height().unwrap(), Some(20));
+    store.flush().unwrap();
+}
+
+#[test]
+fn file_store_round_trips_state_data() {
+    for compression in [StorageCompression::None, StorageCompression::Zstd] {
+        let folder = tempfile::tempdir().unwrap();
+        let mut store = FileHeadStore::open(folder.path())
+            .unwrap()
+            .with_compression(compression);
+        check_round_trip(&mut store);
+
+        let store = FileHeadStore::open(folder.path()).unwrap();
+        assert_eq!(store.heights().unwrap(), vec![10, 20]);
+        assert!(store.quarantined_files().unwrap().is_empty());
+    }
+}
+
+#[test]
+fn file_store_quarantines_unreadable_heads() {
+    let folder = tempfile::tempdir().unwrap();
+    let mut store = FileHeadStore::open(folder.path()).unwrap();
+    let head = head_at(10, "a");
+    put_head(&mut store, 10, &head);
+    std::fs::write(folder.path().join("head").join("11"), b"not a head").unwrap();
+
+    let store = FileHeadStore::open(folder.path()).unwrap();
+    assert_eq!(store.heights().unwrap(), vec![10]);
+    assert_eq!(
+        store.quarantined_files().unwrap(),
+        vec![folder.path().join("quarantine").join("head").join("11")]
+    );
+}
+
+#[test]
+fn file_store_deletes_index_entries_of_corrupted_heads() {
+    let folder = tempfile::tempdir().unwrap();
+    let mut store = FileHeadStore::open(folder.path()).unwrap();
+    let head = head_at(10, "a");
+    put_head(&mut store, 10, &head);
+    put_head(&mut store, 12, &head_at(12, "a"));
+    std::fs::write(folder.path().join("head").join("10"), b"not a head").unwrap();
+
+    store.delete_head(10).unwrap();
+    assert_eq!(store.heights().unwrap(), vec![12]);
+    assert_eq!(
+        store.height_of_block_hash(&block_hash_of(&head)).unwrap(),
+        None
+    );
+    assert_eq!(
+        store
+            .height_of_block_hash(&block_hash_of(&head_at(12, "a")))
+            .unwrap(),
+        Some(12)
+    );
+}
+
+#[test]
+fn sled_store_round_trips_state_data() {
+    for compression in [StorageCompression::None, StorageCompression::Zstd] {
+        let folder = tempfile::tempdir().unwrap();
+        let mut store = SledHeadStore::open(folder.path())
+            .unwrap()
+            .with_compression(compression);
+        check_round_trip(&mut store);
+
+        let epoch_id = head_at(10, "a").header.epoch_id();
+        assert_eq!(store.heights_of_epoch(&epoch_id).unwrap(), vec![10]);
+        put_head(&mut store, 12, &head_at(12, "a"));
+        assert_eq!(store.heights_of_epoch(&epoch_id).unwrap(), vec![10, 12]);
+        store.delete_head(10).unwrap();
+        assert_eq!(store.heights_of_epoch(&epoch_id).unwrap(), vec![12]);
+    }
+}
+
+#[test]
+fn overlay_store_never_writes_to_the_store_beneath() {
+    let folder = tempfile::tempdir().unwrap();
+    check_round_trip(&mut OverlayHeadStore::new(Box::new(
+        FileHeadStore::open(folder.path()).unwrap(),
+    )));
+    let store = FileHeadStore::open(folder.path()).unwrap();
+    assert!(store.heights().unwrap().is_empty());
+    assert_eq!(store.chain_id().unwrap(), None);
+
+    let mut store = FileHeadStore::open(folder.path()).unwrap();
+    let head = head_at(10, "a");
+    put_head(&mut store, 10, &head);
+    let mut overlay = OverlayHeadStore::new(Box::new(store));
+    overlay.delete_head(10).unwrap();
+    put_head(&mut overlay, 20, &head_at(20, "a"));
+    assert_eq!(overlay.heights().unwrap(), vec![20]);
+    assert_eq!(
+        overlay.height_of_block_hash(&block_hash_of(&head)).unwrap(),
+        None
+    );
+
+    let store = FileHeadStore::open(folder.path()).unwrap();
+    assert_eq!(store.heights().unwrap(), vec![10]);
+    assert_eq!(
+        store.height_of_block_hash(&block_hash_of(&head)).unwrap(),
+        Some(10)
+    );
+}
+
+#[test]
+fn cached_store_round_trips_state_data() {
+    let capacity = NonZeroUsize::new(2).unwrap();
+    let folder = tempfile::tempdir().unwrap();
+    let inner = FileHeadStore::open(folder.path()).unwrap();
+    check_round_trip(&mut CachedHeadStore::new(Box::new(inner), capacity));
+
+    let store = FileHeadStore::open(folder.path()).unwrap();
+    let cached = CachedHeadStore::new(Box::new(store), capacity);
+    assert_eq!(cached.cache_stats(), Some(CacheStats::default()));
+    assert!(cached.get_head(10).unwrap().is_some());
+    assert!(cached.get_head(10).unwrap().is_some());
+    assert!(cached.get_head(11).unwrap().is_none());
+    assert_eq!(
+        cached.cache_stats(),
+        Some(CacheStats { hits: 1, misses: 2 })
+    );
+}
+
+#[test]
+fn stored_values_round_trip_with_zstd() {
+    let head = head_at(10, "a");
+    let plain = encode(&head, StorageCompression::None).unwrap();
+    assert_eq!(plain, bytes_of(&head));
+    let compressed = encode(&head, StorageCompression::Zstd).unwrap();
+    assert_ne!(compressed, plain);
+    for bytes in [plain, compressed] {
+        let decoded: ConsensusState = decode("head", &bytes).unwrap();
+        assert_eq!(bytes_of(&decoded), bytes_of(&head));
+    }
+
+    let mut truncated = encode(&head, StorageCompression::Zstd).unwrap();
+    truncated.truncate(truncated.len() / 2);
+    assert!(matches!(
+        decode::<ConsensusState>("head", &truncated),
+        Err(StorageError::CorruptedData { key }) if key == "head"
+    ));
+}
+
+#[test]
+fn uncompressed_values_starting_with_the_zstd_flag_are_decoded() {
+    let bps: BlockProducers = (0..197u8)
+        .map(|index| {
+            ValidatorStakeView::V1(ValidatorStakeViewV1 {
+                account_id: format!("validator-{}.near", index),
+                public_key: ed25519_public_key(&[index; 32]),
+                stake: 1,
+            })
+        })
+        .collect();
+    let plain = encode(&bps, StorageCompression::None).unwrap();
+    assert_eq!(plain[0], 0xc5);
+    let decoded: BlockProducers = decode("bps", &plain).unwrap();
+    assert_eq!(bytes_of(&decoded), bytes_of(&bps));
+}
+
+#[test]
+fn pruning_keeps_the_oldest_recent_and_first_heads_of_epochs() {
+    let heads = [
+        (1, "a"),
+        (2, "a"),
+        (3, "b"),
+        (4, "b"),
+        (5, "b"),
+        (6, "c"),
+        (7, "c"),
+    ]
+    .map(|(height, epoch)| (height, CryptoHash::hash_bytes(epoch.as_bytes())));
+    let policy = |keep_recent_heights, keep_epochs| PruningSection {
+        keep_recent_heights,
+        keep_epochs,
+    };
+    assert_eq!(heights_to_prune(&heads, &policy(2, 1)), vec![2, 3, 4, 5]);
+    assert_eq!(heights_to_prune(&heads, &policy(2, 2)), vec![2, 4, 5]);
+    assert_eq!(
+        heights_to_prune(&heads, &policy(0, 0)),
+        vec![2, 3, 4, 5, 6, 7]
+    );
+    assert!(heights_to_prune(&heads, &policy(7, 0)).is_empty());
+    assert!(heights_to_prune(&[], &policy(0, 0)).is_empty());
+}
+
+#[test]
+fn light_client_prunes_stored_heads() {
+    let folder = tempfile::tempdir().unwrap();
+    let mut light_client =
+        LightClient::with_store(Box::new(FileHeadStore::open(folder.path()).unwrap()));
+    for (height, epoch) in [(1, "a"), (2, "a"), (3, "b"), (4, "b")] {
+        light_client.insert_head(head_at(height, epoch)).unwrap();
+    }
+    let policy = PruningSection {
+        keep_recent_heights: 1,
+        keep_epochs: 1,
+    };
+    assert_eq!(light_client.prune(&policy, true).unwrap(), vec![2]);
+    assert_eq!(light_client.cached_heights(), vec![1, 2, 3, 4]);
+    assert_eq!(light_client.prune(&policy, false).unwrap(), vec![2]);
+    assert_eq!(light_client.cached_heights(), vec![1, 3, 4]);
+    assert!(light_client.prune(&policy, false).unwrap().is_empty());
+
+    let store = FileHeadStore::open(folder.path()).unwrap();
+    assert_eq!(store.heights().unwrap(), vec![1, 3, 4]);
+}
+
+#[test]
+fn snapshot_round_trips_through_archives() {
+    let folder = tempfile::tempdir().unwrap();
+    let mut store = FileHeadStore::open(folder.path()).unwrap();
+    check_round_trip(&mut store);
+    let snapshot = Snapshot::read_from(&store).unwrap();
+    assert_eq!(snapshot.heads.len(), 2);
+    assert_eq!(snapshot.epoch_bps.len(), 1);
+    assert_eq!(snapshot.failed_heads.len(), 1);
+
+    for compress in [false, true] {
+        let archive = snapshot.to_archive(compress).unwrap();
+        assert!(archive.starts_with(SNAPSHOT_MAGIC));
+        let decoded = Snapshot::from_archive(&archive).unwrap();
+        assert_eq!(bytes_of(&decoded), bytes_of(&snapshot));
+
+        let folder = tempfile::tempdir().unwrap();
+        let mut light_client =
+            LightClient::with_store(Box::new(SledHeadStore::open(folder.path()).unwrap()));
+        light_client.import_snapshot(&decoded).unwrap();
+        assert_eq!(light_client.cached_heights(), vec![10, 20]);
+        assert_eq!(
+            bytes_of(&light_client.export_snapshot().unwrap()),
+            bytes_of(&snapshot)
+        );
+    }
+
+    let archive = snapshot.to_archive(false).unwrap();
+    let mut corrupted = archive.clone();
+    corrupted[0] ^= 1;
+    assert!(matches!(
+        Snapshot::from_archive(&corrupted),
+        Err(StorageError::CorruptedData { .. })
+    ));
+    let mut corrupted = archive;
+    corrupted[SNAPSHOT_MAGIC.len()] ^= 1;
+    assert!(matches!(
+        Snapshot::from_archive(&corrupted),
+        Err(StorageError::Backend { .. })
+    ));
+}
+
+#[test]
+fn bundle_is_only_verified_with_the_stored_head() {
+    set_output_format(OutputFormat::Json);
+    let key = b"key".to_vec();
+    let value = b"value".to_vec();
+    let (proof, root) = state_proof_fixture(&key, &value, 2);
+    let mut head = head_at(10, "a");
+    head.header.prev_state_root_of_chunks = vec![root];
+
+    let folder = tempfile::tempdir().unwrap();
+    let mut light_client =
+        LightClient::with_store(Box::new(FileHeadStore::open(folder.path()).unwrap()));
+    light_client.insert_head(head.clone()).unwrap();
+    let bundle_of = |consensus_state: ConsensusState| {
+        let proof = MembershipProof::new(
+            consensus_state.header.height(),
+            key.clone(),
+            value.clone(),
+            proof.clone(),
+        );
+        ProofBundle::new(
+            "testnet".to_owned(),
+            consensus_state,
+            BundledProof::State(StateValueProof::Membership(proof)),
+        )
+    };
+
+    let report = verify_bundle(&light_client, &bundle_of(head.clone()));
+    assert!(report.verified, "{:?}", report.error);
+    assert_eq!(report.head_hash, Some(block_hash_of(&head)));
+
+    let mut other = head.clone();
+    other.header.light_client_block.inner_lite.timestamp += 1;
+    assert!(other.verify_membership(&key, &value, &proof).is_ok());
+    let report = verify_bundle(&light_client, &bundle_of(other));
+    assert!(!report.verified);
+    assert!(report
+        .error
+        .unwrap()
+        .starts_with("The bundled head differs from the stored head"));
+
+    let report = verify_bundle(&light_client, &bundle_of(head_at(11, "a")));
+    assert!(!report.verified);
+    assert!(report
+        .error
+        .unwrap()
+        .starts_with("Missing stored head at height 11"));
+}
+
+/// Returns a checkpoint of the epoch following the fixture header, signed by the `next_bps`
+/// of the fixture header, and the fixture header with its block proof in the checkpoint.
+fn checkpoint_fixture() -> (Header, Header, MerklePath) {
+    let previous_epoch_header = header_fixture(4, 2).header;
+    let block_hashes = [
+        CryptoHash::hash_bytes(b"genesis"),
+        block_hash_of_header(&previous_epoch_header),
+        CryptoHash::hash_bytes(b"last block"),
+    ];
+    let mut checkpoint = previous_epoch_header.clone();
+    let block = &mut checkpoint.light_client_block;
+    block.prev_block_hash = block_hashes[2];
+    block.inner_lite.height = 200;
+    block.inner_lite.epoch_id = previous_epoch_header
+        .light_client_block
+        .inner_lite
+        .next_epoch_id
+        .clone();
+    block.inner_lite.next_epoch_id = EpochId(CryptoHash::hash_bytes(b"epoch after"));
+    block.inner_lite.block_merkle_root = compute_block_merkle_root(&block_hashes);
+    block.approvals_after_next = sign_approvals(block, &fixture_secret_keys(4));
+    let block_proof = compute_block_merkle_path(&block_hashes, 1).unwrap();
+    (checkpoint, previous_epoch_header, block_proof)
+}
+
+fn block_hash_of_header(header: &Header) -> CryptoHash {
+    header.light_client_block.current_block_hash()
+}
+
+#[test]
+fn checkpoint_takes_block_producers_of_its_ancestor() {
+    let (checkpoint, previous_epoch_header, block_proof) = checkpoint_fixture();
+    let trusted_block_hash = block_hash_of_header(&checkpoint);
+    let head = consensus_state_of_checkpoint(
+        checkpoint.clone(),
+        previous_epoch_header.clone(),
+        &block_proof,
+        &trusted_block_hash,
+    )
+    .unwrap();
+    assert_eq!(block_hash_of(&head), trusted_block_hash);
+    assert_eq!(
+        bytes_of(&head.current_bps),
+        bytes_of(&previous_epoch_header.light_client_block.next_bps)
+    );
+
+    let other_block_hash = CryptoHash::hash_bytes(b"other block");
+    assert!(matches!(
+        consensus_state_of_checkpoint(
+            checkpoint.clone(),
+            previous_epoch_header.clone(),
+            &block_proof,
+            &other_block_hash,
+        ),
+        Err(HeaderVerificationError::InvalidTrustedBlockHash { expected, actual })
+            if expected == other_block_hash && actual == trusted_block_hash
+    ));
+
+    // Replacing the `next_bps` keeps the block hash, so the ancestor is still proved.
+    let mut replaced_bps = previous_epoch_header;
+    let next_bps = replaced_bps.light_client_block.next_bps.as_mut().unwrap();
+    let remaining_bps = next_bps.decoded()[1..].to_vec();
+    *next_bps = remaining_bps.into();
+    assert!(matches!(
+        consensus_state_of_checkpoint(checkpoint, replaced_bps, &block_proof, &trusted_block_hash),
+        Err(HeaderVerificationError::InvalidNextBlockProducersHash)
+    ));
+}
+
+#[test]
+fn checkpoint_rejects_fake_previous_epoch_header() {
+    let (mut checkpoint, previous_epoch_header, block_proof) = checkpoint_fixture();
+    let trusted_block_hash = block_hash_of_header(&checkpoint);
+
+    // A self-consistent header of the previous epoch, whose `next_bps` are the block
+    // producers of an attacker, who re-signs the approvals of the checkpoint, which are
+    // not covered by its block hash.
+    let secret_keys = (0..4_u8)
+        .map(|index| Some([index + 0x80; 32]))
+        .collect::<Vec<_>>();
+    let fake_bps = secret_keys
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, secret_key)| {
+            ValidatorStakeView::V1(ValidatorStakeViewV1 {
+                account_id: format!("attacker-{}.near", index),
+                public_key: ed25519_public_key(secret_key),
+                stake: 1_000_000_000_000_000_000_000_000,
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut fake_header = previous_epoch_header;
+    let fake_block = &mut fake_header.light_client_block;
+    fake_block.inner_lite.next_bp_hash = CryptoHash::hash_borsh(&fake_bps);
+    fake_block.next_bps = Some(fake_bps.into());
+    checkpoint.light_client_block.approvals_after_next =
+        sign_approvals(&checkpoint.light_client_block, &secret_keys);
+    assert_eq!(block_hash_of_header(&checkpoint), trusted_block_hash);
+
+    let fake_block_hash = block_hash_of_header(&fake_header);
+    assert!(matches!(
+        consensus_state_of_checkpoint(
+            checkpoint.clone(),
+            fake_header.clone(),
+            &block_proof,
+            &trusted_block_hash,
+        ),
+        Err(HeaderVerificationError::BrokenChain { trusted_block_hash: hash })
+            if hash == trusted_block_hash
+    ));
+
+    // Nor can a block proof of the fake header be built with the trusted checkpoint.
+    let block_hashes = [CryptoHash::hash_bytes(b"genesis"), fake_block_hash];
+    assert!(matches!(
+        consensus_state_of_checkpoint(
+            checkpoint,
+            fake_header,
+            &compute_block_merkle_path(&block_hashes, 1).unwrap(),
+            &trusted_block_hash,
+        ),
+        Err(HeaderVerificationError::BrokenChain { .. })
+    ));
+}
@@ -0,0 +1,43 @@
+//! Tests of the queue of the headers fetched ahead of their verification.
+
+use std::sync::Arc;
+
+use light_client_app_sample::prefetch::PrefetchQueue;
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn cancelled_queue_is_cleared_and_stops_fetching() {
+    let fetching = Arc::new(());
+    let fetcher_fetching = fetching.clone();
+    let (filled, filled_receiver) = oneshot::channel();
+    let mut queue = PrefetchQueue::spawn(4, move |sender| async move {
+        let _fetching = fetcher_fetching;
+        for item in 0..5 {
+            sender.send(item).await.unwrap();
+        }
+        filled.send(()).unwrap();
+        std::future::pending::<()>().await;
+    });
+    assert_eq!(queue.recv().await, Some(0));
+    filled_receiver.await.unwrap();
+
+    assert_eq!(queue.cancel(), 4);
+    assert_eq!(queue.recv().await, None);
+    while Arc::strong_count(&fetching) > 1 {
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(queue.cancel(), 0);
+}
+
+#[tokio::test]
+async fn queue_ends_after_the_fetched_items() {
+    let mut queue = PrefetchQueue::spawn(2, |sender| async move {
+        for item in 0..3 {
+            sender.send(item).await.unwrap();
+        }
+    });
+    for item in 0..3 {
+        assert_eq!(queue.recv().await, Some(item));
+    }
+    assert_eq!(queue.recv().await, None);
+}
@@ -0,0 +1,141 @@
+//! An example NEAR contract of NEAR light client.
+//!
+//! The contract stores the consensus states of NEAR light client in contract storage,
+//! accepts new headers through `update_state` and verifies the state of NEAR through
+//! view methods. All light client datatypes are passed as base64 encoded borsh bytes.
+
+use near_light_client::{
+    near_types::BlockHeight,
+    types::{ConsensusState, Header, Height},
+    BasicNearLightClient,
+};
+use near_sdk::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    env,
+    json_types::Base64VecU8,
+    near_bindgen, require,
+    store::LookupMap,
+    BorshStorageKey, PanicOnDefault,
+};
+
+#[derive(BorshSerialize, BorshStorageKey)]
+#[borsh(crate = "near_sdk::borsh")]
+enum StorageKey {
+    ConsensusStates,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+#[borsh(crate = "near_sdk::borsh")]
+pub struct NearLightClientContract {
+    latest_height: Height,
+    consensus_states: LookupMap<Height, ConsensusState>,
+}
+
+impl BasicNearLightClient for NearLightClientContract {
+    fn latest_height(&self) -> Height {
+        self.latest_height
+    }
+
+    fn get_consensus_state(&self, height: &Height) -> Option<ConsensusState> {
+        self.consensus_states.get(height).cloned()
+    }
+}
+
+#[near_bindgen]
+impl NearLightClientContract {
+    /// Initialize the contract with a trusted consensus state.
+    ///
+    /// Only callable by the contract account itself, e.g. in the batch deploying the contract,
+    /// so that nobody else can install the trust anchor of a deployed but uninitialized contract.
+    #[init]
+    #[private]
+    pub fn new(consensus_state: Base64VecU8) -> Self {
+        let consensus_state: ConsensusState = borsh::from_slice(&consensus_state.0)
+            .unwrap_or_else(|_| env::panic_str("Invalid borsh encoding of consensus state."));
+        let mut contract = Self {
+            latest_height: Height::default(),
+            consensus_states: LookupMap::new(StorageKey::ConsensusStates),
+        };
+        contract.save_consensus_state(consensus_state);
+        contract
+    }
+
+    /// Verify the header and store the consensus state of it.
+    pub fn update_state(&mut self, header: Base64VecU8) {
+        let header: Header = borsh::from_slice(&header.0)
+            .unwrap_or_else(|_| env::panic_str("Invalid borsh encoding of header."));
+        if let Err(err) = self.verify_header(&header) {
+            env::panic_str(format!("Failed to verify header: {:?}", err).as_str());
+        }
+        let latest_consensus_state =
+            BasicNearLightClient::get_consensus_state(self, &self.latest_height)
+                .expect("Should not fail as the contract is initialized.");
+        self.save_consensus_state(latest_consensus_state.next(header));
+    }
+
+    /// Returns the latest block height of the light client.
+    pub fn latest_height(&self) -> BlockHeight {
+        self.latest_height.revision_height
+    }
+
+    /// Returns the borsh encoded consensus state at the block height.
+    pub fn get_consensus_state(&self, height: BlockHeight) -> Option<Base64VecU8> {
+        let height = Height::from_block_height(height);
+        self.consensus_states.get(&height).map(|consensus_state| {
+            Base64VecU8(
+                borsh::to_vec(consensus_state)
+                    .expect("Should not fail based on borsh serialization."),
+            )
+        })
+    }
+
+    /// Verify the value of a storage key with the consensus state at the block height.
+    pub fn verify_membership(
+        &self,
+        height: BlockHeight,
+        key: Base64VecU8,
+        value: Base64VecU8,
+        proofs: Vec<Base64VecU8>,
+    ) -> bool {
+        let consensus_state = self
+            .consensus_states
+            .get(&Height::from_block_height(height));
+        require!(consensus_state.is_some(), "Missing consensus state.");
+        consensus_state
+            .expect("Should not fail based on previous checking.")
+            .verify_membership(
+                &key.0,
+                &value.0,
+                &proofs.into_iter().map(|proof| proof.0).collect(),
+            )
+            .is_ok()
+    }
+
+    /// Verify that a storage key has no value with the consensus state at the block height.
+    pub fn verify_non_membership(
+        &self,
+        height: BlockHeight,
+        key: Base64VecU8,
+        proofs: Vec<Base64VecU8>,
+    ) -> bool {
+        let consensus_state = self
+            .consensus_states
+            .get(&Height::from_block_height(height));
+        require!(consensus_state.is_some(), "Missing consensus state.");
+        consensus_state
+            .expect("Should not fail based on previous checking.")
+            .verify_non_membership(&key.0, &proofs.into_iter().map(|proof| proof.0).collect())
+            .is_ok()
+    }
+}
+
+impl NearLightClientContract {
+    fn save_consensus_state(&mut self, consensus_state: ConsensusState) {
+        let height = consensus_state.header.height();
+        self.consensus_states.insert(height, consensus_state);
+        if self.latest_height < height {
+            self.latest_height = height;
+        }
+    }
+}
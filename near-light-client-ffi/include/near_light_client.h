@@ -0,0 +1,60 @@
+/*
+ * C API of the stateless verification functions of NEAR light client.
+ *
+ * All light client datatypes are passed as borsh encoded byte buffers.
+ * Buffers returned by the library must be released with `nlc_free_buffer`.
+ */
+
+#ifndef NEAR_LIGHT_CLIENT_H
+#define NEAR_LIGHT_CLIENT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define NLC_OK 0
+#define NLC_NULL_POINTER 1
+#define NLC_INVALID_ENCODING 2
+#define NLC_INVALID_HEADER 3
+#define NLC_INVALID_STATE_PROOF 4
+#define NLC_INVALID_TRANSACTION 5
+#define NLC_INTERNAL_ERROR 255
+
+int32_t nlc_verify_header(const uint8_t *consensus_state_ptr, size_t consensus_state_len,
+                          const uint8_t *header_ptr, size_t header_len);
+
+int32_t nlc_update_consensus_state(const uint8_t *consensus_state_ptr, size_t consensus_state_len,
+                                   const uint8_t *header_ptr, size_t header_len,
+                                   uint8_t **out_ptr, size_t *out_len);
+
+void nlc_free_buffer(uint8_t *ptr, size_t len);
+
+int32_t nlc_verify_membership(const uint8_t *consensus_state_ptr, size_t consensus_state_len,
+                              const uint8_t *key_ptr, size_t key_len,
+                              const uint8_t *value_ptr, size_t value_len,
+                              const uint8_t *proofs_ptr, size_t proofs_len);
+
+int32_t nlc_verify_non_membership(const uint8_t *consensus_state_ptr, size_t consensus_state_len,
+                                  const uint8_t *key_ptr, size_t key_len,
+                                  const uint8_t *proofs_ptr, size_t proofs_len);
+
+int32_t nlc_verify_state_proof(const uint8_t *state_root_ptr,
+                               const uint8_t *key_ptr, size_t key_len,
+                               const uint8_t *value_ptr, size_t value_len,
+                               const uint8_t *proofs_ptr, size_t proofs_len);
+
+int32_t nlc_verify_transaction(const uint8_t *consensus_state_ptr, size_t consensus_state_len,
+                               const uint8_t *outcome_with_id_ptr, size_t outcome_with_id_len,
+                               const uint8_t *outcome_proof_ptr, size_t outcome_proof_len,
+                               const uint8_t *outcome_root_proof_ptr, size_t outcome_root_proof_len,
+                               const uint8_t *block_lite_view_ptr, size_t block_lite_view_len,
+                               const uint8_t *block_proof_ptr, size_t block_proof_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* NEAR_LIGHT_CLIENT_H */
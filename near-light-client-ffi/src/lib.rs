@@ -0,0 +1,259 @@
+//! C API of the stateless verification functions of NEAR light client.
+//!
+//! All light client datatypes are passed as borsh encoded byte buffers, and every function
+//! returns one of the `NLC_*` error codes. Buffers returned by the library must be released
+//! with [`nlc_free_buffer`]. See `include/near_light_client.h` for the declarations.
+
+use std::panic::{catch_unwind, UnwindSafe};
+
+use borsh::BorshDeserialize;
+use near_light_client::{
+    near_types::{
+        hash::CryptoHash,
+        merkle::MerklePath,
+        transaction::ExecutionOutcomeWithId,
+        trie::{decode_state_proof, verify_not_in_state, verify_state_proof},
+        LightClientBlockLite,
+    },
+    types::{ConsensusState, Header},
+    BasicNearLightClient, TrustedConsensusState,
+};
+
+/// The call succeeded.
+pub const NLC_OK: i32 = 0;
+/// A pointer argument is null while its length is not zero.
+pub const NLC_NULL_POINTER: i32 = 1;
+/// An argument is not a valid borsh encoding of the expected type.
+pub const NLC_INVALID_ENCODING: i32 = 2;
+/// The header failed verification.
+pub const NLC_INVALID_HEADER: i32 = 3;
+/// The state proof failed verification.
+pub const NLC_INVALID_STATE_PROOF: i32 = 4;
+/// The transaction or receipt outcome failed verification.
+pub const NLC_INVALID_TRANSACTION: i32 = 5;
+/// The library panicked, which is a bug.
+pub const NLC_INTERNAL_ERROR: i32 = 255;
+
+/// Returns the bytes of a buffer passed by the caller.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, unless `len` is zero.
+unsafe fn bytes_of<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], i32> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        return Err(NLC_NULL_POINTER);
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
+}
+
+/// Decode a buffer passed by the caller.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, unless `len` is zero.
+unsafe fn decode<T: BorshDeserialize>(ptr: *const u8, len: usize) -> Result<T, i32> {
+    borsh::from_slice(bytes_of(ptr, len)?).map_err(|_| NLC_INVALID_ENCODING)
+}
+
+fn run(f: impl FnOnce() -> Result<(), i32> + UnwindSafe) -> i32 {
+    match catch_unwind(f) {
+        Ok(Ok(())) => NLC_OK,
+        Ok(Err(code)) => code,
+        Err(_) => NLC_INTERNAL_ERROR,
+    }
+}
+
+/// Verify `header` with `consensus_state`.
+///
+/// # Safety
+///
+/// Every pointer must be valid for reads of the corresponding length.
+#[no_mangle]
+pub unsafe extern "C" fn nlc_verify_header(
+    consensus_state_ptr: *const u8,
+    consensus_state_len: usize,
+    header_ptr: *const u8,
+    header_len: usize,
+) -> i32 {
+    run(|| {
+        let client = TrustedConsensusState::new(decode(consensus_state_ptr, consensus_state_len)?);
+        let header: Header = decode(header_ptr, header_len)?;
+        client
+            .verify_header(&header)
+            .map_err(|_| NLC_INVALID_HEADER)
+    })
+}
+
+/// Verify `header` with `consensus_state`, and write the borsh encoded consensus state
+/// of `header` into a new buffer returned by `out_ptr` and `out_len`.
+///
+/// # Safety
+///
+/// Every input pointer must be valid for reads of the corresponding length,
+/// and `out_ptr` and `out_len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nlc_update_consensus_state(
+    consensus_state_ptr: *const u8,
+    consensus_state_len: usize,
+    header_ptr: *const u8,
+    header_len: usize,
+    out_ptr: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    if out_ptr.is_null() || out_len.is_null() {
+        return NLC_NULL_POINTER;
+    }
+    run(|| {
+        let consensus_state: ConsensusState = decode(consensus_state_ptr, consensus_state_len)?;
+        let header: Header = decode(header_ptr, header_len)?;
+        let client = TrustedConsensusState::new(consensus_state);
+        client
+            .verify_header(&header)
+            .map_err(|_| NLC_INVALID_HEADER)?;
+        let next_consensus_state = client.consensus_state().next(header);
+        let bytes = borsh::to_vec(&next_consensus_state)
+            .expect("Should not fail based on borsh serialization.")
+            .into_boxed_slice();
+        *out_len = bytes.len();
+        *out_ptr = Box::into_raw(bytes) as *mut u8;
+        Ok(())
+    })
+}
+
+/// Release a buffer returned by the library.
+///
+/// # Safety
+///
+/// `ptr` and `len` must be a buffer returned by the library, which is not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn nlc_free_buffer(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+}
+
+/// Verify the value of a storage key with `consensus_state` and `proofs`,
+/// which is a borsh encoded `Vec<Vec<u8>>`.
+///
+/// # Safety
+///
+/// Every pointer must be valid for reads of the corresponding length.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn nlc_verify_membership(
+    consensus_state_ptr: *const u8,
+    consensus_state_len: usize,
+    key_ptr: *const u8,
+    key_len: usize,
+    value_ptr: *const u8,
+    value_len: usize,
+    proofs_ptr: *const u8,
+    proofs_len: usize,
+) -> i32 {
+    run(|| {
+        let consensus_state: ConsensusState = decode(consensus_state_ptr, consensus_state_len)?;
+        consensus_state
+            .verify_membership(
+                bytes_of(key_ptr, key_len)?,
+                bytes_of(value_ptr, value_len)?,
+                &decode(proofs_ptr, proofs_len)?,
+            )
+            .map_err(|_| NLC_INVALID_STATE_PROOF)
+    })
+}
+
+/// Verify that a storage key has no value with `consensus_state` and `proofs`,
+/// which is a borsh encoded `Vec<Vec<u8>>`.
+///
+/// # Safety
+///
+/// Every pointer must be valid for reads of the corresponding length.
+#[no_mangle]
+pub unsafe extern "C" fn nlc_verify_non_membership(
+    consensus_state_ptr: *const u8,
+    consensus_state_len: usize,
+    key_ptr: *const u8,
+    key_len: usize,
+    proofs_ptr: *const u8,
+    proofs_len: usize,
+) -> i32 {
+    run(|| {
+        let consensus_state: ConsensusState = decode(consensus_state_ptr, consensus_state_len)?;
+        consensus_state
+            .verify_non_membership(
+                bytes_of(key_ptr, key_len)?,
+                &decode(proofs_ptr, proofs_len)?,
+            )
+            .map_err(|_| NLC_INVALID_STATE_PROOF)
+    })
+}
+
+/// Verify the value of a storage key in the state with the 32 bytes `state_root`, where
+/// `proofs` is a borsh encoded `Vec<Vec<u8>>`. An empty `value` means the key has no value.
+///
+/// # Safety
+///
+/// Every pointer must be valid for reads of the corresponding length,
+/// and `state_root_ptr` must be valid for reads of 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nlc_verify_state_proof(
+    state_root_ptr: *const u8,
+    key_ptr: *const u8,
+    key_len: usize,
+    value_ptr: *const u8,
+    value_len: usize,
+    proofs_ptr: *const u8,
+    proofs_len: usize,
+) -> i32 {
+    run(|| {
+        let state_root = CryptoHash::try_from(bytes_of(state_root_ptr, 32)?)
+            .expect("Should not fail as the length is 32.");
+        let key = bytes_of(key_ptr, key_len)?;
+        let proofs: Vec<Vec<u8>> = decode(proofs_ptr, proofs_len)?;
+        let nodes = decode_state_proof(&proofs).map_err(|_| NLC_INVALID_STATE_PROOF)?;
+        match value_len {
+            0 => verify_not_in_state(key, &nodes, &state_root),
+            _ => verify_state_proof(key, &nodes, bytes_of(value_ptr, value_len)?, &state_root),
+        }
+        .map_err(|_| NLC_INVALID_STATE_PROOF)
+    })
+}
+
+/// Verify a transaction or receipt outcome with `consensus_state`,
+/// where all the other arguments are borsh encoded as well.
+///
+/// # Safety
+///
+/// Every pointer must be valid for reads of the corresponding length.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn nlc_verify_transaction(
+    consensus_state_ptr: *const u8,
+    consensus_state_len: usize,
+    outcome_with_id_ptr: *const u8,
+    outcome_with_id_len: usize,
+    outcome_proof_ptr: *const u8,
+    outcome_proof_len: usize,
+    outcome_root_proof_ptr: *const u8,
+    outcome_root_proof_len: usize,
+    block_lite_view_ptr: *const u8,
+    block_lite_view_len: usize,
+    block_proof_ptr: *const u8,
+    block_proof_len: usize,
+) -> i32 {
+    run(|| {
+        let consensus_state: ConsensusState = decode(consensus_state_ptr, consensus_state_len)?;
+        consensus_state
+            .verify_transaction_or_receipt(
+                &decode::<ExecutionOutcomeWithId>(outcome_with_id_ptr, outcome_with_id_len)?,
+                &decode::<MerklePath>(outcome_proof_ptr, outcome_proof_len)?,
+                &decode::<MerklePath>(outcome_root_proof_ptr, outcome_root_proof_len)?,
+                &decode::<LightClientBlockLite>(block_lite_view_ptr, block_lite_view_len)?,
+                &decode::<MerklePath>(block_proof_ptr, block_proof_len)?,
+            )
+            .map_err(|_| NLC_INVALID_TRANSACTION)
+    })
+}
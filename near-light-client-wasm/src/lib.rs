@@ -0,0 +1,113 @@
+//! wasm-bindgen bindings of NEAR light client, for verifying NEAR data in browsers and node.
+//!
+//! All light client datatypes are passed as their borsh encodings, and the codecs
+//! between borsh and JSON (the same format as the JSON rpc of NEAR) are provided.
+//! The verifications are stateless: the caller passes the consensus state it trusts.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_light_client::{
+    near_types::{merkle::MerklePath, transaction::ExecutionOutcomeWithId, LightClientBlockLite},
+    types::{ConsensusState, Header},
+    BasicNearLightClient, TrustedConsensusState,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::prelude::*;
+
+fn decode<T: BorshDeserialize>(name: &str, bytes: &[u8]) -> Result<T, JsError> {
+    borsh::from_slice(bytes).map_err(|e| JsError::new(&format!("Invalid {}: {}", name, e)))
+}
+
+fn from_json<T: DeserializeOwned + BorshSerialize>(
+    name: &str,
+    json: &str,
+) -> Result<Vec<u8>, JsError> {
+    let value: T = serde_json::from_str(json)
+        .map_err(|e| JsError::new(&format!("Invalid JSON of {}: {}", name, e)))?;
+    Ok(borsh::to_vec(&value).expect("Should not fail based on borsh serialization."))
+}
+
+fn to_json<T: BorshDeserialize + Serialize>(name: &str, bytes: &[u8]) -> Result<String, JsError> {
+    serde_json::to_string(&decode::<T>(name, bytes)?).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Verify the borsh encoded `header` with the borsh encoded `consensus_state`.
+#[wasm_bindgen(js_name = verifyHeader)]
+pub fn verify_header(consensus_state: &[u8], header: &[u8]) -> Result<(), JsError> {
+    let client = TrustedConsensusState::new(decode("consensus state", consensus_state)?);
+    client
+        .verify_header(&decode::<Header>("header", header)?)
+        .map_err(|e| JsError::new(&format!("{:?}", e)))
+}
+
+/// Verify the value of a storage key with the borsh encoded `consensus_state`
+/// and the borsh encoded `proofs` (a `Vec<Vec<u8>>`).
+#[wasm_bindgen(js_name = verifyMembership)]
+pub fn verify_membership(
+    consensus_state: &[u8],
+    key: &[u8],
+    value: &[u8],
+    proofs: &[u8],
+) -> Result<(), JsError> {
+    decode::<ConsensusState>("consensus state", consensus_state)?
+        .verify_membership(key, value, &decode("proofs", proofs)?)
+        .map_err(|e| JsError::new(&format!("{:?}", e)))
+}
+
+/// Verify that a storage key has no value with the borsh encoded `consensus_state`
+/// and the borsh encoded `proofs` (a `Vec<Vec<u8>>`).
+#[wasm_bindgen(js_name = verifyNonMembership)]
+pub fn verify_non_membership(
+    consensus_state: &[u8],
+    key: &[u8],
+    proofs: &[u8],
+) -> Result<(), JsError> {
+    decode::<ConsensusState>("consensus state", consensus_state)?
+        .verify_non_membership(key, &decode("proofs", proofs)?)
+        .map_err(|e| JsError::new(&format!("{:?}", e)))
+}
+
+/// Verify a transaction or receipt outcome with the borsh encoded `consensus_state`,
+/// where all the other arguments are borsh encoded as well.
+#[wasm_bindgen(js_name = verifyTransaction)]
+pub fn verify_transaction(
+    consensus_state: &[u8],
+    outcome_with_id: &[u8],
+    outcome_proof: &[u8],
+    outcome_root_proof: &[u8],
+    block_lite_view: &[u8],
+    block_proof: &[u8],
+) -> Result<(), JsError> {
+    decode::<ConsensusState>("consensus state", consensus_state)?
+        .verify_transaction_or_receipt(
+            &decode::<ExecutionOutcomeWithId>("outcome", outcome_with_id)?,
+            &decode::<MerklePath>("outcome proof", outcome_proof)?,
+            &decode::<MerklePath>("outcome root proof", outcome_root_proof)?,
+            &decode::<LightClientBlockLite>("block", block_lite_view)?,
+            &decode::<MerklePath>("block proof", block_proof)?,
+        )
+        .map_err(|e| JsError::new(&format!("{:?}", e)))
+}
+
+/// Convert the JSON of a header into its borsh encoding.
+#[wasm_bindgen(js_name = headerFromJson)]
+pub fn header_from_json(json: &str) -> Result<Vec<u8>, JsError> {
+    from_json::<Header>("header", json)
+}
+
+/// Convert the borsh encoding of a header into its JSON.
+#[wasm_bindgen(js_name = headerToJson)]
+pub fn header_to_json(bytes: &[u8]) -> Result<String, JsError> {
+    to_json::<Header>("header", bytes)
+}
+
+/// Convert the JSON of a consensus state into its borsh encoding.
+#[wasm_bindgen(js_name = consensusStateFromJson)]
+pub fn consensus_state_from_json(json: &str) -> Result<Vec<u8>, JsError> {
+    from_json::<ConsensusState>("consensus state", json)
+}
+
+/// Convert the borsh encoding of a consensus state into its JSON.
+#[wasm_bindgen(js_name = consensusStateToJson)]
+pub fn consensus_state_to_json(bytes: &[u8]) -> Result<String, JsError> {
+    to_json::<ConsensusState>("consensus state", bytes)
+}
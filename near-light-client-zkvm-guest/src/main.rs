@@ -0,0 +1,38 @@
+//! An example RISC Zero guest program proving the execution of `verify_header`.
+//!
+//! The guest reads the borsh encoded trusted consensus state and the new header, verifies
+//! the header, and commits the hash of the trusted block, the hash of the new block and
+//! the height of the new block to the journal. The guest panics if the header is invalid,
+//! so that no receipt can be produced for an invalid header.
+
+#![no_main]
+
+use near_light_client::{
+    types::{ConsensusState, Header},
+    BasicNearLightClient, TrustedConsensusState,
+};
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry!(main);
+
+fn main() {
+    let (consensus_state, header): (Vec<u8>, Vec<u8>) = env::read();
+    let consensus_state: ConsensusState =
+        borsh::from_slice(&consensus_state).expect("Invalid borsh encoding of consensus state.");
+    let header: Header = borsh::from_slice(&header).expect("Invalid borsh encoding of header.");
+
+    let trusted_block_hash = consensus_state
+        .header
+        .light_client_block
+        .current_block_hash();
+    let client = TrustedConsensusState::new(consensus_state);
+    if client.verify_header(&header).is_err() {
+        panic!("Failed to verify header at height {}.", header.height());
+    }
+
+    env::commit(&(
+        trusted_block_hash.0,
+        header.light_client_block.current_block_hash().0,
+        header.height().revision_height,
+    ));
+}
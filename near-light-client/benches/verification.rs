@@ -0,0 +1,147 @@
+//! Benchmarks of the core verification paths, run with `cargo bench --features testing`.
+//!
+//! The headers are built by `testing::header_fixture` with the size of NEAR mainnet
+//! (100 block producers and 4 chunks), run with `--features testing,rayon` to benchmark
+//! the parallel verification of approval signatures.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use near_light_client::{
+    near_types::{
+        hash::CryptoHash,
+        merkle::{compute_block_merkle_path, compute_block_merkle_root, merklize},
+        transaction::{
+            build_outcome_proofs, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus,
+        },
+        trie::{verify_state_proof_data, StateProof},
+        LightClientBlockLite,
+    },
+    testing::{header_fixture, state_proof_fixture, HeaderFixture},
+    BasicNearLightClient, TrustedConsensusState,
+};
+
+const MAINNET_BLOCK_PRODUCERS: usize = 100;
+const MAINNET_CHUNKS: usize = 4;
+
+fn header_verification(c: &mut Criterion) {
+    let HeaderFixture {
+        consensus_state,
+        header,
+    } = header_fixture(MAINNET_BLOCK_PRODUCERS, MAINNET_CHUNKS);
+    let mut group = c.benchmark_group("verify_header");
+    for fast_finality_check in [false, true] {
+        let client = TrustedConsensusState::new(consensus_state.clone())
+            .with_fast_finality_check(fast_finality_check);
+        assert!(client.verify_header(&header).is_ok());
+        let name = match fast_finality_check {
+            false => "all_signatures",
+            true => "fast_finality_check",
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| client.verify_header(black_box(&header)))
+        });
+    }
+    group.finish();
+}
+
+fn membership_proofs(c: &mut Criterion) {
+    let key = [0x5a_u8; 32];
+    let value = [0x42_u8; 64];
+    let mut group = c.benchmark_group("verify_membership");
+    for depth in [1, 4, 8, 16, 32] {
+        let (proofs, state_root) = state_proof_fixture(&key, &value, depth);
+        assert!(verify_state_proof_data(&key, &proofs, &value, &state_root).is_ok());
+        group.bench_with_input(BenchmarkId::new("ordered", depth), &proofs, |b, proofs| {
+            b.iter(|| verify_state_proof_data(&key, black_box(proofs), &value, &state_root))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("state_proof", depth),
+            &proofs,
+            |b, proofs| {
+                b.iter(|| {
+                    StateProof::new(black_box(proofs))
+                        .and_then(|proof| proof.verify_membership(&key, &value, &state_root))
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn merklize_chunk_roots(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merklize");
+    for chunk_count in [1, 4, 16, 64] {
+        let chunk_roots = (0..chunk_count as u64)
+            .map(|index| CryptoHash::hash_borsh(&index))
+            .collect::<Vec<_>>();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(chunk_count),
+            &chunk_roots,
+            |b, chunk_roots| b.iter(|| merklize(black_box(chunk_roots)).0),
+        );
+    }
+    group.finish();
+}
+
+fn outcome_verification(c: &mut Criterion) {
+    let chunk_outcomes = (0..MAINNET_CHUNKS as u64)
+        .map(|chunk| {
+            (0..32_u64)
+                .map(|index| ExecutionOutcomeWithId {
+                    id: CryptoHash::hash_borsh(&(chunk, index)),
+                    outcome: ExecutionOutcome {
+                        logs: vec!["EVENT_JSON:{}".into(); 2],
+                        receipt_ids: vec![CryptoHash::hash_borsh(&(index, chunk))],
+                        gas_burnt: 2_428_000_000_000,
+                        tokens_burnt: 242_800_000_000_000_000_000,
+                        executor_id: "app.near".into(),
+                        status: ExecutionStatus::SuccessValue(Vec::new()),
+                    },
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let proofs = build_outcome_proofs(&chunk_outcomes, 1, 7)
+        .expect("Should not fail based on the indexes in range.");
+    let HeaderFixture {
+        mut consensus_state,
+        header,
+    } = header_fixture(MAINNET_BLOCK_PRODUCERS, MAINNET_CHUNKS);
+    let mut block_lite_view = LightClientBlockLite {
+        inner_lite: header.light_client_block.inner_lite.clone(),
+        inner_rest_hash: header.light_client_block.inner_rest_hash,
+        prev_block_hash: header.light_client_block.prev_block_hash,
+    };
+    block_lite_view.inner_lite.outcome_root = proofs.outcome_root;
+    let block_hashes = (0..1000_u64)
+        .map(|height| CryptoHash::hash_borsh(&height))
+        .chain([block_lite_view.current_block_hash()])
+        .collect::<Vec<_>>();
+    let block_proof = compute_block_merkle_path(&block_hashes, block_hashes.len() - 1)
+        .expect("Should not fail based on the index in range.");
+    consensus_state
+        .header
+        .light_client_block
+        .inner_lite
+        .block_merkle_root = compute_block_merkle_root(&block_hashes);
+    let outcome_with_id = &chunk_outcomes[1][7];
+    let verify = || {
+        consensus_state.verify_transaction_or_receipt(
+            black_box(outcome_with_id),
+            &proofs.outcome_proof,
+            &proofs.outcome_root_proof,
+            &block_lite_view,
+            &block_proof,
+        )
+    };
+    assert!(verify().is_ok());
+    c.bench_function("verify_transaction_or_receipt", |b| b.iter(verify));
+}
+
+criterion_group!(
+    benches,
+    header_verification,
+    membership_proofs,
+    merklize_chunk_roots,
+    outcome_verification
+);
+criterion_main!(benches);
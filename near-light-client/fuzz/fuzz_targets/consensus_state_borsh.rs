@@ -0,0 +1,22 @@
+//! Deserialize consensus states from arbitrary bytes, checking the round trip and that the
+//! block producers kept in their borsh serialization can be decoded.
+
+#![no_main]
+
+use borsh::BorshDeserialize;
+use libfuzzer_sys::fuzz_target;
+use near_light_client::types::ConsensusState;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(state) = ConsensusState::try_from_slice(data) {
+        assert_eq!(borsh::to_vec(&state).unwrap(), data);
+        for epoch_id in [state.header.epoch_id(), state.header.next_epoch_id()] {
+            if let Some(bps) = state.get_block_producers_of(&epoch_id) {
+                assert_eq!(
+                    Some(bps.len()),
+                    state.block_producers_of(&epoch_id).map(|bps| bps.len())
+                );
+            }
+        }
+    }
+});
@@ -0,0 +1,16 @@
+//! Deserialize public keys and signatures from arbitrary bytes, checking the round trip.
+
+#![no_main]
+
+use borsh::BorshDeserialize;
+use libfuzzer_sys::fuzz_target;
+use near_light_client::near_types::signature::{PublicKey, Signature};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(public_key) = PublicKey::try_from_slice(data) {
+        assert_eq!(borsh::to_vec(&public_key).unwrap(), data);
+    }
+    if let Ok(signature) = Signature::try_from_slice(data) {
+        assert_eq!(borsh::to_vec(&signature).unwrap(), data);
+    }
+});
@@ -0,0 +1,45 @@
+//! Verify random sequences of trie nodes against random keys, with the state root of the
+//! first node so that the verification walks into the nodes.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use near_light_client::near_types::trie::{
+    decode_state_proof, verify_not_in_state_by_hash, verify_not_in_state_data,
+    verify_state_proof_by_hash, verify_state_proof_data, RawTrieNodeWithSize, StateProof,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    key: Vec<u8>,
+    value: Vec<u8>,
+    nodes: Vec<RawTrieNodeWithSize>,
+    /// Items of the proof data which may not be trie nodes.
+    others: Vec<Vec<u8>>,
+}
+
+fuzz_target!(|input: Input| {
+    let Some(root) = input.nodes.first().map(RawTrieNodeWithSize::hash) else {
+        return;
+    };
+    let proofs = input
+        .nodes
+        .iter()
+        .map(RawTrieNodeWithSize::encode)
+        .chain(input.others)
+        .collect::<Vec<_>>();
+
+    let membership = verify_state_proof_data(&input.key, &proofs, &input.value, &root);
+    let non_membership = verify_not_in_state_data(&input.key, &proofs, &root);
+    assert!(membership.is_err() || non_membership.is_err());
+
+    if let Ok(nodes) = decode_state_proof(&proofs) {
+        let _ = verify_state_proof_by_hash(&input.key, &nodes, &input.value, &root);
+        let _ = verify_not_in_state_by_hash(&input.key, &nodes, &root);
+    }
+    if let Ok(proof) = StateProof::new(&proofs) {
+        let _ = proof.verify_membership(&input.key, &input.value, &root);
+        let _ = proof.verify_non_membership(&input.key, &root);
+    }
+});
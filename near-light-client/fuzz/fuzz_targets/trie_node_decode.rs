@@ -0,0 +1,14 @@
+//! Decode trie nodes from arbitrary bytes, which only succeeds for their canonical encoding
+//! with `decode_strict`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use near_light_client::near_types::trie::RawTrieNodeWithSize;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = RawTrieNodeWithSize::decode(data);
+    if let Ok(node) = RawTrieNodeWithSize::decode_strict(data) {
+        assert_eq!(node.encode(), data);
+    }
+});
@@ -0,0 +1,81 @@
+//! CBOR encoding of the light client datatypes, for transports preferring a self-describing
+//! compact encoding.
+//!
+//! The encoding follows the `serde` implementations of the types, in which hashes, public keys
+//! and signatures are represented the same as the JSON rpc of NEAR.
+
+use alloc::{string::String, vec::Vec};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    commitment::CommitmentProof,
+    evm_abi::MembershipProofBundle,
+    types::{
+        ConsensusState, Header, HistoricalMembershipProof, MembershipProof, NonMembershipProof,
+        OutcomeProof,
+    },
+    witness::HeaderWitness,
+};
+
+/// Error type for decoding CBOR encoded data.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum CborDecodingError {
+    UnexpectedEnd,
+    /// The data is not well-formed CBOR at `offset`.
+    Syntax {
+        offset: usize,
+    },
+    /// The data is well-formed CBOR but does not match the type.
+    Semantic {
+        offset: Option<usize>,
+        message: String,
+    },
+    RecursionLimitExceeded,
+}
+
+/// Types encodable in CBOR.
+pub trait CborEncoding: Serialize + DeserializeOwned {
+    /// Encode the value in CBOR.
+    fn to_cbor(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(self, &mut bytes)
+            .expect("Should not fail based on writing to a `Vec`.");
+        bytes
+    }
+
+    /// Decode a value from CBOR encoded `bytes`.
+    fn from_cbor(bytes: &[u8]) -> Result<Self, CborDecodingError> {
+        ciborium::de::from_reader(bytes).map_err(|error| match error {
+            ciborium::de::Error::Io(_) => CborDecodingError::UnexpectedEnd,
+            ciborium::de::Error::Syntax(offset) => CborDecodingError::Syntax { offset },
+            ciborium::de::Error::Semantic(offset, message) => {
+                CborDecodingError::Semantic { offset, message }
+            }
+            ciborium::de::Error::RecursionLimitExceeded => {
+                CborDecodingError::RecursionLimitExceeded
+            }
+        })
+    }
+}
+
+impl CborEncoding for Header {}
+
+impl CborEncoding for ConsensusState {}
+
+impl CborEncoding for CommitmentProof {}
+
+impl CborEncoding for MembershipProofBundle {}
+
+impl CborEncoding for HeaderWitness {}
+
+impl CborEncoding for MembershipProof {}
+
+impl CborEncoding for NonMembershipProof {}
+
+impl CborEncoding for OutcomeProof {}
+
+impl CborEncoding for HistoricalMembershipProof {}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(CborDecodingError);
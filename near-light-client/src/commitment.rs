@@ -0,0 +1,127 @@
+//! An ICS-23-style adapter of NEAR state proofs.
+//!
+//! A [`CommitmentProof`] carries everything needed to verify a storage key of a contract
+//! against a state root, so that it can be passed around as an opaque commitment proof
+//! and checked with the same `verify_membership(prefix, path, value)` shape as ICS-23.
+
+use alloc::vec::Vec;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{
+    near_types::{
+        hash::CryptoHash,
+        trie::{verify_not_in_state_data, verify_state_proof_data},
+    },
+    CommitmentProofVerificationError,
+};
+
+/// A self-describing proof of the value (or absence) of a storage key in a state trie.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct CommitmentProof {
+    /// The full trie key, which is the commitment prefix followed by the path
+    pub key: Vec<u8>,
+    /// The value of the key, `None` if this is a proof of absence
+    pub value: Option<Vec<u8>>,
+    /// The proof data returned by the `view_state` rpc of NEAR
+    pub proof: Vec<Vec<u8>>,
+    /// The state root the proof is built on
+    pub root: CryptoHash,
+}
+
+impl CommitmentProof {
+    /// Create a proof that `key` has `value` in the state with `root`.
+    pub fn new_existence(
+        key: Vec<u8>,
+        value: Vec<u8>,
+        proof: Vec<Vec<u8>>,
+        root: CryptoHash,
+    ) -> Self {
+        Self {
+            key,
+            value: Some(value),
+            proof,
+            root,
+        }
+    }
+
+    /// Create a proof that `key` has no value in the state with `root`.
+    pub fn new_non_existence(key: Vec<u8>, proof: Vec<Vec<u8>>, root: CryptoHash) -> Self {
+        Self {
+            key,
+            value: None,
+            proof,
+            root,
+        }
+    }
+
+    /// Verify the proof data is consistent with the key, value and root carried by the proof.
+    pub fn verify(&self) -> Result<(), CommitmentProofVerificationError> {
+        match &self.value {
+            Some(value) => verify_state_proof_data(&self.key, &self.proof, value, &self.root)?,
+            None => verify_not_in_state_data(&self.key, &self.proof, &self.root)?,
+        }
+        Ok(())
+    }
+
+    /// Verify that `prefix` followed by `path` has `value` in the state with the trusted `root`.
+    pub fn verify_membership(
+        &self,
+        root: &CryptoHash,
+        prefix: &[u8],
+        path: &[u8],
+        value: &[u8],
+    ) -> Result<(), CommitmentProofVerificationError> {
+        self.check_root_and_key(root, prefix, path)?;
+        match &self.value {
+            Some(proved_value) if proved_value.as_slice() == value => self.verify(),
+            Some(_) => Err(CommitmentProofVerificationError::ValueMismatch),
+            None => Err(CommitmentProofVerificationError::ExistenceProofExpected),
+        }
+    }
+
+    /// Verify that `prefix` followed by `path` has no value in the state with the trusted `root`.
+    pub fn verify_non_membership(
+        &self,
+        root: &CryptoHash,
+        prefix: &[u8],
+        path: &[u8],
+    ) -> Result<(), CommitmentProofVerificationError> {
+        self.check_root_and_key(root, prefix, path)?;
+        if self.value.is_some() {
+            return Err(CommitmentProofVerificationError::NonExistenceProofExpected);
+        }
+        self.verify()
+    }
+
+    fn check_root_and_key(
+        &self,
+        root: &CryptoHash,
+        prefix: &[u8],
+        path: &[u8],
+    ) -> Result<(), CommitmentProofVerificationError> {
+        if self.root != *root {
+            return Err(CommitmentProofVerificationError::RootMismatch {
+                expected: *root,
+                actual: self.root,
+            });
+        }
+        if self.key.len() != prefix.len() + path.len()
+            || !self.key.starts_with(prefix)
+            || !self.key.ends_with(path)
+        {
+            return Err(CommitmentProofVerificationError::KeyMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(CommitmentProof);
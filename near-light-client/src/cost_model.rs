@@ -0,0 +1,100 @@
+//! Accounting of the costly operations performed by verification functions.
+//!
+//! The operations are counted into the context of the innermost [`measure`] running on
+//! the current thread, so that verifications running on other threads at the same time
+//! are not counted. The signature verifications are counted on the calling thread of the
+//! [`SignatureVerifier`], which includes those of custom verifiers and those run in parallel
+//! with feature `rayon`.
+
+use crate::near_types::signature::{PublicKey, Signature, SignatureVerifier};
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::cell::Cell;
+
+std::thread_local! {
+    static CURRENT_COST: Cell<VerificationCost> = Cell::new(VerificationCost::default());
+}
+
+/// The costly operations performed by a verification call.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct VerificationCost {
+    /// Number of sha256 invocations.
+    pub hash_invocations: u64,
+    /// Total length of the data hashed by sha256.
+    pub bytes_hashed: u64,
+    /// Number of ed25519 signature verifications.
+    pub signature_verifications: u64,
+}
+
+impl VerificationCost {
+    fn add(self, other: Self) -> Self {
+        Self {
+            hash_invocations: self.hash_invocations + other.hash_invocations,
+            bytes_hashed: self.bytes_hashed + other.bytes_hashed,
+            signature_verifications: self.signature_verifications + other.signature_verifications,
+        }
+    }
+}
+
+/// Run `f` and return its result alongside the cost of the operations it performed on
+/// the current thread.
+///
+/// The cost is also counted into the enclosing `measure`, if any.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, VerificationCost) {
+    let outer = CURRENT_COST.with(|cost| cost.replace(VerificationCost::default()));
+    let result = f();
+    let cost = CURRENT_COST.with(|cost| {
+        let measured = cost.get();
+        cost.set(outer.add(measured));
+        measured
+    });
+    (result, cost)
+}
+
+fn record(f: impl FnOnce(&mut VerificationCost)) {
+    CURRENT_COST.with(|cost| {
+        let mut current = cost.get();
+        f(&mut current);
+        cost.set(current);
+    });
+}
+
+pub(crate) fn record_hash(data_len: usize) {
+    record(|cost| {
+        cost.hash_invocations += 1;
+        cost.bytes_hashed += data_len as u64;
+    });
+}
+
+/// Wrapper of a [`SignatureVerifier`] counting its verifications, which are recorded on
+/// the thread dropping the wrapper.
+pub(crate) struct CountingVerifier<'a> {
+    verifier: &'a dyn SignatureVerifier,
+    verifications: AtomicU64,
+}
+
+impl<'a> CountingVerifier<'a> {
+    pub(crate) fn new(verifier: &'a dyn SignatureVerifier) -> Self {
+        Self {
+            verifier,
+            verifications: AtomicU64::new(0),
+        }
+    }
+}
+
+impl SignatureVerifier for CountingVerifier<'_> {
+    fn verify(&self, signature: &Signature, data: &[u8], public_key: &PublicKey) -> bool {
+        self.verifications.fetch_add(1, Ordering::Relaxed);
+        self.verifier.verify(signature, data, public_key)
+    }
+}
+
+impl Drop for CountingVerifier<'_> {
+    fn drop(&mut self) {
+        let verifications = *self.verifications.get_mut();
+        record(|cost| cost.signature_verifications += verifications);
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(VerificationCost);
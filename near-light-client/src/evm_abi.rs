@@ -0,0 +1,255 @@
+//! EVM ABI encoding of membership proofs.
+//!
+//! A [`MembershipProofBundle`] is encoded exactly as `abi.encode` encodes the arguments of
+//! the following Solidity declaration, so that a NEAR verifier contract can decode it with
+//! `abi.decode(data, (bytes, bytes, bytes[], bytes32, NearBlockHeader))`:
+//!
+//! ```solidity
+//! struct NearBlockHeader {
+//!     uint64 height;
+//!     bytes32 epochId;
+//!     bytes32 nextEpochId;
+//!     bytes32 prevStateRoot;
+//!     bytes32 outcomeRoot;
+//!     uint64 timestamp;
+//!     bytes32 nextBpHash;
+//!     bytes32 blockMerkleRoot;
+//!     bytes32 innerRestHash;
+//!     bytes32 prevBlockHash;
+//! }
+//! ```
+//!
+//! The header fields are enough to recompute the block hash on chain.
+
+use alloc::vec::Vec;
+
+use crate::near_types::{hash::CryptoHash, BlockHeaderInnerLite, EpochId, LightClientBlock};
+
+const WORD: usize = 32;
+/// Number of words of the static `NearBlockHeader` tuple.
+const HEADER_WORDS: usize = 10;
+/// Number of words of the head of the encoded bundle.
+const HEAD_WORDS: usize = 4 + HEADER_WORDS;
+
+/// Error type for decoding an ABI encoded [`MembershipProofBundle`].
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum EvmAbiDecodingError {
+    /// The data ends before the word at `offset`.
+    UnexpectedEnd { offset: usize },
+    /// The word at `offset` is not a valid offset or length.
+    InvalidOffset { offset: usize },
+    /// The word at `offset` has non-zero bytes out of the range of its type.
+    InvalidPadding { offset: usize },
+}
+
+/// The fields of the header of the block whose chunk contains the proven state.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvmHeaderFields {
+    pub height: u64,
+    pub epoch_id: CryptoHash,
+    pub next_epoch_id: CryptoHash,
+    pub prev_state_root: CryptoHash,
+    pub outcome_root: CryptoHash,
+    pub timestamp: u64,
+    pub next_bp_hash: CryptoHash,
+    pub block_merkle_root: CryptoHash,
+    pub inner_rest_hash: CryptoHash,
+    pub prev_block_hash: CryptoHash,
+}
+
+impl From<&LightClientBlock> for EvmHeaderFields {
+    fn from(block: &LightClientBlock) -> Self {
+        let inner_lite = &block.inner_lite;
+        Self {
+            height: inner_lite.height,
+            epoch_id: inner_lite.epoch_id.0,
+            next_epoch_id: inner_lite.next_epoch_id.0,
+            prev_state_root: inner_lite.prev_state_root,
+            outcome_root: inner_lite.outcome_root,
+            timestamp: inner_lite.timestamp,
+            next_bp_hash: inner_lite.next_bp_hash,
+            block_merkle_root: inner_lite.block_merkle_root,
+            inner_rest_hash: block.inner_rest_hash,
+            prev_block_hash: block.prev_block_hash,
+        }
+    }
+}
+
+impl EvmHeaderFields {
+    /// Returns the `inner_lite` of the block built from the fields.
+    pub fn inner_lite(&self) -> BlockHeaderInnerLite {
+        BlockHeaderInnerLite {
+            height: self.height,
+            epoch_id: EpochId(self.epoch_id),
+            next_epoch_id: EpochId(self.next_epoch_id),
+            prev_state_root: self.prev_state_root,
+            outcome_root: self.outcome_root,
+            timestamp: self.timestamp,
+            next_bp_hash: self.next_bp_hash,
+            block_merkle_root: self.block_merkle_root,
+        }
+    }
+}
+
+/// A proof of the value of a storage key, bundled with the header it is verified against.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MembershipProofBundle {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// The proof data returned by the `view_state` rpc of NEAR
+    pub proof: Vec<Vec<u8>>,
+    /// The state root of the chunk the proof is built on
+    pub chunk_root: CryptoHash,
+    pub header: EvmHeaderFields,
+}
+
+impl MembershipProofBundle {
+    /// Encode the bundle in the EVM ABI layout described in the module documentation.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let mut head = Vec::with_capacity(HEAD_WORDS * WORD);
+        let mut tail = Vec::new();
+
+        push_uint(&mut head, (HEAD_WORDS * WORD + tail.len()) as u64);
+        push_bytes(&mut tail, &self.key);
+        push_uint(&mut head, (HEAD_WORDS * WORD + tail.len()) as u64);
+        push_bytes(&mut tail, &self.value);
+        push_uint(&mut head, (HEAD_WORDS * WORD + tail.len()) as u64);
+        push_bytes_array(&mut tail, &self.proof);
+        head.extend_from_slice(&self.chunk_root.0);
+
+        let header = &self.header;
+        push_uint(&mut head, header.height);
+        head.extend_from_slice(&header.epoch_id.0);
+        head.extend_from_slice(&header.next_epoch_id.0);
+        head.extend_from_slice(&header.prev_state_root.0);
+        head.extend_from_slice(&header.outcome_root.0);
+        push_uint(&mut head, header.timestamp);
+        head.extend_from_slice(&header.next_bp_hash.0);
+        head.extend_from_slice(&header.block_merkle_root.0);
+        head.extend_from_slice(&header.inner_rest_hash.0);
+        head.extend_from_slice(&header.prev_block_hash.0);
+
+        head.extend(tail);
+        head
+    }
+
+    /// Decode a bundle encoded by [`MembershipProofBundle::abi_encode`].
+    pub fn abi_decode(data: &[u8]) -> Result<Self, EvmAbiDecodingError> {
+        let header_word = |index: usize| 4 + index;
+        Ok(Self {
+            key: read_bytes(data, read_offset(data, 0, 0)?)?,
+            value: read_bytes(data, read_offset(data, 0, WORD)?)?,
+            proof: read_bytes_array(data, read_offset(data, 0, 2 * WORD)?)?,
+            chunk_root: read_hash(data, 3 * WORD)?,
+            header: EvmHeaderFields {
+                height: read_uint(data, header_word(0) * WORD)?,
+                epoch_id: read_hash(data, header_word(1) * WORD)?,
+                next_epoch_id: read_hash(data, header_word(2) * WORD)?,
+                prev_state_root: read_hash(data, header_word(3) * WORD)?,
+                outcome_root: read_hash(data, header_word(4) * WORD)?,
+                timestamp: read_uint(data, header_word(5) * WORD)?,
+                next_bp_hash: read_hash(data, header_word(6) * WORD)?,
+                block_merkle_root: read_hash(data, header_word(7) * WORD)?,
+                inner_rest_hash: read_hash(data, header_word(8) * WORD)?,
+                prev_block_hash: read_hash(data, header_word(9) * WORD)?,
+            },
+        })
+    }
+}
+
+fn push_uint(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&[0u8; WORD - 8]);
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    push_uint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+    buf.resize(buf.len() + padding_of(bytes.len()), 0);
+}
+
+fn push_bytes_array(buf: &mut Vec<u8>, items: &[Vec<u8>]) {
+    push_uint(buf, items.len() as u64);
+    // The offsets of the items are relative to the first word after the length.
+    let mut offset = items.len() * WORD;
+    for item in items {
+        push_uint(buf, offset as u64);
+        offset += WORD + item.len() + padding_of(item.len());
+    }
+    for item in items {
+        push_bytes(buf, item);
+    }
+}
+
+fn padding_of(len: usize) -> usize {
+    (WORD - len % WORD) % WORD
+}
+
+fn read_word(data: &[u8], offset: usize) -> Result<&[u8], EvmAbiDecodingError> {
+    offset
+        .checked_add(WORD)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(EvmAbiDecodingError::UnexpectedEnd { offset })
+}
+
+fn read_uint(data: &[u8], offset: usize) -> Result<u64, EvmAbiDecodingError> {
+    let word = read_word(data, offset)?;
+    if word[..WORD - 8].iter().any(|byte| *byte != 0) {
+        return Err(EvmAbiDecodingError::InvalidPadding { offset });
+    }
+    Ok(u64::from_be_bytes(
+        word[WORD - 8..]
+            .try_into()
+            .expect("Should not fail based on the length of a word."),
+    ))
+}
+
+fn read_hash(data: &[u8], offset: usize) -> Result<CryptoHash, EvmAbiDecodingError> {
+    read_word(data, offset).map(|word| {
+        CryptoHash::try_from(word).expect("Should not fail based on the length of a word.")
+    })
+}
+
+/// Read the offset at `offset`, which is relative to `base`, and return it as an absolute one.
+fn read_offset(data: &[u8], base: usize, offset: usize) -> Result<usize, EvmAbiDecodingError> {
+    let offset = base + offset;
+    let relative = read_uint(data, offset)?;
+    usize::try_from(relative)
+        .ok()
+        .and_then(|relative| relative.checked_add(base))
+        .filter(|absolute| *absolute <= data.len())
+        .ok_or(EvmAbiDecodingError::InvalidOffset { offset })
+}
+
+fn read_bytes(data: &[u8], offset: usize) -> Result<Vec<u8>, EvmAbiDecodingError> {
+    let len = read_offset(data, 0, offset)?;
+    let start = offset + WORD;
+    let bytes = start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .ok_or(EvmAbiDecodingError::InvalidOffset { offset })?;
+    let padding = start + len..start + len + padding_of(len);
+    match data.get(padding) {
+        Some(padding) if padding.iter().all(|byte| *byte == 0) => Ok(bytes.to_vec()),
+        Some(_) => Err(EvmAbiDecodingError::InvalidPadding { offset }),
+        None => Err(EvmAbiDecodingError::UnexpectedEnd { offset }),
+    }
+}
+
+fn read_bytes_array(data: &[u8], offset: usize) -> Result<Vec<Vec<u8>>, EvmAbiDecodingError> {
+    let len = read_offset(data, 0, offset)?;
+    let base = offset + WORD;
+    (0..len)
+        .map(|index| read_bytes(data, read_offset(data, base, index * WORD)?))
+        .collect()
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(EvmAbiDecodingError, EvmHeaderFields, MembershipProofBundle);
@@ -0,0 +1,464 @@
+//! Implementations of the `ibc-rs` client traits for NEAR light client.
+//!
+//! The heights of the light client are mapped to IBC heights with the same revision number
+//! and revision height, which is [`NEAR_REVISION_NUMBER`] for NEAR blocks. The commitment root of a consensus state is the
+//! concatenation of `prev_state_root_of_chunks` of its header, and a commitment proof
+//! is a borsh serialized [`CommitmentProof`] built on one of these state roots.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use ibc::core::client::context::client_state::{
+    ClientStateCommon, ClientStateExecution, ClientStateValidation,
+};
+use ibc::core::client::context::consensus_state::ConsensusState as IbcConsensusStateTrait;
+use ibc::core::client::context::{ClientExecutionContext, ClientValidationContext};
+use ibc::core::client::types::error::ClientError;
+use ibc::core::client::types::{Height as IbcHeight, Status};
+use ibc::core::commitment_types::commitment::{
+    CommitmentPrefix, CommitmentProofBytes, CommitmentRoot,
+};
+use ibc::core::host::types::identifiers::{ClientId, ClientType};
+use ibc::core::host::types::path::{ClientConsensusStatePath, ClientStatePath, Path};
+use ibc::primitives::Timestamp;
+use ibc_proto::google::protobuf::Any;
+use prost::Message;
+
+use crate::{
+    commitment::CommitmentProof,
+    near_types::hash::CryptoHash,
+    proto::v1,
+    types::{ClientState, ConsensusState, Header, Height, Misbehaviour},
+    BasicNearLightClient, TrustedConsensusState,
+};
+
+/// The client type of NEAR light client.
+pub const NEAR_CLIENT_TYPE: &str = "15-near";
+pub use crate::types::NEAR_REVISION_NUMBER;
+
+pub const CLIENT_STATE_TYPE_URL: &str = "/near.lightclient.v1.ClientState";
+pub const CONSENSUS_STATE_TYPE_URL: &str = "/near.lightclient.v1.ConsensusState";
+pub const HEADER_TYPE_URL: &str = "/near.lightclient.v1.Header";
+pub const MISBEHAVIOUR_TYPE_URL: &str = "/near.lightclient.v1.Misbehaviour";
+
+/// Context of the host chain required to validate client messages of NEAR light client.
+pub trait NearClientValidationContext {
+    /// Returns the consensus state of the client at the given height.
+    fn consensus_state(
+        &self,
+        client_id: &ClientId,
+        height: Height,
+    ) -> Result<ConsensusState, ClientError>;
+
+    /// Returns the current timestamp of the host chain.
+    fn host_timestamp(&self) -> Result<Timestamp, ClientError>;
+}
+
+/// The consensus state of NEAR light client along with its commitment root.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct IbcConsensusState {
+    pub consensus_state: ConsensusState,
+    root: CommitmentRoot,
+}
+
+impl From<ConsensusState> for IbcConsensusState {
+    fn from(consensus_state: ConsensusState) -> Self {
+        let root = CommitmentRoot::from_bytes(
+            &consensus_state
+                .header
+                .prev_state_root_of_chunks
+                .iter()
+                .flat_map(|hash| hash.0)
+                .collect::<Vec<u8>>(),
+        );
+        Self {
+            consensus_state,
+            root,
+        }
+    }
+}
+
+impl IbcConsensusStateTrait for IbcConsensusState {
+    fn root(&self) -> &CommitmentRoot {
+        &self.root
+    }
+
+    fn timestamp(&self) -> Timestamp {
+        Timestamp::from_nanoseconds(
+            self.consensus_state
+                .header
+                .light_client_block
+                .inner_lite
+                .timestamp,
+        )
+        .expect("Should not fail as any u64 is a valid timestamp.")
+    }
+}
+
+/// Convert the height of a NEAR block into an IBC height.
+pub fn to_ibc_height(height: Height) -> Result<IbcHeight, ClientError> {
+    IbcHeight::new(height.revision_number, height.revision_height)
+}
+
+/// Convert an IBC height into the height of a NEAR block.
+pub fn from_ibc_height(height: &IbcHeight) -> Height {
+    Height::new(height.revision_number(), height.revision_height())
+}
+
+/// Build the raw trie key of the `path` under the commitment `prefix`.
+///
+/// See [`crate::ibc_paths`] for the layout of the state of an IBC host contract on NEAR.
+pub fn commitment_key(prefix: &CommitmentPrefix, path: &Path) -> Vec<u8> {
+    crate::ibc_paths::path_key(prefix.as_bytes(), &path.to_string())
+}
+
+fn other_error(description: String) -> ClientError {
+    ClientError::Other { description }
+}
+
+fn decode_any<M, T>(any: Any, type_url: &str) -> Result<T, ClientError>
+where
+    M: Message + Default,
+    T: TryFrom<M>,
+    T::Error: core::fmt::Debug,
+{
+    if any.type_url != type_url {
+        return Err(other_error(format!(
+            "unexpected type url {}, expected {}",
+            any.type_url, type_url
+        )));
+    }
+    let message = M::decode(any.value.as_slice()).map_err(|e| other_error(e.to_string()))?;
+    T::try_from(message).map_err(|e| other_error(format!("{:?}", e)))
+}
+
+impl From<ClientState> for Any {
+    fn from(value: ClientState) -> Self {
+        Any {
+            type_url: CLIENT_STATE_TYPE_URL.to_string(),
+            value: v1::ClientState::from(value).encode_to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Any> for ClientState {
+    type Error = ClientError;
+
+    fn try_from(any: Any) -> Result<Self, Self::Error> {
+        decode_any::<v1::ClientState, _>(any, CLIENT_STATE_TYPE_URL)
+    }
+}
+
+impl From<IbcConsensusState> for Any {
+    fn from(value: IbcConsensusState) -> Self {
+        Any {
+            type_url: CONSENSUS_STATE_TYPE_URL.to_string(),
+            value: v1::ConsensusState::from(value.consensus_state).encode_to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Any> for IbcConsensusState {
+    type Error = ClientError;
+
+    fn try_from(any: Any) -> Result<Self, Self::Error> {
+        decode_any::<v1::ConsensusState, ConsensusState>(any, CONSENSUS_STATE_TYPE_URL)
+            .map(Into::into)
+    }
+}
+
+impl From<Header> for Any {
+    fn from(value: Header) -> Self {
+        Any {
+            type_url: HEADER_TYPE_URL.to_string(),
+            value: v1::Header::from(value).encode_to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Any> for Header {
+    type Error = ClientError;
+
+    fn try_from(any: Any) -> Result<Self, Self::Error> {
+        decode_any::<v1::Header, _>(any, HEADER_TYPE_URL)
+    }
+}
+
+impl From<Misbehaviour> for Any {
+    fn from(value: Misbehaviour) -> Self {
+        Any {
+            type_url: MISBEHAVIOUR_TYPE_URL.to_string(),
+            value: v1::Misbehaviour::from(value).encode_to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Any> for Misbehaviour {
+    type Error = ClientError;
+
+    fn try_from(any: Any) -> Result<Self, Self::Error> {
+        decode_any::<v1::Misbehaviour, _>(any, MISBEHAVIOUR_TYPE_URL)
+    }
+}
+
+/// Returns the consensus state to store for `header`, which is verified with `trusted`.
+fn next_consensus_state(trusted: &ConsensusState, header: Header) -> ConsensusState {
+    trusted.next(header)
+}
+
+/// Decode the commitment proof in `proof` and check it is built on a state root in `root`.
+fn decode_commitment_proof(
+    proof: &CommitmentProofBytes,
+    root: &CommitmentRoot,
+) -> Result<CommitmentProof, ClientError> {
+    let proof: CommitmentProof = borsh::from_slice(&Vec::<u8>::from(proof.clone()))
+        .map_err(|e| other_error(format!("invalid commitment proof: {}", e)))?;
+    if !root
+        .as_bytes()
+        .chunks(32)
+        .filter_map(|bytes| CryptoHash::try_from(bytes).ok())
+        .any(|state_root| state_root == proof.root)
+    {
+        return Err(other_error(
+            "commitment proof is not built on the root".to_string(),
+        ));
+    }
+    Ok(proof)
+}
+
+impl ClientStateCommon for ClientState {
+    fn verify_consensus_state(&self, consensus_state: Any) -> Result<(), ClientError> {
+        IbcConsensusState::try_from(consensus_state).map(|_| ())
+    }
+
+    fn client_type(&self) -> ClientType {
+        ClientType::new(NEAR_CLIENT_TYPE).expect("Should not fail as the client type is valid.")
+    }
+
+    fn latest_height(&self) -> IbcHeight {
+        to_ibc_height(self.latest_height).expect("Should not fail as the height is not zero.")
+    }
+
+    fn validate_proof_height(&self, proof_height: IbcHeight) -> Result<(), ClientError> {
+        let latest_height = ClientStateCommon::latest_height(self);
+        if latest_height < proof_height {
+            return Err(ClientError::InvalidProofHeight {
+                latest_height,
+                proof_height,
+            });
+        }
+        Ok(())
+    }
+
+    fn verify_upgrade_client(
+        &self,
+        _upgraded_client_state: Any,
+        _upgraded_consensus_state: Any,
+        _proof_upgrade_client: CommitmentProofBytes,
+        _proof_upgrade_consensus_state: CommitmentProofBytes,
+        _root: &CommitmentRoot,
+    ) -> Result<(), ClientError> {
+        Err(other_error(
+            "upgrading NEAR light client is not supported".to_string(),
+        ))
+    }
+
+    fn verify_membership(
+        &self,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        root: &CommitmentRoot,
+        path: Path,
+        value: Vec<u8>,
+    ) -> Result<(), ClientError> {
+        let proof = decode_commitment_proof(proof, root)?;
+        proof
+            .verify_membership(
+                &proof.root,
+                prefix.as_bytes(),
+                path.to_string().as_bytes(),
+                &value,
+            )
+            .map_err(|e| other_error(format!("{:?}", e)))
+    }
+
+    fn verify_non_membership(
+        &self,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        root: &CommitmentRoot,
+        path: Path,
+    ) -> Result<(), ClientError> {
+        let proof = decode_commitment_proof(proof, root)?;
+        proof
+            .verify_non_membership(&proof.root, prefix.as_bytes(), path.to_string().as_bytes())
+            .map_err(|e| other_error(format!("{:?}", e)))
+    }
+}
+
+impl<V> ClientStateValidation<V> for ClientState
+where
+    V: ClientValidationContext + NearClientValidationContext,
+{
+    fn verify_client_message(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        client_message: Any,
+    ) -> Result<(), ClientError> {
+        let trusted = TrustedConsensusState::new(NearClientValidationContext::consensus_state(
+            ctx,
+            client_id,
+            self.latest_height,
+        )?);
+        // The kind of the client message is told by its type url.
+        if client_message.type_url == MISBEHAVIOUR_TYPE_URL {
+            trusted
+                .verify_misbehaviour(&Misbehaviour::try_from(client_message)?)
+                .map_err(|e| other_error(format!("{:?}", e)))
+        } else {
+            trusted
+                .verify_header(&Header::try_from(client_message)?)
+                .map_err(|e| other_error(format!("{:?}", e)))
+        }
+    }
+
+    fn check_for_misbehaviour(
+        &self,
+        _ctx: &V,
+        _client_id: &ClientId,
+        client_message: Any,
+    ) -> Result<bool, ClientError> {
+        // A misbehaviour message has been verified by `verify_client_message` already.
+        Ok(client_message.type_url == MISBEHAVIOUR_TYPE_URL)
+    }
+
+    fn status(&self, ctx: &V, client_id: &ClientId) -> Result<Status, ClientError> {
+        if self.frozen_height.is_some() {
+            return Ok(Status::Frozen);
+        }
+        let latest_consensus_state =
+            NearClientValidationContext::consensus_state(ctx, client_id, self.latest_height)?;
+        let latest_timestamp = latest_consensus_state
+            .header
+            .light_client_block
+            .inner_lite
+            .timestamp;
+        let host_timestamp = ctx.host_timestamp()?.nanoseconds();
+        if latest_timestamp.saturating_add(self.trusting_period) < host_timestamp {
+            return Ok(Status::Expired);
+        }
+        Ok(Status::Active)
+    }
+
+    fn check_substitute(&self, _ctx: &V, _substitute_client_state: Any) -> Result<(), ClientError> {
+        Err(other_error(
+            "recovering NEAR light client is not supported".to_string(),
+        ))
+    }
+}
+
+impl<E> ClientStateExecution<E> for ClientState
+where
+    E: ClientExecutionContext + NearClientValidationContext,
+    <E as ClientValidationContext>::ClientStateRef: From<ClientState>,
+    <E as ClientValidationContext>::ConsensusStateRef: From<IbcConsensusState>,
+{
+    fn initialise(
+        &self,
+        ctx: &mut E,
+        client_id: &ClientId,
+        consensus_state: Any,
+    ) -> Result<(), ClientError> {
+        let consensus_state = IbcConsensusState::try_from(consensus_state)?;
+        ctx.store_client_state(ClientStatePath::new(client_id.clone()), self.clone().into())
+            .map_err(|e| other_error(e.to_string()))?;
+        ctx.store_consensus_state(
+            ClientConsensusStatePath::new(
+                client_id.clone(),
+                self.latest_height.revision_number,
+                self.latest_height.revision_height,
+            ),
+            consensus_state.into(),
+        )
+        .map_err(|e| other_error(e.to_string()))
+    }
+
+    fn update_state(
+        &self,
+        ctx: &mut E,
+        client_id: &ClientId,
+        header: Any,
+    ) -> Result<Vec<IbcHeight>, ClientError> {
+        let header = Header::try_from(header)?;
+        let trusted =
+            NearClientValidationContext::consensus_state(ctx, client_id, self.latest_height)?;
+        let height = header.height();
+        let ibc_height = to_ibc_height(height)?;
+        let consensus_state = next_consensus_state(&trusted, header);
+        ctx.store_consensus_state(
+            ClientConsensusStatePath::new(
+                client_id.clone(),
+                height.revision_number,
+                height.revision_height,
+            ),
+            IbcConsensusState::from(consensus_state).into(),
+        )
+        .map_err(|e| other_error(e.to_string()))?;
+        if height > self.latest_height {
+            let client_state = ClientState {
+                latest_height: height,
+                ..self.clone()
+            };
+            ctx.store_client_state(ClientStatePath::new(client_id.clone()), client_state.into())
+                .map_err(|e| other_error(e.to_string()))?;
+        }
+        Ok(vec![ibc_height])
+    }
+
+    fn update_state_on_misbehaviour(
+        &self,
+        ctx: &mut E,
+        client_id: &ClientId,
+        _client_message: Any,
+    ) -> Result<(), ClientError> {
+        let client_state = ClientState {
+            frozen_height: Some(self.latest_height),
+            ..self.clone()
+        };
+        ctx.store_client_state(ClientStatePath::new(client_id.clone()), client_state.into())
+            .map_err(|e| other_error(e.to_string()))
+    }
+
+    fn update_state_on_upgrade(
+        &self,
+        _ctx: &mut E,
+        _client_id: &ClientId,
+        _upgraded_client_state: Any,
+        _upgraded_consensus_state: Any,
+    ) -> Result<IbcHeight, ClientError> {
+        Err(other_error(
+            "upgrading NEAR light client is not supported".to_string(),
+        ))
+    }
+
+    fn update_on_recovery(
+        &self,
+        _ctx: &mut E,
+        _subject_client_id: &ClientId,
+        _substitute_client_state: Any,
+        _substitute_consensus_state: Any,
+    ) -> Result<(), ClientError> {
+        Err(other_error(
+            "recovering NEAR light client is not supported".to_string(),
+        ))
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(IbcConsensusState);
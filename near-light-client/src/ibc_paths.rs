@@ -0,0 +1,78 @@
+//! Helpers to construct the raw trie keys of the state stored by an IBC host contract on NEAR.
+//!
+//! The host contract is expected to write the value of every ICS-24 path directly into its
+//! storage, under the key of its store prefix followed by the path string. So the commitment
+//! prefix of the host (as registered in the connection of the counterparty) is the raw trie
+//! prefix of the contract data with the store prefix, and the raw trie key of a path is the
+//! commitment prefix followed by the path, as expected by [`crate::commitment::CommitmentProof`].
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::near_types::{get_raw_prefix_for_contract_data, AccountId};
+
+/// Returns the commitment prefix of the IBC host contract `host_account_id`, which stores
+/// the IBC state under `store_prefix`.
+pub fn commitment_prefix(host_account_id: &AccountId, store_prefix: &[u8]) -> Vec<u8> {
+    get_raw_prefix_for_contract_data(host_account_id, store_prefix)
+}
+
+/// Returns the raw trie key of the ICS-24 `path` under `commitment_prefix`.
+pub fn path_key(commitment_prefix: &[u8], path: &str) -> Vec<u8> {
+    [commitment_prefix, path.as_bytes()].concat()
+}
+
+/// Returns the ICS-24 path of a client state.
+pub fn client_state_path(client_id: &str) -> String {
+    format!("clients/{}/clientState", client_id)
+}
+
+/// Returns the ICS-24 path of a consensus state of a client.
+pub fn client_consensus_state_path(
+    client_id: &str,
+    revision_number: u64,
+    revision_height: u64,
+) -> String {
+    format!(
+        "clients/{}/consensusStates/{}-{}",
+        client_id, revision_number, revision_height
+    )
+}
+
+/// Returns the ICS-24 path of a connection end.
+pub fn connection_path(connection_id: &str) -> String {
+    format!("connections/{}", connection_id)
+}
+
+/// Returns the ICS-24 path of a channel end.
+pub fn channel_end_path(port_id: &str, channel_id: &str) -> String {
+    format!("channelEnds/ports/{}/channels/{}", port_id, channel_id)
+}
+
+/// Returns the ICS-24 path of the next sequence to receive of a channel.
+pub fn next_sequence_recv_path(port_id: &str, channel_id: &str) -> String {
+    format!("nextSequenceRecv/ports/{}/channels/{}", port_id, channel_id)
+}
+
+/// Returns the ICS-24 path of a packet commitment.
+pub fn packet_commitment_path(port_id: &str, channel_id: &str, sequence: u64) -> String {
+    format!(
+        "commitments/ports/{}/channels/{}/sequences/{}",
+        port_id, channel_id, sequence
+    )
+}
+
+/// Returns the ICS-24 path of a packet acknowledgement commitment.
+pub fn packet_acknowledgement_path(port_id: &str, channel_id: &str, sequence: u64) -> String {
+    format!(
+        "acks/ports/{}/channels/{}/sequences/{}",
+        port_id, channel_id, sequence
+    )
+}
+
+/// Returns the ICS-24 path of a packet receipt.
+pub fn packet_receipt_path(port_id: &str, channel_id: &str, sequence: u64) -> String {
+    format!(
+        "receipts/ports/{}/channels/{}/sequences/{}",
+        port_id, channel_id, sequence
+    )
+}
@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(
     warnings,
     trivial_casts,
@@ -10,22 +10,71 @@
 
 extern crate alloc;
 
+// Only checked for `wasm32`, as the native builds of the workspace unify the features of
+// the contracts with the `std` of the other members.
+#[cfg(all(
+    feature = "cosmwasm",
+    target_arch = "wasm32",
+    any(feature = "std", feature = "rayon", feature = "testing")
+))]
+compile_error!("feature `cosmwasm` can not be used with `std`, `rayon` or `testing` for wasm32.");
+
+/// Implement `Debug` of the types as writing their type names, used with feature `strip-debug`.
+#[cfg(feature = "strip-debug")]
+macro_rules! opaque_debug {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl core::fmt::Debug for $t {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.write_str(stringify!($t))
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod commitment;
+#[cfg(feature = "cost-model")]
+pub mod cost_model;
+pub mod evm_abi;
+#[cfg(feature = "ibc")]
+pub mod ibc_client;
+pub mod ibc_paths;
 pub mod near_types;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "ed25519-dalek")]
+pub mod test_vectors;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod types;
+pub mod witness;
 
-use alloc::vec::Vec;
+use alloc::{borrow::Cow, collections::BTreeSet, string::String, vec::Vec};
 use near_types::{
+    block_producers::BlockProducers,
     hash::{sha256, CryptoHash},
     merkle::{compute_root_from_path, merklize, MerklePath},
-    signature::{PublicKey, Signature},
+    signature::{PublicKey, Signature, SignatureVerifier},
     transaction::ExecutionOutcomeWithId,
-    trie::{verify_not_in_state, verify_state_proof, RawTrieNodeWithSize},
-    LightClientBlockLite, ValidatorStakeView,
+    trie::{
+        decode_state_proof, verify_not_in_state_by_hash, verify_not_in_state_data,
+        verify_state_proof_by_hash, verify_state_proof_data, ProofErrorContext,
+        RawTrieNodeWithSize, StateProof,
+    },
+    LightClientBlock, LightClientBlockLite, ValidatorStakeView,
+};
+use types::{
+    ClientState, ConsensusState, Header, Height, HistoricalMembershipProof, MembershipProof,
+    Misbehaviour, NonMembershipProof, OutcomeProof, SealedHeader, StateChange, StateValueProof,
+    PROOF_FORMAT_VERSION,
 };
-use types::{ConsensusState, Header, Height};
 
 /// Error type for header verification.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
 pub enum HeaderVerificationError {
     InvalidBlockHeight,
     InvalidEpochId,
@@ -40,34 +89,257 @@ pub enum HeaderVerificationError {
     BlockIsNotFinal,
     InvalidNextBlockProducersHash,
     InvalidPrevStateRootOfChunks,
+    InconsistentNextBlockInnerHash {
+        expected: CryptoHash,
+        actual: CryptoHash,
+    },
+    NextHeaderNotAdjacent {
+        block_hash: CryptoHash,
+        prev_block_hash: CryptoHash,
+    },
+    BrokenChain {
+        trusted_block_hash: CryptoHash,
+    },
+    TooManyNextBlockProducers {
+        count: usize,
+    },
+    ZeroStakeOfNextBlockProducer {
+        account_id: String,
+    },
+    DuplicateAccountIdInNextBlockProducers {
+        account_id: String,
+    },
+    DuplicatePublicKeyInNextBlockProducers {
+        public_key: PublicKey,
+    },
+    InvalidTrustedBlockHash {
+        expected: CryptoHash,
+        actual: CryptoHash,
+    },
+}
+
+/// The maximum number of block producers of an epoch accepted by the light client.
+///
+/// NEAR mainnet has 100 block producer seats, the bound only rejects degenerate sets.
+pub const MAX_BLOCK_PRODUCERS: usize = 1024;
+
+/// Error type for misbehaviour verification.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum MisbehaviourVerificationError {
+    InvalidHeader1(HeaderVerificationError),
+    InvalidHeader2(HeaderVerificationError),
+    HeadersNotConflicting,
 }
 
 /// Error type for state proof verification.
-#[derive(Debug, Clone)]
+///
+/// For the variants with `expected` and `actual` hashes, the `expected` one is the hash
+/// referenced by the trusted data (the state root or the parent node), and the `actual` one
+/// is the hash of the given data. The `context` tells where in the trie the verification fails.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
 pub enum StateProofVerificationError {
     MissingProofData,
-    InvalidRootHashOfProofData,
-    InvalidProofData { proof_index: u16 },
-    InvalidLeafNodeHash { proof_index: u16 },
-    InvalidLeafNodeKey { proof_index: u16 },
-    InvalidLeafNodeValueHash { proof_index: u16 },
-    InvalidExtensionNodeHash { proof_index: u16 },
-    InvalidExtensionNodeKey { proof_index: u16 },
-    InvalidBranchNodeHash { proof_index: u16 },
-    InvalidBranchNodeValueHash { proof_index: u16 },
-    MissingBranchNodeValue { proof_index: u16 },
-    MissingBranchNodeChildHash { proof_index: u16 },
+    InvalidRootHashOfProofData {
+        root_hash: CryptoHash,
+    },
+    InvalidProofData {
+        proof_index: u16,
+    },
+    InvalidLeafNodeHash {
+        proof_index: u16,
+        expected: CryptoHash,
+        actual: CryptoHash,
+        context: ProofErrorContext,
+    },
+    InvalidLeafNodeKey {
+        proof_index: u16,
+        context: ProofErrorContext,
+    },
+    InvalidLeafNodeValueHash {
+        proof_index: u16,
+        expected: CryptoHash,
+        actual: CryptoHash,
+        context: ProofErrorContext,
+    },
+    InvalidExtensionNodeHash {
+        proof_index: u16,
+        expected: CryptoHash,
+        actual: CryptoHash,
+        context: ProofErrorContext,
+    },
+    InvalidExtensionNodeKey {
+        proof_index: u16,
+        context: ProofErrorContext,
+    },
+    InvalidBranchNodeHash {
+        proof_index: u16,
+        expected: CryptoHash,
+        actual: CryptoHash,
+        context: ProofErrorContext,
+    },
+    InvalidBranchNodeValueHash {
+        proof_index: u16,
+        expected: CryptoHash,
+        actual: CryptoHash,
+        context: ProofErrorContext,
+    },
+    MissingBranchNodeValue {
+        proof_index: u16,
+        context: ProofErrorContext,
+    },
+    MissingBranchNodeChildHash {
+        proof_index: u16,
+        context: ProofErrorContext,
+    },
     InvalidProofDataLength,
+    MissingProofNode {
+        node_hash: CryptoHash,
+    },
     SpecifiedKeyHasValueInState,
+    UnsupportedProofVersion {
+        version: u8,
+    },
+}
+
+/// Error type for commitment proof verification.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum CommitmentProofVerificationError {
+    RootMismatch {
+        expected: CryptoHash,
+        actual: CryptoHash,
+    },
+    KeyMismatch,
+    ValueMismatch,
+    ExistenceProofExpected,
+    NonExistenceProofExpected,
+    StateProof(StateProofVerificationError),
+}
+
+impl From<StateProofVerificationError> for CommitmentProofVerificationError {
+    fn from(error: StateProofVerificationError) -> Self {
+        Self::StateProof(error)
+    }
+}
+
+/// Error type for verifying the value of a storage key in the state of an old block.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum HistoricalProofVerificationError {
+    UnsupportedProofVersion {
+        version: u8,
+    },
+    /// The old block is not an ancestor of the trusted header.
+    InvalidBlockProof,
+    /// The chunk root is not in the `prev_state_root` of the old block.
+    InvalidChunkRootProof,
+    StateProof(StateProofVerificationError),
+}
+
+impl From<StateProofVerificationError> for HistoricalProofVerificationError {
+    fn from(error: StateProofVerificationError) -> Self {
+        Self::StateProof(error)
+    }
+}
+
+/// Error type for verifying the change of a storage key between two heights.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum StateChangeVerificationError {
+    /// The old height is not lower than the new height.
+    InvalidHeightOrder,
+    /// The height of a proof is not the height of the consensus state to verify it with.
+    HeightMismatch {
+        expected: Height,
+        actual: Height,
+    },
+    KeyMismatch,
+    InvalidOldProof(StateProofVerificationError),
+    InvalidNewProof(StateProofVerificationError),
 }
 
 /// Error type for transaction verification.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
 pub enum TransactionVerificationError {
     InvalidOutcomeProof,
     InvalidBlockProof,
+    UnsupportedProofVersion { version: u8 },
+}
+
+/// Error type for checking that data belongs to the chain followed by the client.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum ChainIdVerificationError {
+    ChainIdMismatch { expected: String, actual: String },
+}
+
+/// Error type for accessing the consensus states of a light client.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum ClientError {
+    /// The client has no consensus state yet.
+    Uninitialized,
+    MissingConsensusState {
+        height: Height,
+    },
 }
 
+/// Error type for the storage of the consensus states of a light client, returned by
+/// implementations of [`BasicNearLightClient`] which persist their data.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum StorageError {
+    /// The data stored for `key` can not be decoded.
+    CorruptedData { key: String },
+    /// The storage backend failed with `message`.
+    Backend { message: String },
+}
+
+/// Error type wrapping all the error types of the verifications and the client, for
+/// applications running multiple verification flows.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum Error {
+    Header(HeaderVerificationError),
+    Misbehaviour(MisbehaviourVerificationError),
+    StateProof(StateProofVerificationError),
+    CommitmentProof(CommitmentProofVerificationError),
+    HistoricalProof(HistoricalProofVerificationError),
+    StateChange(StateChangeVerificationError),
+    Transaction(TransactionVerificationError),
+    ChainId(ChainIdVerificationError),
+    Client(ClientError),
+    Storage(StorageError),
+}
+
+macro_rules! impl_from_for_error {
+    ($($variant:ident($t:ty)),* $(,)?) => {
+        $(
+            impl From<$t> for Error {
+                fn from(error: $t) -> Self {
+                    Self::$variant(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_error!(
+    Header(HeaderVerificationError),
+    Misbehaviour(MisbehaviourVerificationError),
+    StateProof(StateProofVerificationError),
+    CommitmentProof(CommitmentProofVerificationError),
+    HistoricalProof(HistoricalProofVerificationError),
+    StateChange(StateChangeVerificationError),
+    Transaction(TransactionVerificationError),
+    ChainId(ChainIdVerificationError),
+    Client(ClientError),
+    Storage(StorageError),
+);
+
 /// This trait is a minimal interface for NEAR light client,
 /// providing a few functions for header verification.
 pub trait BasicNearLightClient {
@@ -77,119 +349,494 @@ pub trait BasicNearLightClient {
     /// Returns the consensus state at the given `Height`.
     fn get_consensus_state(&self, height: &Height) -> Option<ConsensusState>;
 
+    /// Returns whether to stop verifying approval signatures once the approved stake
+    /// is more than 2/3 of the total stake.
+    ///
+    /// Defaults to `false`, which means all approval signatures are verified.
+    fn fast_finality_check(&self) -> bool {
+        false
+    }
+
+    /// Returns the verifier of the signatures of block producers.
+    #[cfg(feature = "ed25519-dalek")]
+    fn signature_verifier(&self) -> &dyn SignatureVerifier {
+        &near_types::signature::Ed25519DalekVerifier
+    }
+
+    /// Returns the verifier of the signatures of block producers.
+    #[cfg(not(feature = "ed25519-dalek"))]
+    fn signature_verifier(&self) -> &dyn SignatureVerifier;
+
     /// Verify header data with the consensus state of latest height.
     fn verify_header(&self, header: &Header) -> Result<(), HeaderVerificationError> {
-        let latest_consensus_state = self
-            .get_consensus_state(&self.latest_height())
-            .expect("Should not fail if the light client is initialized properly.");
-        let latest_header = &latest_consensus_state.header;
+        verify_header_with_hash(
+            self,
+            header,
+            &header.light_client_block.current_block_hash(),
+        )
+    }
 
-        let approval_message = header.light_client_block.approval_message();
+    /// Verify a [`SealedHeader`] with the consensus state of latest height, with the block
+    /// hash it is sealed with.
+    fn verify_sealed_header(&self, header: &SealedHeader) -> Result<(), HeaderVerificationError> {
+        verify_header_with_hash(self, header, &header.hash())
+    }
 
-        // Check the height of the block is higher than the height of the current head.
-        if header.height() <= latest_header.height() {
-            return Err(HeaderVerificationError::InvalidBlockHeight);
-        }
+    /// Verify header data with the consensus state of latest height, and return the cost
+    /// of the verification alongside the result.
+    #[cfg(feature = "cost-model")]
+    fn verify_header_with_cost(
+        &self,
+        header: &Header,
+    ) -> (
+        Result<(), HeaderVerificationError>,
+        cost_model::VerificationCost,
+    ) {
+        cost_model::measure(|| self.verify_header(header))
+    }
 
-        // Check the epoch of the block is equal to the epoch_id or next_epoch_id
-        // known for the current head.
-        if header.epoch_id() != latest_header.epoch_id()
-            && header.epoch_id() != latest_header.next_epoch_id()
-        {
-            return Err(HeaderVerificationError::InvalidEpochId);
+    /// Verify that both headers of `misbehaviour` are valid with the consensus state of
+    /// latest height, and they are different blocks at the same height.
+    fn verify_misbehaviour(
+        &self,
+        misbehaviour: &Misbehaviour,
+    ) -> Result<(), MisbehaviourVerificationError> {
+        let (header_1, header_2) = (&misbehaviour.header_1, &misbehaviour.header_2);
+        let block_hash_1 = header_1.light_client_block.current_block_hash();
+        let block_hash_2 = header_2.light_client_block.current_block_hash();
+        if header_1.height() != header_2.height() || block_hash_1 == block_hash_2 {
+            return Err(MisbehaviourVerificationError::HeadersNotConflicting);
         }
+        verify_header_with_hash(self, header_1, &block_hash_1)
+            .map_err(MisbehaviourVerificationError::InvalidHeader1)?;
+        verify_header_with_hash(self, header_2, &block_hash_2)
+            .map_err(MisbehaviourVerificationError::InvalidHeader2)
+    }
 
-        // If the epoch of the block is equal to the next_epoch_id of the head,
-        // then next_bps is not None.
-        if header.epoch_id() == latest_header.next_epoch_id()
-            && header.light_client_block.next_bps.is_none()
-        {
-            return Err(HeaderVerificationError::MissingNextBlockProducersInHead);
+    /// Verify header data with the consensus state of latest height, and verify that
+    /// the header descends from the header of latest height.
+    ///
+    /// The header is treated as a descendant if its `prev_block_hash` is the hash of the
+    /// latest header, or `block_proof` proves the hash of the latest header is included in
+    /// the `block_merkle_root` of the header.
+    fn verify_header_chained(
+        &self,
+        header: &Header,
+        block_proof: Option<&MerklePath>,
+    ) -> Result<(), HeaderVerificationError> {
+        verify_header_with_hash(
+            self,
+            header,
+            &header.light_client_block.current_block_hash(),
+        )?;
+        verify_descendant(self, header, block_proof)
+    }
+
+    /// Verify a [`SealedHeader`] as [`BasicNearLightClient::verify_header_chained`] does,
+    /// with the block hash it is sealed with.
+    fn verify_sealed_header_chained(
+        &self,
+        header: &SealedHeader,
+        block_proof: Option<&MerklePath>,
+    ) -> Result<(), HeaderVerificationError> {
+        verify_header_with_hash(self, header, &header.hash())?;
+        verify_descendant(self, header, block_proof)
+    }
+
+    /// Verify header data with the consensus state of latest height, and verify that
+    /// the `next_block_inner_hash` of the header is consistent with `next_header`,
+    /// which must be the header of the next block.
+    fn verify_header_with_next(
+        &self,
+        header: &Header,
+        next_header: &Header,
+    ) -> Result<(), HeaderVerificationError> {
+        let block_hash = header.light_client_block.current_block_hash();
+        verify_header_with_hash(self, header, &block_hash)?;
+        verify_next_block_inner_hash_with_hash(header, &block_hash, next_header)
+    }
+}
+
+/// Verify `header`, whose block hash is `block_hash`, with the consensus state of latest
+/// height of `client`.
+fn verify_header_with_hash<C: BasicNearLightClient + ?Sized>(
+    client: &C,
+    header: &Header,
+    block_hash: &CryptoHash,
+) -> Result<(), HeaderVerificationError> {
+    let latest_consensus_state = client
+        .get_consensus_state(&client.latest_height())
+        .expect("Should not fail if the light client is initialized properly.");
+    let latest_header = &latest_consensus_state.header;
+
+    let approval_message = header
+        .light_client_block
+        .approval_message_with_hash(block_hash);
+
+    // Check the height of the block is higher than the height of the current head.
+    if header.height() <= latest_header.height() {
+        return Err(HeaderVerificationError::InvalidBlockHeight);
+    }
+
+    // Check the epoch of the block is equal to the epoch_id or next_epoch_id
+    // known for the current head.
+    if header.epoch_id() != latest_header.epoch_id()
+        && header.epoch_id() != latest_header.next_epoch_id()
+    {
+        return Err(HeaderVerificationError::InvalidEpochId);
+    }
+
+    // If the epoch of the block is equal to the next_epoch_id of the head,
+    // then next_bps is not None.
+    if header.epoch_id() == latest_header.next_epoch_id()
+        && header.light_client_block.next_bps.is_none()
+    {
+        return Err(HeaderVerificationError::MissingNextBlockProducersInHead);
+    }
+
+    // 1. The approvals_after_next contains valid signatures on approval_message
+    // from the block producers of the corresponding epoch.
+    // 2. The signatures present in approvals_after_next correspond to
+    // more than 2/3 of the total stake.
+    let mut total_stake = 0;
+    let mut approved_stake = 0;
+
+    let bps = latest_consensus_state.get_block_producers_of(&header.epoch_id());
+    if bps.is_none() {
+        return Err(HeaderVerificationError::MissingCachedEpochBlockProducers {
+            epoch_id: header.epoch_id(),
+        });
+    }
+
+    let epoch_block_producers = bps.expect("Should not fail based on previous checking.");
+    let approvals = header
+        .light_client_block
+        .approvals_after_next
+        .iter()
+        .zip(epoch_block_producers.iter())
+        .map(|(maybe_signature, block_producer)| {
+            (maybe_signature, block_producer.as_validator_stake())
+        })
+        .collect::<Vec<_>>();
+    for (_, bp_stake_view) in approvals.iter() {
+        total_stake += bp_stake_view.stake;
+    }
+
+    let mut signed_approvals = Vec::new();
+    for (maybe_signature, bp_stake_view) in approvals {
+        // Stop collecting signatures once the approved stake is enough to finalize the block.
+        if client.fast_finality_check() && approved_stake * 3 > total_stake * 2 {
+            break;
         }
 
-        // 1. The approvals_after_next contains valid signatures on approval_message
-        // from the block producers of the corresponding epoch.
-        // 2. The signatures present in approvals_after_next correspond to
-        // more than 2/3 of the total stake.
-        let mut total_stake = 0;
-        let mut approved_stake = 0;
-
-        let bps = latest_consensus_state.get_block_producers_of(&header.epoch_id());
-        if bps.is_none() {
-            return Err(HeaderVerificationError::MissingCachedEpochBlockProducers {
-                epoch_id: header.epoch_id(),
-            });
+        if let Some(signature) = maybe_signature {
+            approved_stake += bp_stake_view.stake;
+            signed_approvals.push((signature, &bp_stake_view.public_key));
         }
+    }
 
-        let epoch_block_producers = bps.expect("Should not fail based on previous checking.");
-        for (maybe_signature, block_producer) in header
-            .light_client_block
-            .approvals_after_next
-            .iter()
-            .zip(epoch_block_producers.iter())
+    verify_approval_signatures(
+        client.signature_verifier(),
+        &approval_message,
+        &signed_approvals,
+    )?;
+
+    if approved_stake * 3 <= total_stake * 2 {
+        return Err(HeaderVerificationError::BlockIsNotFinal);
+    }
+
+    verify_header_consistency(header)
+}
+
+/// Verify that `header` descends from the header of latest height of `client`, see
+/// [`BasicNearLightClient::verify_header_chained`].
+fn verify_descendant<C: BasicNearLightClient + ?Sized>(
+    client: &C,
+    header: &Header,
+    block_proof: Option<&MerklePath>,
+) -> Result<(), HeaderVerificationError> {
+    let latest_consensus_state = client
+        .get_consensus_state(&client.latest_height())
+        .expect("Should not fail if the light client is initialized properly.");
+    let trusted_block_hash = latest_consensus_state
+        .header
+        .light_client_block
+        .current_block_hash();
+    if header.light_client_block.prev_block_hash == trusted_block_hash {
+        return Ok(());
+    }
+    match block_proof {
+        Some(block_proof)
+            if compute_root_from_path(block_proof, trusted_block_hash)
+                == header.light_client_block.inner_lite.block_merkle_root =>
         {
-            let bp_stake_view = block_producer.clone().into_validator_stake();
-            let bp_stake = bp_stake_view.stake;
-            total_stake += bp_stake;
+            Ok(())
+        }
+        _ => Err(HeaderVerificationError::BrokenChain { trusted_block_hash }),
+    }
+}
 
-            if maybe_signature.is_none() {
-                continue;
-            }
+/// A light client holding only one consensus state trusted by the caller, to verify the
+/// headers following it, such as in stateless verifiers which are passed the trusted
+/// consensus state with each call.
+#[cfg(feature = "ed25519-dalek")]
+#[derive(Clone)]
+pub struct TrustedConsensusState {
+    consensus_state: ConsensusState,
+    fast_finality_check: bool,
+}
 
-            approved_stake += bp_stake;
-
-            let validator_public_key = bp_stake_view.public_key.clone();
-            if !maybe_signature
-                .as_ref()
-                .expect("Should not fail based on previous checking.")
-                .verify(&approval_message, &validator_public_key)
-            {
-                return Err(HeaderVerificationError::InvalidValidatorSignature {
-                    signature: maybe_signature
-                        .clone()
-                        .expect("Should not fail based on previous checking."),
-                    pubkey: validator_public_key,
-                });
-            }
+#[cfg(feature = "ed25519-dalek")]
+impl TrustedConsensusState {
+    /// Create the light client trusting `consensus_state`.
+    pub fn new(consensus_state: ConsensusState) -> Self {
+        Self {
+            consensus_state,
+            fast_finality_check: false,
         }
+    }
+    /// Stop verifying approval signatures once the block is final,
+    /// see [`BasicNearLightClient::fast_finality_check`].
+    pub fn with_fast_finality_check(mut self, fast_finality_check: bool) -> Self {
+        self.fast_finality_check = fast_finality_check;
+        self
+    }
+    /// Returns the trusted consensus state.
+    pub fn consensus_state(&self) -> &ConsensusState {
+        &self.consensus_state
+    }
+}
 
-        if approved_stake * 3 <= total_stake * 2 {
-            return Err(HeaderVerificationError::BlockIsNotFinal);
+#[cfg(feature = "ed25519-dalek")]
+impl BasicNearLightClient for TrustedConsensusState {
+    fn latest_height(&self) -> Height {
+        self.consensus_state.header.height()
+    }
+
+    fn get_consensus_state(&self, height: &Height) -> Option<ConsensusState> {
+        (*height == self.latest_height()).then(|| self.consensus_state.clone())
+    }
+
+    fn fast_finality_check(&self) -> bool {
+        self.fast_finality_check
+    }
+}
+
+/// Verify that the `next_block_inner_hash` of `header` is the inner hash of `next_header`,
+/// which must be the header of the next block of `header`.
+///
+/// The `next_block_inner_hash` is used to build the approval message, so that this check
+/// ensures the approvals are validated against the actual next block. If `next_header` is not
+/// the header of the next block, there is nothing to check against, and
+/// `NextHeaderNotAdjacent` is returned.
+pub fn verify_next_block_inner_hash(
+    header: &Header,
+    next_header: &Header,
+) -> Result<(), HeaderVerificationError> {
+    verify_next_block_inner_hash_with_hash(
+        header,
+        &header.light_client_block.current_block_hash(),
+        next_header,
+    )
+}
+
+/// Same as [`verify_next_block_inner_hash`], with `block_hash` as the hash of the block of
+/// `header`.
+fn verify_next_block_inner_hash_with_hash(
+    header: &Header,
+    block_hash: &CryptoHash,
+    next_header: &Header,
+) -> Result<(), HeaderVerificationError> {
+    let block_hash = *block_hash;
+    if next_header.light_client_block.prev_block_hash != block_hash {
+        return Err(HeaderVerificationError::NextHeaderNotAdjacent {
+            block_hash,
+            prev_block_hash: next_header.light_client_block.prev_block_hash,
+        });
+    }
+    let actual = next_header.light_client_block.inner_hash();
+    if header.light_client_block.next_block_inner_hash != actual {
+        return Err(HeaderVerificationError::InconsistentNextBlockInnerHash {
+            expected: header.light_client_block.next_block_inner_hash,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Verify the signatures of approvals on `approval_message`, returning the error of
+/// the first invalid one.
+#[cfg(not(feature = "rayon"))]
+fn verify_approval_signatures(
+    verifier: &dyn SignatureVerifier,
+    approval_message: &[u8],
+    signed_approvals: &[(&Signature, &PublicKey)],
+) -> Result<(), HeaderVerificationError> {
+    #[cfg(feature = "cost-model")]
+    let verifier = &cost_model::CountingVerifier::new(verifier);
+    signed_approvals
+        .iter()
+        .find(|(signature, public_key)| !verifier.verify(signature, approval_message, public_key))
+        .map_or(Ok(()), |(signature, public_key)| {
+            Err(HeaderVerificationError::InvalidValidatorSignature {
+                signature: (*signature).clone(),
+                pubkey: (*public_key).clone(),
+            })
+        })
+}
+
+/// Verify the signatures of approvals on `approval_message` in parallel, returning the error of
+/// the first invalid one.
+#[cfg(feature = "rayon")]
+fn verify_approval_signatures(
+    verifier: &dyn SignatureVerifier,
+    approval_message: &[u8],
+    signed_approvals: &[(&Signature, &PublicKey)],
+) -> Result<(), HeaderVerificationError> {
+    use rayon::prelude::*;
+
+    #[cfg(feature = "cost-model")]
+    let verifier = &cost_model::CountingVerifier::new(verifier);
+    signed_approvals
+        .par_iter()
+        .find_first(|(signature, public_key)| {
+            !verifier.verify(signature, approval_message, public_key)
+        })
+        .map_or(Ok(()), |(signature, public_key)| {
+            Err(HeaderVerificationError::InvalidValidatorSignature {
+                signature: (*signature).clone(),
+                pubkey: (*public_key).clone(),
+            })
+        })
+}
+
+/// Verify the proofs of a storage key with the consensus states at two heights, and
+/// return the change of its value between the heights.
+pub fn verify_state_change(
+    old_state: &ConsensusState,
+    old_proof: &StateValueProof,
+    new_state: &ConsensusState,
+    new_proof: &StateValueProof,
+) -> Result<StateChange, StateChangeVerificationError> {
+    let (old_height, new_height) = (old_state.header.height(), new_state.header.height());
+    if old_height >= new_height {
+        return Err(StateChangeVerificationError::InvalidHeightOrder);
+    }
+    for (height, proof) in [(old_height, old_proof), (new_height, new_proof)] {
+        if proof.height() != height {
+            return Err(StateChangeVerificationError::HeightMismatch {
+                expected: height,
+                actual: proof.height(),
+            });
         }
+    }
+    if old_proof.key() != new_proof.key() {
+        return Err(StateChangeVerificationError::KeyMismatch);
+    }
+    old_state
+        .verify_state_value_proof(old_proof)
+        .map_err(StateChangeVerificationError::InvalidOldProof)?;
+    new_state
+        .verify_state_value_proof(new_proof)
+        .map_err(StateChangeVerificationError::InvalidNewProof)?;
+    Ok(StateChange {
+        key: old_proof.key().to_vec(),
+        old_height,
+        old_value: old_proof.value().map(<[u8]>::to_vec),
+        new_height,
+        new_value: new_proof.value().map(<[u8]>::to_vec),
+    })
+}
 
-        // If next_bps is not none, sha256(borsh(next_bps)) corresponds to
-        // the next_bp_hash in inner_lite.
-        if header.light_client_block.next_bps.is_some() {
-            let block_view_next_bps_serialized = borsh::to_vec(
-                header
-                    .light_client_block
-                    .next_bps
-                    .as_deref()
-                    .expect("Should not fail based on previous checking."),
-            )
-            .expect("Should not fail based on borsh serialization.");
-            if sha256(&block_view_next_bps_serialized).as_slice()
-                != header.light_client_block.inner_lite.next_bp_hash.as_ref()
-            {
-                return Err(HeaderVerificationError::InvalidNextBlockProducersHash);
-            }
+/// Check that `bps` is a sane set of block producers: it is not longer than
+/// [`MAX_BLOCK_PRODUCERS`], all stakes are non-zero, and there are no duplicate account ids
+/// or public keys.
+pub fn validate_block_producers(bps: &[ValidatorStakeView]) -> Result<(), HeaderVerificationError> {
+    if bps.len() > MAX_BLOCK_PRODUCERS {
+        return Err(HeaderVerificationError::TooManyNextBlockProducers { count: bps.len() });
+    }
+    let mut account_ids = BTreeSet::new();
+    let mut public_keys = BTreeSet::new();
+    for bp in bps {
+        let ValidatorStakeView::V1(bp) = bp;
+        if bp.stake == 0 {
+            return Err(HeaderVerificationError::ZeroStakeOfNextBlockProducer {
+                account_id: bp.account_id.clone(),
+            });
         }
+        if !account_ids.insert(bp.account_id.as_str()) {
+            return Err(
+                HeaderVerificationError::DuplicateAccountIdInNextBlockProducers {
+                    account_id: bp.account_id.clone(),
+                },
+            );
+        }
+        if !public_keys.insert(
+            borsh::to_vec(&bp.public_key).expect("Should not fail based on borsh serialization."),
+        ) {
+            return Err(
+                HeaderVerificationError::DuplicatePublicKeyInNextBlockProducers {
+                    public_key: bp.public_key.clone(),
+                },
+            );
+        }
+    }
+    Ok(())
+}
 
-        // Check the `prev_state_root` is the merkle root of `prev_state_root_of_chunks`.
-        if header.light_client_block.inner_lite.prev_state_root
-            != merklize(&header.prev_state_root_of_chunks).0
-        {
-            return Err(HeaderVerificationError::InvalidPrevStateRootOfChunks);
+/// Check the data of `header` is consistent with the hashes in its `inner_lite`:
+/// the `next_bps` (if any) is a sane set with hash `next_bp_hash`, and the `prev_state_root`
+/// is the merkle root of `prev_state_root_of_chunks`.
+fn verify_header_consistency(header: &Header) -> Result<(), HeaderVerificationError> {
+    // If next_bps is not none, sha256(borsh(next_bps)) corresponds to
+    // the next_bp_hash in inner_lite.
+    if let Some(next_bps) = header.light_client_block.next_bps.as_ref() {
+        if next_bps.hash() != header.light_client_block.inner_lite.next_bp_hash {
+            return Err(HeaderVerificationError::InvalidNextBlockProducersHash);
         }
+        validate_block_producers(&next_bps.decoded())?;
+    }
+
+    // Check the `prev_state_root` is the merkle root of `prev_state_root_of_chunks`.
+    if header.light_client_block.inner_lite.prev_state_root
+        != merklize(&header.prev_state_root_of_chunks).0
+    {
+        return Err(HeaderVerificationError::InvalidPrevStateRootOfChunks);
+    }
 
+    Ok(())
+}
+
+impl ClientState {
+    /// Check that `chain_id`, as reported by the source of some data such as the rpc node
+    /// serving the headers, is the chain followed by the client.
+    ///
+    /// Host applications should refuse the data on failure, to avoid mixing the data of
+    /// different chains, e.g. mainnet and testnet.
+    ///
+    /// The headers of NEAR don't carry the chain id, so it is not checked by
+    /// [`BasicNearLightClient::verify_header`]. A header of another chain fails there anyway,
+    /// as its epoch and block producers don't follow the trusted head, which leaves the
+    /// source of the data, such as the initial checkpoint, to be checked by this function.
+    pub fn verify_chain_id(&self, chain_id: &str) -> Result<(), ChainIdVerificationError> {
+        if self.chain_id != chain_id {
+            return Err(ChainIdVerificationError::ChainIdMismatch {
+                expected: self.chain_id.clone(),
+                actual: chain_id.into(),
+            });
+        }
         Ok(())
     }
 }
 
 impl Header {
+    /// Seal the header with the hash of its block, see [`SealedHeader`].
+    pub fn seal(self) -> SealedHeader {
+        SealedHeader::new(self)
+    }
     ///
     pub fn height(&self) -> Height {
-        self.light_client_block.inner_lite.height
+        Height::from_block_height(self.light_client_block.inner_lite.height)
     }
     ///
     pub fn epoch_id(&self) -> CryptoHash {
@@ -202,17 +849,92 @@ impl Header {
 }
 
 impl ConsensusState {
+    /// Create the consensus state of `header` with the block producers of its epoch.
+    pub fn new(current_bps: Option<BlockProducers>, header: Header) -> Self {
+        Self {
+            current_bps,
+            header,
+        }
+    }
+
+    /// Create the consensus state of a trusted block as the trust anchor of a light client.
+    ///
+    /// The block is checked to be internally consistent: its recomputed hash must be
+    /// `trusted_block_hash`, which is obtained out of band (e.g. from a checkpoint),
+    /// the `next_bps` must match `next_bp_hash`, and `prev_state_root_of_chunks` must match
+    /// `prev_state_root`. The `current_bps` can not be checked with the block itself, but must
+    /// be a sane set of block producers.
+    pub fn from_trusted_block(
+        light_client_block: LightClientBlock,
+        prev_state_root_of_chunks: Vec<CryptoHash>,
+        current_bps: Option<BlockProducers>,
+        trusted_block_hash: &CryptoHash,
+    ) -> Result<Self, HeaderVerificationError> {
+        let actual = light_client_block.current_block_hash();
+        if actual != *trusted_block_hash {
+            return Err(HeaderVerificationError::InvalidTrustedBlockHash {
+                expected: *trusted_block_hash,
+                actual,
+            });
+        }
+        let header = Header {
+            light_client_block,
+            prev_state_root_of_chunks,
+        };
+        verify_header_consistency(&header)?;
+        if let Some(current_bps) = current_bps.as_ref() {
+            validate_block_producers(&current_bps.decoded())?;
+        }
+        Ok(Self::new(current_bps, header))
+    }
+
+    /// Create the consensus state of `header`, which is verified with this consensus state.
+    pub fn next(&self, header: Header) -> Self {
+        Self::new(self.block_producers_of(&header.epoch_id()).cloned(), header)
+    }
+
+    /// Returns the total stake of `current_bps`, zero if they are unknown.
+    pub fn current_total_stake(&self) -> u128 {
+        self.current_bps
+            .as_ref()
+            .map_or(0, BlockProducers::total_stake)
+    }
+
+    /// Returns the total stake of the `next_bps` of `header`, zero if they are unknown.
+    pub fn next_total_stake(&self) -> u128 {
+        self.header
+            .light_client_block
+            .next_bps
+            .as_ref()
+            .map_or(0, BlockProducers::total_stake)
+    }
+
+    /// Returns the total stake of the block producers of the current epoch or the next.
+    pub fn total_stake_of(&self, epoch_id: &CryptoHash) -> Option<u128> {
+        self.block_producers_of(epoch_id)
+            .map(BlockProducers::total_stake)
+    }
+
     /// Returns the block producers corresponding to current epoch or the next.
-    pub fn get_block_producers_of(&self, epoch_id: &CryptoHash) -> Option<Vec<ValidatorStakeView>> {
+    pub fn block_producers_of(&self, epoch_id: &CryptoHash) -> Option<&BlockProducers> {
         if *epoch_id == self.header.epoch_id() {
-            return self.current_bps.clone();
+            self.current_bps.as_ref()
         } else if *epoch_id == self.header.next_epoch_id() {
-            return self.header.light_client_block.next_bps.clone();
+            self.header.light_client_block.next_bps.as_ref()
         } else {
-            return None;
+            None
         }
     }
 
+    /// Returns the decoded block producers corresponding to current epoch or the next.
+    pub fn get_block_producers_of(
+        &self,
+        epoch_id: &CryptoHash,
+    ) -> Option<Cow<'_, [ValidatorStakeView]>> {
+        self.block_producers_of(epoch_id)
+            .map(BlockProducers::decoded)
+    }
+
     /// Verify the value of a certain storage key with proof data.
     ///
     /// The `proofs` must be the proof data at `height - 1`.
@@ -227,19 +949,9 @@ impl ConsensusState {
         }
         let root_hash = CryptoHash(sha256(proofs[0].as_ref()));
         if !self.header.prev_state_root_of_chunks.contains(&root_hash) {
-            return Err(StateProofVerificationError::InvalidRootHashOfProofData);
-        }
-        let mut nodes: Vec<RawTrieNodeWithSize> = Vec::new();
-        let mut proof_index: u16 = 0;
-        for proof in proofs {
-            if let Ok(node) = RawTrieNodeWithSize::decode(proof) {
-                nodes.push(node);
-            } else {
-                return Err(StateProofVerificationError::InvalidProofData { proof_index });
-            }
-            proof_index += 1;
+            return Err(StateProofVerificationError::InvalidRootHashOfProofData { root_hash });
         }
-        return verify_state_proof(&key, &nodes, value, &root_hash);
+        return verify_state_proof_data(&key, proofs, value, &root_hash);
     }
 
     /// Verify that the value of a certain storage key is empty with proof data.
@@ -255,19 +967,138 @@ impl ConsensusState {
         }
         let root_hash = CryptoHash(sha256(proofs[0].as_ref()));
         if !self.header.prev_state_root_of_chunks.contains(&root_hash) {
-            return Err(StateProofVerificationError::InvalidRootHashOfProofData);
+            return Err(StateProofVerificationError::InvalidRootHashOfProofData { root_hash });
         }
-        let mut nodes: Vec<RawTrieNodeWithSize> = Vec::new();
-        let mut proof_index: u16 = 0;
-        for proof in proofs {
-            if let Ok(node) = RawTrieNodeWithSize::decode(proof) {
-                nodes.push(node);
-            } else {
-                return Err(StateProofVerificationError::InvalidProofData { proof_index });
-            }
-            proof_index += 1;
+        return verify_not_in_state_data(&key, proofs, &root_hash);
+    }
+
+    /// Verify the value of a certain storage key with proof data in any order.
+    ///
+    /// The `proofs` must be the proof data at `height - 1`, but the nodes don't need to be
+    /// ordered from root to leaf, and the proof data may contain nodes of other keys.
+    pub fn verify_membership_by_hash(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        proofs: &Vec<Vec<u8>>,
+    ) -> Result<(), StateProofVerificationError> {
+        let nodes = decode_state_proof(proofs)?;
+        let root_hash = self.find_state_root_in(&nodes)?;
+        return verify_state_proof_by_hash(&key, &nodes, value, &root_hash);
+    }
+
+    /// Verify that the value of a certain storage key is empty with proof data in any order.
+    ///
+    /// The `proofs` must be the proof data at `height - 1`, but the nodes don't need to be
+    /// ordered from root to leaf, and the proof data may contain nodes of other keys.
+    pub fn verify_non_membership_by_hash(
+        &self,
+        key: &[u8],
+        proofs: &Vec<Vec<u8>>,
+    ) -> Result<(), StateProofVerificationError> {
+        let nodes = decode_state_proof(proofs)?;
+        let root_hash = self.find_state_root_in(&nodes)?;
+        return verify_not_in_state_by_hash(&key, &nodes, &root_hash);
+    }
+
+    /// Verify the value of a certain storage key with a decoded [`StateProof`].
+    ///
+    /// The proof must be built from the proof data at `height - 1`, and can be reused to
+    /// verify other keys without decoding the proof data again.
+    pub fn verify_membership_with_state_proof(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        proof: &StateProof,
+    ) -> Result<(), StateProofVerificationError> {
+        let root_hash = self.find_state_root_of(proof)?;
+        proof.verify_membership(key, value, &root_hash)
+    }
+
+    /// Verify that the value of a certain storage key is empty with a decoded [`StateProof`].
+    ///
+    /// The proof must be built from the proof data at `height - 1`, and can be reused to
+    /// verify other keys without decoding the proof data again.
+    pub fn verify_non_membership_with_state_proof(
+        &self,
+        key: &[u8],
+        proof: &StateProof,
+    ) -> Result<(), StateProofVerificationError> {
+        let root_hash = self.find_state_root_of(proof)?;
+        proof.verify_non_membership(key, &root_hash)
+    }
+
+    /// Verify the values of multiple storage keys with a decoded [`StateProof`], returning
+    /// the result of each `(key, value)` item in order.
+    ///
+    /// The proof must be built from the proof data at `height - 1` of all the keys, e.g. the
+    /// proof data of multiple `view_state` queries merged, which may be of the states of
+    /// different shards. Each key is looked up from every `prev_state_root` of chunks in
+    /// the proof, and the error with the first of them is returned if none proves the value.
+    pub fn verify_membership_batch(
+        &self,
+        items: &[(&[u8], &[u8])],
+        proof: &StateProof,
+    ) -> Vec<Result<(), StateProofVerificationError>> {
+        let roots: Vec<&CryptoHash> = self
+            .header
+            .prev_state_root_of_chunks
+            .iter()
+            .filter(|root| proof.contains(root))
+            .collect();
+        items
+            .iter()
+            .map(|(key, value)| {
+                let mut first_error = None;
+                for root in roots.iter() {
+                    match proof.verify_membership(key, value, root) {
+                        Ok(()) => return Ok(()),
+                        Err(err) => {
+                            first_error.get_or_insert(err);
+                        }
+                    }
+                }
+                Err(first_error.unwrap_or_else(|| {
+                    StateProofVerificationError::InvalidRootHashOfProofData {
+                        root_hash: proof.nodes()[0].hash(),
+                    }
+                }))
+            })
+            .collect()
+    }
+
+    /// Returns the first `prev_state_root` of chunks which is the hash of a node of `proof`.
+    fn find_state_root_of(
+        &self,
+        proof: &StateProof,
+    ) -> Result<CryptoHash, StateProofVerificationError> {
+        self.header
+            .prev_state_root_of_chunks
+            .iter()
+            .find(|root| proof.contains(root))
+            .copied()
+            .ok_or_else(|| StateProofVerificationError::InvalidRootHashOfProofData {
+                root_hash: proof.nodes()[0].hash(),
+            })
+    }
+
+    /// Returns the first `prev_state_root` of chunks which is the hash of one of `nodes`.
+    fn find_state_root_in(
+        &self,
+        nodes: &[RawTrieNodeWithSize],
+    ) -> Result<CryptoHash, StateProofVerificationError> {
+        if nodes.is_empty() {
+            return Err(StateProofVerificationError::MissingProofData);
         }
-        return verify_not_in_state(&key, &nodes, &root_hash);
+        let node_hashes: Vec<CryptoHash> = nodes.iter().map(|node| node.hash()).collect();
+        self.header
+            .prev_state_root_of_chunks
+            .iter()
+            .find(|root| node_hashes.contains(root))
+            .copied()
+            .ok_or(StateProofVerificationError::InvalidRootHashOfProofData {
+                root_hash: node_hashes[0],
+            })
     }
 
     /// Verify the given transaction or receipt outcome with proof data.
@@ -298,4 +1129,144 @@ impl ConsensusState {
             return Err(TransactionVerificationError::InvalidBlockProof);
         }
     }
+
+    /// Verify a [`MembershipProof`] created for the height of this consensus state.
+    pub fn verify_membership_proof(
+        &self,
+        proof: &MembershipProof,
+    ) -> Result<(), StateProofVerificationError> {
+        if proof.version != PROOF_FORMAT_VERSION {
+            return Err(StateProofVerificationError::UnsupportedProofVersion {
+                version: proof.version,
+            });
+        }
+        self.verify_membership(&proof.key, &proof.value, &proof.proof)
+    }
+
+    /// Verify a [`NonMembershipProof`] created for the height of this consensus state.
+    pub fn verify_non_membership_proof(
+        &self,
+        proof: &NonMembershipProof,
+    ) -> Result<(), StateProofVerificationError> {
+        if proof.version != PROOF_FORMAT_VERSION {
+            return Err(StateProofVerificationError::UnsupportedProofVersion {
+                version: proof.version,
+            });
+        }
+        self.verify_non_membership(&proof.key, &proof.proof)
+    }
+
+    /// Verify a [`HistoricalMembershipProof`] created for the height of this consensus state,
+    /// so that the state of old blocks can be verified without keeping their headers.
+    pub fn verify_historical_membership(
+        &self,
+        proof: &HistoricalMembershipProof,
+    ) -> Result<(), HistoricalProofVerificationError> {
+        if proof.version != PROOF_FORMAT_VERSION {
+            return Err(HistoricalProofVerificationError::UnsupportedProofVersion {
+                version: proof.version,
+            });
+        }
+        if compute_root_from_path(
+            &proof.block_proof,
+            proof.block_lite_view.current_block_hash(),
+        ) != self.header.light_client_block.inner_lite.block_merkle_root
+        {
+            return Err(HistoricalProofVerificationError::InvalidBlockProof);
+        }
+        if compute_root_from_path(
+            &proof.chunk_root_proof,
+            CryptoHash::hash_borsh(&proof.chunk_root),
+        ) != proof.block_lite_view.inner_lite.prev_state_root
+        {
+            return Err(HistoricalProofVerificationError::InvalidChunkRootProof);
+        }
+        let state_proof = StateProof::new(&proof.proof)?;
+        state_proof.verify_membership(&proof.key, &proof.value, &proof.chunk_root)?;
+        Ok(())
+    }
+
+    /// Verify a [`StateValueProof`] created for the height of this consensus state.
+    pub fn verify_state_value_proof(
+        &self,
+        proof: &StateValueProof,
+    ) -> Result<(), StateProofVerificationError> {
+        match proof {
+            StateValueProof::Membership(proof) => self.verify_membership_proof(proof),
+            StateValueProof::NonMembership(proof) => self.verify_non_membership_proof(proof),
+        }
+    }
+
+    /// Verify an [`OutcomeProof`] created for the height of this consensus state.
+    pub fn verify_outcome_proof(
+        &self,
+        proof: &OutcomeProof,
+    ) -> Result<(), TransactionVerificationError> {
+        if proof.version != PROOF_FORMAT_VERSION {
+            return Err(TransactionVerificationError::UnsupportedProofVersion {
+                version: proof.version,
+            });
+        }
+        self.verify_transaction_or_receipt(
+            &proof.outcome_with_id,
+            &proof.outcome_proof,
+            &proof.outcome_root_proof,
+            &proof.block_lite_view,
+            &proof.block_proof,
+        )
+    }
+
+    /// Verify a [`StateValueProof`] created for the height of this consensus state, and
+    /// return the cost of the verification alongside the result.
+    #[cfg(feature = "cost-model")]
+    pub fn verify_state_value_proof_with_cost(
+        &self,
+        proof: &StateValueProof,
+    ) -> (
+        Result<(), StateProofVerificationError>,
+        cost_model::VerificationCost,
+    ) {
+        cost_model::measure(|| self.verify_state_value_proof(proof))
+    }
+
+    /// Verify an [`OutcomeProof`] created for the height of this consensus state, and
+    /// return the cost of the verification alongside the result.
+    #[cfg(feature = "cost-model")]
+    pub fn verify_outcome_proof_with_cost(
+        &self,
+        proof: &OutcomeProof,
+    ) -> (
+        Result<(), TransactionVerificationError>,
+        cost_model::VerificationCost,
+    ) {
+        cost_model::measure(|| self.verify_outcome_proof(proof))
+    }
+
+    /// Verify a [`HistoricalMembershipProof`] created for the height of this consensus state,
+    /// and return the cost of the verification alongside the result.
+    #[cfg(feature = "cost-model")]
+    pub fn verify_historical_membership_with_cost(
+        &self,
+        proof: &HistoricalMembershipProof,
+    ) -> (
+        Result<(), HistoricalProofVerificationError>,
+        cost_model::VerificationCost,
+    ) {
+        cost_model::measure(|| self.verify_historical_membership(proof))
+    }
 }
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(
+    HeaderVerificationError,
+    MisbehaviourVerificationError,
+    StateProofVerificationError,
+    CommitmentProofVerificationError,
+    HistoricalProofVerificationError,
+    StateChangeVerificationError,
+    TransactionVerificationError,
+    ChainIdVerificationError,
+    ClientError,
+    StorageError,
+    Error
+);
@@ -0,0 +1,289 @@
+//! Block producers of an epoch, kept in their borsh serialization until decoded.
+//!
+//! The block producers are stored in every consensus state, but most updates only hash
+//! them (to check `next_bp_hash`) or pass them on to the next consensus state. Deserializing
+//! a [`BlockProducers`] only checks the serialization is well-formed, the account ids and
+//! public keys are decoded on demand by [`BlockProducers::decoded`].
+
+#[cfg(feature = "borsh-schema")]
+use alloc::collections::BTreeMap;
+use alloc::{borrow::Cow, vec::Vec};
+use borsh::io::{Error, ErrorKind, Read, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use super::{
+    hash::{sha256, CryptoHash},
+    signature::ED25519_PUBLIC_KEY_LENGTH,
+    Balance, ValidatorStakeView,
+};
+
+/// Length of the borsh serialization of `u32` lengths.
+const LENGTH_SIZE: usize = 4;
+/// Length of the borsh serialization of stakes.
+const STAKE_SIZE: usize = 16;
+/// Size of the chunks in which account ids are read, to avoid allocating by untrusted lengths.
+const READ_CHUNK_SIZE: usize = 256;
+
+/// Block producers of an epoch, serialized the same as `Vec<ValidatorStakeView>`.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct BlockProducers(Repr);
+
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+enum Repr {
+    /// The well-formed borsh serialization of the block producers.
+    Raw(Vec<u8>),
+    Decoded(Vec<ValidatorStakeView>),
+}
+
+impl BlockProducers {
+    /// Create the block producers from their borsh serialization, which is checked to be
+    /// well-formed but not decoded.
+    pub fn from_raw(raw: Vec<u8>) -> Result<Self, Error> {
+        let mut reader = raw.as_slice();
+        let checked = read_raw(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Not all bytes read of block producers.",
+            ));
+        }
+        Ok(Self(Repr::Raw(checked)))
+    }
+
+    /// Returns whether the block producers are decoded.
+    pub fn is_decoded(&self) -> bool {
+        matches!(self.0, Repr::Decoded(_))
+    }
+
+    /// Returns the number of block producers.
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Repr::Raw(raw) => read_length(raw, 0) as usize,
+            Repr::Decoded(bps) => bps.len(),
+        }
+    }
+
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the decoded block producers, decoding them if they are not yet.
+    pub fn decoded(&self) -> Cow<'_, [ValidatorStakeView]> {
+        match &self.0 {
+            Repr::Raw(raw) => Cow::Owned(
+                Vec::<ValidatorStakeView>::try_from_slice(raw)
+                    .expect("Should not fail as every item is decoded on deserialization."),
+            ),
+            Repr::Decoded(bps) => Cow::Borrowed(bps),
+        }
+    }
+
+    ///
+    pub fn into_vec(self) -> Vec<ValidatorStakeView> {
+        match self.0 {
+            Repr::Decoded(bps) => bps,
+            Repr::Raw(_) => self.decoded().into_owned(),
+        }
+    }
+
+    /// Returns the borsh serialization of the block producers.
+    pub fn to_borsh(&self) -> Cow<'_, [u8]> {
+        match &self.0 {
+            Repr::Raw(raw) => Cow::Borrowed(raw),
+            Repr::Decoded(bps) => Cow::Owned(
+                borsh::to_vec(bps).expect("Should not fail based on borsh serialization."),
+            ),
+        }
+    }
+
+    /// Returns the sha256 hash of the borsh serialization, which is the `next_bp_hash`
+    /// of the last block of the previous epoch.
+    pub fn hash(&self) -> CryptoHash {
+        CryptoHash(sha256(&self.to_borsh()))
+    }
+
+    /// Returns the sum of the stakes of the block producers, without decoding them.
+    pub fn total_stake(&self) -> Balance {
+        match &self.0 {
+            Repr::Raw(raw) => raw_stakes(raw).fold(0, u128::saturating_add),
+            Repr::Decoded(bps) => bps
+                .iter()
+                .map(|bp| bp.as_validator_stake().stake)
+                .fold(0, u128::saturating_add),
+        }
+    }
+}
+
+impl From<Vec<ValidatorStakeView>> for BlockProducers {
+    fn from(bps: Vec<ValidatorStakeView>) -> Self {
+        Self(Repr::Decoded(bps))
+    }
+}
+
+impl FromIterator<ValidatorStakeView> for BlockProducers {
+    fn from_iter<I: IntoIterator<Item = ValidatorStakeView>>(iter: I) -> Self {
+        Self(Repr::Decoded(iter.into_iter().collect()))
+    }
+}
+
+impl From<BlockProducers> for Vec<ValidatorStakeView> {
+    fn from(bps: BlockProducers) -> Self {
+        bps.into_vec()
+    }
+}
+
+impl BorshSerialize for BlockProducers {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        match &self.0 {
+            Repr::Raw(raw) => writer.write_all(raw),
+            Repr::Decoded(bps) => BorshSerialize::serialize(bps, writer),
+        }
+    }
+}
+
+impl BorshDeserialize for BlockProducers {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        read_raw(reader).map(|raw| Self(Repr::Raw(raw)))
+    }
+}
+
+/// Read the borsh serialization of a `Vec<ValidatorStakeView>` from `reader`.
+///
+/// Every item is framed by its tags and lengths, then decoded with the same deserialization
+/// as [`BlockProducers::decoded`], so that decoding the returned bytes can not fail.
+fn read_raw<R: Read>(reader: &mut R) -> Result<Vec<u8>, Error> {
+    let mut raw = Vec::new();
+    let offset = read_exact(reader, &mut raw, LENGTH_SIZE)?;
+    for _ in 0..read_length(&raw, offset) {
+        let item = read_exact(reader, &mut raw, 1)?;
+        check_tag(&raw, item, "validator stake view")?;
+        let offset = read_exact(reader, &mut raw, LENGTH_SIZE)?;
+        let account_id_length = read_length(&raw, offset) as usize;
+        read_exact(reader, &mut raw, account_id_length)?;
+        let offset = read_exact(reader, &mut raw, 1)?;
+        check_tag(&raw, offset, "public key")?;
+        read_exact(reader, &mut raw, ED25519_PUBLIC_KEY_LENGTH + STAKE_SIZE)?;
+        ValidatorStakeView::try_from_slice(&raw[item..])?;
+    }
+    Ok(raw)
+}
+
+/// Read `len` bytes from `reader` to the end of `raw`, returning the offset of them in `raw`.
+fn read_exact<R: Read>(reader: &mut R, raw: &mut Vec<u8>, len: usize) -> Result<usize, Error> {
+    let offset = raw.len();
+    let mut remaining = len;
+    while remaining > 0 {
+        let start = raw.len();
+        let chunk = remaining.min(READ_CHUNK_SIZE);
+        raw.resize(start + chunk, 0);
+        reader.read_exact(&mut raw[start..])?;
+        remaining -= chunk;
+    }
+    Ok(offset)
+}
+
+/// Check the enum tag at `offset` is the only supported variant.
+fn check_tag(raw: &[u8], offset: usize, name: &str) -> Result<(), Error> {
+    if raw[offset] != 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            alloc::format!("Unsupported variant {} of {}.", raw[offset], name),
+        ));
+    }
+    Ok(())
+}
+
+fn read_length(raw: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(
+        raw[offset..offset + LENGTH_SIZE]
+            .try_into()
+            .expect("Should not fail based on the size of `u32`."),
+    )
+}
+
+/// Returns the stakes in the well-formed borsh serialization `raw`.
+fn raw_stakes(raw: &[u8]) -> impl Iterator<Item = Balance> + '_ {
+    let mut offset = LENGTH_SIZE;
+    (0..read_length(raw, 0)).map(move |_| {
+        let account_id_length = read_length(raw, offset + 1) as usize;
+        offset += 1 + LENGTH_SIZE + account_id_length + 1 + ED25519_PUBLIC_KEY_LENGTH;
+        let stake = u128::from_le_bytes(
+            raw[offset..offset + STAKE_SIZE]
+                .try_into()
+                .expect("Should not fail based on the size of `u128`."),
+        );
+        offset += STAKE_SIZE;
+        stake
+    })
+}
+
+#[cfg(feature = "borsh-schema")]
+impl borsh::BorshSchema for BlockProducers {
+    fn add_definitions_recursively(
+        definitions: &mut BTreeMap<borsh::schema::Declaration, borsh::schema::Definition>,
+    ) {
+        <Vec<ValidatorStakeView>>::add_definitions_recursively(definitions);
+    }
+
+    fn declaration() -> borsh::schema::Declaration {
+        <Vec<ValidatorStakeView>>::declaration()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for BlockProducers {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.decoded().iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BlockProducers {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Vec<ValidatorStakeView> as serde::Deserialize>::deserialize(deserializer).map(Into::into)
+    }
+}
+
+#[cfg(feature = "scale")]
+impl codec::Encode for BlockProducers {
+    fn encode_to<T: codec::Output + ?Sized>(&self, dest: &mut T) {
+        self.decoded().as_ref().encode_to(dest)
+    }
+}
+
+#[cfg(feature = "scale")]
+impl codec::EncodeLike for BlockProducers {}
+
+#[cfg(feature = "scale")]
+impl codec::Decode for BlockProducers {
+    fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+        <Vec<ValidatorStakeView>>::decode(input).map(Into::into)
+    }
+}
+
+#[cfg(feature = "scale")]
+impl scale_info::TypeInfo for BlockProducers {
+    type Identity = Self;
+
+    fn type_info() -> scale_info::Type {
+        <Vec<ValidatorStakeView>>::type_info()
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> arbitrary::Arbitrary<'a> for BlockProducers {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bps = Vec::<ValidatorStakeView>::arbitrary(u)?;
+        if u.arbitrary()? {
+            let raw = borsh::to_vec(&bps).expect("Should not fail based on borsh serialization.");
+            return Ok(Self(Repr::Raw(raw)));
+        }
+        Ok(bps.into())
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(BlockProducers, Repr);
@@ -1,9 +1,15 @@
 use alloc::string::{String, ToString};
 use borsh::{BorshDeserialize, BorshSerialize};
-use core::fmt::{self, Debug, Display};
+use core::fmt::{self, Display};
 use sha256::digest;
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, BorshDeserialize, BorshSerialize, Hash)]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct CryptoHash(pub [u8; 32]);
 
 impl CryptoHash {
@@ -17,15 +23,14 @@ impl CryptoHash {
     }
     /// Calculates hash of given bytes.
     pub fn hash_bytes(bytes: &[u8]) -> CryptoHash {
-        CryptoHash(hex::decode(digest(bytes)).unwrap().try_into().unwrap())
+        CryptoHash(sha256(bytes))
     }
     /// Calculates hash of borsh-serialised representation of an object.
     ///
     /// Note that if you have a slice of objects to serialise, you might
     /// prefer using [`Self::hash_borsh_slice`] instead.
     pub fn hash_borsh<T: BorshSerialize>(value: &T) -> CryptoHash {
-        let hash_str = digest(borsh::to_vec(value).unwrap());
-        CryptoHash(hex::decode(hash_str).unwrap().try_into().unwrap())
+        CryptoHash(sha256(&borsh::to_vec(value).unwrap()))
     }
 }
 
@@ -53,22 +58,46 @@ impl TryFrom<&[u8]> for CryptoHash {
     }
 }
 
-impl Debug for CryptoHash {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+#[cfg(not(feature = "strip-debug"))]
+impl fmt::Debug for CryptoHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&bs58::encode(self.0).into_string(), f)
     }
 }
 
 impl Display for CryptoHash {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        fmt::Display::fmt(&bs58::encode(self.0).into_string(), f)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&bs58::encode(self.0).into_string(), f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CryptoHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bs58::encode(self.0).into_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CryptoHash {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let bytes = bs58::decode(encoded)
+            .into_vec()
+            .map_err(serde::de::Error::custom)?;
+        CryptoHash::try_from(bytes.as_slice()).map_err(serde::de::Error::custom)
     }
 }
 
 pub fn sha256(data: &[u8]) -> [u8; 32] {
+    #[cfg(feature = "cost-model")]
+    crate::cost_model::record_hash(data.len());
     hex::decode(digest(data)).unwrap().try_into().unwrap()
 }
 
 pub fn combine_hash(hash1: &CryptoHash, hash2: &CryptoHash) -> CryptoHash {
     CryptoHash(sha256(&[hash1.0.as_ref(), hash2.0.as_ref()].concat()))
 }
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(CryptoHash);
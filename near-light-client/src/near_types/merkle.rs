@@ -5,13 +5,29 @@ use borsh::{BorshDeserialize, BorshSerialize};
 /// Hash used by a struct implementing the Merkle tree.
 pub type MerkleHash = CryptoHash;
 
-#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub enum Direction {
     Left,
     Right,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct MerklePathItem {
     pub hash: MerkleHash,
     pub direction: Direction,
@@ -19,6 +35,128 @@ pub struct MerklePathItem {
 
 pub type MerklePath = Vec<MerklePathItem>;
 
+/// Merkle tree that only maintains the path for the next leaf, i.e,
+/// when a new leaf is inserted, the existing `path` is its proof.
+/// The root can be computed by folding `path` from right but is not explicitly
+/// maintained to save space.
+/// The size of the object is O(log(n)) where n is the number of leaves in the tree, i.e, `size`.
+///
+/// The `path` always has one element for each bit set in `size`, which is checked when the tree
+/// is created from existing data with `new` or any of the decodings.
+#[derive(Default, Clone, PartialEq, Eq, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "scale", derive(codec::Encode, scale_info::TypeInfo))]
+pub struct PartialMerkleTree {
+    /// Path for the next leaf.
+    path: Vec<MerkleHash>,
+    /// Number of leaves in the tree.
+    size: u64,
+}
+
+impl PartialMerkleTree {
+    /// Create a tree from an existing path and leaf count, e.g. the `PartialMerkleTree`
+    /// stored by a NEAR node for a certain block.
+    ///
+    /// Returns `None` if the length of `path` is not the count of bits set in `size`.
+    pub fn new(path: Vec<MerkleHash>, size: u64) -> Option<Self> {
+        (path.len() == size.count_ones() as usize).then_some(Self { path, size })
+    }
+
+    /// Returns the root of the tree, which is the `block_merkle_root` of the next block
+    /// if the leaves are the block hashes of the chain.
+    pub fn root(&self) -> MerkleHash {
+        if self.path.is_empty() {
+            CryptoHash::default()
+        } else {
+            let mut res = *self.path.last().unwrap();
+            let len = self.path.len();
+            for i in (0..len - 1).rev() {
+                res = combine_hash(&self.path[i], &res);
+            }
+            res
+        }
+    }
+
+    /// Append a new leaf to the tree.
+    pub fn insert(&mut self, elem: MerkleHash) {
+        let mut s = self.size;
+        let mut node = elem;
+        while s % 2 == 1 {
+            let last_path_elem = self
+                .path
+                .pop()
+                .expect("Should not fail as the path has an element for each bit set in size.");
+            node = combine_hash(&last_path_elem, &node);
+            s /= 2;
+        }
+        self.path.push(node);
+        self.size += 1;
+    }
+
+    /// Returns the number of leaves in the tree.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the path for the next leaf.
+    pub fn get_path(&self) -> &[MerkleHash] {
+        &self.path
+    }
+
+    /// Verify that `item_hash` is a leaf of the tree with the given merkle path.
+    pub fn verify_hash(&self, path: &MerklePath, item_hash: MerkleHash) -> bool {
+        verify_hash(self.root(), path, item_hash)
+    }
+}
+
+impl BorshDeserialize for PartialMerkleTree {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let path = Vec::<MerkleHash>::deserialize_reader(reader)?;
+        let size = u64::deserialize_reader(reader)?;
+        Self::new(path, size).ok_or_else(|| {
+            borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, INCONSISTENT_TREE)
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PartialMerkleTree {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct RawPartialMerkleTree {
+            path: Vec<MerkleHash>,
+            size: u64,
+        }
+        let raw = <RawPartialMerkleTree as serde::Deserialize>::deserialize(deserializer)?;
+        Self::new(raw.path, raw.size).ok_or_else(|| serde::de::Error::custom(INCONSISTENT_TREE))
+    }
+}
+
+#[cfg(feature = "scale")]
+impl codec::Decode for PartialMerkleTree {
+    fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let path = Vec::<MerkleHash>::decode(input)?;
+        let size = u64::decode(input)?;
+        Self::new(path, size).ok_or_else(|| INCONSISTENT_TREE.into())
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> arbitrary::Arbitrary<'a> for PartialMerkleTree {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let size = u64::from(u.arbitrary::<u32>()?);
+        let path = (0..size.count_ones())
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<Vec<MerkleHash>>>()?;
+        Ok(Self { path, size })
+    }
+}
+
+/// Error message of decoding a `PartialMerkleTree` whose path doesn't match its size.
+const INCONSISTENT_TREE: &str = "the path length of PartialMerkleTree doesn't match its size";
+
 /// Merklize an array of items. If the array is empty, returns hash of 0
 pub fn merklize<T: BorshSerialize>(arr: &[T]) -> (MerkleHash, Vec<MerklePath>) {
     if arr.is_empty() {
@@ -94,6 +232,74 @@ pub fn merklize<T: BorshSerialize>(arr: &[T]) -> (MerkleHash, Vec<MerklePath>) {
     (hashes[0], paths)
 }
 
+/// Returns the merkle path of the item at `index` of `arr`, which is the same
+/// as the corresponding element of the paths returned by `merklize`.
+pub fn merkle_path_of<T: BorshSerialize>(arr: &[T], index: usize) -> Option<MerklePath> {
+    if index >= arr.len() {
+        return None;
+    }
+    let (_, mut paths) = merklize(arr);
+    Some(paths.swap_remove(index))
+}
+
+/// Compute the root of the block merkle tree of the given block hashes.
+///
+/// The result is the same as the root of a `PartialMerkleTree` which has all
+/// `block_hashes` inserted in order.
+pub fn compute_block_merkle_root(block_hashes: &[CryptoHash]) -> MerkleHash {
+    match block_hashes.len() {
+        0 => MerkleHash::default(),
+        1 => block_hashes[0],
+        len => {
+            let split = split_point_of_block_merkle_tree(len);
+            combine_hash(
+                &compute_block_merkle_root(&block_hashes[..split]),
+                &compute_block_merkle_root(&block_hashes[split..]),
+            )
+        }
+    }
+}
+
+/// Returns the merkle path of the block hash at `index` of `block_hashes`,
+/// which can be verified with the root returned by `compute_block_merkle_root`.
+///
+/// This is the shape of the `block_proof` returned by NEAR RPC `light_client_proof`.
+pub fn compute_block_merkle_path(block_hashes: &[CryptoHash], index: usize) -> Option<MerklePath> {
+    if index >= block_hashes.len() {
+        return None;
+    }
+    if block_hashes.len() == 1 {
+        return Some(vec![]);
+    }
+    let split = split_point_of_block_merkle_tree(block_hashes.len());
+    let (left, right) = block_hashes.split_at(split);
+    if index < split {
+        let mut path = compute_block_merkle_path(left, index)?;
+        path.push(MerklePathItem {
+            hash: compute_block_merkle_root(right),
+            direction: Direction::Right,
+        });
+        Some(path)
+    } else {
+        let mut path = compute_block_merkle_path(right, index - split)?;
+        path.push(MerklePathItem {
+            hash: compute_block_merkle_root(left),
+            direction: Direction::Left,
+        });
+        Some(path)
+    }
+}
+
+/// The left subtree of a block merkle tree is always the largest perfect tree
+/// which has less leaves than the whole tree.
+fn split_point_of_block_merkle_tree(len: usize) -> usize {
+    let mut split = 1;
+    while split * 2 < len {
+        split *= 2;
+    }
+    split
+}
+
 /// Verify merkle path for given item and corresponding path.
 pub fn verify_path<T: BorshSerialize>(root: MerkleHash, path: &MerklePath, item: &T) -> bool {
     verify_hash(root, path, CryptoHash::hash_borsh(&item))
@@ -117,3 +323,6 @@ pub fn compute_root_from_path(path: &MerklePath, item_hash: MerkleHash) -> Merkl
     }
     res
 }
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(Direction, MerklePathItem, PartialMerkleTree);
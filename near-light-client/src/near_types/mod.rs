@@ -3,34 +3,47 @@
 //! Most of the codes in this module are ported from `nearcore` v1.30.0
 //! and are applied by necessary changes to remove std dependencies.
 use self::{
+    block_producers::BlockProducers,
     hash::{combine_hash, sha256, CryptoHash},
     signature::{PublicKey, Signature},
 };
 use alloc::{string::String, vec::Vec};
 use borsh::{BorshDeserialize, BorshSerialize};
 
+pub mod block_producers;
 pub mod hash;
 pub mod merkle;
+pub mod sdk_collections;
 pub mod signature;
 pub mod transaction;
 pub mod trie;
+pub mod trie_key;
 
 /// This column id is used when storing Key-Value data from a contract on an `account_id`.
-pub const CONTRACT_DATA: u8 = 9;
-pub const ACCOUNT_DATA_SEPARATOR: u8 = b',';
+pub const CONTRACT_DATA: u8 = trie_key::col::CONTRACT_DATA;
+pub const ACCOUNT_DATA_SEPARATOR: u8 = trie_key::ACCOUNT_DATA_SEPARATOR;
 
 pub type BlockHeight = u64;
 pub type AccountId = String;
 pub type Balance = u128;
 pub type MerkleHash = CryptoHash;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
 pub enum BlockId {
     Height(BlockHeight),
     Hash(CryptoHash),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct LightClientBlockLite {
     pub inner_lite: BlockHeaderInnerLite,
     pub inner_rest_hash: CryptoHash,
@@ -54,10 +67,26 @@ impl LightClientBlockLite {
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Eq, PartialEq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct EpochId(pub CryptoHash);
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, Eq, PartialEq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct BlockHeaderInnerLite {
     /// Height of this block.
     pub height: BlockHeight,
@@ -77,69 +106,148 @@ pub struct BlockHeaderInnerLite {
     pub block_merkle_root: CryptoHash,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct ValidatorStakeViewV1 {
     pub account_id: AccountId,
     pub public_key: PublicKey,
+    #[cfg_attr(feature = "serde", serde(with = "dec_format"))]
     pub stake: Balance,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "validator_stake_struct_version"))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub enum ValidatorStakeView {
     V1(ValidatorStakeViewV1),
 }
 
+/// Serialize balances as decimal strings, the same as the JSON rpc of NEAR.
+#[cfg(feature = "serde")]
+mod dec_format {
+    use alloc::string::{String, ToString};
+
+    use super::Balance;
+
+    pub fn serialize<S: serde::Serializer>(
+        value: &Balance,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Balance, D::Error> {
+        <String as serde::Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl ValidatorStakeView {
     pub fn into_validator_stake(self) -> ValidatorStakeViewV1 {
         match self {
             Self::V1(inner) => inner,
         }
     }
+    //
+    pub fn as_validator_stake(&self) -> &ValidatorStakeViewV1 {
+        match self {
+            Self::V1(inner) => inner,
+        }
+    }
 }
 
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct LightClientBlock {
     pub prev_block_hash: CryptoHash,
     pub next_block_inner_hash: CryptoHash,
     pub inner_lite: BlockHeaderInnerLite,
     pub inner_rest_hash: CryptoHash,
-    pub next_bps: Option<Vec<ValidatorStakeView>>,
+    pub next_bps: Option<BlockProducers>,
     pub approvals_after_next: Vec<Option<Signature>>,
 }
 
 impl LightClientBlock {
-    //
-    pub fn current_block_hash(&self) -> CryptoHash {
+    /// Returns the hash of the inner parts (`inner_lite` and `inner_rest`) of the block,
+    /// which is the `next_block_inner_hash` of the previous block.
+    pub fn inner_hash(&self) -> CryptoHash {
         combine_hash(
-            &combine_hash(
-                &CryptoHash(sha256(borsh::to_vec(&self.inner_lite).unwrap().as_ref())),
-                &self.inner_rest_hash,
-            ),
-            &self.prev_block_hash,
+            &CryptoHash(sha256(borsh::to_vec(&self.inner_lite).unwrap().as_ref())),
+            &self.inner_rest_hash,
         )
     }
     //
+    pub fn current_block_hash(&self) -> CryptoHash {
+        combine_hash(&self.inner_hash(), &self.prev_block_hash)
+    }
+    //
     pub fn next_block_hash(&self) -> CryptoHash {
         combine_hash(&self.next_block_inner_hash, &self.current_block_hash())
     }
-    //
+    /// Returns the message signed by the block producers in `approvals_after_next`.
     pub fn approval_message(&self) -> Vec<u8> {
-        [
-            borsh::to_vec(&ApprovalInner::Endorsement(self.next_block_hash()))
-                .unwrap()
-                .as_ref(),
-            (self.inner_lite.height + 2).to_le_bytes().as_ref(),
-        ]
-        .concat()
+        self.approval_message_with_hash(&self.current_block_hash())
+    }
+    /// Returns the message signed by the block producers in `approvals_after_next`, given
+    /// `block_hash` as the hash of this block, e.g. of a sealed header.
+    pub fn approval_message_with_hash(&self, block_hash: &CryptoHash) -> Vec<u8> {
+        approval_message_for(
+            &combine_hash(&self.next_block_inner_hash, block_hash),
+            self.inner_lite.height + 2,
+        )
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub enum ApprovalInner {
     Endorsement(CryptoHash),
     Skip(BlockHeight),
 }
 
+/// Returns the message signed by a block producer to endorse the block with `next_block_hash`
+/// at `target_height`, which is the height of the block after the next one.
+pub fn approval_message_for(next_block_hash: &CryptoHash, target_height: BlockHeight) -> Vec<u8> {
+    [
+        borsh::to_vec(&ApprovalInner::Endorsement(*next_block_hash))
+            .unwrap()
+            .as_ref(),
+        target_height.to_le_bytes().as_ref(),
+    ]
+    .concat()
+}
+
 pub fn get_raw_prefix_for_contract_data(account_id: &AccountId, prefix: &[u8]) -> Vec<u8> {
     let mut res = Vec::with_capacity(1 + account_id.as_bytes().len() + 1 + prefix.len());
     res.push(CONTRACT_DATA);
@@ -148,3 +256,15 @@ pub fn get_raw_prefix_for_contract_data(account_id: &AccountId, prefix: &[u8]) -
     res.extend(prefix);
     res
 }
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(
+    BlockId,
+    LightClientBlockLite,
+    EpochId,
+    BlockHeaderInnerLite,
+    ValidatorStakeViewV1,
+    ValidatorStakeView,
+    LightClientBlock,
+    ApprovalInner
+);
@@ -0,0 +1,91 @@
+//! Helpers to construct the raw trie keys of the entries of `near-sdk` collections.
+//!
+//! The layouts follow the collections in `near_sdk::collections` (and the default
+//! `Identity` hasher of `near_sdk::store::LookupMap`), in which every entry is stored
+//! under the collection prefix followed by a suffix derived from the key or index.
+
+use super::{get_raw_prefix_for_contract_data, AccountId};
+use alloc::vec::Vec;
+use borsh::BorshSerialize;
+
+/// Suffix of the prefix of the key index map in `UnorderedMap` and `UnorderedSet`.
+const INDEX_SUFFIX: u8 = b'i';
+/// Suffix of the prefix of the keys vector in `UnorderedMap`.
+const KEYS_SUFFIX: u8 = b'k';
+/// Suffix of the prefix of the values vector in `UnorderedMap`.
+const VALUES_SUFFIX: u8 = b'v';
+/// Suffix of the prefix of the elements vector in `UnorderedSet`.
+const ELEMENTS_SUFFIX: u8 = b'e';
+
+/// Returns the raw trie key of the entry of `key` in a `LookupMap` with `prefix`.
+pub fn lookup_map_entry_key<K: BorshSerialize>(
+    account_id: &AccountId,
+    prefix: &[u8],
+    key: &K,
+) -> Vec<u8> {
+    collection_key(account_id, prefix, &[], &borsh_bytes(key))
+}
+
+/// Returns the raw trie key of `element` in a `LookupSet` with `prefix`.
+pub fn lookup_set_entry_key<T: BorshSerialize>(
+    account_id: &AccountId,
+    prefix: &[u8],
+    element: &T,
+) -> Vec<u8> {
+    collection_key(account_id, prefix, &[], &borsh_bytes(element))
+}
+
+/// Returns the raw trie key of the element at `index` in a `Vector` with `prefix`.
+pub fn vector_element_key(account_id: &AccountId, prefix: &[u8], index: u64) -> Vec<u8> {
+    collection_key(account_id, prefix, &[], &index.to_le_bytes())
+}
+
+/// Returns the raw trie key of the value of a `LazyOption` with `prefix`.
+pub fn lazy_option_key(account_id: &AccountId, prefix: &[u8]) -> Vec<u8> {
+    collection_key(account_id, prefix, &[], &[])
+}
+
+/// Returns the raw trie key of the index of `key` in an `UnorderedMap` with `prefix`.
+///
+/// The value of this key is the borsh serialized `u64` index of the entry, which can be
+/// used with `unordered_map_key_key` and `unordered_map_value_key`.
+pub fn unordered_map_index_key<K: BorshSerialize>(
+    account_id: &AccountId,
+    prefix: &[u8],
+    key: &K,
+) -> Vec<u8> {
+    collection_key(account_id, prefix, &[INDEX_SUFFIX], &borsh_bytes(key))
+}
+
+/// Returns the raw trie key of the key at `index` in an `UnorderedMap` with `prefix`.
+pub fn unordered_map_key_key(account_id: &AccountId, prefix: &[u8], index: u64) -> Vec<u8> {
+    collection_key(account_id, prefix, &[KEYS_SUFFIX], &index.to_le_bytes())
+}
+
+/// Returns the raw trie key of the value at `index` in an `UnorderedMap` with `prefix`.
+pub fn unordered_map_value_key(account_id: &AccountId, prefix: &[u8], index: u64) -> Vec<u8> {
+    collection_key(account_id, prefix, &[VALUES_SUFFIX], &index.to_le_bytes())
+}
+
+/// Returns the raw trie key of the index of `element` in an `UnorderedSet` with `prefix`.
+pub fn unordered_set_index_key<T: BorshSerialize>(
+    account_id: &AccountId,
+    prefix: &[u8],
+    element: &T,
+) -> Vec<u8> {
+    collection_key(account_id, prefix, &[INDEX_SUFFIX], &borsh_bytes(element))
+}
+
+/// Returns the raw trie key of the element at `index` in an `UnorderedSet` with `prefix`.
+pub fn unordered_set_element_key(account_id: &AccountId, prefix: &[u8], index: u64) -> Vec<u8> {
+    collection_key(account_id, prefix, &[ELEMENTS_SUFFIX], &index.to_le_bytes())
+}
+
+fn borsh_bytes<T: BorshSerialize>(value: &T) -> Vec<u8> {
+    borsh::to_vec(value).expect("Should not fail based on borsh serialization.")
+}
+
+fn collection_key(account_id: &AccountId, prefix: &[u8], suffix: &[u8], key: &[u8]) -> Vec<u8> {
+    let storage_key = [prefix, suffix, key].concat();
+    get_raw_prefix_for_contract_data(account_id, &storage_key)
+}
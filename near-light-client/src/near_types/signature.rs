@@ -1,27 +1,52 @@
+#[cfg(feature = "serde")]
+use alloc::string::String;
+#[cfg(feature = "borsh-schema")]
+use alloc::{collections::BTreeMap, vec};
 use alloc::{format, string::ToString, vec::Vec};
 use borsh::io::{Error, ErrorKind, Write};
 use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(feature = "ed25519-dalek")]
 use ed25519_dalek::Verifier;
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct ED25519PublicKey(pub [u8; ed25519_dalek::PUBLIC_KEY_LENGTH]);
+/// Length of ed25519 public keys in bytes.
+pub const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
+/// Length of ed25519 signatures in bytes.
+pub const ED25519_SIGNATURE_LENGTH: usize = 64;
 
-#[derive(Debug, Clone)]
-pub struct Secp256K1PublicKey([u8; 64]);
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct ED25519PublicKey(pub [u8; ED25519_PUBLIC_KEY_LENGTH]);
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub enum PublicKey {
     /// 256 bit elliptic curve based public-key.
     ED25519(ED25519PublicKey),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
 pub enum KeyType {
     ED25519 = 0,
 }
 
 /// Signature container supporting different curves.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub enum Signature {
     ED25519(Vec<u8>),
 }
@@ -29,6 +54,7 @@ pub enum Signature {
 impl Signature {
     /// Verifies that this signature is indeed signs the data with given public key.
     /// Also if public key doesn't match on the curve returns `false`.
+    #[cfg(feature = "ed25519-dalek")]
     pub fn verify(&self, data: &[u8], public_key: &PublicKey) -> bool {
         match (&self, public_key) {
             (Signature::ED25519(sig_bytes), PublicKey::ED25519(public_key)) => {
@@ -47,6 +73,148 @@ impl Signature {
     }
 }
 
+/// Verifier of the signatures of block producers.
+///
+/// The default [`Ed25519DalekVerifier`] verifies signatures with `ed25519-dalek`, which
+/// can be replaced by a verifier using the ed25519 precompile of a zkVM, for example.
+pub trait SignatureVerifier: Sync {
+    /// Verifies that `signature` signs `data` with `public_key`.
+    fn verify(&self, signature: &Signature, data: &[u8], public_key: &PublicKey) -> bool;
+}
+
+/// Verifier of signatures based on `ed25519-dalek`.
+#[cfg(feature = "ed25519-dalek")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ed25519DalekVerifier;
+
+#[cfg(feature = "ed25519-dalek")]
+impl SignatureVerifier for Ed25519DalekVerifier {
+    fn verify(&self, signature: &Signature, data: &[u8], public_key: &PublicKey) -> bool {
+        signature.verify(data, public_key)
+    }
+}
+
+#[cfg(feature = "borsh-schema")]
+impl borsh::BorshSchema for PublicKey {
+    fn add_definitions_recursively(
+        definitions: &mut BTreeMap<borsh::schema::Declaration, borsh::schema::Definition>,
+    ) {
+        <[u8; ED25519_PUBLIC_KEY_LENGTH]>::add_definitions_recursively(definitions);
+        borsh::schema::add_definition(
+            Self::declaration(),
+            borsh::schema::Definition::Enum {
+                tag_width: 1,
+                variants: vec![(
+                    KeyType::ED25519 as i64,
+                    "ED25519".to_string(),
+                    <[u8; ED25519_PUBLIC_KEY_LENGTH]>::declaration(),
+                )],
+            },
+            definitions,
+        );
+    }
+
+    fn declaration() -> borsh::schema::Declaration {
+        "PublicKey".to_string()
+    }
+}
+
+#[cfg(feature = "borsh-schema")]
+impl borsh::BorshSchema for Signature {
+    fn add_definitions_recursively(
+        definitions: &mut BTreeMap<borsh::schema::Declaration, borsh::schema::Definition>,
+    ) {
+        <[u8; ED25519_SIGNATURE_LENGTH]>::add_definitions_recursively(definitions);
+        borsh::schema::add_definition(
+            Self::declaration(),
+            borsh::schema::Definition::Enum {
+                tag_width: 1,
+                variants: vec![(
+                    KeyType::ED25519 as i64,
+                    "ED25519".to_string(),
+                    <[u8; ED25519_SIGNATURE_LENGTH]>::declaration(),
+                )],
+            },
+            definitions,
+        );
+    }
+
+    fn declaration() -> borsh::schema::Declaration {
+        "Signature".to_string()
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> arbitrary::Arbitrary<'a> for Signature {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bytes: [u8; ED25519_SIGNATURE_LENGTH] = u.arbitrary()?;
+        Ok(Signature::ED25519(bytes.to_vec()))
+    }
+}
+
+/// Prefix of the string representation of ED25519 public keys and signatures.
+#[cfg(feature = "serde")]
+const ED25519_PREFIX: &str = "ed25519:";
+
+#[cfg(feature = "serde")]
+fn decode_ed25519_str<E: serde::de::Error>(encoded: &str) -> Result<Vec<u8>, E> {
+    let data = encoded
+        .strip_prefix(ED25519_PREFIX)
+        .ok_or_else(|| E::custom(format!("unsupported key type of {}", encoded)))?;
+    bs58::decode(data).into_vec().map_err(E::custom)
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PublicKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PublicKey::ED25519(public_key) => serializer.serialize_str(&format!(
+                "{}{}",
+                ED25519_PREFIX,
+                bs58::encode(public_key.0).into_string()
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PublicKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let bytes: [u8; ED25519_PUBLIC_KEY_LENGTH] = decode_ed25519_str::<D::Error>(&encoded)?
+            .try_into()
+            .map_err(|_| serde::de::Error::custom("invalid length of ED25519 public key"))?;
+        Ok(PublicKey::ED25519(ED25519PublicKey(bytes)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Signature {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Signature::ED25519(signature) => serializer.serialize_str(&format!(
+                "{}{}",
+                ED25519_PREFIX,
+                bs58::encode(signature).into_string()
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Signature {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let bytes = decode_ed25519_str::<D::Error>(&encoded)?;
+        if bytes.len() != ED25519_SIGNATURE_LENGTH {
+            return Err(serde::de::Error::custom(
+                "invalid length of ED25519 signature",
+            ));
+        }
+        Ok(Signature::ED25519(bytes))
+    }
+}
+
 impl TryFrom<u8> for KeyType {
     type Error = Error;
 
@@ -103,10 +271,13 @@ impl BorshDeserialize for Signature {
             .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
         match key_type {
             KeyType::ED25519 => {
-                let array: [u8; ed25519_dalek::SIGNATURE_LENGTH] =
+                let array: [u8; ED25519_SIGNATURE_LENGTH] =
                     BorshDeserialize::deserialize_reader(reader)?;
                 Ok(Signature::ED25519(array.to_vec()))
             }
         }
     }
 }
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(ED25519PublicKey, PublicKey, KeyType, Signature);
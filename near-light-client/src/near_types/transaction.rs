@@ -1,4 +1,7 @@
-use super::{AccountId, Balance};
+use super::{
+    merkle::{merklize, MerkleHash, MerklePath},
+    AccountId, Balance,
+};
 use crate::CryptoHash;
 use alloc::{string::String, vec::Vec};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -8,7 +11,15 @@ pub type LogEntry = String;
 pub type Gas = u64;
 
 /// The status of execution for a transaction or a receipt.
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub enum ExecutionStatus {
     /// The execution is pending or unknown.
     Unknown,
@@ -22,7 +33,15 @@ pub enum ExecutionStatus {
 }
 
 /// Execution outcome for one signed transaction or one receipt.
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct ExecutionOutcome {
     /// Logs from this transaction or receipt.
     pub logs: Vec<LogEntry>,
@@ -44,7 +63,9 @@ pub struct ExecutionOutcome {
 }
 
 /// ExecutionOutcome for proof. Excludes logs and metadata
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
 struct PartialExecutionOutcome {
     pub receipt_ids: Vec<CryptoHash>,
     pub gas_burnt: Gas,
@@ -66,7 +87,9 @@ impl From<&ExecutionOutcome> for PartialExecutionOutcome {
 }
 
 /// ExecutionStatus for proof. Excludes failure debug info.
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
 pub enum PartialExecutionStatus {
     Unknown,
     Failure,
@@ -88,7 +111,15 @@ impl From<ExecutionStatus> for PartialExecutionStatus {
 /// Execution outcome with the identifier.
 /// For a signed transaction, the ID is the hash of the transaction.
 /// For a receipt, the ID is the receipt ID.
-#[derive(PartialEq, Clone, Debug, BorshSerialize, BorshDeserialize, Eq)]
+#[derive(PartialEq, Clone, BorshSerialize, BorshDeserialize, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct ExecutionOutcomeWithId {
     /// The transaction hash or the receipt ID.
     pub id: CryptoHash,
@@ -112,3 +143,64 @@ impl ExecutionOutcomeWithId {
         result
     }
 }
+
+/// The proof data of an execution outcome, in the shape expected by
+/// `ConsensusState::verify_transaction_or_receipt`.
+#[derive(PartialEq, Clone, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct OutcomeProofs {
+    /// Proof of the outcome in the outcomes of its chunk.
+    pub outcome_proof: MerklePath,
+    /// Proof of the outcome root of the chunk in the outcome root of the block.
+    pub outcome_root_proof: MerklePath,
+    /// The `outcome_root` of the block.
+    pub outcome_root: MerkleHash,
+}
+
+/// Build the proofs of the outcome at `outcome_index` of the chunk at `chunk_index`,
+/// from all outcomes of all chunks in a block.
+///
+/// This is useful to construct valid proof data in tests without a live RPC node.
+pub fn build_outcome_proofs(
+    chunk_outcomes: &[Vec<ExecutionOutcomeWithId>],
+    chunk_index: usize,
+    outcome_index: usize,
+) -> Option<OutcomeProofs> {
+    let outcome_hashes = chunk_outcomes
+        .get(chunk_index)?
+        .iter()
+        .map(|outcome| outcome.to_hashes())
+        .collect::<Vec<_>>();
+    if outcome_index >= outcome_hashes.len() {
+        return None;
+    }
+    let (_, mut outcome_paths) = merklize(&outcome_hashes);
+    let chunk_outcome_roots = chunk_outcomes
+        .iter()
+        .map(|outcomes| {
+            merklize(
+                &outcomes
+                    .iter()
+                    .map(|outcome| outcome.to_hashes())
+                    .collect::<Vec<_>>(),
+            )
+            .0
+        })
+        .collect::<Vec<_>>();
+    let (outcome_root, mut outcome_root_paths) = merklize(&chunk_outcome_roots);
+    Some(OutcomeProofs {
+        outcome_proof: outcome_paths.swap_remove(outcome_index),
+        outcome_root_proof: outcome_root_paths.swap_remove(chunk_index),
+        outcome_root,
+    })
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(
+    ExecutionStatus,
+    ExecutionOutcome,
+    PartialExecutionOutcome,
+    PartialExecutionStatus,
+    ExecutionOutcomeWithId,
+    OutcomeProofs
+);
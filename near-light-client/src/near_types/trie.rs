@@ -1,33 +1,75 @@
 use self::nibble_slice::NibbleSlice;
 use super::super::StateProofVerificationError;
 use super::{hash::sha256, CryptoHash};
-use alloc::{vec, vec::Vec};
+use alloc::{collections::BTreeMap, vec, vec::Vec};
 use borsh::io::{Error, ErrorKind, Read};
 use byteorder::{ByteOrder, LittleEndian};
 
 pub mod nibble_slice;
 
-#[derive(Debug, Eq, PartialEq)]
+/// Trie node with memory usage, which is the format of nodes in NEAR state proofs.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
 pub struct RawTrieNodeWithSize {
     node: RawTrieNode,
     memory_usage: u64,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// Trie node of NEAR state.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
 #[allow(clippy::large_enum_variant)]
 pub enum RawTrieNode {
+    /// Encoded key, value length and value hash.
     Leaf(Vec<u8>, u32, CryptoHash),
+    /// Children and optional value (length and hash).
     Branch([Option<CryptoHash>; 16], Option<(u32, CryptoHash)>),
+    /// Encoded key and child hash.
     Extension(Vec<u8>, CryptoHash),
 }
 
+/// Type of a trie node.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum TrieNodeType {
+    Leaf,
+    Extension,
+    Branch,
+}
+
+/// Where in the trie a state proof verification fails.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct ProofErrorContext {
+    /// Type of the node at which the verification fails.
+    pub node_type: TrieNodeType,
+    /// Count of the key nibbles consumed before reaching the node.
+    pub consumed_nibbles: usize,
+    /// The key nibbles (one nibble per byte) not consumed before reaching the node.
+    pub remaining_key: Vec<u8>,
+}
+
 const LEAF_NODE: u8 = 0;
 const BRANCH_NODE_NO_VALUE: u8 = 1;
 const BRANCH_NODE_WITH_VALUE: u8 = 2;
 const EXTENSION_NODE: u8 = 3;
 
-fn decode_children(bytes: &[u8]) -> Result<[Option<CryptoHash>; 16], Error> {
-    let mut cursor = bytes;
+/// Read a key with its `u32` length prefix, without allocating more than the remaining bytes.
+fn decode_key(cursor: &mut &[u8]) -> Result<Vec<u8>, Error> {
+    let mut four_bytes: [u8; 4] = [0; 4];
+    cursor.read_exact(&mut four_bytes)?;
+    let key_length = LittleEndian::read_u32(&four_bytes) as usize;
+    if key_length > cursor.len() {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Invalid key length"));
+    }
+    let mut key = vec![0; key_length];
+    cursor.read_exact(&mut key)?;
+    Ok(key)
+}
+
+fn decode_children(cursor: &mut &[u8]) -> Result<[Option<CryptoHash>; 16], Error> {
     let mut children: [Option<CryptoHash>; 16] = Default::default();
     let mut two_bytes: [u8; 2] = [0; 2];
     cursor.read_exact(&mut two_bytes)?;
@@ -45,6 +87,85 @@ fn decode_children(bytes: &[u8]) -> Result<[Option<CryptoHash>; 16], Error> {
 }
 
 impl RawTrieNode {
+    /// Create a leaf node with the encoded key (see `NibbleSlice::encoded`) and the value.
+    pub fn new_leaf(encoded_key: Vec<u8>, value: &[u8]) -> Self {
+        RawTrieNode::Leaf(encoded_key, value.len() as u32, CryptoHash(sha256(value)))
+    }
+
+    /// Create a branch node with the children and an optional value.
+    pub fn new_branch(children: [Option<CryptoHash>; 16], value: Option<&[u8]>) -> Self {
+        RawTrieNode::Branch(
+            children,
+            value.map(|value| (value.len() as u32, CryptoHash(sha256(value)))),
+        )
+    }
+
+    /// Create an extension node with the encoded key (see `NibbleSlice::encoded`)
+    /// and the hash of its child.
+    pub fn new_extension(encoded_key: Vec<u8>, child: CryptoHash) -> Self {
+        RawTrieNode::Extension(encoded_key, child)
+    }
+
+    ///
+    pub fn node_type(&self) -> TrieNodeType {
+        match self {
+            RawTrieNode::Leaf(..) => TrieNodeType::Leaf,
+            RawTrieNode::Extension(..) => TrieNodeType::Extension,
+            RawTrieNode::Branch(..) => TrieNodeType::Branch,
+        }
+    }
+
+    /// Returns the children of a branch node, or `None` for other node types.
+    pub fn children(&self) -> Option<&[Option<CryptoHash>; 16]> {
+        match self {
+            RawTrieNode::Branch(children, _) => Some(children),
+            _ => None,
+        }
+    }
+
+    /// Returns the hashes of all nodes referenced by this node.
+    pub fn child_hashes(&self) -> Vec<CryptoHash> {
+        match self {
+            RawTrieNode::Leaf(..) => vec![],
+            RawTrieNode::Branch(children, _) => children.iter().flatten().copied().collect(),
+            RawTrieNode::Extension(_, child) => vec![*child],
+        }
+    }
+
+    /// Returns the encoded key of a leaf or extension node, or `None` for a branch node.
+    pub fn encoded_key(&self) -> Option<&[u8]> {
+        match self {
+            RawTrieNode::Leaf(key, _, _) | RawTrieNode::Extension(key, _) => Some(key),
+            RawTrieNode::Branch(..) => None,
+        }
+    }
+
+    /// Returns the key nibbles of a leaf or extension node, or `None` for a branch node
+    /// or an empty encoded key.
+    pub fn key_nibbles(&self) -> Option<NibbleSlice<'_>> {
+        self.encoded_key()
+            .and_then(NibbleSlice::try_from_encoded)
+            .map(|(nibbles, _)| nibbles)
+    }
+
+    /// Returns the value length of a leaf node or a branch node with value.
+    pub fn value_length(&self) -> Option<u32> {
+        match self {
+            RawTrieNode::Leaf(_, value_length, _) => Some(*value_length),
+            RawTrieNode::Branch(_, value) => value.map(|(value_length, _)| value_length),
+            RawTrieNode::Extension(..) => None,
+        }
+    }
+
+    /// Returns the value hash of a leaf node or a branch node with value.
+    pub fn value_hash(&self) -> Option<CryptoHash> {
+        match self {
+            RawTrieNode::Leaf(_, _, value_hash) => Some(*value_hash),
+            RawTrieNode::Branch(_, value) => value.map(|(_, value_hash)| value_hash),
+            RawTrieNode::Extension(..) => None,
+        }
+    }
+
     fn encode_into(&self, out: &mut Vec<u8>) {
         // size in state_parts = size + 8 for RawTrieNodeWithSize + 8 for borsh vector length
         match &self {
@@ -89,27 +210,37 @@ impl RawTrieNode {
     }
 
     fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode_with_rest(bytes).map(|(node, _)| node)
+    }
+
+    /// Decode a node which must occupy all of `bytes`.
+    fn decode_strict(bytes: &[u8]) -> Result<Self, Error> {
+        let (node, rest) = Self::decode_with_rest(bytes)?;
+        if !rest.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "Trailing bytes"));
+        }
+        Ok(node)
+    }
+
+    /// Decode a node from the beginning of `bytes`, and returns it with the remaining bytes.
+    fn decode_with_rest(bytes: &[u8]) -> Result<(Self, &[u8]), Error> {
         let mut cursor = bytes;
         let mut one_byte: [u8; 1] = [0; 1];
         cursor.read_exact(&mut one_byte)?;
-        match one_byte[0] {
+        let node = match one_byte[0] {
             LEAF_NODE => {
-                let mut four_bytes: [u8; 4] = [0; 4];
-                cursor.read_exact(&mut four_bytes)?;
-                let key_length = LittleEndian::read_u32(&four_bytes);
-                let mut key = vec![0; key_length as usize];
-                cursor.read_exact(&mut key)?;
+                let key = decode_key(&mut cursor)?;
                 let mut four_bytes: [u8; 4] = [0; 4];
                 cursor.read_exact(&mut four_bytes)?;
                 let value_length = LittleEndian::read_u32(&four_bytes);
                 let mut arr = [0; 32];
                 cursor.read_exact(&mut arr)?;
                 let value_hash = CryptoHash(arr);
-                Ok(RawTrieNode::Leaf(key, value_length, value_hash))
+                RawTrieNode::Leaf(key, value_length, value_hash)
             }
             BRANCH_NODE_NO_VALUE => {
-                let children = decode_children(cursor)?;
-                Ok(RawTrieNode::Branch(children, None))
+                let children = decode_children(&mut cursor)?;
+                RawTrieNode::Branch(children, None)
             }
             BRANCH_NODE_WITH_VALUE => {
                 let mut four_bytes: [u8; 4] = [0; 4];
@@ -118,38 +249,92 @@ impl RawTrieNode {
                 let mut arr = [0; 32];
                 cursor.read_exact(&mut arr)?;
                 let value_hash = CryptoHash(arr);
-                let children = decode_children(cursor)?;
-                Ok(RawTrieNode::Branch(
-                    children,
-                    Some((value_length, value_hash)),
-                ))
+                let children = decode_children(&mut cursor)?;
+                RawTrieNode::Branch(children, Some((value_length, value_hash)))
             }
             EXTENSION_NODE => {
-                let mut four_bytes: [u8; 4] = [0; 4];
-                cursor.read_exact(&mut four_bytes)?;
-                let key_length = LittleEndian::read_u32(&four_bytes);
-                let mut key = vec![0; key_length as usize];
-                cursor.read_exact(&mut key)?;
+                let key = decode_key(&mut cursor)?;
                 let mut child = [0; 32];
                 cursor.read_exact(&mut child)?;
-                Ok(RawTrieNode::Extension(key, CryptoHash(child)))
+                RawTrieNode::Extension(key, CryptoHash(child))
             }
-            _ => Err(Error::new(ErrorKind::Other, "Wrong type")),
-        }
+            _ => return Err(Error::new(ErrorKind::Other, "Wrong type")),
+        };
+        Ok((node, cursor))
     }
 }
 
 impl RawTrieNodeWithSize {
+    ///
+    pub fn new(node: RawTrieNode, memory_usage: u64) -> Self {
+        RawTrieNodeWithSize { node, memory_usage }
+    }
+    ///
+    pub fn node(&self) -> &RawTrieNode {
+        &self.node
+    }
+    ///
+    pub fn memory_usage(&self) -> u64 {
+        self.memory_usage
+    }
+    /// Returns the children of a branch node, or `None` for other node types.
+    pub fn children(&self) -> Option<&[Option<CryptoHash>; 16]> {
+        self.node.children()
+    }
+    /// Returns the key nibbles of a leaf or extension node, or `None` for a branch node.
+    pub fn key_nibbles(&self) -> Option<NibbleSlice<'_>> {
+        self.node.key_nibbles()
+    }
+    /// Returns the value hash of a leaf node or a branch node with value.
+    pub fn value_hash(&self) -> Option<CryptoHash> {
+        self.node.value_hash()
+    }
+
+    /// Serialize the node in the same format as it appears in state proofs.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    /// Returns the hash of the node, which is referenced by its parent node.
+    pub fn hash(&self) -> CryptoHash {
+        self.hash_with(&mut Vec::new())
+    }
+
+    /// Returns the hash of the node, encoding it in the cleared `scratch` buffer.
+    fn hash_with(&self, scratch: &mut Vec<u8>) -> CryptoHash {
+        scratch.clear();
+        self.encode_into(scratch);
+        CryptoHash(sha256(scratch))
+    }
+
     fn encode_into(&self, out: &mut Vec<u8>) {
         self.node.encode_into(out);
         out.extend(self.memory_usage.to_le_bytes());
     }
 
+    /// Decode a node, ignoring any bytes between the node and the memory usage.
+    ///
+    /// Use `decode_strict` for untrusted data.
     pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode_with(bytes, RawTrieNode::decode)
+    }
+
+    /// Decode a node, failing if there are any trailing bytes after the node,
+    /// so that only the canonical encoding of a node can be decoded.
+    pub fn decode_strict(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode_with(bytes, RawTrieNode::decode_strict)
+    }
+
+    fn decode_with(
+        bytes: &[u8],
+        decode_node: fn(&[u8]) -> Result<RawTrieNode, Error>,
+    ) -> Result<Self, Error> {
         if bytes.len() < 8 {
             return Err(Error::new(ErrorKind::Other, "Wrong type"));
         }
-        let node = RawTrieNode::decode(&bytes[0..bytes.len() - 8])?;
+        let node = decode_node(&bytes[0..bytes.len() - 8])?;
         let mut arr: [u8; 8] = Default::default();
         arr.copy_from_slice(&bytes[bytes.len() - 8..]);
         let memory_usage = u64::from_le_bytes(arr);
@@ -157,90 +342,287 @@ impl RawTrieNodeWithSize {
     }
 }
 
-pub fn verify_state_proof(
-    key: &[u8],
-    nodes: &[RawTrieNodeWithSize],
-    value: &[u8],
+/// Format of the state proof data returned by NEAR RPC `view_state` with `include_proof`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum StateProofFormat {
+    /// All items of the proof data are encoded trie nodes.
+    NodesOnly,
+    /// The proof data also contains the raw values read by the query, which is
+    /// the format of the recorded storage returned by newer nearcore versions.
+    NodesWithValues,
+}
+
+/// A decoded trie node with the proof data item it is decoded from.
+///
+/// The nodes are decoded with `decode_strict`, so the item is the encoding of the node
+/// and its hash is the hash of the node, without encoding the node again.
+type RawNode<'a> = (RawTrieNodeWithSize, &'a [u8]);
+
+impl StateProofFormat {
+    /// Detect the format of the given proof data.
+    pub fn detect(proofs: &[Vec<u8>]) -> Self {
+        let (nodes, others) = split_state_proof(proofs);
+        if others.iter().any(|(_, proof)| is_value_of(proof, &nodes)) {
+            StateProofFormat::NodesWithValues
+        } else {
+            StateProofFormat::NodesOnly
+        }
+    }
+}
+
+/// Split the proof data into the decoded trie nodes and the other items with their indexes.
+fn split_state_proof(proofs: &[Vec<u8>]) -> (Vec<RawNode<'_>>, Vec<(u16, &Vec<u8>)>) {
+    let mut nodes = Vec::new();
+    let mut others = Vec::new();
+    for (proof_index, proof) in (0_u16..).zip(proofs.iter()) {
+        match RawTrieNodeWithSize::decode_strict(proof) {
+            Ok(node) => nodes.push((node, proof.as_slice())),
+            Err(_) => others.push((proof_index, proof)),
+        }
+    }
+    (nodes, others)
+}
+
+/// Returns true if `bytes` is the value referenced by any of `nodes`.
+fn is_value_of(bytes: &[u8], nodes: &[RawNode<'_>]) -> bool {
+    let hash = CryptoHash(sha256(bytes));
+    nodes
+        .iter()
+        .any(|(node, _)| node.value_hash() == Some(hash))
+}
+
+/// Decode the trie nodes in the given proof data, with the format detected automatically.
+///
+/// For format `NodesWithValues`, the values referenced by the nodes are skipped.
+/// Any other item which can not be decoded as a trie node is invalid.
+pub fn decode_state_proof(
+    proofs: &[Vec<u8>],
+) -> Result<Vec<RawTrieNodeWithSize>, StateProofVerificationError> {
+    decode_raw_state_proof(proofs).map(|nodes| nodes.into_iter().map(|(node, _)| node).collect())
+}
+
+/// Decode the trie nodes in the given proof data like [`decode_state_proof`], keeping the
+/// items they are decoded from.
+fn decode_raw_state_proof(
+    proofs: &[Vec<u8>],
+) -> Result<Vec<RawNode<'_>>, StateProofVerificationError> {
+    let (nodes, others) = split_state_proof(proofs);
+    for (proof_index, proof) in others {
+        if !is_value_of(proof, &nodes) {
+            return Err(StateProofVerificationError::InvalidProofData { proof_index });
+        }
+    }
+    Ok(nodes)
+}
+
+/// State proof data decoded once, with the hashes of all nodes computed on construction,
+/// which can be verified against multiple keys and state roots.
+///
+/// The nodes are looked up by their hashes, so they don't need to be ordered and the
+/// proof data may contain nodes of other keys, such as the proof data of a `view_state`
+/// query for a prefix.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct StateProof {
+    nodes: Vec<RawTrieNodeWithSize>,
+    index: BTreeMap<CryptoHash, usize>,
+}
+
+impl StateProof {
+    /// Decode the given proof data with [`decode_state_proof`] and hash all the nodes.
+    pub fn new(proofs: &[Vec<u8>]) -> Result<Self, StateProofVerificationError> {
+        let raw_nodes = decode_raw_state_proof(proofs)?;
+        if raw_nodes.is_empty() {
+            return Err(StateProofVerificationError::MissingProofData);
+        }
+        let mut nodes = Vec::with_capacity(raw_nodes.len());
+        let mut index = BTreeMap::new();
+        for (position, (node, bytes)) in raw_nodes.into_iter().enumerate() {
+            index.insert(CryptoHash(sha256(bytes)), position);
+            nodes.push(node);
+        }
+        Ok(Self { nodes, index })
+    }
+
+    /// Returns the decoded nodes, in the order of the proof data.
+    pub fn nodes(&self) -> &[RawTrieNodeWithSize] {
+        &self.nodes
+    }
+
+    /// Returns whether the proof contains the node with `hash`.
+    pub fn contains(&self, hash: &CryptoHash) -> bool {
+        self.index.contains_key(hash)
+    }
+
+    /// Verify the value of `key` in the state with `state_root`.
+    pub fn verify_membership(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        state_root: &CryptoHash,
+    ) -> Result<(), StateProofVerificationError> {
+        check_membership(lookup(key, state_root, self.hashed_nodes())?, value)
+    }
+
+    /// Verify that `key` has no value in the state with `state_root`.
+    pub fn verify_non_membership(
+        &self,
+        key: &[u8],
+        state_root: &CryptoHash,
+    ) -> Result<(), StateProofVerificationError> {
+        check_non_membership(lookup(key, state_root, self.hashed_nodes())?)
+    }
+
+    /// Returns a function providing the nodes by their precomputed hashes.
+    fn hashed_nodes<'s>(&'s self) -> impl FnMut(u16, &CryptoHash) -> NodeWithHash<'s> + 's {
+        move |_, expected_hash| {
+            self.index
+                .get(expected_hash)
+                .map(|position| (&self.nodes[*position], *expected_hash))
+                .ok_or(StateProofVerificationError::MissingProofNode {
+                    node_hash: *expected_hash,
+                })
+        }
+    }
+}
+
+/// Result of looking up a key in a trie with proof data.
+enum Lookup<'k> {
+    /// The key has a value in the trie, with the node holding the value.
+    Value {
+        node_index: u16,
+        position: Position<'k>,
+        value_hash: CryptoHash,
+    },
+    /// The key has no value in the trie, with the node proving it.
+    Absent {
+        node_index: u16,
+        position: Position<'k>,
+        reason: Absence,
+    },
+}
+
+/// The reason why a key has no value in the trie.
+enum Absence {
+    /// The key of the leaf node is different from the remaining key.
+    LeafKeyMismatch,
+    /// The remaining key doesn't start with the key of the extension node.
+    ExtensionKeyMismatch,
+    /// The key ends at a branch node without value.
+    MissingBranchValue,
+    /// The branch node has no child for the next nibble of the key.
+    MissingBranchChild,
+}
+
+/// A node visited while walking the trie, with the key nibbles not consumed yet.
+struct Position<'k> {
+    node_type: TrieNodeType,
+    consumed_nibbles: usize,
+    remaining_key: NibbleSlice<'k>,
+}
+
+impl Position<'_> {
+    fn context(&self) -> ProofErrorContext {
+        ProofErrorContext {
+            node_type: self.node_type,
+            consumed_nibbles: self.consumed_nibbles,
+            remaining_key: self.remaining_key.to_nibbles(),
+        }
+    }
+}
+
+/// A node provided for walking the trie, with its actual hash.
+type NodeWithHash<'a> = Result<(&'a RawTrieNodeWithSize, CryptoHash), StateProofVerificationError>;
+
+/// Walk the trie from `state_root` along `key`, with the nodes provided by `next_node`.
+///
+/// The `next_node` returns the node which should have `expected_hash` at step `node_index`
+/// together with the actual hash of the node, it MUST fail if the node can not be found.
+fn lookup<'a, 'k, F>(
+    key: &'k [u8],
     state_root: &CryptoHash,
-) -> Result<(), StateProofVerificationError> {
-    let mut v = Vec::new();
-    let mut hash_node = |node: &RawTrieNodeWithSize| {
-        v.clear();
-        node.encode_into(&mut v);
-        CryptoHash(sha256(&v))
-    };
+    mut next_node: F,
+) -> Result<Lookup<'k>, StateProofVerificationError>
+where
+    F: FnMut(u16, &CryptoHash) -> NodeWithHash<'a>,
+{
+    let key_length = NibbleSlice::new(key).len();
     let mut key = NibbleSlice::new(key);
     let mut expected_hash = *state_root;
 
-    for (node_index, node) in (0_u16..).zip(nodes.iter()) {
-        match node {
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Leaf(node_key, _, value_hash),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-
-                let nib = &NibbleSlice::from_encoded(node_key).0;
-                if &key != nib {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-
-                match CryptoHash(sha256(value)) == *value_hash {
-                    true => return Ok(()),
-                    false => {
-                        return Err(StateProofVerificationError::InvalidProofData {
+    for node_index in 0..=u16::MAX {
+        let (node, actual_hash) = next_node(node_index, &expected_hash)?;
+        let position = Position {
+            node_type: node.node.node_type(),
+            consumed_nibbles: key_length - key.len(),
+            remaining_key: key,
+        };
+        if actual_hash != expected_hash {
+            return Err(invalid_node_hash(
+                node_index,
+                position.context(),
+                expected_hash,
+                actual_hash,
+            ));
+        }
+        match &node.node {
+            RawTrieNode::Leaf(node_key, _, value_hash) => {
+                let nib = &match NibbleSlice::try_from_encoded(node_key) {
+                    Some((nib, _)) => nib,
+                    None => {
+                        return Err(StateProofVerificationError::InvalidLeafNodeKey {
                             proof_index: node_index,
+                            context: position.context(),
                         })
                     }
-                }
-            }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Extension(node_key, child_hash),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
+                };
+                if &key != nib {
+                    return Ok(Lookup::Absent {
+                        node_index,
+                        position,
+                        reason: Absence::LeafKeyMismatch,
                     });
                 }
+                return Ok(Lookup::Value {
+                    node_index,
+                    position,
+                    value_hash: *value_hash,
+                });
+            }
+            RawTrieNode::Extension(node_key, child_hash) => {
                 expected_hash = *child_hash;
 
-                let nib = NibbleSlice::from_encoded(node_key).0;
+                let nib = match NibbleSlice::try_from_encoded(node_key) {
+                    Some((nib, _)) => nib,
+                    None => {
+                        return Err(StateProofVerificationError::InvalidExtensionNodeKey {
+                            proof_index: node_index,
+                            context: position.context(),
+                        })
+                    }
+                };
                 if !key.starts_with(&nib) {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
+                    return Ok(Lookup::Absent {
+                        node_index,
+                        position,
+                        reason: Absence::ExtensionKeyMismatch,
                     });
                 }
                 key = key.mid(nib.len());
             }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Branch(children, node_value),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-
+            RawTrieNode::Branch(children, node_value) => {
                 if key.is_empty() {
-                    let maybe_value_hash = node_value.map(|x| x.1);
-                    let expected_value_hash = CryptoHash(sha256(value));
-                    return match maybe_value_hash {
-                        Some(value_hash) => match expected_value_hash == value_hash {
-                            true => Ok(()),
-                            false => Err(StateProofVerificationError::InvalidProofData {
-                                proof_index: node_index,
-                            }),
-                        },
-                        None => Err(StateProofVerificationError::InvalidProofData {
-                            proof_index: node_index,
+                    return match node_value {
+                        Some((_, value_hash)) => Ok(Lookup::Value {
+                            node_index,
+                            position,
+                            value_hash: *value_hash,
+                        }),
+                        None => Ok(Lookup::Absent {
+                            node_index,
+                            position,
+                            reason: Absence::MissingBranchValue,
                         }),
                     };
                 }
@@ -251,8 +633,10 @@ pub fn verify_state_proof(
                         expected_hash = *child_hash;
                     }
                     None => {
-                        return Err(StateProofVerificationError::InvalidProofData {
-                            proof_index: node_index,
+                        return Ok(Lookup::Absent {
+                            node_index,
+                            position,
+                            reason: Absence::MissingBranchChild,
                         })
                     }
                 }
@@ -262,87 +646,324 @@ pub fn verify_state_proof(
     Err(StateProofVerificationError::InvalidProofDataLength)
 }
 
-pub fn verify_not_in_state(
-    key: &[u8],
-    nodes: &[RawTrieNodeWithSize],
-    state_root: &CryptoHash,
-) -> Result<(), StateProofVerificationError> {
-    let mut v = Vec::new();
-    let mut hash_node = |node: &RawTrieNodeWithSize| {
-        v.clear();
-        node.encode_into(&mut v);
-        CryptoHash(sha256(&v))
-    };
-    let mut key = NibbleSlice::new(key);
-    let mut expected_hash = *state_root;
+/// Returns the error for a node which doesn't have the expected hash.
+fn invalid_node_hash(
+    proof_index: u16,
+    context: ProofErrorContext,
+    expected: CryptoHash,
+    actual: CryptoHash,
+) -> StateProofVerificationError {
+    match context.node_type {
+        TrieNodeType::Leaf => StateProofVerificationError::InvalidLeafNodeHash {
+            proof_index,
+            expected,
+            actual,
+            context,
+        },
+        TrieNodeType::Extension => StateProofVerificationError::InvalidExtensionNodeHash {
+            proof_index,
+            expected,
+            actual,
+            context,
+        },
+        TrieNodeType::Branch => StateProofVerificationError::InvalidBranchNodeHash {
+            proof_index,
+            expected,
+            actual,
+            context,
+        },
+    }
+}
 
-    for (node_index, node) in (0_u16..).zip(nodes.iter()) {
-        match node {
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Leaf(node_key, _, _),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
+/// Returns a function providing the nodes in the order of `nodes`,
+/// which must be ordered from root to leaf.
+fn ordered_nodes<'a>(
+    nodes: &'a [RawTrieNodeWithSize],
+) -> impl FnMut(u16, &CryptoHash) -> NodeWithHash<'a> + 'a {
+    let mut scratch = Vec::new();
+    move |node_index, _| {
+        let node = nodes
+            .get(node_index as usize)
+            .ok_or(StateProofVerificationError::InvalidProofDataLength)?;
+        Ok((node, node.hash_with(&mut scratch)))
+    }
+}
 
-                let nib = &NibbleSlice::from_encoded(node_key).0;
-                if &key != nib {
-                    return Ok(());
-                }
+/// Returns a function providing the nodes in the order of `nodes`, which must be ordered
+/// from root to leaf, with the hashes of the items they are decoded from.
+fn ordered_raw_nodes<'a>(
+    nodes: &'a [RawNode<'_>],
+) -> impl FnMut(u16, &CryptoHash) -> NodeWithHash<'a> + 'a {
+    move |node_index, _| {
+        let (node, bytes) = nodes
+            .get(node_index as usize)
+            .ok_or(StateProofVerificationError::InvalidProofDataLength)?;
+        Ok((node, CryptoHash(sha256(bytes))))
+    }
+}
 
-                return Err(StateProofVerificationError::SpecifiedKeyHasValueInState);
+/// Returns a function providing the nodes by their hashes, regardless of
+/// the order of `nodes`.
+fn indexed_nodes<'a>(
+    nodes: &'a [RawTrieNodeWithSize],
+) -> impl FnMut(u16, &CryptoHash) -> NodeWithHash<'a> + 'a {
+    let mut scratch = Vec::new();
+    let index: BTreeMap<CryptoHash, &'a RawTrieNodeWithSize> = nodes
+        .iter()
+        .map(|node| (node.hash_with(&mut scratch), node))
+        .collect();
+    move |_, expected_hash| {
+        index
+            .get(expected_hash)
+            .map(|node| (*node, *expected_hash))
+            .ok_or(StateProofVerificationError::MissingProofNode {
+                node_hash: *expected_hash,
+            })
+    }
+}
+
+fn check_membership(lookup: Lookup<'_>, value: &[u8]) -> Result<(), StateProofVerificationError> {
+    match lookup {
+        Lookup::Value {
+            node_index,
+            position,
+            value_hash,
+        } => {
+            let actual = CryptoHash(sha256(value));
+            if actual == value_hash {
+                return Ok(());
             }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Extension(node_key, child_hash),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
+            match position.node_type {
+                TrieNodeType::Branch => {
+                    Err(StateProofVerificationError::InvalidBranchNodeValueHash {
                         proof_index: node_index,
-                    });
+                        expected: value_hash,
+                        actual,
+                        context: position.context(),
+                    })
                 }
-                expected_hash = *child_hash;
-
-                let nib = NibbleSlice::from_encoded(node_key).0;
-                if !key.starts_with(&nib) {
-                    return Ok(());
-                }
-                key = key.mid(nib.len());
+                _ => Err(StateProofVerificationError::InvalidLeafNodeValueHash {
+                    proof_index: node_index,
+                    expected: value_hash,
+                    actual,
+                    context: position.context(),
+                }),
             }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Branch(children, node_value),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
+        }
+        Lookup::Absent {
+            node_index,
+            position,
+            reason,
+        } => {
+            let context = position.context();
+            Err(match reason {
+                Absence::LeafKeyMismatch => StateProofVerificationError::InvalidLeafNodeKey {
+                    proof_index: node_index,
+                    context,
+                },
+                Absence::ExtensionKeyMismatch => {
+                    StateProofVerificationError::InvalidExtensionNodeKey {
                         proof_index: node_index,
-                    });
+                        context,
+                    }
+                }
+                Absence::MissingBranchValue => {
+                    StateProofVerificationError::MissingBranchNodeValue {
+                        proof_index: node_index,
+                        context,
+                    }
                 }
+                Absence::MissingBranchChild => {
+                    StateProofVerificationError::MissingBranchNodeChildHash {
+                        proof_index: node_index,
+                        context,
+                    }
+                }
+            })
+        }
+    }
+}
 
-                if key.is_empty() {
-                    match node_value {
-                        Some(_) => {
-                            return Err(StateProofVerificationError::SpecifiedKeyHasValueInState)
+fn check_non_membership(lookup: Lookup<'_>) -> Result<(), StateProofVerificationError> {
+    match lookup {
+        Lookup::Value { .. } => Err(StateProofVerificationError::SpecifiedKeyHasValueInState),
+        Lookup::Absent { .. } => Ok(()),
+    }
+}
+
+/// A visited node while walking a state proof with `walk_proof`.
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct ProofStep<'a> {
+    /// Index of the node in the proof data.
+    pub node_index: u16,
+    /// The visited node.
+    pub node: &'a RawTrieNodeWithSize,
+    /// The hash which the node is expected to have.
+    pub expected_hash: CryptoHash,
+    /// Whether the node has the expected hash.
+    pub hash_matched: bool,
+    /// Count of the key nibbles consumed after visiting the node.
+    pub consumed_nibbles: usize,
+    /// The hash of the next node to visit, or `None` if the walk stops at this node.
+    pub next_expected_hash: Option<CryptoHash>,
+}
+
+struct ProofWalker<'a> {
+    key: &'a [u8],
+    nodes: &'a [RawTrieNodeWithSize],
+    node_index: usize,
+    consumed_nibbles: usize,
+    expected_hash: Option<CryptoHash>,
+    scratch: Vec<u8>,
+}
+
+impl<'a> Iterator for ProofWalker<'a> {
+    type Item = ProofStep<'a>;
+
+    fn next(&mut self) -> Option<ProofStep<'a>> {
+        let node = self.nodes.get(self.node_index)?;
+        let expected_hash = self.expected_hash.take()?;
+        let node_index = self.node_index as u16;
+        self.node_index += 1;
+
+        let hash_matched = node.hash_with(&mut self.scratch) == expected_hash;
+        let key = NibbleSlice::new(self.key).mid(self.consumed_nibbles);
+        if hash_matched {
+            match &node.node {
+                // A node with an empty encoded key stops the walk.
+                RawTrieNode::Leaf(node_key, _, _) => {
+                    if let Some((nib, _)) = NibbleSlice::try_from_encoded(node_key) {
+                        if key == nib {
+                            self.consumed_nibbles += nib.len();
                         }
-                        None => return Ok(()),
                     }
                 }
-
-                let index = key.at(0);
-                match &children[index as usize] {
-                    Some(child_hash) => {
-                        key = key.mid(1);
-                        expected_hash = *child_hash;
+                RawTrieNode::Extension(node_key, child_hash) => {
+                    let nib = NibbleSlice::try_from_encoded(node_key).map(|(nib, _)| nib);
+                    if let Some(nib) = nib.filter(|nib| key.starts_with(nib)) {
+                        self.consumed_nibbles += nib.len();
+                        self.expected_hash = Some(*child_hash);
                     }
-                    None => {
-                        return Ok(());
+                }
+                RawTrieNode::Branch(children, _) => {
+                    if !key.is_empty() {
+                        if let Some(child_hash) = children[key.at(0) as usize] {
+                            self.consumed_nibbles += 1;
+                            self.expected_hash = Some(child_hash);
+                        }
                     }
                 }
             }
         }
+        Some(ProofStep {
+            node_index,
+            node,
+            expected_hash,
+            hash_matched,
+            consumed_nibbles: self.consumed_nibbles,
+            next_expected_hash: self.expected_hash,
+        })
     }
-    Err(StateProofVerificationError::InvalidProofDataLength)
 }
+
+/// Walk the trie nodes ordered from root to leaf along `key`, yielding every visited node.
+///
+/// The walk stops at the node where the key ends or diverges from the trie, or where
+/// the node doesn't have the expected hash. This is useful to find out where exactly
+/// a state proof fails the verification.
+pub fn walk_proof<'a>(
+    key: &'a [u8],
+    nodes: &'a [RawTrieNodeWithSize],
+    root: &CryptoHash,
+) -> impl Iterator<Item = ProofStep<'a>> + 'a {
+    ProofWalker {
+        key,
+        nodes,
+        node_index: 0,
+        consumed_nibbles: 0,
+        expected_hash: Some(*root),
+        scratch: Vec::new(),
+    }
+}
+
+/// Verify the value of `key` with the trie nodes ordered from root to leaf.
+pub fn verify_state_proof(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    value: &[u8],
+    state_root: &CryptoHash,
+) -> Result<(), StateProofVerificationError> {
+    check_membership(lookup(key, state_root, ordered_nodes(nodes))?, value)
+}
+
+/// Verify that `key` has no value with the trie nodes ordered from root to leaf.
+pub fn verify_not_in_state(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+) -> Result<(), StateProofVerificationError> {
+    check_non_membership(lookup(key, state_root, ordered_nodes(nodes))?)
+}
+
+/// Verify the value of `key` with the proof data, whose trie nodes are ordered from root
+/// to leaf.
+///
+/// This is the same as [`verify_state_proof`] with the nodes decoded by
+/// [`decode_state_proof`], but the nodes are hashed with the proof data directly
+/// instead of being encoded again.
+pub fn verify_state_proof_data(
+    key: &[u8],
+    proofs: &[Vec<u8>],
+    value: &[u8],
+    state_root: &CryptoHash,
+) -> Result<(), StateProofVerificationError> {
+    let nodes = decode_raw_state_proof(proofs)?;
+    check_membership(lookup(key, state_root, ordered_raw_nodes(&nodes))?, value)
+}
+
+/// Verify that `key` has no value with the proof data, whose trie nodes are ordered from
+/// root to leaf, like [`verify_state_proof_data`].
+pub fn verify_not_in_state_data(
+    key: &[u8],
+    proofs: &[Vec<u8>],
+    state_root: &CryptoHash,
+) -> Result<(), StateProofVerificationError> {
+    let nodes = decode_raw_state_proof(proofs)?;
+    check_non_membership(lookup(key, state_root, ordered_raw_nodes(&nodes))?)
+}
+
+/// Verify the value of `key` with the trie nodes in any order.
+///
+/// The nodes are indexed by their hashes and the trie is walked from `state_root`,
+/// so the verification fails if any node on the path of `key` is missing.
+pub fn verify_state_proof_by_hash(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    value: &[u8],
+    state_root: &CryptoHash,
+) -> Result<(), StateProofVerificationError> {
+    check_membership(lookup(key, state_root, indexed_nodes(nodes))?, value)
+}
+
+/// Verify that `key` has no value with the trie nodes in any order.
+///
+/// The nodes are indexed by their hashes and the trie is walked from `state_root`,
+/// so the verification fails if any node on the path of `key` is missing.
+pub fn verify_not_in_state_by_hash(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+) -> Result<(), StateProofVerificationError> {
+    check_non_membership(lookup(key, state_root, indexed_nodes(nodes))?)
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(
+    RawTrieNodeWithSize,
+    RawTrieNode,
+    TrieNodeType,
+    ProofErrorContext,
+    StateProofFormat,
+    StateProof,
+    ProofStep<'_>
+);
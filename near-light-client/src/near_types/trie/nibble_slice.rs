@@ -16,7 +16,7 @@
 
 //! Nibble-orientated view onto byte-slice, allowing nibble-precision offsets.
 
-use alloc::fmt;
+use alloc::{fmt, vec::Vec};
 use core::cmp::{min, Ordering};
 use elastic_array::ElasticArray36;
 
@@ -91,6 +91,20 @@ impl<'a> NibbleSlice<'a> {
         )
     }
 
+    /// Create a new nibble slice from the given HPE encoded data, or `None` if `data` is empty.
+    pub fn try_from_encoded(data: &'a [u8]) -> Option<(Self, bool)> {
+        if data.is_empty() {
+            None
+        } else {
+            Some(Self::from_encoded(data))
+        }
+    }
+
+    /// Collect the nibbles of this slice, one nibble per byte.
+    pub fn to_nibbles(&self) -> Vec<u8> {
+        (0..self.len()).map(|i| self.at(i)).collect()
+    }
+
     /// Is this an empty slice?
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -238,3 +252,41 @@ impl fmt::Debug for NibbleSlice<'_> {
         Ok(())
     }
 }
+
+/// Convert the bytes of a key to nibbles, one nibble per byte with the high nibble first.
+///
+/// This is the order in which the key is consumed while walking a NEAR state trie.
+pub fn bytes_to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    NibbleSlice::new(bytes).to_nibbles()
+}
+
+/// Convert nibbles (one nibble per byte) back to bytes, or `None` if the count of
+/// nibbles is odd or any of them is greater than `0x0f`.
+pub fn nibbles_to_bytes(nibbles: &[u8]) -> Option<Vec<u8>> {
+    if nibbles.len() % 2 != 0 || nibbles.iter().any(|nibble| *nibble > 0x0f) {
+        return None;
+    }
+    Some(
+        nibbles
+            .chunks(2)
+            .map(|pair| pair[0] * 16 + pair[1])
+            .collect(),
+    )
+}
+
+/// Encode nibbles (one nibble per byte) as the key of a leaf or extension node,
+/// in hex-prefix notation noting whether it `is_leaf`.
+pub fn encode_key_nibbles(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    NibbleSlice::encode_nibbles(nibbles, is_leaf).to_vec()
+}
+
+/// Decode the key of a leaf or extension node to nibbles (one nibble per byte) and
+/// whether it is the key of a leaf node, or `None` if `encoded` is empty.
+pub fn decode_key_nibbles(encoded: &[u8]) -> Option<(Vec<u8>, bool)> {
+    NibbleSlice::try_from_encoded(encoded).map(|(nibbles, is_leaf)| (nibbles.to_nibbles(), is_leaf))
+}
+
+/// Returns the length (in nibbles) of the longest common prefix of two keys.
+pub fn common_prefix_nibbles(key1: &[u8], key2: &[u8]) -> usize {
+    NibbleSlice::new(key1).common_prefix(&NibbleSlice::new(key2))
+}
@@ -0,0 +1,206 @@
+//! Keys of all columns of NEAR state, ported from `nearcore` v1.30.0.
+
+use super::{hash::CryptoHash, signature::PublicKey, AccountId};
+use alloc::vec::Vec;
+
+/// Column ids of the NEAR state trie, which are the first byte of every trie key.
+pub mod col {
+    /// This column id is used when storing `primitives::account::Account` type about a given
+    /// `account_id`.
+    pub const ACCOUNT: u8 = 0;
+    /// This column id is used when storing contract blob for a given `account_id`.
+    pub const CONTRACT_CODE: u8 = 1;
+    /// This column id is used when storing `primitives::account::AccessKey` type for a given
+    /// `account_id`.
+    pub const ACCESS_KEY: u8 = 2;
+    /// This column id is used when storing `primitives::receipt::ReceivedData` type (data received
+    /// for a key `data_id`). The required postponed receipt might be still not received or requires
+    /// more pending input data.
+    pub const RECEIVED_DATA: u8 = 3;
+    /// This column id is used when storing `primitives::hash::CryptoHash` (ReceiptId) type. The ReceivedData
+    /// is not available and is needed for the postponed receipt to execute.
+    pub const POSTPONED_RECEIPT_ID: u8 = 4;
+    /// This column id is used when storing the number of missing data inputs that are still not
+    /// available for a key `receipt_id`.
+    pub const PENDING_DATA_COUNT: u8 = 5;
+    /// This column id is used when storing the postponed receipts (`primitives::receipt::Receipt`).
+    pub const POSTPONED_RECEIPT: u8 = 6;
+    /// This column id is used when storing the indices of the delayed receipts queue.
+    /// NOTE: It is a singleton per shard.
+    pub const DELAYED_RECEIPT_INDICES: u8 = 7;
+    /// This column id is used when storing delayed receipts, because the shard is overwhelmed.
+    pub const DELAYED_RECEIPT: u8 = 8;
+    /// This column id is used when storing Key-Value data from a contract on an `account_id`.
+    pub const CONTRACT_DATA: u8 = 9;
+}
+
+/// Separator between the account id and the rest of the key in trie keys.
+pub const ACCOUNT_DATA_SEPARATOR: u8 = b',';
+/// Separator between the account id and the public key in access key trie keys.
+pub const ACCESS_KEY_SEPARATOR: u8 = col::ACCESS_KEY;
+
+/// Describes the key of a specific key-value record in a state trie.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+pub enum TrieKey {
+    /// Used to store `primitives::account::Account` struct for a given `AccountId`.
+    Account { account_id: AccountId },
+    /// Used to store `Vec<u8>` contract code for a given `AccountId`.
+    ContractCode { account_id: AccountId },
+    /// Used to store `primitives::account::AccessKey` struct for a given `AccountId` and
+    /// a given `public_key` of the `AccessKey`.
+    AccessKey {
+        account_id: AccountId,
+        public_key: PublicKey,
+    },
+    /// Used to store `primitives::receipt::ReceivedData` struct for a given receiver's `AccountId`
+    /// of `DataReceipt` and a given `data_id` (the unique identifier for the data).
+    ReceivedData {
+        receiver_id: AccountId,
+        data_id: CryptoHash,
+    },
+    /// Used to store receipt ID `primitives::hash::CryptoHash` for a given receiver's `AccountId`
+    /// of the receipt and a given `data_id` (the unique identifier for the required input data).
+    PostponedReceiptId {
+        receiver_id: AccountId,
+        data_id: CryptoHash,
+    },
+    /// Used to store the number `u32` of pending data inputs for a given receiver's `AccountId`
+    /// of the receipt and a given `receipt_id` of the receipt.
+    PendingDataCount {
+        receiver_id: AccountId,
+        receipt_id: CryptoHash,
+    },
+    /// Used to store the postponed receipt `primitives::receipt::Receipt` for a given receiver's
+    /// `AccountId` of the receipt and a given `receipt_id` of the receipt.
+    PostponedReceipt {
+        receiver_id: AccountId,
+        receipt_id: CryptoHash,
+    },
+    /// Used to store indices of the delayed receipts queue (`node-runtime::DelayedReceiptIndices`).
+    /// NOTE: It is a singleton per shard.
+    DelayedReceiptIndices,
+    /// Used to store a delayed receipt `primitives::receipt::Receipt` for a given index `u64`
+    /// in a delayed receipt queue. The queue is unique per shard.
+    DelayedReceipt { index: u64 },
+    /// Used to store a key-value record `Vec<u8>` within a contract deployed on a given `AccountId`
+    /// and a given key.
+    ContractData { account_id: AccountId, key: Vec<u8> },
+}
+
+impl TrieKey {
+    /// Returns the length of the raw trie key.
+    pub fn len(&self) -> usize {
+        match self {
+            TrieKey::Account { account_id } | TrieKey::ContractCode { account_id } => {
+                1 + account_id.len()
+            }
+            TrieKey::AccessKey {
+                account_id,
+                public_key,
+            } => 1 + account_id.len() + 1 + public_key_len(public_key),
+            TrieKey::ReceivedData { receiver_id, .. }
+            | TrieKey::PostponedReceiptId { receiver_id, .. }
+            | TrieKey::PendingDataCount { receiver_id, .. }
+            | TrieKey::PostponedReceipt { receiver_id, .. } => {
+                1 + receiver_id.len() + 1 + CryptoHash::new().as_bytes().len()
+            }
+            TrieKey::DelayedReceiptIndices => 1,
+            TrieKey::DelayedReceipt { .. } => 1 + size_of::<u64>(),
+            TrieKey::ContractData { account_id, key } => 1 + account_id.len() + 1 + key.len(),
+        }
+    }
+
+    /// Returns true if the raw trie key is empty, which never happens.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the raw trie key.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(self.len());
+        match self {
+            TrieKey::Account { account_id } => {
+                res.push(col::ACCOUNT);
+                res.extend(account_id.as_bytes());
+            }
+            TrieKey::ContractCode { account_id } => {
+                res.push(col::CONTRACT_CODE);
+                res.extend(account_id.as_bytes());
+            }
+            TrieKey::AccessKey {
+                account_id,
+                public_key,
+            } => {
+                res.push(col::ACCESS_KEY);
+                res.extend(account_id.as_bytes());
+                res.push(ACCESS_KEY_SEPARATOR);
+                res.extend(
+                    borsh::to_vec(public_key)
+                        .expect("Should not fail based on borsh serialization."),
+                );
+            }
+            TrieKey::ReceivedData {
+                receiver_id,
+                data_id,
+            } => {
+                res.push(col::RECEIVED_DATA);
+                res.extend(receiver_id.as_bytes());
+                res.push(ACCOUNT_DATA_SEPARATOR);
+                res.extend(data_id.as_ref());
+            }
+            TrieKey::PostponedReceiptId {
+                receiver_id,
+                data_id,
+            } => {
+                res.push(col::POSTPONED_RECEIPT_ID);
+                res.extend(receiver_id.as_bytes());
+                res.push(ACCOUNT_DATA_SEPARATOR);
+                res.extend(data_id.as_ref());
+            }
+            TrieKey::PendingDataCount {
+                receiver_id,
+                receipt_id,
+            } => {
+                res.push(col::PENDING_DATA_COUNT);
+                res.extend(receiver_id.as_bytes());
+                res.push(ACCOUNT_DATA_SEPARATOR);
+                res.extend(receipt_id.as_ref());
+            }
+            TrieKey::PostponedReceipt {
+                receiver_id,
+                receipt_id,
+            } => {
+                res.push(col::POSTPONED_RECEIPT);
+                res.extend(receiver_id.as_bytes());
+                res.push(ACCOUNT_DATA_SEPARATOR);
+                res.extend(receipt_id.as_ref());
+            }
+            TrieKey::DelayedReceiptIndices => {
+                res.push(col::DELAYED_RECEIPT_INDICES);
+            }
+            TrieKey::DelayedReceipt { index } => {
+                res.push(col::DELAYED_RECEIPT);
+                res.extend(index.to_le_bytes());
+            }
+            TrieKey::ContractData { account_id, key } => {
+                res.push(col::CONTRACT_DATA);
+                res.extend(account_id.as_bytes());
+                res.push(ACCOUNT_DATA_SEPARATOR);
+                res.extend(key);
+            }
+        }
+        debug_assert_eq!(res.len(), self.len());
+        res
+    }
+}
+
+fn public_key_len(public_key: &PublicKey) -> usize {
+    match public_key {
+        PublicKey::ED25519(key) => 1 + key.0.len(),
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(TrieKey);
@@ -0,0 +1,309 @@
+//! Protobuf encodings of the light client datatypes.
+//!
+//! The messages are generated by `prost` from `proto/near/lightclient/v1/lightclient.proto`.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    near_types::{
+        block_producers::BlockProducers,
+        hash::CryptoHash,
+        signature::{PublicKey, Signature},
+        BlockHeaderInnerLite, EpochId, LightClientBlock, ValidatorStakeView, ValidatorStakeViewV1,
+    },
+    types::{ClientState, ConsensusState, Header, Height, Misbehaviour},
+};
+
+#[allow(unused_qualifications, missing_docs)]
+pub mod v1 {
+    include!("proto/near.lightclient.v1.rs");
+}
+
+/// Error type for converting protobuf messages into light client datatypes.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum ProtoDecodingError {
+    MissingField { field: &'static str },
+    InvalidHashLength { field: &'static str, length: usize },
+    InvalidPublicKey { account_id: String },
+    InvalidStake { account_id: String },
+    InvalidSignature { index: usize },
+}
+
+fn decode_hash(field: &'static str, bytes: &[u8]) -> Result<CryptoHash, ProtoDecodingError> {
+    CryptoHash::try_from(bytes).map_err(|_| ProtoDecodingError::InvalidHashLength {
+        field,
+        length: bytes.len(),
+    })
+}
+
+fn encode_bps(bps: BlockProducers) -> v1::ValidatorStakeViewList {
+    v1::ValidatorStakeViewList {
+        validators: bps.into_vec().into_iter().map(Into::into).collect(),
+    }
+}
+
+fn decode_bps(bps: v1::ValidatorStakeViewList) -> Result<BlockProducers, ProtoDecodingError> {
+    bps.validators.into_iter().map(TryInto::try_into).collect()
+}
+
+impl From<BlockHeaderInnerLite> for v1::BlockHeaderInnerLite {
+    fn from(value: BlockHeaderInnerLite) -> Self {
+        Self {
+            height: value.height,
+            epoch_id: value.epoch_id.0 .0.to_vec(),
+            next_epoch_id: value.next_epoch_id.0 .0.to_vec(),
+            prev_state_root: value.prev_state_root.0.to_vec(),
+            outcome_root: value.outcome_root.0.to_vec(),
+            timestamp: value.timestamp,
+            next_bp_hash: value.next_bp_hash.0.to_vec(),
+            block_merkle_root: value.block_merkle_root.0.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<v1::BlockHeaderInnerLite> for BlockHeaderInnerLite {
+    type Error = ProtoDecodingError;
+
+    fn try_from(value: v1::BlockHeaderInnerLite) -> Result<Self, Self::Error> {
+        Ok(Self {
+            height: value.height,
+            epoch_id: EpochId(decode_hash("epoch_id", &value.epoch_id)?),
+            next_epoch_id: EpochId(decode_hash("next_epoch_id", &value.next_epoch_id)?),
+            prev_state_root: decode_hash("prev_state_root", &value.prev_state_root)?,
+            outcome_root: decode_hash("outcome_root", &value.outcome_root)?,
+            timestamp: value.timestamp,
+            next_bp_hash: decode_hash("next_bp_hash", &value.next_bp_hash)?,
+            block_merkle_root: decode_hash("block_merkle_root", &value.block_merkle_root)?,
+        })
+    }
+}
+
+impl From<ValidatorStakeView> for v1::ValidatorStakeView {
+    fn from(value: ValidatorStakeView) -> Self {
+        let value = value.into_validator_stake();
+        Self {
+            account_id: value.account_id,
+            public_key: borsh::to_vec(&value.public_key)
+                .expect("Should not fail based on borsh serialization."),
+            stake: value.stake.to_string(),
+        }
+    }
+}
+
+impl TryFrom<v1::ValidatorStakeView> for ValidatorStakeView {
+    type Error = ProtoDecodingError;
+
+    fn try_from(value: v1::ValidatorStakeView) -> Result<Self, Self::Error> {
+        let public_key = borsh::from_slice::<PublicKey>(&value.public_key).map_err(|_| {
+            ProtoDecodingError::InvalidPublicKey {
+                account_id: value.account_id.clone(),
+            }
+        })?;
+        let stake = value
+            .stake
+            .parse()
+            .map_err(|_| ProtoDecodingError::InvalidStake {
+                account_id: value.account_id.clone(),
+            })?;
+        Ok(Self::V1(ValidatorStakeViewV1 {
+            account_id: value.account_id,
+            public_key,
+            stake,
+        }))
+    }
+}
+
+impl From<LightClientBlock> for v1::LightClientBlock {
+    fn from(value: LightClientBlock) -> Self {
+        Self {
+            prev_block_hash: value.prev_block_hash.0.to_vec(),
+            next_block_inner_hash: value.next_block_inner_hash.0.to_vec(),
+            inner_lite: Some(value.inner_lite.into()),
+            inner_rest_hash: value.inner_rest_hash.0.to_vec(),
+            next_bps: value.next_bps.map(encode_bps),
+            approvals_after_next: value
+                .approvals_after_next
+                .iter()
+                .map(|maybe_signature| v1::Approval {
+                    signature: maybe_signature.as_ref().map_or_else(Vec::new, |signature| {
+                        borsh::to_vec(signature)
+                            .expect("Should not fail based on borsh serialization.")
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<v1::LightClientBlock> for LightClientBlock {
+    type Error = ProtoDecodingError;
+
+    fn try_from(value: v1::LightClientBlock) -> Result<Self, Self::Error> {
+        Ok(Self {
+            prev_block_hash: decode_hash("prev_block_hash", &value.prev_block_hash)?,
+            next_block_inner_hash: decode_hash(
+                "next_block_inner_hash",
+                &value.next_block_inner_hash,
+            )?,
+            inner_lite: value
+                .inner_lite
+                .ok_or(ProtoDecodingError::MissingField {
+                    field: "inner_lite",
+                })?
+                .try_into()?,
+            inner_rest_hash: decode_hash("inner_rest_hash", &value.inner_rest_hash)?,
+            next_bps: value.next_bps.map(decode_bps).transpose()?,
+            approvals_after_next: value
+                .approvals_after_next
+                .iter()
+                .enumerate()
+                .map(|(index, approval)| {
+                    if approval.signature.is_empty() {
+                        return Ok(None);
+                    }
+                    borsh::from_slice::<Signature>(&approval.signature)
+                        .map(Some)
+                        .map_err(|_| ProtoDecodingError::InvalidSignature { index })
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<Header> for v1::Header {
+    fn from(value: Header) -> Self {
+        Self {
+            light_client_block: Some(value.light_client_block.into()),
+            prev_state_root_of_chunks: value
+                .prev_state_root_of_chunks
+                .iter()
+                .map(|hash| hash.0.to_vec())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<v1::Header> for Header {
+    type Error = ProtoDecodingError;
+
+    fn try_from(value: v1::Header) -> Result<Self, Self::Error> {
+        Ok(Self {
+            light_client_block: value
+                .light_client_block
+                .ok_or(ProtoDecodingError::MissingField {
+                    field: "light_client_block",
+                })?
+                .try_into()?,
+            prev_state_root_of_chunks: value
+                .prev_state_root_of_chunks
+                .iter()
+                .map(|hash| decode_hash("prev_state_root_of_chunks", hash))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<ConsensusState> for v1::ConsensusState {
+    fn from(value: ConsensusState) -> Self {
+        Self {
+            current_bps: value.current_bps.map(encode_bps),
+            header: Some(value.header.into()),
+        }
+    }
+}
+
+impl TryFrom<v1::ConsensusState> for ConsensusState {
+    type Error = ProtoDecodingError;
+
+    fn try_from(value: v1::ConsensusState) -> Result<Self, Self::Error> {
+        Ok(Self {
+            current_bps: value.current_bps.map(decode_bps).transpose()?,
+            header: value
+                .header
+                .ok_or(ProtoDecodingError::MissingField { field: "header" })?
+                .try_into()?,
+        })
+    }
+}
+
+impl From<Height> for v1::Height {
+    fn from(value: Height) -> Self {
+        Self {
+            revision_number: value.revision_number,
+            revision_height: value.revision_height,
+        }
+    }
+}
+
+impl From<v1::Height> for Height {
+    fn from(value: v1::Height) -> Self {
+        Self::new(value.revision_number, value.revision_height)
+    }
+}
+
+impl From<ClientState> for v1::ClientState {
+    fn from(value: ClientState) -> Self {
+        Self {
+            latest_height: Some(value.latest_height.into()),
+            trusting_period: value.trusting_period,
+            frozen_height: value.frozen_height.map(Into::into),
+            chain_id: value.chain_id,
+        }
+    }
+}
+
+impl TryFrom<v1::ClientState> for ClientState {
+    type Error = ProtoDecodingError;
+
+    fn try_from(value: v1::ClientState) -> Result<Self, Self::Error> {
+        if value.chain_id.is_empty() {
+            return Err(ProtoDecodingError::MissingField { field: "chain_id" });
+        }
+        Ok(Self {
+            chain_id: value.chain_id,
+            latest_height: value
+                .latest_height
+                .ok_or(ProtoDecodingError::MissingField {
+                    field: "latest_height",
+                })?
+                .into(),
+            trusting_period: value.trusting_period,
+            frozen_height: value.frozen_height.map(Into::into),
+        })
+    }
+}
+
+impl From<Misbehaviour> for v1::Misbehaviour {
+    fn from(value: Misbehaviour) -> Self {
+        Self {
+            client_id: value.client_id,
+            header_1: Some(value.header_1.into()),
+            header_2: Some(value.header_2.into()),
+        }
+    }
+}
+
+impl TryFrom<v1::Misbehaviour> for Misbehaviour {
+    type Error = ProtoDecodingError;
+
+    fn try_from(value: v1::Misbehaviour) -> Result<Self, Self::Error> {
+        Ok(Self {
+            client_id: value.client_id,
+            header_1: value
+                .header_1
+                .ok_or(ProtoDecodingError::MissingField { field: "header_1" })?
+                .try_into()?,
+            header_2: value
+                .header_2
+                .ok_or(ProtoDecodingError::MissingField { field: "header_2" })?
+                .try_into()?,
+        })
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(ProtoDecodingError);
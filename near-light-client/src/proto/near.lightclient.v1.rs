@@ -0,0 +1,109 @@
+// This file is @generated by prost-build.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlockHeaderInnerLite {
+    #[prost(uint64, tag = "1")]
+    pub height: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub epoch_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub next_epoch_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub prev_state_root: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub outcome_root: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "6")]
+    pub timestamp: u64,
+    #[prost(bytes = "vec", tag = "7")]
+    pub next_bp_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "8")]
+    pub block_merkle_root: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidatorStakeView {
+    #[prost(string, tag = "1")]
+    pub account_id: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+    /// Decimal string of an unsigned 128-bit integer.
+    #[prost(string, tag = "3")]
+    pub stake: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ValidatorStakeViewList {
+    #[prost(message, repeated, tag = "1")]
+    pub validators: ::prost::alloc::vec::Vec<ValidatorStakeView>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Approval {
+    /// Empty if the block producer did not approve the block.
+    #[prost(bytes = "vec", tag = "1")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LightClientBlock {
+    #[prost(bytes = "vec", tag = "1")]
+    pub prev_block_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub next_block_inner_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub inner_lite: ::core::option::Option<BlockHeaderInnerLite>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub inner_rest_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "5")]
+    pub next_bps: ::core::option::Option<ValidatorStakeViewList>,
+    #[prost(message, repeated, tag = "6")]
+    pub approvals_after_next: ::prost::alloc::vec::Vec<Approval>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Header {
+    #[prost(message, optional, tag = "1")]
+    pub light_client_block: ::core::option::Option<LightClientBlock>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub prev_state_root_of_chunks: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsensusState {
+    #[prost(message, optional, tag = "1")]
+    pub current_bps: ::core::option::Option<ValidatorStakeViewList>,
+    #[prost(message, optional, tag = "2")]
+    pub header: ::core::option::Option<Header>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Height {
+    #[prost(uint64, tag = "1")]
+    pub revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    pub revision_height: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClientState {
+    #[prost(message, optional, tag = "1")]
+    pub latest_height: ::core::option::Option<Height>,
+    #[prost(uint64, tag = "2")]
+    pub trusting_period: u64,
+    /// Absent if the client is not frozen.
+    #[prost(message, optional, tag = "3")]
+    pub frozen_height: ::core::option::Option<Height>,
+    /// Identifier of the NEAR chain followed by the client, such as `mainnet`.
+    #[prost(string, tag = "4")]
+    pub chain_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Misbehaviour {
+    #[prost(string, tag = "1")]
+    pub client_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub header_1: ::core::option::Option<Header>,
+    #[prost(message, optional, tag = "3")]
+    pub header_2: ::core::option::Option<Header>,
+}
@@ -0,0 +1,132 @@
+//! Recorded test vectors, the headers and proofs of a NEAR chain with the verification
+//! results expected for them.
+//!
+//! The vectors are stored as their borsh serialization in the `test-vectors` folder of this
+//! crate, one vector per file, and are recorded from NEAR rpc by the `record-fixtures`
+//! command of the sample app, so that the verification can be regression tested offline.
+
+use alloc::{string::String, vec::Vec};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{
+    types::{ConsensusState, Header, Height, OutcomeProof, StateValueProof},
+    BasicNearLightClient, Error, TrustedConsensusState,
+};
+
+/// Extension of the files of test vectors.
+pub const TEST_VECTOR_FILE_EXTENSION: &str = "borsh";
+
+/// Data to verify in a test vector.
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub enum TestCase {
+    /// A header verified with the trusted consensus state of a light client.
+    Header {
+        trusted: ConsensusState,
+        header: Header,
+    },
+    /// A state proof verified with the consensus state at the height of the proof.
+    StateValue {
+        consensus_state: ConsensusState,
+        proof: StateValueProof,
+    },
+    /// An outcome proof verified with the consensus state at the height of the proof.
+    Outcome {
+        consensus_state: ConsensusState,
+        proof: OutcomeProof,
+    },
+}
+
+/// A test case recorded from a NEAR chain, with its expected verification result.
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct TestVector {
+    /// Identifier of the NEAR chain the case is recorded from
+    pub chain_id: String,
+    pub case: TestCase,
+    /// Whether the verification of the case is expected to succeed
+    pub accepted: bool,
+}
+
+impl TestCase {
+    /// Returns the name of the kind of the case.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TestCase::Header { .. } => "header",
+            TestCase::StateValue {
+                proof: StateValueProof::Membership(_),
+                ..
+            } => "membership",
+            TestCase::StateValue {
+                proof: StateValueProof::NonMembership(_),
+                ..
+            } => "non_membership",
+            TestCase::Outcome { .. } => "outcome",
+        }
+    }
+
+    /// Returns the height of the header or proof of the case.
+    pub fn height(&self) -> Height {
+        match self {
+            TestCase::Header { header, .. } => header.height(),
+            TestCase::StateValue { proof, .. } => match proof {
+                StateValueProof::Membership(proof) => proof.height,
+                StateValueProof::NonMembership(proof) => proof.height,
+            },
+            TestCase::Outcome { proof, .. } => proof.height,
+        }
+    }
+
+    /// Verify the case.
+    pub fn verify(&self) -> Result<(), Error> {
+        match self {
+            TestCase::Header { trusted, header } => {
+                TrustedConsensusState::new(trusted.clone()).verify_header(header)?
+            }
+            TestCase::StateValue {
+                consensus_state,
+                proof,
+            } => consensus_state.verify_state_value_proof(proof)?,
+            TestCase::Outcome {
+                consensus_state,
+                proof,
+            } => consensus_state.verify_outcome_proof(proof)?,
+        }
+        Ok(())
+    }
+}
+
+impl TestVector {
+    ///
+    pub fn new(chain_id: String, case: TestCase, accepted: bool) -> Self {
+        Self {
+            chain_id,
+            case,
+            accepted,
+        }
+    }
+
+    /// Returns the file name of the vector in the `test-vectors` folder.
+    pub fn file_name(&self) -> String {
+        alloc::format!(
+            "{}-{}-{}.{}",
+            self.chain_id,
+            self.case.kind(),
+            self.case.height().revision_height,
+            TEST_VECTOR_FILE_EXTENSION
+        )
+    }
+
+    /// Returns the borsh serialization of the vector, which is the content of its file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(self).expect("Should not fail based on borsh serialization.")
+    }
+
+    /// Returns whether the verification result of the case is the expected one.
+    pub fn check(&self) -> bool {
+        self.case.verify().is_ok() == self.accepted
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(TestCase, TestVector);
@@ -0,0 +1,174 @@
+//! Utilities for property testing, fuzzing and building fixtures,
+//! only available with feature `testing`.
+
+use crate::{
+    near_types::{
+        hash::CryptoHash,
+        merkle::merklize,
+        signature::{ED25519PublicKey, PublicKey, Signature},
+        trie::{
+            nibble_slice::{bytes_to_nibbles, encode_key_nibbles},
+            RawTrieNode, RawTrieNodeWithSize,
+        },
+        BlockHeaderInnerLite, EpochId, LightClientBlock, ValidatorStakeView, ValidatorStakeViewV1,
+    },
+    types::{ConsensusState, Header},
+};
+use alloc::{format, vec, vec::Vec};
+use arbitrary::{Arbitrary, Unstructured};
+use core::fmt::Debug;
+use ed25519_dalek::Signer;
+use proptest::{collection::vec, prelude::any, strategy::Strategy};
+
+/// Max length of the random bytes used to generate a value.
+const MAX_UNSTRUCTURED_BYTES: usize = 4096;
+
+/// Returns a `proptest` strategy generating values with their `Arbitrary` implementation.
+pub fn arbitrary_strategy<T>() -> impl Strategy<Value = T>
+where
+    T: for<'a> Arbitrary<'a> + Debug,
+{
+    vec(any::<u8>(), 0..MAX_UNSTRUCTURED_BYTES)
+        .prop_filter_map("Not enough random bytes to generate the value.", |bytes| {
+            T::arbitrary(&mut Unstructured::new(&bytes)).ok()
+        })
+}
+
+/// Returns the public key of the given ed25519 secret key.
+pub fn ed25519_public_key(secret_key: &[u8; 32]) -> PublicKey {
+    PublicKey::ED25519(ED25519PublicKey(keypair_of(secret_key).public.to_bytes()))
+}
+
+/// Sign the message with the given ed25519 secret key.
+pub fn sign_ed25519(secret_key: &[u8; 32], message: &[u8]) -> Signature {
+    Signature::ED25519(keypair_of(secret_key).sign(message).to_bytes().to_vec())
+}
+
+/// Sign the approval message of `block` with the secret keys of the block producers
+/// of its epoch (ordered as the block producers), returning valid `approvals_after_next`.
+///
+/// The block producers without secret key are treated as not approving the block.
+pub fn sign_approvals(
+    block: &LightClientBlock,
+    secret_keys: &[Option<[u8; 32]>],
+) -> Vec<Option<Signature>> {
+    let approval_message = block.approval_message();
+    secret_keys
+        .iter()
+        .map(|secret_key| {
+            secret_key
+                .as_ref()
+                .map(|secret_key| sign_ed25519(secret_key, &approval_message))
+        })
+        .collect()
+}
+
+/// Returns the secret keys of the `bp_count` block producers of [`header_fixture`], which can
+/// sign the approvals of a fixture header again with `sign_approvals` after modifying it.
+pub fn fixture_secret_keys(bp_count: usize) -> Vec<Option<[u8; 32]>> {
+    (0..bp_count as u64)
+        .map(|index| {
+            let mut secret_key = [0u8; 32];
+            secret_key[..8].copy_from_slice(&(index + 1).to_le_bytes());
+            Some(secret_key)
+        })
+        .collect()
+}
+
+/// A trusted consensus state, and a valid header of the same epoch.
+pub struct HeaderFixture {
+    pub consensus_state: ConsensusState,
+    pub header: Header,
+}
+
+/// Build a [`HeaderFixture`] with `bp_count` block producers of equal stake, all of which
+/// approve the header, and `chunk_count` chunks.
+///
+/// The header carries the same block producers as its `next_bps`, so that verifying it
+/// also checks the `next_bp_hash` and the sanity of the block producers.
+pub fn header_fixture(bp_count: usize, chunk_count: usize) -> HeaderFixture {
+    let secret_keys = fixture_secret_keys(bp_count);
+    let bps = (0..bp_count)
+        .zip(secret_keys.iter().flatten())
+        .map(|(index, secret_key)| {
+            ValidatorStakeView::V1(ValidatorStakeViewV1 {
+                account_id: format!("validator-{}.near", index),
+                public_key: ed25519_public_key(secret_key),
+                stake: 1_000_000_000_000_000_000_000_000,
+            })
+        })
+        .collect::<Vec<_>>();
+    let epoch_id = EpochId(CryptoHash::hash_bytes(b"epoch"));
+    let next_epoch_id = EpochId(CryptoHash::hash_bytes(b"next epoch"));
+    let prev_state_root_of_chunks = (0..chunk_count as u64)
+        .map(|index| CryptoHash::hash_borsh(&index))
+        .collect::<Vec<_>>();
+    let block_at = |height: u64, next_bps: Option<Vec<ValidatorStakeView>>| LightClientBlock {
+        prev_block_hash: CryptoHash::hash_borsh(&(height - 1)),
+        next_block_inner_hash: CryptoHash::hash_borsh(&(height + 1)),
+        inner_lite: BlockHeaderInnerLite {
+            height,
+            epoch_id: epoch_id.clone(),
+            next_epoch_id: next_epoch_id.clone(),
+            prev_state_root: merklize(&prev_state_root_of_chunks).0,
+            outcome_root: CryptoHash::default(),
+            timestamp: height * 1_000_000_000,
+            next_bp_hash: next_bps
+                .as_ref()
+                .map_or_else(CryptoHash::default, CryptoHash::hash_borsh),
+            block_merkle_root: CryptoHash::default(),
+        },
+        inner_rest_hash: CryptoHash::default(),
+        next_bps: next_bps.map(Into::into),
+        approvals_after_next: Vec::new(),
+    };
+    let consensus_state = ConsensusState::new(
+        Some(bps.clone().into()),
+        Header {
+            light_client_block: block_at(100, None),
+            prev_state_root_of_chunks: prev_state_root_of_chunks.clone(),
+        },
+    );
+    let mut block = block_at(101, Some(bps));
+    block.approvals_after_next = sign_approvals(&block, &secret_keys);
+    HeaderFixture {
+        consensus_state,
+        header: Header {
+            light_client_block: block,
+            prev_state_root_of_chunks,
+        },
+    }
+}
+
+/// Build the state proof data of `key` with `value`, ordered from root to leaf, in which the
+/// leaf is below `depth` branch nodes with all children present. Returns the proof data and
+/// the state root.
+///
+/// The `depth` must not be more than the count of nibbles of `key`.
+pub fn state_proof_fixture(key: &[u8], value: &[u8], depth: usize) -> (Vec<Vec<u8>>, CryptoHash) {
+    let nibbles = bytes_to_nibbles(key);
+    assert!(depth <= nibbles.len(), "The depth exceeds the key length.");
+    let leaf = RawTrieNodeWithSize::new(
+        RawTrieNode::new_leaf(encode_key_nibbles(&nibbles[depth..], true), value),
+        0,
+    );
+    let mut child_hash = leaf.hash();
+    let mut proofs = vec![leaf.encode()];
+    for nibble in nibbles[..depth].iter().rev() {
+        let mut children: [Option<CryptoHash>; 16] =
+            core::array::from_fn(|index| Some(CryptoHash::hash_borsh(&(index as u8))));
+        children[*nibble as usize] = Some(child_hash);
+        let branch = RawTrieNodeWithSize::new(RawTrieNode::new_branch(children, None), 0);
+        child_hash = branch.hash();
+        proofs.push(branch.encode());
+    }
+    proofs.reverse();
+    (proofs, child_hash)
+}
+
+fn keypair_of(secret_key: &[u8; 32]) -> ed25519_dalek::Keypair {
+    let secret = ed25519_dalek::SecretKey::from_bytes(secret_key)
+        .expect("Should not fail as any 32 bytes is a valid ed25519 secret key.");
+    let public = ed25519_dalek::PublicKey::from(&secret);
+    ed25519_dalek::Keypair { secret, public }
+}
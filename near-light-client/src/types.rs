@@ -1,22 +1,478 @@
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use borsh::{BorshDeserialize, BorshSerialize};
+use core::{
+    fmt::{self, Display},
+    ops::Deref,
+};
 
-use crate::near_types::{hash::CryptoHash, LightClientBlock, ValidatorStakeView};
+use crate::near_types::{
+    block_producers::BlockProducers, hash::CryptoHash, merkle::MerklePath,
+    transaction::ExecutionOutcomeWithId, LightClientBlock, LightClientBlockLite,
+};
 
-pub type Height = u64;
+/// The revision number of the heights of NEAR blocks, as NEAR block heights never reset.
+pub const NEAR_REVISION_NUMBER: u64 = 0;
+
+/// The height of a header, which is the height of the NEAR block in a revision of the chain.
+///
+/// Heights are ordered by `revision_number` first, then `revision_height`, the same as IBC.
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, BorshDeserialize, BorshSerialize,
+)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct Height {
+    pub revision_number: u64,
+    pub revision_height: u64,
+}
+
+impl Height {
+    ///
+    pub const fn new(revision_number: u64, revision_height: u64) -> Self {
+        Self {
+            revision_number,
+            revision_height,
+        }
+    }
+
+    /// Returns the height of the NEAR block at `block_height`, in [`NEAR_REVISION_NUMBER`].
+    pub const fn from_block_height(block_height: u64) -> Self {
+        Self::new(NEAR_REVISION_NUMBER, block_height)
+    }
+}
+
+impl From<u64> for Height {
+    fn from(block_height: u64) -> Self {
+        Self::from_block_height(block_height)
+    }
+}
+
+impl Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.revision_number, self.revision_height)
+    }
+}
+
+/// The format version of the proof bundles, stored as their first byte.
+pub const PROOF_FORMAT_VERSION: u8 = 1;
 
 /// The header data struct of NEAR light client.
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct Header {
     pub light_client_block: LightClientBlock,
     pub prev_state_root_of_chunks: Vec<CryptoHash>,
 }
 
+/// A [`Header`] sealed with the hash of its block, which is computed once on sealing.
+///
+/// Computing the block hash serializes `inner_lite` and hashes it three times, use this
+/// wrapper on paths where the hash of the same header is needed repeatedly.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+pub struct SealedHeader {
+    header: Header,
+    hash: CryptoHash,
+}
+
+impl SealedHeader {
+    /// Seal `header` with the hash of its block.
+    pub fn new(header: Header) -> Self {
+        let hash = header.light_client_block.current_block_hash();
+        Self { header, hash }
+    }
+    /// Returns the hash of the block of the header.
+    pub fn hash(&self) -> CryptoHash {
+        self.hash
+    }
+    ///
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    ///
+    pub fn into_header(self) -> Header {
+        self.header
+    }
+}
+
+impl Deref for SealedHeader {
+    type Target = Header;
+
+    fn deref(&self) -> &Self::Target {
+        &self.header
+    }
+}
+
+impl From<Header> for SealedHeader {
+    fn from(header: Header) -> Self {
+        Self::new(header)
+    }
+}
+
+impl From<SealedHeader> for Header {
+    fn from(sealed: SealedHeader) -> Self {
+        sealed.header
+    }
+}
+
 /// The consensus state of NEAR light client.
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
 pub struct ConsensusState {
     /// Block producers of current epoch
-    pub current_bps: Option<Vec<ValidatorStakeView>>,
+    pub current_bps: Option<BlockProducers>,
     /// Header data
     pub header: Header,
 }
+
+/// The client state of NEAR light client.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct ClientState {
+    /// Identifier of the NEAR chain followed by the client, such as `mainnet` or `testnet`,
+    /// which is not committed to by the headers, see [`ClientState::verify_chain_id`]
+    pub chain_id: String,
+    /// Height of the latest header verified by the client
+    pub latest_height: Height,
+    /// Duration in nanoseconds within which a consensus state is trusted
+    pub trusting_period: u64,
+    /// Height at which the client was frozen because of misbehaviour
+    pub frozen_height: Option<Height>,
+}
+
+/// Evidence of two conflicting headers, both of which are valid for the NEAR light client.
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct Misbehaviour {
+    /// Identifier of the client to freeze
+    pub client_id: String,
+    pub header_1: Header,
+    pub header_2: Header,
+}
+
+/// Everything needed to verify offline the value of a storage key, with the consensus state
+/// at `height`.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct MembershipProof {
+    /// Format version of the proof, [`PROOF_FORMAT_VERSION`] for proofs created by this crate
+    pub version: u8,
+    /// Height of the consensus state to verify the proof with
+    pub height: Height,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// The proof data at `height - 1` returned by the `view_state` rpc of NEAR
+    pub proof: Vec<Vec<u8>>,
+}
+
+impl MembershipProof {
+    ///
+    pub fn new(height: Height, key: Vec<u8>, value: Vec<u8>, proof: Vec<Vec<u8>>) -> Self {
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            height,
+            key,
+            value,
+            proof,
+        }
+    }
+}
+
+/// Everything needed to verify offline that a storage key has no value, with the consensus
+/// state at `height`.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct NonMembershipProof {
+    /// Format version of the proof, [`PROOF_FORMAT_VERSION`] for proofs created by this crate
+    pub version: u8,
+    /// Height of the consensus state to verify the proof with
+    pub height: Height,
+    pub key: Vec<u8>,
+    /// The proof data at `height - 1` returned by the `view_state` rpc of NEAR
+    pub proof: Vec<Vec<u8>>,
+}
+
+impl NonMembershipProof {
+    ///
+    pub fn new(height: Height, key: Vec<u8>, proof: Vec<Vec<u8>>) -> Self {
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            height,
+            key,
+            proof,
+        }
+    }
+}
+
+/// Everything needed to verify offline a transaction or receipt outcome, with the consensus
+/// state at `height`.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct OutcomeProof {
+    /// Format version of the proof, [`PROOF_FORMAT_VERSION`] for proofs created by this crate
+    pub version: u8,
+    /// Height of the consensus state to verify the proof with
+    pub height: Height,
+    pub outcome_with_id: ExecutionOutcomeWithId,
+    /// Proof of the outcome in the outcomes of its chunk
+    pub outcome_proof: MerklePath,
+    /// Proof of the outcome root of the chunk in the outcome root of the block
+    pub outcome_root_proof: MerklePath,
+    /// The block containing the outcome
+    pub block_lite_view: LightClientBlockLite,
+    /// Proof of the block in the `block_merkle_root` of the header at `height`
+    pub block_proof: MerklePath,
+}
+
+impl OutcomeProof {
+    ///
+    pub fn new(
+        height: Height,
+        outcome_with_id: ExecutionOutcomeWithId,
+        outcome_proof: MerklePath,
+        outcome_root_proof: MerklePath,
+        block_lite_view: LightClientBlockLite,
+        block_proof: MerklePath,
+    ) -> Self {
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            height,
+            outcome_with_id,
+            outcome_proof,
+            outcome_root_proof,
+            block_lite_view,
+            block_proof,
+        }
+    }
+}
+
+/// Everything needed to verify the value of a storage key in the state of an old block,
+/// with the consensus state at `height` only.
+///
+/// The old block is proven to be an ancestor of the header at `height` by `block_proof`,
+/// and the state root of the chunk is proven to be in the `prev_state_root` of the old block
+/// by `chunk_root_proof`. The state is the one before the old block is applied.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct HistoricalMembershipProof {
+    /// Format version of the proof, [`PROOF_FORMAT_VERSION`] for proofs created by this crate
+    pub version: u8,
+    /// Height of the consensus state to verify the proof with
+    pub height: Height,
+    /// The old block the state proof is rooted in
+    pub block_lite_view: LightClientBlockLite,
+    /// Proof of the old block in the `block_merkle_root` of the header at `height`
+    pub block_proof: MerklePath,
+    /// The state root of the chunk the state proof is built on
+    pub chunk_root: CryptoHash,
+    /// Proof of `chunk_root` in the `prev_state_root` of the old block
+    pub chunk_root_proof: MerklePath,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// The proof data at the height of the old block minus one
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// A proof of the value, or the absence, of a storage key.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub enum StateValueProof {
+    Membership(MembershipProof),
+    NonMembership(NonMembershipProof),
+}
+
+impl StateValueProof {
+    ///
+    pub fn key(&self) -> &[u8] {
+        match self {
+            StateValueProof::Membership(proof) => &proof.key,
+            StateValueProof::NonMembership(proof) => &proof.key,
+        }
+    }
+    ///
+    pub fn height(&self) -> Height {
+        match self {
+            StateValueProof::Membership(proof) => proof.height,
+            StateValueProof::NonMembership(proof) => proof.height,
+        }
+    }
+    /// Returns the proven value, `None` for a proof of absence.
+    pub fn value(&self) -> Option<&[u8]> {
+        match self {
+            StateValueProof::Membership(proof) => Some(&proof.value),
+            StateValueProof::NonMembership(_) => None,
+        }
+    }
+}
+
+/// A verified change of the value of a storage key between two heights.
+///
+/// A `None` value means the key has no value at the height.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct StateChange {
+    pub key: Vec<u8>,
+    pub old_height: Height,
+    pub old_value: Option<Vec<u8>>,
+    pub new_height: Height,
+    pub new_value: Option<Vec<u8>>,
+}
+
+impl StateChange {
+    /// Returns whether the value at the new height is different from the old one.
+    pub fn is_changed(&self) -> bool {
+        self.old_value != self.new_value
+    }
+}
+
+/// A proof bundled by a [`ProofBundle`].
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub enum BundledProof {
+    State(StateValueProof),
+    Outcome(OutcomeProof),
+}
+
+impl BundledProof {
+    ///
+    pub fn height(&self) -> Height {
+        match self {
+            BundledProof::State(proof) => proof.height(),
+            BundledProof::Outcome(proof) => proof.height,
+        }
+    }
+}
+
+/// A proof together with the consensus state it is verified with, which can be verified
+/// offline, e.g. on an air-gapped machine, by anyone trusting the consensus state.
+#[derive(Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "borsh-schema", derive(borsh::BorshSchema))]
+#[cfg_attr(feature = "testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "scale",
+    derive(codec::Encode, codec::Decode, scale_info::TypeInfo)
+)]
+pub struct ProofBundle {
+    /// Format version of the bundle, [`PROOF_FORMAT_VERSION`] for bundles created by this crate
+    pub version: u8,
+    /// Id of the NEAR chain, e.g. `mainnet`
+    pub chain_id: String,
+    /// The consensus state at the height of the proof
+    pub consensus_state: ConsensusState,
+    pub proof: BundledProof,
+}
+
+impl ProofBundle {
+    ///
+    pub fn new(chain_id: String, consensus_state: ConsensusState, proof: BundledProof) -> Self {
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            chain_id,
+            consensus_state,
+            proof,
+        }
+    }
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(
+    Height,
+    Header,
+    SealedHeader,
+    ConsensusState,
+    ClientState,
+    Misbehaviour,
+    MembershipProof,
+    NonMembershipProof,
+    OutcomeProof,
+    HistoricalMembershipProof,
+    StateValueProof,
+    StateChange,
+    BundledProof,
+    ProofBundle
+);
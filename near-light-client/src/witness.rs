@@ -0,0 +1,148 @@
+//! Witness export for re-executing header verification inside a zk circuit.
+//!
+//! The witness is a flat, canonical structure containing every preimage hashed during
+//! header verification, together with the public keys, stakes and signatures of the
+//! block producers, so that a circuit can recompute all hashes and check all signatures
+//! without any knowledge of the NEAR datatypes.
+
+use alloc::vec::Vec;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{
+    near_types::{
+        hash::{combine_hash, CryptoHash},
+        signature::{PublicKey, Signature},
+    },
+    types::{ConsensusState, Header, Height},
+    HeaderVerificationError,
+};
+
+/// A preimage and its sha256 hash.
+#[derive(Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashPreimage {
+    pub preimage: Vec<u8>,
+    pub hash: CryptoHash,
+}
+
+impl HashPreimage {
+    fn new(preimage: Vec<u8>) -> Self {
+        let hash = CryptoHash::hash_bytes(&preimage);
+        Self { preimage, hash }
+    }
+
+    fn of_pair(hash1: &CryptoHash, hash2: &CryptoHash) -> Self {
+        Self {
+            preimage: [hash1.0, hash2.0].concat(),
+            hash: combine_hash(hash1, hash2),
+        }
+    }
+}
+
+/// The approval of a block producer of the epoch of the header.
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ApprovalWitness {
+    pub public_key: PublicKey,
+    pub stake: u128,
+    /// `None` if the block producer did not approve the block.
+    pub signature: Option<Signature>,
+}
+
+/// All data needed to re-execute the verification of a header against a trusted
+/// consensus state.
+///
+/// The hash preimages are listed in the order they are computed during verification,
+/// and the approvals are listed in the order of the block producers of the epoch.
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(not(feature = "strip-debug"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderWitness {
+    pub trusted_height: Height,
+    pub trusted_epoch_id: CryptoHash,
+    pub trusted_next_epoch_id: CryptoHash,
+    pub height: Height,
+    pub epoch_id: CryptoHash,
+    /// Preimage of the hash of `inner_lite`, which is its borsh serialization.
+    pub inner_lite: HashPreimage,
+    /// Preimage of the inner hash, `inner_lite` hash followed by `inner_rest_hash`.
+    pub inner_hash: HashPreimage,
+    /// Preimage of the block hash, inner hash followed by `prev_block_hash`.
+    pub current_block_hash: HashPreimage,
+    /// Preimage of the next block hash, `next_block_inner_hash` followed by the block hash.
+    pub next_block_hash: HashPreimage,
+    /// The message signed by the block producers.
+    pub approval_message: Vec<u8>,
+    pub approvals: Vec<ApprovalWitness>,
+    pub total_stake: u128,
+    /// Preimage of `next_bp_hash`, which is the borsh serialization of `next_bps`.
+    pub next_bps: Option<HashPreimage>,
+    /// Leaves of the merkle tree whose root is `prev_state_root`.
+    pub prev_state_root_of_chunks: Vec<CryptoHash>,
+    pub prev_state_root: CryptoHash,
+}
+
+/// Exports the witness for verifying `header` with the trusted `consensus_state`.
+///
+/// This function does not verify the header, it fails only if the block producers of
+/// the epoch of the header are not known by the consensus state.
+pub fn export_header_witness(
+    consensus_state: &ConsensusState,
+    header: &Header,
+) -> Result<HeaderWitness, HeaderVerificationError> {
+    let block = &header.light_client_block;
+    let epoch_block_producers = consensus_state
+        .get_block_producers_of(&header.epoch_id())
+        .ok_or(HeaderVerificationError::MissingCachedEpochBlockProducers {
+            epoch_id: header.epoch_id(),
+        })?;
+
+    let inner_lite = HashPreimage::new(
+        borsh::to_vec(&block.inner_lite).expect("Should not fail based on borsh serialization."),
+    );
+    let inner_hash = HashPreimage::of_pair(&inner_lite.hash, &block.inner_rest_hash);
+    let current_block_hash = HashPreimage::of_pair(&inner_hash.hash, &block.prev_block_hash);
+    let next_block_hash =
+        HashPreimage::of_pair(&block.next_block_inner_hash, &current_block_hash.hash);
+
+    let approvals = block
+        .approvals_after_next
+        .iter()
+        .zip(epoch_block_producers.iter())
+        .map(|(maybe_signature, block_producer)| {
+            let block_producer = block_producer.as_validator_stake();
+            ApprovalWitness {
+                public_key: block_producer.public_key.clone(),
+                stake: block_producer.stake,
+                signature: maybe_signature.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+    let total_stake = approvals.iter().map(|approval| approval.stake).sum();
+
+    Ok(HeaderWitness {
+        trusted_height: consensus_state.header.height(),
+        trusted_epoch_id: consensus_state.header.epoch_id(),
+        trusted_next_epoch_id: consensus_state.header.next_epoch_id(),
+        height: header.height(),
+        epoch_id: header.epoch_id(),
+        inner_lite,
+        inner_hash,
+        current_block_hash,
+        next_block_hash,
+        approval_message: block.approval_message(),
+        approvals,
+        total_stake,
+        next_bps: block
+            .next_bps
+            .as_ref()
+            .map(|next_bps| HashPreimage::new(next_bps.to_borsh().into_owned())),
+        prev_state_root_of_chunks: header.prev_state_root_of_chunks.clone(),
+        prev_state_root: block.inner_lite.prev_state_root,
+    })
+}
+
+#[cfg(feature = "strip-debug")]
+opaque_debug!(HashPreimage, ApprovalWitness, HeaderWitness);
@@ -0,0 +1,102 @@
+//! Tests of the cost accounting of verification calls, run with
+//! `--features testing,cost-model`.
+
+use near_light_client::{
+    cost_model::{measure, VerificationCost},
+    near_types::{
+        hash::CryptoHash,
+        signature::{PublicKey, Signature, SignatureVerifier},
+    },
+    testing::{header_fixture, state_proof_fixture},
+    types::{ConsensusState, Height, MembershipProof, StateValueProof, PROOF_FORMAT_VERSION},
+    BasicNearLightClient,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Verifier accepting every signature, which counts its calls.
+#[derive(Default)]
+struct AcceptingVerifier(AtomicU64);
+
+impl SignatureVerifier for AcceptingVerifier {
+    fn verify(&self, _signature: &Signature, _data: &[u8], _public_key: &PublicKey) -> bool {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+}
+
+struct FixtureClient(ConsensusState, AcceptingVerifier);
+
+impl BasicNearLightClient for FixtureClient {
+    fn latest_height(&self) -> Height {
+        self.0.header.height()
+    }
+
+    fn get_consensus_state(&self, height: &Height) -> Option<ConsensusState> {
+        (*height == self.latest_height()).then(|| self.0.clone())
+    }
+
+    fn signature_verifier(&self) -> &dyn SignatureVerifier {
+        &self.1
+    }
+}
+
+#[test]
+fn signatures_of_custom_verifier_are_counted() {
+    let fixture = header_fixture(7, 2);
+    let client = FixtureClient(fixture.consensus_state, AcceptingVerifier::default());
+    let (result, cost) = client.verify_header_with_cost(&fixture.header);
+    assert!(result.is_ok());
+    assert_eq!(cost.signature_verifications, 7);
+    assert_eq!(client.1 .0.load(Ordering::Relaxed), 7);
+    assert!(cost.hash_invocations > 0);
+}
+
+#[test]
+fn concurrent_verifications_are_not_counted_into_each_other() {
+    let fixture = header_fixture(4, 2);
+    let client = FixtureClient(fixture.consensus_state, AcceptingVerifier::default());
+    let (_, expected) = client.verify_header_with_cost(&fixture.header);
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..50 {
+                    let (_, cost) = client.verify_header_with_cost(&fixture.header);
+                    assert_eq!(cost, expected);
+                    CryptoHash::hash_bytes(b"unmeasured");
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn nested_measures_are_counted_into_the_enclosing_one() {
+    let key = b"key".to_vec();
+    let value = b"value".to_vec();
+    let (proof, state_root) = state_proof_fixture(&key, &value, 3);
+    let mut consensus_state = header_fixture(4, 2).consensus_state;
+    consensus_state.header.prev_state_root_of_chunks = vec![state_root];
+    let proof = StateValueProof::Membership(MembershipProof {
+        version: PROOF_FORMAT_VERSION,
+        height: consensus_state.header.height(),
+        key,
+        value,
+        proof,
+    });
+
+    let ((result, inner), outer) = measure(|| {
+        CryptoHash::hash_bytes(b"outer");
+        consensus_state.verify_state_value_proof_with_cost(&proof)
+    });
+    assert!(result.is_ok());
+    assert_eq!(inner.signature_verifications, 0);
+    assert!(inner.hash_invocations > 0);
+    assert_eq!(
+        outer,
+        VerificationCost {
+            hash_invocations: inner.hash_invocations + 1,
+            bytes_hashed: inner.bytes_hashed + b"outer".len() as u64,
+            signature_verifications: 0,
+        }
+    );
+}
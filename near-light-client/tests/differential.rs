@@ -0,0 +1,546 @@
+//! Differential tests against the datatypes and functions of nearcore (`near-primitives`
+//! and `near-crypto`), run with `--features differential`.
+//!
+//! The headers are verified by this crate and by a reference validator following the light
+//! client spec of NEAR, in which all hashes, approval messages and signature checks are done
+//! by nearcore, asserting both make the same accept/reject decision.
+
+use borsh::BorshDeserialize;
+use near_light_client::{
+    ibc_paths::{
+        channel_end_path, client_consensus_state_path, client_state_path, commitment_prefix,
+        connection_path, next_sequence_recv_path, packet_acknowledgement_path,
+        packet_commitment_path, packet_receipt_path, path_key,
+    },
+    near_types::{
+        hash::CryptoHash,
+        merkle::{
+            compute_block_merkle_path, compute_block_merkle_root, compute_root_from_path,
+            merkle_path_of, merklize, MerklePath, PartialMerkleTree,
+        },
+        sdk_collections::{
+            lazy_option_key, lookup_map_entry_key, lookup_set_entry_key, unordered_map_index_key,
+            unordered_map_key_key, unordered_map_value_key, unordered_set_element_key,
+            unordered_set_index_key, vector_element_key,
+        },
+        signature::{ED25519PublicKey, PublicKey, Signature},
+        trie_key::TrieKey,
+        LightClientBlock, ValidatorStakeView,
+    },
+    testing::{header_fixture, HeaderFixture},
+    types::Header,
+    BasicNearLightClient, TrustedConsensusState,
+};
+use near_primitives::{
+    block_header::{Approval, ApprovalInner},
+    views::{
+        validator_stake_view::ValidatorStakeView as NearValidatorStakeView,
+        BlockHeaderInnerLiteView, LightClientBlockLiteView,
+    },
+};
+use proptest::{collection::vec, prelude::*};
+
+const BLOCK_PRODUCERS: usize = 7;
+
+/// Convert a value to the type of the same borsh layout, between this crate and nearcore.
+fn reencode<T: borsh::BorshSerialize, N: BorshDeserialize>(value: &T) -> Option<N> {
+    N::try_from_slice(&borsh::to_vec(value).unwrap()).ok()
+}
+
+fn near_hash(hash: &CryptoHash) -> near_primitives::hash::CryptoHash {
+    near_primitives::hash::CryptoHash(hash.0)
+}
+
+fn near_block_hash(block: &LightClientBlock) -> near_primitives::hash::CryptoHash {
+    let inner_lite = &block.inner_lite;
+    LightClientBlockLiteView {
+        prev_block_hash: near_hash(&block.prev_block_hash),
+        inner_rest_hash: near_hash(&block.inner_rest_hash),
+        inner_lite: BlockHeaderInnerLiteView {
+            height: inner_lite.height,
+            epoch_id: near_hash(&inner_lite.epoch_id.0),
+            next_epoch_id: near_hash(&inner_lite.next_epoch_id.0),
+            prev_state_root: near_hash(&inner_lite.prev_state_root),
+            outcome_root: near_hash(&inner_lite.outcome_root),
+            timestamp: inner_lite.timestamp,
+            timestamp_nanosec: inner_lite.timestamp,
+            next_bp_hash: near_hash(&inner_lite.next_bp_hash),
+            block_merkle_root: near_hash(&inner_lite.block_merkle_root),
+        },
+    }
+    .hash()
+}
+
+/// Validate `block` with the trusted `head` and the block producers of the epoch of `block`,
+/// as the light client spec of NEAR does.
+fn reference_validate(
+    head: &LightClientBlock,
+    epoch_block_producers: &[ValidatorStakeView],
+    block: &LightClientBlock,
+) -> bool {
+    let (head, new) = (&head.inner_lite, &block.inner_lite);
+    if new.height <= head.height {
+        return false;
+    }
+    if new.epoch_id != head.epoch_id && new.epoch_id != head.next_epoch_id {
+        return false;
+    }
+    if new.epoch_id == head.next_epoch_id && block.next_bps.is_none() {
+        return false;
+    }
+
+    let next_block_hash = near_primitives::merkle::combine_hash(
+        &near_hash(&block.next_block_inner_hash),
+        &near_block_hash(block),
+    );
+    let approval_message =
+        Approval::get_data_for_sig(&ApprovalInner::Endorsement(next_block_hash), new.height + 2);
+
+    let mut total_stake = 0;
+    let mut approved_stake = 0;
+    for (maybe_signature, block_producer) in block
+        .approvals_after_next
+        .iter()
+        .zip(epoch_block_producers.iter())
+    {
+        let block_producer: NearValidatorStakeView = reencode(block_producer).unwrap();
+        let NearValidatorStakeView::V1(block_producer) = block_producer;
+        total_stake += block_producer.stake;
+        let Some(signature) = maybe_signature else {
+            continue;
+        };
+        approved_stake += block_producer.stake;
+        match reencode::<_, near_crypto::Signature>(signature) {
+            Some(signature) if signature.verify(&approval_message, &block_producer.public_key) => {}
+            _ => return false,
+        }
+    }
+    if approved_stake * 3 <= total_stake * 2 {
+        return false;
+    }
+
+    if let Some(next_bps) = block.next_bps.as_ref() {
+        let next_bps: Vec<NearValidatorStakeView> = reencode(next_bps).unwrap();
+        if near_primitives::hash::CryptoHash::hash_borsh(&next_bps) != near_hash(&new.next_bp_hash)
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// Mutations of a valid header, each keeping or breaking a check of the verification.
+#[derive(Clone, Debug)]
+enum Mutation {
+    Unchanged,
+    /// Remove the approvals of the given block producers.
+    DropApprovals(Vec<usize>),
+    /// Flip a byte of the signature of a block producer.
+    CorruptSignature {
+        index: usize,
+        byte: usize,
+    },
+    /// Remove the approvals after the given count.
+    TruncateApprovals(usize),
+    LowerHeight(u64),
+    UnknownEpoch,
+    NextEpoch,
+    /// Remove a block producer of the next epoch without updating `next_bp_hash`.
+    DropNextBlockProducer(usize),
+    RemoveNextBlockProducers,
+    TamperInnerRestHash,
+}
+
+fn mutation_strategy() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        Just(Mutation::Unchanged),
+        vec(0..BLOCK_PRODUCERS, 0..BLOCK_PRODUCERS).prop_map(Mutation::DropApprovals),
+        (0..BLOCK_PRODUCERS, 0..64_usize)
+            .prop_map(|(index, byte)| Mutation::CorruptSignature { index, byte }),
+        (0..BLOCK_PRODUCERS).prop_map(Mutation::TruncateApprovals),
+        (0..=100_u64).prop_map(Mutation::LowerHeight),
+        Just(Mutation::UnknownEpoch),
+        Just(Mutation::NextEpoch),
+        (0..BLOCK_PRODUCERS).prop_map(Mutation::DropNextBlockProducer),
+        Just(Mutation::RemoveNextBlockProducers),
+        Just(Mutation::TamperInnerRestHash),
+    ]
+}
+
+fn apply(mutation: &Mutation, header: &mut Header) {
+    let block = &mut header.light_client_block;
+    match mutation {
+        Mutation::Unchanged => (),
+        Mutation::DropApprovals(indexes) => {
+            for index in indexes {
+                block.approvals_after_next[*index] = None;
+            }
+        }
+        Mutation::CorruptSignature { index, byte } => {
+            if let Some(Signature::ED25519(bytes)) = block.approvals_after_next[*index].as_mut() {
+                bytes[*byte] ^= 0x01;
+            }
+        }
+        Mutation::TruncateApprovals(count) => block.approvals_after_next.truncate(*count),
+        Mutation::LowerHeight(height) => block.inner_lite.height = *height,
+        Mutation::UnknownEpoch => {
+            block.inner_lite.epoch_id.0 = CryptoHash::hash_bytes(b"unknown epoch")
+        }
+        Mutation::NextEpoch => block.inner_lite.epoch_id = block.inner_lite.next_epoch_id.clone(),
+        Mutation::DropNextBlockProducer(index) => {
+            if let Some(next_bps) = block.next_bps.take() {
+                let mut next_bps = next_bps.into_vec();
+                next_bps.remove(*index);
+                block.next_bps = Some(next_bps.into());
+            }
+        }
+        Mutation::RemoveNextBlockProducers => block.next_bps = None,
+        Mutation::TamperInnerRestHash => {
+            block.inner_rest_hash = CryptoHash::hash_bytes(b"inner rest")
+        }
+    }
+}
+
+/// Returns the raw trie key of `storage_key` in the contract data of `account_id`, by nearcore.
+fn near_contract_data_key(account_id: &str, storage_key: &[u8]) -> Vec<u8> {
+    near_primitives::trie_key::TrieKey::ContractData {
+        account_id: account_id.parse().unwrap(),
+        key: storage_key.to_vec(),
+    }
+    .to_vec()
+}
+
+/// The keys are the ones written by an IBC host contract `ibc.near`, which stores the value
+/// of every ICS-24 path under its store prefix `b"ibc"` followed by the path.
+#[test]
+fn ibc_path_keys_match_host_contract_layout() {
+    let host = String::from("ibc.near");
+    let prefix = commitment_prefix(&host, b"ibc");
+    assert_eq!(prefix, b"\x09ibc.near,ibc".to_vec());
+    assert_eq!(prefix, near_contract_data_key(&host, b"ibc"));
+
+    let cases = [
+        (
+            client_state_path("07-tendermint-0"),
+            "clients/07-tendermint-0/clientState",
+        ),
+        (
+            client_consensus_state_path("07-tendermint-0", 1, 42),
+            "clients/07-tendermint-0/consensusStates/1-42",
+        ),
+        (connection_path("connection-0"), "connections/connection-0"),
+        (
+            channel_end_path("transfer", "channel-0"),
+            "channelEnds/ports/transfer/channels/channel-0",
+        ),
+        (
+            next_sequence_recv_path("transfer", "channel-0"),
+            "nextSequenceRecv/ports/transfer/channels/channel-0",
+        ),
+        (
+            packet_commitment_path("transfer", "channel-0", 7),
+            "commitments/ports/transfer/channels/channel-0/sequences/7",
+        ),
+        (
+            packet_acknowledgement_path("transfer", "channel-0", 7),
+            "acks/ports/transfer/channels/channel-0/sequences/7",
+        ),
+        (
+            packet_receipt_path("transfer", "channel-0", 7),
+            "receipts/ports/transfer/channels/channel-0/sequences/7",
+        ),
+    ];
+    for (path, expected) in cases {
+        assert_eq!(path, expected);
+        assert_eq!(
+            path_key(&prefix, &path),
+            near_contract_data_key(&host, &[b"ibc", expected.as_bytes()].concat())
+        );
+    }
+}
+
+fn near_account_id(account_id: &str) -> near_primitives::types::AccountId {
+    account_id.parse().unwrap()
+}
+
+/// Convert a trie key of this crate to the trie key of nearcore.
+fn near_trie_key(trie_key: &TrieKey) -> near_primitives::trie_key::TrieKey {
+    use near_primitives::trie_key::TrieKey as NearTrieKey;
+    match trie_key {
+        TrieKey::Account { account_id } => NearTrieKey::Account {
+            account_id: near_account_id(account_id),
+        },
+        TrieKey::ContractCode { account_id } => NearTrieKey::ContractCode {
+            account_id: near_account_id(account_id),
+        },
+        TrieKey::AccessKey {
+            account_id,
+            public_key,
+        } => NearTrieKey::AccessKey {
+            account_id: near_account_id(account_id),
+            public_key: reencode(public_key).unwrap(),
+        },
+        TrieKey::ReceivedData {
+            receiver_id,
+            data_id,
+        } => NearTrieKey::ReceivedData {
+            receiver_id: near_account_id(receiver_id),
+            data_id: near_hash(data_id),
+        },
+        TrieKey::PostponedReceiptId {
+            receiver_id,
+            data_id,
+        } => NearTrieKey::PostponedReceiptId {
+            receiver_id: near_account_id(receiver_id),
+            data_id: near_hash(data_id),
+        },
+        TrieKey::PendingDataCount {
+            receiver_id,
+            receipt_id,
+        } => NearTrieKey::PendingDataCount {
+            receiver_id: near_account_id(receiver_id),
+            receipt_id: near_hash(receipt_id),
+        },
+        TrieKey::PostponedReceipt {
+            receiver_id,
+            receipt_id,
+        } => NearTrieKey::PostponedReceipt {
+            receiver_id: near_account_id(receiver_id),
+            receipt_id: near_hash(receipt_id),
+        },
+        TrieKey::DelayedReceiptIndices => NearTrieKey::DelayedReceiptIndices,
+        TrieKey::DelayedReceipt { index } => NearTrieKey::DelayedReceipt { index: *index },
+        TrieKey::ContractData { account_id, key } => NearTrieKey::ContractData {
+            account_id: near_account_id(account_id),
+            key: key.clone(),
+        },
+    }
+}
+
+/// Trie keys of all columns, with valid account ids of NEAR.
+fn trie_key_strategy() -> impl Strategy<Value = TrieKey> {
+    let account_id = || "[a-z0-9]{2,20}(\\.[a-z0-9]{1,20})?";
+    let hash = || any::<[u8; 32]>().prop_map(CryptoHash);
+    prop_oneof![
+        account_id().prop_map(|account_id| TrieKey::Account { account_id }),
+        account_id().prop_map(|account_id| TrieKey::ContractCode { account_id }),
+        (account_id(), any::<[u8; 32]>()).prop_map(|(account_id, key)| TrieKey::AccessKey {
+            account_id,
+            public_key: PublicKey::ED25519(ED25519PublicKey(key)),
+        }),
+        (account_id(), hash()).prop_map(|(receiver_id, data_id)| TrieKey::ReceivedData {
+            receiver_id,
+            data_id,
+        }),
+        (account_id(), hash()).prop_map(|(receiver_id, data_id)| {
+            TrieKey::PostponedReceiptId {
+                receiver_id,
+                data_id,
+            }
+        }),
+        (account_id(), hash()).prop_map(|(receiver_id, receipt_id)| {
+            TrieKey::PendingDataCount {
+                receiver_id,
+                receipt_id,
+            }
+        }),
+        (account_id(), hash()).prop_map(|(receiver_id, receipt_id)| {
+            TrieKey::PostponedReceipt {
+                receiver_id,
+                receipt_id,
+            }
+        }),
+        Just(TrieKey::DelayedReceiptIndices),
+        any::<u64>().prop_map(|index| TrieKey::DelayedReceipt { index }),
+        (account_id(), vec(any::<u8>(), 0..64))
+            .prop_map(|(account_id, key)| TrieKey::ContractData { account_id, key }),
+    ]
+}
+
+/// The storage keys are the ones written by `near_sdk::collections` for the collections
+/// created with the given prefixes.
+#[test]
+fn sdk_collection_keys_match_near_sdk_layout() {
+    let account_id = String::from("contract.near");
+    let alice = String::from("alice");
+    let contract_data = |storage_key: &[u8]| near_contract_data_key(&account_id, storage_key);
+
+    // `LookupMap::new(b"m")` and `LookupSet::new(b"l")`.
+    assert_eq!(
+        lookup_map_entry_key(&account_id, b"m", &alice),
+        b"\x09contract.near,m\x05\x00\x00\x00alice".to_vec()
+    );
+    assert_eq!(
+        lookup_map_entry_key(&account_id, b"m", &alice),
+        contract_data(b"m\x05\x00\x00\x00alice")
+    );
+    assert_eq!(
+        lookup_map_entry_key(&account_id, b"m", &7_u64),
+        contract_data(b"m\x07\x00\x00\x00\x00\x00\x00\x00")
+    );
+    assert_eq!(
+        lookup_set_entry_key(&account_id, b"l", &alice),
+        contract_data(b"l\x05\x00\x00\x00alice")
+    );
+
+    // `Vector::new(b"v")` and `LazyOption::new(b"o", ..)`.
+    assert_eq!(
+        vector_element_key(&account_id, b"v", 258),
+        contract_data(b"v\x02\x01\x00\x00\x00\x00\x00\x00")
+    );
+    assert_eq!(lazy_option_key(&account_id, b"o"), contract_data(b"o"));
+
+    // `UnorderedMap::new(b"u")`, whose index map and key/value vectors are prefixed
+    // with `b"ui"`, `b"uk"` and `b"uv"`.
+    assert_eq!(
+        unordered_map_index_key(&account_id, b"u", &alice),
+        contract_data(b"ui\x05\x00\x00\x00alice")
+    );
+    assert_eq!(
+        unordered_map_key_key(&account_id, b"u", 3),
+        contract_data(b"uk\x03\x00\x00\x00\x00\x00\x00\x00")
+    );
+    assert_eq!(
+        unordered_map_value_key(&account_id, b"u", 3),
+        contract_data(b"uv\x03\x00\x00\x00\x00\x00\x00\x00")
+    );
+
+    // `UnorderedSet::new(b"s")`, whose index map and elements vector are prefixed
+    // with `b"si"` and `b"se"`.
+    assert_eq!(
+        unordered_set_index_key(&account_id, b"s", &alice),
+        contract_data(b"si\x05\x00\x00\x00alice")
+    );
+    assert_eq!(
+        unordered_set_element_key(&account_id, b"s", 3),
+        contract_data(b"se\x03\x00\x00\x00\x00\x00\x00\x00")
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn header_decisions_match_nearcore(mutation in mutation_strategy()) {
+        let HeaderFixture { consensus_state, mut header } = header_fixture(BLOCK_PRODUCERS, 4);
+        apply(&mutation, &mut header);
+        let expected = consensus_state
+            .get_block_producers_of(&header.epoch_id())
+            .map_or(false, |bps| {
+                reference_validate(
+                    &consensus_state.header.light_client_block,
+                    &bps,
+                    &header.light_client_block,
+                )
+            });
+        let actual = TrustedConsensusState::new(consensus_state).verify_header(&header).is_ok();
+        prop_assert_eq!(actual, expected, "{:?}", mutation);
+    }
+
+    #[test]
+    fn trie_keys_match_nearcore(trie_key in trie_key_strategy()) {
+        let near_trie_key = near_trie_key(&trie_key);
+        prop_assert_eq!(trie_key.to_vec(), near_trie_key.to_vec());
+        prop_assert_eq!(trie_key.len(), near_trie_key.len());
+    }
+
+    #[test]
+    fn merklize_matches_nearcore(items in vec(any::<[u8; 32]>(), 0..40)) {
+        let hashes = items.iter().map(|item| CryptoHash(*item)).collect::<Vec<_>>();
+        let near_hashes = items
+            .iter()
+            .map(|item| near_primitives::hash::CryptoHash(*item))
+            .collect::<Vec<_>>();
+        let (root, paths) = merklize(&hashes);
+        let (near_root, near_paths) = near_primitives::merkle::merklize(&near_hashes);
+        prop_assert_eq!(root.0, near_root.0);
+        for (index, (path, near_path)) in paths.iter().zip(near_paths.iter()).enumerate() {
+            let converted: near_primitives::merkle::MerklePath = reencode(path).unwrap();
+            prop_assert_eq!(&converted, near_path);
+            prop_assert!(near_primitives::merkle::verify_path(
+                near_root,
+                near_path,
+                &near_hashes[index]
+            ));
+        }
+    }
+
+    #[test]
+    fn compute_root_from_path_matches_nearcore(
+        path in vec((any::<[u8; 32]>(), any::<bool>()), 0..16),
+        item in any::<[u8; 32]>(),
+    ) {
+        let path = path
+            .into_iter()
+            .map(|(hash, left)| near_primitives::merkle::MerklePathItem {
+                hash: near_primitives::hash::CryptoHash(hash),
+                direction: match left {
+                    true => near_primitives::merkle::Direction::Left,
+                    false => near_primitives::merkle::Direction::Right,
+                },
+            })
+            .collect::<Vec<_>>();
+        let converted: MerklePath = reencode(&path).unwrap();
+        prop_assert_eq!(
+            compute_root_from_path(&converted, CryptoHash(item)).0,
+            near_primitives::merkle::compute_root_from_path(
+                &path,
+                near_primitives::hash::CryptoHash(item)
+            )
+            .0
+        );
+    }
+
+    #[test]
+    fn block_merkle_root_matches_nearcore(items in vec(any::<[u8; 32]>(), 0..64)) {
+        let mut tree = near_primitives::merkle::PartialMerkleTree::default();
+        for item in items.iter() {
+            tree.insert(near_primitives::hash::CryptoHash(*item));
+        }
+        let hashes = items.iter().map(|item| CryptoHash(*item)).collect::<Vec<_>>();
+        prop_assert_eq!(compute_block_merkle_root(&hashes).0, tree.root().0);
+    }
+
+    #[test]
+    fn block_merkle_paths_match_nearcore(items in vec(any::<[u8; 32]>(), 1..64)) {
+        let mut near_tree = near_primitives::merkle::PartialMerkleTree::default();
+        for item in items.iter() {
+            near_tree.insert(near_primitives::hash::CryptoHash(*item));
+        }
+        let hashes = items.iter().map(|item| CryptoHash(*item)).collect::<Vec<_>>();
+        for (index, item) in items.iter().enumerate() {
+            let path = compute_block_merkle_path(&hashes, index).unwrap();
+            let converted: near_primitives::merkle::MerklePath = reencode(&path).unwrap();
+            prop_assert!(near_primitives::merkle::verify_hash(
+                near_tree.root(),
+                &converted,
+                near_primitives::hash::CryptoHash(*item)
+            ));
+        }
+        prop_assert!(compute_block_merkle_path(&hashes, hashes.len()).is_none());
+    }
+
+    #[test]
+    fn merkle_path_of_matches_nearcore(items in vec(any::<[u8; 32]>(), 1..40)) {
+        let (_, near_paths) = near_primitives::merkle::merklize(&items);
+        for (index, near_path) in near_paths.iter().enumerate() {
+            let converted: near_primitives::merkle::MerklePath =
+                reencode(&merkle_path_of(&items, index).unwrap()).unwrap();
+            prop_assert_eq!(&converted, near_path);
+        }
+        prop_assert!(merkle_path_of(&items, items.len()).is_none());
+    }
+
+    #[test]
+    fn partial_merkle_tree_matches_nearcore(items in vec(any::<[u8; 32]>(), 0..64)) {
+        let mut tree = PartialMerkleTree::default();
+        let mut near_tree = near_primitives::merkle::PartialMerkleTree::default();
+        for item in items.iter() {
+            tree.insert(CryptoHash(*item));
+            near_tree.insert(near_primitives::hash::CryptoHash(*item));
+            let converted: PartialMerkleTree = reencode(&near_tree).unwrap();
+            prop_assert_eq!(&converted, &tree);
+            prop_assert_eq!(tree.root().0, near_tree.root().0);
+        }
+    }
+}
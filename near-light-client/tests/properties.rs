@@ -0,0 +1,1188 @@
+//! Property tests of the encodings and merkle utilities, run with `--features testing`.
+
+use borsh::BorshDeserialize;
+use near_light_client::{
+    commitment::CommitmentProof,
+    evm_abi::MembershipProofBundle,
+    near_types::{
+        block_producers::BlockProducers,
+        hash::{sha256, CryptoHash},
+        merkle::{
+            compute_block_merkle_path, compute_block_merkle_root, compute_root_from_path,
+            merkle_path_of, merklize, verify_hash, verify_path, Direction, MerklePathItem,
+            PartialMerkleTree,
+        },
+        signature::{PublicKey, Signature},
+        transaction::{build_outcome_proofs, ExecutionOutcomeWithId},
+        trie::{
+            decode_state_proof,
+            nibble_slice::{
+                bytes_to_nibbles, decode_key_nibbles, encode_key_nibbles, nibbles_to_bytes,
+            },
+            verify_not_in_state, verify_not_in_state_data, verify_state_proof,
+            verify_state_proof_data, walk_proof, ProofErrorContext, RawTrieNode,
+            RawTrieNodeWithSize, StateProof, StateProofFormat, TrieNodeType,
+        },
+        LightClientBlockLite, ValidatorStakeView,
+    },
+    testing::{
+        arbitrary_strategy, fixture_secret_keys, header_fixture, sign_approvals,
+        state_proof_fixture, HeaderFixture,
+    },
+    types::{
+        ConsensusState, Header, Height, HistoricalMembershipProof, MembershipProof, Misbehaviour,
+        NonMembershipProof, OutcomeProof, ProofBundle, StateValueProof, PROOF_FORMAT_VERSION,
+    },
+    validate_block_producers, verify_next_block_inner_hash, verify_state_change,
+    witness::export_header_witness,
+    BasicNearLightClient, CommitmentProofVerificationError, Error, HeaderVerificationError,
+    MisbehaviourVerificationError, StateProofVerificationError, TrustedConsensusState,
+};
+use proptest::{collection::vec, prelude::*};
+
+proptest! {
+    #[test]
+    fn trie_node_round_trip(node in arbitrary_strategy::<RawTrieNodeWithSize>()) {
+        let bytes = node.encode();
+        prop_assert_eq!(RawTrieNodeWithSize::decode_strict(&bytes).unwrap(), node);
+    }
+
+    #[test]
+    fn trie_node_with_trailing_bytes_is_rejected(
+        node in arbitrary_strategy::<RawTrieNodeWithSize>(),
+        trailing in vec(any::<u8>(), 1..16),
+    ) {
+        let mut bytes = node.encode();
+        let memory_usage = bytes.split_off(bytes.len() - 8);
+        bytes.extend(trailing);
+        bytes.extend(memory_usage);
+        prop_assert!(RawTrieNodeWithSize::decode_strict(&bytes).is_err());
+    }
+
+    #[test]
+    fn public_key_and_signature_round_trip(
+        public_key in arbitrary_strategy::<PublicKey>(),
+        signature in arbitrary_strategy::<Signature>(),
+    ) {
+        let mut bytes = borsh::to_vec(&public_key).unwrap();
+        prop_assert_eq!(PublicKey::try_from_slice(&bytes).unwrap(), public_key);
+        bytes.push(0);
+        prop_assert!(PublicKey::try_from_slice(&bytes).is_err());
+        let mut bytes = borsh::to_vec(&signature).unwrap();
+        prop_assert_eq!(Signature::try_from_slice(&bytes).unwrap(), signature);
+        bytes.push(0);
+        prop_assert!(Signature::try_from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn header_round_trip(header in arbitrary_strategy::<Header>()) {
+        let bytes = borsh::to_vec(&header).unwrap();
+        let decoded = Header::try_from_slice(&bytes).unwrap();
+        prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn consensus_state_round_trip(state in arbitrary_strategy::<ConsensusState>()) {
+        let bytes = borsh::to_vec(&state).unwrap();
+        let decoded = ConsensusState::try_from_slice(&bytes).unwrap();
+        prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn header_witness_hashes_match_block(
+        mut state in arbitrary_strategy::<ConsensusState>(),
+        header in arbitrary_strategy::<Header>(),
+    ) {
+        state.header.light_client_block.inner_lite.epoch_id =
+            header.light_client_block.inner_lite.epoch_id.clone();
+        state.current_bps = Some(Vec::new().into());
+        let witness = export_header_witness(&state, &header).unwrap();
+        let block = &header.light_client_block;
+        prop_assert_eq!(witness.current_block_hash.hash, block.current_block_hash());
+        prop_assert_eq!(witness.next_block_hash.hash, block.next_block_hash());
+        prop_assert_eq!(witness.approval_message, block.approval_message());
+    }
+
+    #[test]
+    fn membership_proof_bundle_abi_round_trip(
+        bundle in arbitrary_strategy::<MembershipProofBundle>(),
+    ) {
+        let data = bundle.abi_encode();
+        prop_assert_eq!(data.len() % 32, 0);
+        prop_assert_eq!(MembershipProofBundle::abi_decode(&data).unwrap(), bundle);
+    }
+
+    #[test]
+    fn consensus_state_computes_total_stake(state in arbitrary_strategy::<ConsensusState>()) {
+        let state = ConsensusState::new(state.current_bps, state.header);
+        let expected = state.current_bps.as_ref().map(|bps| {
+            bps.decoded()
+                .iter()
+                .map(|bp| bp.clone().into_validator_stake().stake)
+                .fold(0, u128::saturating_add)
+        });
+        prop_assert_eq!(state.total_stake_of(&state.header.epoch_id()), expected);
+        prop_assert_eq!(state.current_total_stake(), expected.unwrap_or(0));
+        let next_bps = state.header.light_client_block.next_bps.as_ref();
+        prop_assert_eq!(state.next_total_stake(), next_bps.map_or(0, BlockProducers::total_stake));
+    }
+
+    #[test]
+    fn block_producers_are_decoded_on_demand(
+        bps in vec(arbitrary_strategy::<ValidatorStakeView>(), 0..8),
+    ) {
+        let bytes = borsh::to_vec(&bps).unwrap();
+        let raw = BlockProducers::try_from_slice(&bytes).unwrap();
+        prop_assert!(!raw.is_decoded());
+        prop_assert_eq!(raw.len(), bps.len());
+        let raw_bytes = raw.to_borsh();
+        prop_assert_eq!(raw_bytes.as_ref(), bytes.as_slice());
+        prop_assert_eq!(raw.total_stake(), BlockProducers::from(bps.clone()).total_stake());
+        prop_assert_eq!(borsh::to_vec(&raw.into_vec()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn malformed_block_producers_are_rejected(bytes in vec(any::<u8>(), 0..256)) {
+        let expected = Vec::<ValidatorStakeView>::try_from_slice(&bytes);
+        let raw = BlockProducers::try_from_slice(&bytes);
+        prop_assert_eq!(raw.is_ok(), expected.is_ok());
+        if let (Ok(raw), Ok(expected)) = (raw, expected) {
+            let decoded = borsh::to_vec(raw.decoded().as_ref()).unwrap();
+            prop_assert_eq!(decoded, borsh::to_vec(&expected).unwrap());
+        }
+    }
+
+    #[test]
+    fn duplicate_block_producers_are_rejected(
+        bps in vec(arbitrary_strategy::<ValidatorStakeView>(), 1..8),
+    ) {
+        let duplicated = [bps.as_slice(), &bps[..1]].concat();
+        prop_assert!(validate_block_producers(&duplicated).is_err());
+    }
+
+    #[test]
+    fn trusted_block_is_checked_for_consistency(
+        mut header in arbitrary_strategy::<Header>(),
+        other_hash in arbitrary_strategy::<CryptoHash>(),
+    ) {
+        let block = &mut header.light_client_block;
+        block.next_bps = None;
+        block.inner_lite.prev_state_root = merklize(&header.prev_state_root_of_chunks).0;
+        let block_hash = header.light_client_block.current_block_hash();
+        prop_assume!(block_hash != other_hash);
+        prop_assert!(ConsensusState::from_trusted_block(
+            header.light_client_block.clone(),
+            header.prev_state_root_of_chunks.clone(),
+            None,
+            &block_hash,
+        )
+        .is_ok());
+        prop_assert!(ConsensusState::from_trusted_block(
+            header.light_client_block,
+            header.prev_state_root_of_chunks,
+            None,
+            &other_hash,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn heights_are_ordered_by_revision_first(
+        revision_number in 0..u64::MAX,
+        height_1 in any::<u64>(),
+        height_2 in any::<u64>(),
+    ) {
+        prop_assert!(Height::new(revision_number, height_1) < Height::new(revision_number + 1, height_2));
+        prop_assert_eq!(
+            Height::new(revision_number, height_1).cmp(&Height::new(revision_number, height_2)),
+            height_1.cmp(&height_2)
+        );
+    }
+
+    #[test]
+    fn next_block_inner_hash_is_checked_with_adjacent_header(
+        mut header in arbitrary_strategy::<Header>(),
+        mut next_header in arbitrary_strategy::<Header>(),
+        other_hash in arbitrary_strategy::<CryptoHash>(),
+    ) {
+        let block_hash = header.light_client_block.current_block_hash();
+        let next_inner_hash = next_header.light_client_block.inner_hash();
+        prop_assume!(other_hash != block_hash && other_hash != next_inner_hash);
+
+        next_header.light_client_block.prev_block_hash = block_hash;
+        header.light_client_block.next_block_inner_hash = next_inner_hash;
+        prop_assert!(verify_next_block_inner_hash(&header, &next_header).is_ok());
+
+        header.light_client_block.next_block_inner_hash = other_hash;
+        let block_hash = header.light_client_block.current_block_hash();
+        next_header.light_client_block.prev_block_hash = block_hash;
+        prop_assert!(matches!(
+            verify_next_block_inner_hash(&header, &next_header),
+            Err(HeaderVerificationError::InconsistentNextBlockInnerHash { expected, actual })
+                if expected == other_hash && actual == next_inner_hash
+        ));
+
+        next_header.light_client_block.prev_block_hash = other_hash;
+        prop_assert!(matches!(
+            verify_next_block_inner_hash(&header, &next_header),
+            Err(HeaderVerificationError::NextHeaderNotAdjacent {
+                block_hash: hash,
+                prev_block_hash,
+            }) if hash == block_hash && prev_block_hash == other_hash
+        ));
+    }
+
+    #[test]
+    fn sealed_header_caches_block_hash(header in arbitrary_strategy::<Header>()) {
+        let block_hash = header.light_client_block.current_block_hash();
+        let sealed = header.seal();
+        prop_assert_eq!(sealed.hash(), block_hash);
+        prop_assert_eq!(sealed.into_header().light_client_block.current_block_hash(), block_hash);
+    }
+
+    #[test]
+    fn membership_proof_round_trip(proof in arbitrary_strategy::<MembershipProof>()) {
+        let bytes = borsh::to_vec(&proof).unwrap();
+        prop_assert_eq!(bytes[0], proof.version);
+        prop_assert_eq!(MembershipProof::try_from_slice(&bytes).unwrap(), proof);
+    }
+
+    #[test]
+    fn outcome_proof_round_trip(proof in arbitrary_strategy::<OutcomeProof>()) {
+        let bytes = borsh::to_vec(&proof).unwrap();
+        prop_assert_eq!(OutcomeProof::try_from_slice(&bytes).unwrap(), proof);
+    }
+
+    #[test]
+    fn proof_bundle_round_trip(bundle in arbitrary_strategy::<ProofBundle>()) {
+        let bytes = borsh::to_vec(&bundle).unwrap();
+        prop_assert_eq!(bytes[0], bundle.version);
+        let decoded = ProofBundle::try_from_slice(&bytes).unwrap();
+        prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn merklize_paths_verify(items in vec(vec(any::<u8>(), 0..64), 1..64)) {
+        let (root, paths) = merklize(&items);
+        prop_assert_eq!(paths.len(), items.len());
+        for (item, path) in items.iter().zip(paths.iter()) {
+            prop_assert!(verify_path(root, path, item));
+        }
+    }
+
+    #[test]
+    fn block_merkle_paths_verify(hashes in vec(arbitrary_strategy::<CryptoHash>(), 1..64)) {
+        let mut tree = PartialMerkleTree::default();
+        hashes.iter().for_each(|hash| tree.insert(*hash));
+        let root = compute_block_merkle_root(&hashes);
+        prop_assert_eq!(tree.root(), root);
+        for (index, hash) in hashes.iter().enumerate() {
+            let path = compute_block_merkle_path(&hashes, index).unwrap();
+            prop_assert!(verify_hash(root, &path, *hash));
+        }
+    }
+
+    #[test]
+    fn outcome_proofs_verify(
+        chunk_outcomes in vec(vec(arbitrary_strategy::<ExecutionOutcomeWithId>(), 1..8), 1..6),
+        chunk_index in any::<usize>(),
+        outcome_index in any::<usize>(),
+    ) {
+        let chunk_index = chunk_index % chunk_outcomes.len();
+        let outcome_index = outcome_index % chunk_outcomes[chunk_index].len();
+        let proofs = build_outcome_proofs(&chunk_outcomes, chunk_index, outcome_index).unwrap();
+        let outcome_hashes = chunk_outcomes[chunk_index]
+            .iter()
+            .map(|outcome| outcome.to_hashes())
+            .collect::<Vec<_>>();
+        let (chunk_outcome_root, _) = merklize(&outcome_hashes);
+        prop_assert!(verify_path(
+            chunk_outcome_root,
+            &proofs.outcome_proof,
+            &outcome_hashes[outcome_index]
+        ));
+        prop_assert!(verify_hash(
+            proofs.outcome_root,
+            &proofs.outcome_root_proof,
+            chunk_outcome_root
+        ));
+        prop_assert_eq!(
+            merkle_path_of(&outcome_hashes, outcome_index),
+            Some(proofs.outcome_proof)
+        );
+        prop_assert!(build_outcome_proofs(&chunk_outcomes, chunk_outcomes.len(), 0).is_none());
+        prop_assert!(
+            build_outcome_proofs(&chunk_outcomes, chunk_index, outcome_hashes.len()).is_none()
+        );
+    }
+
+    #[test]
+    fn partial_merkle_tree_rejects_inconsistent_path(
+        path in vec(arbitrary_strategy::<CryptoHash>(), 0..8),
+        size in 0u64..256,
+    ) {
+        let consistent = path.len() == size.count_ones() as usize;
+        prop_assert_eq!(PartialMerkleTree::new(path.clone(), size).is_some(), consistent);
+        let bytes = borsh::to_vec(&(path, size)).unwrap();
+        let decoded = PartialMerkleTree::try_from_slice(&bytes);
+        prop_assert_eq!(decoded.is_ok(), consistent);
+        if let Ok(tree) = decoded {
+            prop_assert_eq!(borsh::to_vec(&tree).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn nibbles_round_trip(bytes in vec(any::<u8>(), 0..64), is_leaf in any::<bool>()) {
+        let nibbles = bytes_to_nibbles(&bytes);
+        prop_assert_eq!(nibbles_to_bytes(&nibbles).unwrap(), bytes);
+        let encoded = encode_key_nibbles(&nibbles, is_leaf);
+        prop_assert_eq!(decode_key_nibbles(&encoded).unwrap(), (nibbles, is_leaf));
+    }
+
+    #[test]
+    fn state_proof_verifies_leaf_for_multiple_keys(
+        key in vec(any::<u8>(), 1..32),
+        other_key in vec(any::<u8>(), 1..32),
+        value in vec(any::<u8>(), 0..64),
+    ) {
+        prop_assume!(key != other_key);
+        let encoded_key = encode_key_nibbles(&bytes_to_nibbles(&key), true);
+        let leaf = RawTrieNodeWithSize::new(RawTrieNode::new_leaf(encoded_key, &value), 0);
+        let root = leaf.hash();
+        let proof = StateProof::new(&[leaf.encode()]).unwrap();
+        prop_assert!(proof.verify_membership(&key, &value, &root).is_ok());
+        prop_assert!(proof.verify_non_membership(&other_key, &root).is_ok());
+        prop_assert!(proof.verify_non_membership(&key, &root).is_err());
+    }
+
+    #[test]
+    fn membership_batch_verifies_keys_of_multiple_shards(
+        mut state in arbitrary_strategy::<ConsensusState>(),
+        key in vec(any::<u8>(), 1..32),
+        other_key in vec(any::<u8>(), 1..32),
+        value in vec(any::<u8>(), 0..64),
+    ) {
+        prop_assume!(key != other_key);
+        let leaf_of = |key: &[u8]| {
+            let encoded_key = encode_key_nibbles(&bytes_to_nibbles(key), true);
+            RawTrieNodeWithSize::new(RawTrieNode::new_leaf(encoded_key, &value), 0)
+        };
+        let (leaf, other_leaf) = (leaf_of(&key), leaf_of(&other_key));
+        state.header.prev_state_root_of_chunks = vec![leaf.hash(), other_leaf.hash()];
+        let proof = StateProof::new(&[other_leaf.encode(), leaf.encode()]).unwrap();
+        let other_value = [value.as_slice(), &[0]].concat();
+        let results = state.verify_membership_batch(
+            &[
+                (key.as_slice(), value.as_slice()),
+                (other_key.as_slice(), value.as_slice()),
+                (key.as_slice(), other_value.as_slice()),
+            ],
+            &proof,
+        );
+        prop_assert!(results[0].is_ok());
+        prop_assert!(results[1].is_ok());
+        prop_assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn state_proof_data_is_hashed_without_encoding(
+        node in arbitrary_strategy::<RawTrieNodeWithSize>(),
+        key in vec(any::<u8>(), 0..32),
+        value in vec(any::<u8>(), 0..64),
+    ) {
+        let root = node.hash();
+        let proofs = [node.encode()];
+        prop_assert_eq!(
+            verify_state_proof_data(&key, &proofs, &value, &root).is_ok(),
+            verify_state_proof(&key, &[node.clone()], &value, &root).is_ok()
+        );
+        prop_assert_eq!(
+            verify_not_in_state_data(&key, &proofs, &root).is_ok(),
+            verify_not_in_state(&key, &[node], &root).is_ok()
+        );
+    }
+
+    #[test]
+    fn header_fixture_is_verified(bp_count in 1..16_usize, chunk_count in 1..8_usize) {
+        let fixture = header_fixture(bp_count, chunk_count);
+        let client = TrustedConsensusState::new(fixture.consensus_state);
+        prop_assert!(client.verify_header(&fixture.header).is_ok());
+        let sealed = fixture.header.seal();
+        prop_assert!(client.verify_sealed_header(&sealed).is_ok());
+        prop_assert!(matches!(
+            client.verify_sealed_header_chained(&sealed, None),
+            Err(HeaderVerificationError::BrokenChain { .. })
+        ));
+    }
+
+    #[test]
+    fn header_is_verified_to_chain_to_latest_head(
+        bp_count in 1..8_usize,
+        sibling_hash in arbitrary_strategy::<CryptoHash>(),
+    ) {
+        let HeaderFixture { consensus_state, header } = header_fixture(bp_count, 1);
+        let secret_keys = fixture_secret_keys(bp_count);
+        let trusted_block_hash = consensus_state.header.light_client_block.current_block_hash();
+        let client = TrustedConsensusState::new(consensus_state);
+        let sign = |mut header: Header| {
+            header.light_client_block.approvals_after_next =
+                sign_approvals(&header.light_client_block, &secret_keys);
+            header
+        };
+
+        // The fixture header doesn't point to the trusted block.
+        prop_assert!(client.verify_header(&header).is_ok());
+        prop_assert!(matches!(
+            client.verify_header_chained(&header, None),
+            Err(HeaderVerificationError::BrokenChain { trusted_block_hash: hash })
+                if hash == trusted_block_hash
+        ));
+
+        let mut chained = header.clone();
+        chained.light_client_block.prev_block_hash = trusted_block_hash;
+        let chained = sign(chained);
+        prop_assert!(client.verify_header_chained(&chained, None).is_ok());
+
+        let mut stale = chained.clone();
+        stale.light_client_block.inner_lite.height = client.latest_height().revision_height;
+        let stale = sign(stale);
+        prop_assert!(matches!(
+            client.verify_header_chained(&stale, None),
+            Err(HeaderVerificationError::InvalidBlockHeight)
+        ));
+
+        let block_proof = vec![MerklePathItem { hash: sibling_hash, direction: Direction::Right }];
+        let mut descendant = header;
+        descendant.light_client_block.inner_lite.block_merkle_root =
+            compute_root_from_path(&block_proof, trusted_block_hash);
+        let descendant = sign(descendant);
+        prop_assert!(client.verify_header_chained(&descendant, Some(&block_proof)).is_ok());
+        let wrong_proof = vec![MerklePathItem { hash: sibling_hash, direction: Direction::Left }];
+        prop_assert!(matches!(
+            client.verify_header_chained(&descendant, Some(&wrong_proof)),
+            Err(HeaderVerificationError::BrokenChain { .. })
+        ));
+    }
+
+    #[test]
+    fn misbehaviour_is_verified_for_conflicting_headers(bp_count in 1..8_usize) {
+        let HeaderFixture { consensus_state, header } = header_fixture(bp_count, 1);
+        let secret_keys = fixture_secret_keys(bp_count);
+        let client = TrustedConsensusState::new(consensus_state);
+        let sign = |mut header: Header| {
+            header.light_client_block.approvals_after_next =
+                sign_approvals(&header.light_client_block, &secret_keys);
+            header
+        };
+        let misbehaviour = |header_1: &Header, header_2: &Header| Misbehaviour {
+            client_id: "15-near-0".to_string(),
+            header_1: header_1.clone(),
+            header_2: header_2.clone(),
+        };
+
+        let mut conflicting = header.clone();
+        conflicting.light_client_block.inner_rest_hash = CryptoHash::hash_bytes(b"conflicting");
+        let conflicting = sign(conflicting);
+        prop_assert!(client.verify_misbehaviour(&misbehaviour(&header, &conflicting)).is_ok());
+
+        prop_assert!(matches!(
+            client.verify_misbehaviour(&misbehaviour(&header, &header)),
+            Err(MisbehaviourVerificationError::HeadersNotConflicting)
+        ));
+
+        let mut higher = header.clone();
+        higher.light_client_block.inner_lite.height += 1;
+        let higher = sign(higher);
+        prop_assert!(client.verify_header(&higher).is_ok());
+        prop_assert!(matches!(
+            client.verify_misbehaviour(&misbehaviour(&header, &higher)),
+            Err(MisbehaviourVerificationError::HeadersNotConflicting)
+        ));
+
+        let mut unsigned = conflicting.clone();
+        unsigned.light_client_block.inner_rest_hash = CryptoHash::hash_bytes(b"unsigned");
+        prop_assert!(matches!(
+            client.verify_misbehaviour(&misbehaviour(&header, &unsigned)),
+            Err(MisbehaviourVerificationError::InvalidHeader2(
+                HeaderVerificationError::InvalidValidatorSignature { .. }
+            ))
+        ));
+        prop_assert!(matches!(
+            client.verify_misbehaviour(&misbehaviour(&unsigned, &header)),
+            Err(MisbehaviourVerificationError::InvalidHeader1(
+                HeaderVerificationError::InvalidValidatorSignature { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn state_proof_fixture_is_verified(
+        key in vec(any::<u8>(), 1..16),
+        value in vec(any::<u8>(), 0..64),
+        depth in 0..32_usize,
+    ) {
+        prop_assume!(depth <= key.len() * 2);
+        let (proofs, state_root) = state_proof_fixture(&key, &value, depth);
+        prop_assert_eq!(proofs.len(), depth + 1);
+        prop_assert!(verify_state_proof_data(&key, &proofs, &value, &state_root).is_ok());
+    }
+
+    #[test]
+    fn state_proof_format_is_detected(
+        key in vec(any::<u8>(), 1..16),
+        value in vec(any::<u8>(), 0..64),
+        depth in 0..32_usize,
+    ) {
+        prop_assume!(depth <= key.len() * 2);
+        prop_assume!(RawTrieNodeWithSize::decode_strict(&value).is_err());
+        let (proofs, state_root) = state_proof_fixture(&key, &value, depth);
+        prop_assert_eq!(StateProofFormat::detect(&proofs), StateProofFormat::NodesOnly);
+        let nodes = decode_state_proof(&proofs).unwrap();
+
+        let mut proofs_with_values = proofs.clone();
+        proofs_with_values.insert(depth / 2, value.clone());
+        prop_assert_eq!(
+            StateProofFormat::detect(&proofs_with_values),
+            StateProofFormat::NodesWithValues
+        );
+        prop_assert_eq!(decode_state_proof(&proofs_with_values).unwrap(), nodes);
+        prop_assert!(StateProof::new(&proofs_with_values).is_ok());
+
+        let malformed = vec![0xff; 3];
+        prop_assume!(malformed != value);
+        let mut malformed_proofs = proofs_with_values;
+        malformed_proofs.push(malformed);
+        prop_assert_eq!(
+            StateProofFormat::detect(&malformed_proofs),
+            StateProofFormat::NodesWithValues
+        );
+        let proof_index = (malformed_proofs.len() - 1) as u16;
+        prop_assert!(matches!(
+            decode_state_proof(&malformed_proofs),
+            Err(StateProofVerificationError::InvalidProofData { proof_index: index })
+                if index == proof_index
+        ));
+        prop_assert!(
+            verify_state_proof_data(&key, &malformed_proofs, &value, &state_root).is_err()
+        );
+    }
+
+    #[test]
+    fn tampered_state_proof_reports_expected_and_actual_hashes(
+        key in vec(any::<u8>(), 1..16),
+        value in vec(any::<u8>(), 0..64),
+        depth in 0..32_usize,
+        tampered_index in any::<usize>(),
+    ) {
+        prop_assume!(depth <= key.len() * 2);
+        let (mut proofs, state_root) = state_proof_fixture(&key, &value, depth);
+
+        let other_value = [value.as_slice(), &[0]].concat();
+        let value_hash = CryptoHash(sha256(&value));
+        let other_value_hash = CryptoHash(sha256(&other_value));
+        prop_assert!(matches!(
+            verify_state_proof_data(&key, &proofs, &other_value, &state_root),
+            Err(StateProofVerificationError::InvalidLeafNodeValueHash {
+                proof_index,
+                expected,
+                actual,
+                ..
+            }) if proof_index as usize == depth
+                && expected == value_hash
+                && actual == other_value_hash
+        ));
+
+        let tampered_index = tampered_index % (depth + 1);
+        let node = RawTrieNodeWithSize::decode_strict(&proofs[tampered_index]).unwrap();
+        let tampered = RawTrieNodeWithSize::new(node.node().clone(), node.memory_usage() + 1);
+        proofs[tampered_index] = tampered.encode();
+        let result = verify_state_proof_data(&key, &proofs, &value, &state_root);
+        if tampered_index == depth {
+            prop_assert!(matches!(
+                result,
+                Err(StateProofVerificationError::InvalidLeafNodeHash {
+                    proof_index,
+                    expected,
+                    actual,
+                    ..
+                }) if proof_index as usize == tampered_index
+                    && expected == node.hash()
+                    && actual == tampered.hash()
+            ));
+        } else {
+            prop_assert!(matches!(
+                result,
+                Err(StateProofVerificationError::InvalidBranchNodeHash {
+                    proof_index,
+                    expected,
+                    actual,
+                    ..
+                }) if proof_index as usize == tampered_index
+                    && expected == node.hash()
+                    && actual == tampered.hash()
+            ));
+        }
+    }
+
+    #[test]
+    fn state_proof_errors_tell_where_the_verification_fails(
+        key in vec(any::<u8>(), 1..16),
+        value in vec(any::<u8>(), 0..64),
+        depth in 1..32_usize,
+    ) {
+        prop_assume!(depth < key.len() * 2);
+        let nibbles = bytes_to_nibbles(&key);
+        let (mut proofs, state_root) = state_proof_fixture(&key, &value, depth);
+
+        let mut other_key = key.clone();
+        *other_key.last_mut().unwrap() ^= 1;
+        let other_nibbles = bytes_to_nibbles(&other_key);
+        let leaf_context = ProofErrorContext {
+            node_type: TrieNodeType::Leaf,
+            consumed_nibbles: depth,
+            remaining_key: other_nibbles[depth..].to_vec(),
+        };
+        prop_assert!(matches!(
+            verify_state_proof_data(&other_key, &proofs, &value, &state_root),
+            Err(StateProofVerificationError::InvalidLeafNodeKey { proof_index, context })
+                if proof_index as usize == depth && context == leaf_context
+        ));
+
+        let branch_index = depth - 1;
+        let node = RawTrieNodeWithSize::decode_strict(&proofs[branch_index]).unwrap();
+        proofs[branch_index] = RawTrieNodeWithSize::new(node.node().clone(), 1).encode();
+        let branch_context = ProofErrorContext {
+            node_type: TrieNodeType::Branch,
+            consumed_nibbles: branch_index,
+            remaining_key: nibbles[branch_index..].to_vec(),
+        };
+        prop_assert!(matches!(
+            verify_state_proof_data(&key, &proofs, &value, &state_root),
+            Err(StateProofVerificationError::InvalidBranchNodeHash { proof_index, context, .. })
+                if proof_index as usize == branch_index && context == branch_context
+        ));
+    }
+
+    #[test]
+    fn commitment_proofs_round_trip_and_verify(
+        prefix in vec(any::<u8>(), 0..8),
+        path in vec(any::<u8>(), 1..16),
+        value in vec(any::<u8>(), 0..64),
+        depth in 0..32_usize,
+    ) {
+        let key = [prefix.as_slice(), path.as_slice()].concat();
+        prop_assume!(depth < key.len() * 2);
+        let (proofs, root) = state_proof_fixture(&key, &value, depth);
+        let mut other_path = path.clone();
+        *other_path.last_mut().unwrap() ^= 1;
+        let other_key = [prefix.as_slice(), other_path.as_slice()].concat();
+        let decode = |proof: &CommitmentProof| {
+            CommitmentProof::try_from_slice(&borsh::to_vec(proof).unwrap()).unwrap()
+        };
+
+        let existence = decode(&CommitmentProof::new_existence(
+            key.clone(),
+            value.clone(),
+            proofs.clone(),
+            root,
+        ));
+        prop_assert!(existence.verify_membership(&root, &prefix, &path, &value).is_ok());
+        let other_value = [value.as_slice(), &[0]].concat();
+        prop_assert!(matches!(
+            existence.verify_membership(&root, &prefix, &path, &other_value),
+            Err(CommitmentProofVerificationError::ValueMismatch)
+        ));
+        prop_assert!(matches!(
+            existence.verify_non_membership(&root, &prefix, &path),
+            Err(CommitmentProofVerificationError::NonExistenceProofExpected)
+        ));
+
+        let non_existence =
+            decode(&CommitmentProof::new_non_existence(other_key, proofs.clone(), root));
+        prop_assert!(non_existence.verify_non_membership(&root, &prefix, &other_path).is_ok());
+        prop_assert!(matches!(
+            non_existence.verify_membership(&root, &prefix, &other_path, &value),
+            Err(CommitmentProofVerificationError::ExistenceProofExpected)
+        ));
+        prop_assert!(matches!(
+            non_existence.verify_non_membership(&root, &prefix, &path),
+            Err(CommitmentProofVerificationError::KeyMismatch)
+        ));
+
+        // A proof of absence of a key which has a value is rejected by the verifier.
+        let forged = decode(&CommitmentProof::new_non_existence(key, proofs, root));
+        prop_assert!(forged.verify_non_membership(&root, &prefix, &path).is_err());
+    }
+
+    #[test]
+    fn walk_proof_visits_nodes_from_root_to_leaf(
+        key in vec(any::<u8>(), 1..16),
+        value in vec(any::<u8>(), 0..64),
+        depth in 0..32_usize,
+    ) {
+        prop_assume!(depth <= key.len() * 2);
+        let (proofs, state_root) = state_proof_fixture(&key, &value, depth);
+        let nodes = decode_state_proof(&proofs).unwrap();
+        let steps = walk_proof(&key, &nodes, &state_root).collect::<Vec<_>>();
+        prop_assert_eq!(steps.len(), depth + 1);
+        let mut expected_hash = state_root;
+        for (index, step) in steps.iter().enumerate() {
+            prop_assert_eq!(step.node_index as usize, index);
+            prop_assert_eq!(step.node, &nodes[index]);
+            prop_assert_eq!(step.expected_hash, expected_hash);
+            prop_assert!(step.hash_matched);
+            if index < depth {
+                prop_assert_eq!(step.node.node().node_type(), TrieNodeType::Branch);
+                prop_assert_eq!(step.consumed_nibbles, index + 1);
+                prop_assert_eq!(step.next_expected_hash, Some(nodes[index + 1].hash()));
+                expected_hash = nodes[index + 1].hash();
+            } else {
+                prop_assert_eq!(step.node.node().node_type(), TrieNodeType::Leaf);
+                prop_assert_eq!(step.consumed_nibbles, key.len() * 2);
+                prop_assert_eq!(step.next_expected_hash, None);
+            }
+        }
+
+        let other_root = CryptoHash::hash_borsh(&state_root);
+        let steps = walk_proof(&key, &nodes, &other_root).collect::<Vec<_>>();
+        prop_assert_eq!(steps.len(), 1);
+        prop_assert!(!steps[0].hash_matched);
+        prop_assert_eq!(steps[0].consumed_nibbles, 0);
+    }
+
+    #[test]
+    fn membership_by_hash_accepts_unordered_proof_data(
+        mut state in arbitrary_strategy::<ConsensusState>(),
+        key in vec(any::<u8>(), 1..16),
+        value in vec(any::<u8>(), 0..64),
+        depth in 1..32_usize,
+        rotation in any::<usize>(),
+        unrelated in arbitrary_strategy::<RawTrieNodeWithSize>(),
+    ) {
+        prop_assume!(depth <= key.len() * 2);
+        let (proofs, state_root) = state_proof_fixture(&key, &value, depth);
+        state.header.prev_state_root_of_chunks = vec![state_root];
+
+        let mut shuffled = proofs.clone();
+        shuffled.reverse();
+        shuffled.rotate_left(rotation % proofs.len());
+        prop_assert!(state.verify_membership_by_hash(&key, &value, &shuffled).is_ok());
+
+        let mut with_unrelated = shuffled.clone();
+        with_unrelated.insert(rotation % shuffled.len(), unrelated.encode());
+        prop_assert!(state.verify_membership_by_hash(&key, &value, &with_unrelated).is_ok());
+
+        let mut missing = shuffled;
+        let leaf_position = missing.iter().position(|proof| proof == &proofs[depth]).unwrap();
+        let leaf = missing.remove(leaf_position);
+        let leaf_hash = RawTrieNodeWithSize::decode_strict(&leaf).unwrap().hash();
+        prop_assert!(matches!(
+            state.verify_membership_by_hash(&key, &value, &missing),
+            Err(StateProofVerificationError::MissingProofNode { node_hash })
+                if node_hash == leaf_hash
+        ));
+    }
+
+    #[test]
+    fn nodes_with_empty_key_are_rejected(
+        key in vec(any::<u8>(), 0..32),
+        value in vec(any::<u8>(), 0..64),
+        child in arbitrary_strategy::<CryptoHash>(),
+        memory_usage in any::<u64>(),
+    ) {
+        let context = ProofErrorContext {
+            node_type: TrieNodeType::Leaf,
+            consumed_nibbles: 0,
+            remaining_key: bytes_to_nibbles(&key),
+        };
+        let leaf = RawTrieNodeWithSize::new(RawTrieNode::new_leaf(vec![], &value), memory_usage);
+        prop_assert!(leaf.key_nibbles().is_none());
+        let root = leaf.hash();
+        let proofs = [leaf.encode()];
+        prop_assert!(matches!(
+            verify_state_proof_data(&key, &proofs, &value, &root),
+            Err(StateProofVerificationError::InvalidLeafNodeKey { proof_index: 0, context: c })
+                if c == context
+        ));
+        prop_assert!(matches!(
+            verify_not_in_state(&key, &[leaf.clone()], &root),
+            Err(StateProofVerificationError::InvalidLeafNodeKey { proof_index: 0, .. })
+        ));
+        let steps = walk_proof(&key, core::slice::from_ref(&leaf), &root).collect::<Vec<_>>();
+        prop_assert_eq!(steps.len(), 1);
+        prop_assert_eq!(steps[0].consumed_nibbles, 0);
+
+        let extension =
+            RawTrieNodeWithSize::new(RawTrieNode::new_extension(vec![], child), memory_usage);
+        prop_assert!(extension.key_nibbles().is_none());
+        let root = extension.hash();
+        let context = ProofErrorContext { node_type: TrieNodeType::Extension, ..context };
+        prop_assert!(matches!(
+            verify_not_in_state_data(&key, &[extension.encode()], &root),
+            Err(StateProofVerificationError::InvalidExtensionNodeKey {
+                proof_index: 0,
+                context: c,
+            }) if c == context
+        ));
+        let steps = walk_proof(&key, core::slice::from_ref(&extension), &root);
+        let steps = steps.collect::<Vec<_>>();
+        prop_assert_eq!(steps.len(), 1);
+        prop_assert_eq!(steps[0].next_expected_hash, None);
+    }
+
+    #[test]
+    fn state_change_from_absent_to_value(
+        mut old_state in arbitrary_strategy::<ConsensusState>(),
+        mut new_state in arbitrary_strategy::<ConsensusState>(),
+        key in vec(any::<u8>(), 1..32),
+        other_key in vec(any::<u8>(), 1..32),
+        value in vec(any::<u8>(), 0..64),
+    ) {
+        prop_assume!(key != other_key);
+        let leaf_of = |key: &[u8]| {
+            let encoded_key = encode_key_nibbles(&bytes_to_nibbles(key), true);
+            RawTrieNodeWithSize::new(RawTrieNode::new_leaf(encoded_key, &value), 0)
+        };
+        let (old_leaf, new_leaf) = (leaf_of(&other_key), leaf_of(&key));
+        let old_height = old_state.header.height().revision_height % (u64::MAX / 2);
+        old_state.header.light_client_block.inner_lite.height = old_height;
+        old_state.header.prev_state_root_of_chunks = vec![old_leaf.hash()];
+        new_state.header.light_client_block.inner_lite.height = old_height + 1;
+        new_state.header.prev_state_root_of_chunks = vec![new_leaf.hash()];
+
+        let old_proof = StateValueProof::NonMembership(NonMembershipProof::new(
+            Height::from_block_height(old_height),
+            key.clone(),
+            vec![old_leaf.encode()],
+        ));
+        let new_proof = StateValueProof::Membership(MembershipProof::new(
+            Height::from_block_height(old_height + 1),
+            key.clone(),
+            value.clone(),
+            vec![new_leaf.encode()],
+        ));
+        let change = verify_state_change(&old_state, &old_proof, &new_state, &new_proof).unwrap();
+        prop_assert!(change.is_changed());
+        prop_assert_eq!(change.old_value, None);
+        prop_assert_eq!(change.new_value, Some(value));
+        prop_assert!(verify_state_change(&new_state, &new_proof, &old_state, &old_proof).is_err());
+    }
+
+    #[test]
+    fn historical_membership_verifies_through_ancestry(
+        mut state in arbitrary_strategy::<ConsensusState>(),
+        mut block_lite_view in arbitrary_strategy::<LightClientBlockLite>(),
+        other_hashes in vec(arbitrary_strategy::<CryptoHash>(), 1..16),
+        key in vec(any::<u8>(), 1..32),
+        value in vec(any::<u8>(), 0..64),
+    ) {
+        let encoded_key = encode_key_nibbles(&bytes_to_nibbles(&key), true);
+        let leaf = RawTrieNodeWithSize::new(RawTrieNode::new_leaf(encoded_key, &value), 0);
+        let chunk_roots = [&[leaf.hash()], other_hashes.as_slice()].concat();
+        let (prev_state_root, chunk_root_proofs) = merklize(&chunk_roots);
+        block_lite_view.inner_lite.prev_state_root = prev_state_root;
+
+        let block_hashes = [other_hashes.as_slice(), &[block_lite_view.current_block_hash()]].concat();
+        state.header.light_client_block.inner_lite.block_merkle_root =
+            compute_block_merkle_root(&block_hashes);
+        let block_proof = compute_block_merkle_path(&block_hashes, block_hashes.len() - 1).unwrap();
+
+        let mut proof = HistoricalMembershipProof {
+            version: PROOF_FORMAT_VERSION,
+            height: state.header.height(),
+            block_lite_view,
+            block_proof,
+            chunk_root: leaf.hash(),
+            chunk_root_proof: chunk_root_proofs[0].clone(),
+            key,
+            value,
+            proof: vec![leaf.encode()],
+        };
+        prop_assert!(state.verify_historical_membership(&proof).is_ok());
+        proof.chunk_root_proof = chunk_root_proofs[1].clone();
+        prop_assert!(state.verify_historical_membership(&proof).is_err());
+    }
+
+    #[test]
+    fn verification_errors_convert_to_unified_error(
+        state in arbitrary_strategy::<ConsensusState>(),
+        key in vec(any::<u8>(), 1..32),
+    ) {
+        let verify_all = |header: &Header| -> Result<(), Error> {
+            TrustedConsensusState::new(state.clone()).verify_header(header)?;
+            state.verify_non_membership(&key, &Vec::new())?;
+            Ok(())
+        };
+        let trusted_header = state.header.clone();
+        prop_assert!(matches!(
+            verify_all(&trusted_header),
+            Err(Error::Header(HeaderVerificationError::InvalidBlockHeight))
+        ));
+        let HeaderFixture { consensus_state, header } = header_fixture(4, 1);
+        let verify_fixture = || -> Result<(), Error> {
+            TrustedConsensusState::new(consensus_state.clone()).verify_header(&header)?;
+            consensus_state.verify_non_membership(&key, &Vec::new())?;
+            Ok(())
+        };
+        prop_assert!(matches!(
+            verify_fixture(),
+            Err(Error::StateProof(StateProofVerificationError::MissingProofData))
+        ));
+    }
+}
+
+#[cfg(feature = "proto")]
+proptest! {
+    #[test]
+    fn header_proto_round_trip(header in arbitrary_strategy::<Header>()) {
+        use near_light_client::proto::v1;
+        use prost::Message;
+
+        let bytes = v1::Header::from(header.clone()).encode_to_vec();
+        let decoded = Header::try_from(v1::Header::decode(bytes.as_slice()).unwrap()).unwrap();
+        prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), borsh::to_vec(&header).unwrap());
+    }
+
+    #[test]
+    fn consensus_state_proto_round_trip(state in arbitrary_strategy::<ConsensusState>()) {
+        use near_light_client::proto::v1;
+        use prost::Message;
+
+        let bytes = v1::ConsensusState::from(state.clone()).encode_to_vec();
+        let decoded =
+            ConsensusState::try_from(v1::ConsensusState::decode(bytes.as_slice()).unwrap()).unwrap();
+        prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), borsh::to_vec(&state).unwrap());
+    }
+
+    #[test]
+    fn client_state_checks_chain_id(
+        state in arbitrary_strategy::<near_light_client::types::ClientState>(),
+    ) {
+        use near_light_client::{proto::v1, types::ClientState};
+        use prost::Message;
+
+        let bytes = v1::ClientState::from(state.clone()).encode_to_vec();
+        let decoded = ClientState::try_from(v1::ClientState::decode(bytes.as_slice()).unwrap());
+        prop_assert_eq!(decoded.is_ok(), !state.chain_id.is_empty());
+        prop_assert!(state.verify_chain_id(&state.chain_id).is_ok());
+        let other_chain_id = format!("{}-other", state.chain_id);
+        prop_assert!(state.verify_chain_id(&other_chain_id).is_err());
+    }
+}
+
+#[cfg(feature = "scale")]
+proptest! {
+    #[test]
+    fn header_scale_round_trip(header in arbitrary_strategy::<Header>()) {
+        use codec::{Decode, Encode};
+
+        let bytes = header.encode();
+        let decoded = Header::decode(&mut bytes.as_slice()).unwrap();
+        prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), borsh::to_vec(&header).unwrap());
+    }
+}
+
+#[cfg(feature = "cbor")]
+proptest! {
+    #[test]
+    fn header_cbor_round_trip(header in arbitrary_strategy::<Header>()) {
+        use near_light_client::cbor::CborEncoding;
+
+        let decoded = Header::from_cbor(&header.to_cbor()).unwrap();
+        prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), borsh::to_vec(&header).unwrap());
+    }
+
+    #[test]
+    fn membership_proof_bundle_cbor_round_trip(
+        bundle in arbitrary_strategy::<MembershipProofBundle>(),
+    ) {
+        use near_light_client::cbor::CborEncoding;
+
+        prop_assert_eq!(MembershipProofBundle::from_cbor(&bundle.to_cbor()).unwrap(), bundle);
+    }
+}
+
+#[cfg(feature = "serde")]
+proptest! {
+    #[test]
+    fn membership_proof_json_round_trip(proof in arbitrary_strategy::<MembershipProof>()) {
+        let json = serde_json::to_string(&proof).unwrap();
+        prop_assert_eq!(serde_json::from_str::<MembershipProof>(&json).unwrap(), proof);
+    }
+}
+
+/// An IBC host holding the states of a single NEAR light client, to drive the `ibc-rs`
+/// client traits.
+#[cfg(feature = "ibc")]
+mod ibc_host {
+    use ibc::core::client::context::{ClientExecutionContext, ClientValidationContext};
+    use ibc::core::client::types::{error::ClientError, Height as IbcHeight};
+    use ibc::core::handler::types::error::ContextError;
+    use ibc::core::host::types::identifiers::ClientId;
+    use ibc::core::host::types::path::{ClientConsensusStatePath, ClientStatePath};
+    use ibc::primitives::Timestamp;
+    use near_light_client::{
+        ibc_client::{IbcConsensusState, NearClientValidationContext},
+        types::{ClientState, ConsensusState, Height},
+    };
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    pub struct MockHost {
+        pub client_state: Option<ClientState>,
+        pub consensus_states: BTreeMap<Height, IbcConsensusState>,
+    }
+
+    fn not_found(what: &str) -> ClientError {
+        ClientError::Other {
+            description: format!("missing {}", what),
+        }
+    }
+
+    fn height_of(path: &ClientConsensusStatePath) -> Height {
+        Height::new(path.revision_number, path.revision_height)
+    }
+
+    impl ClientValidationContext for MockHost {
+        type ClientStateRef = ClientState;
+        type ConsensusStateRef = IbcConsensusState;
+
+        fn client_state(&self, _client_id: &ClientId) -> Result<ClientState, ContextError> {
+            self.client_state
+                .clone()
+                .ok_or_else(|| not_found("client state").into())
+        }
+
+        fn consensus_state(
+            &self,
+            path: &ClientConsensusStatePath,
+        ) -> Result<IbcConsensusState, ContextError> {
+            self.consensus_states
+                .get(&height_of(path))
+                .cloned()
+                .ok_or_else(|| not_found("consensus state").into())
+        }
+
+        fn client_update_meta(
+            &self,
+            _client_id: &ClientId,
+            _height: &IbcHeight,
+        ) -> Result<(Timestamp, IbcHeight), ContextError> {
+            Err(not_found("update meta").into())
+        }
+    }
+
+    impl ClientExecutionContext for MockHost {
+        type ClientStateMut = ClientState;
+
+        fn store_client_state(
+            &mut self,
+            _path: ClientStatePath,
+            client_state: ClientState,
+        ) -> Result<(), ContextError> {
+            self.client_state = Some(client_state);
+            Ok(())
+        }
+
+        fn store_consensus_state(
+            &mut self,
+            path: ClientConsensusStatePath,
+            consensus_state: IbcConsensusState,
+        ) -> Result<(), ContextError> {
+            self.consensus_states
+                .insert(height_of(&path), consensus_state);
+            Ok(())
+        }
+
+        fn delete_consensus_state(
+            &mut self,
+            path: ClientConsensusStatePath,
+        ) -> Result<(), ContextError> {
+            self.consensus_states.remove(&height_of(&path));
+            Ok(())
+        }
+
+        fn store_update_meta(
+            &mut self,
+            _client_id: ClientId,
+            _height: IbcHeight,
+            _host_timestamp: Timestamp,
+            _host_height: IbcHeight,
+        ) -> Result<(), ContextError> {
+            Ok(())
+        }
+
+        fn delete_update_meta(
+            &mut self,
+            _client_id: ClientId,
+            _height: IbcHeight,
+        ) -> Result<(), ContextError> {
+            Ok(())
+        }
+    }
+
+    impl NearClientValidationContext for MockHost {
+        fn consensus_state(
+            &self,
+            _client_id: &ClientId,
+            height: Height,
+        ) -> Result<ConsensusState, ClientError> {
+            self.consensus_states
+                .get(&height)
+                .map(|state| state.consensus_state.clone())
+                .ok_or_else(|| not_found("consensus state"))
+        }
+
+        fn host_timestamp(&self) -> Result<Timestamp, ClientError> {
+            Timestamp::from_nanoseconds(0).map_err(|e| ClientError::Other {
+                description: e.to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "ibc")]
+proptest! {
+    #[test]
+    fn ibc_client_verifies_and_applies_headers(bp_count in 1..8_usize) {
+        use ibc::core::client::context::client_state::{
+            ClientStateExecution, ClientStateValidation,
+        };
+        use ibc::core::host::types::identifiers::ClientId;
+        use ibc_host::MockHost;
+        use near_light_client::{ibc_client::to_ibc_height, types::ClientState};
+
+        let HeaderFixture { consensus_state, header } = header_fixture(bp_count, 2);
+        let client_id: ClientId = "15-near-0".parse().unwrap();
+        let trusted_height = consensus_state.header.height();
+        let client_state = ClientState {
+            chain_id: "testnet".to_string(),
+            latest_height: trusted_height,
+            trusting_period: u64::MAX,
+            frozen_height: None,
+        };
+        let mut host = MockHost::default();
+        host.client_state = Some(client_state.clone());
+        host.consensus_states.insert(trusted_height, consensus_state.into());
+
+        prop_assert!(client_state
+            .verify_client_message(&host, &client_id, header.clone().into())
+            .is_ok());
+        let mut tampered = header.clone();
+        tampered.light_client_block.inner_lite.timestamp += 1;
+        prop_assert!(client_state
+            .verify_client_message(&host, &client_id, tampered.into())
+            .is_err());
+
+        let heights = client_state
+            .update_state(&mut host, &client_id, header.clone().into())
+            .unwrap();
+        prop_assert_eq!(heights, vec![to_ibc_height(header.height()).unwrap()]);
+        prop_assert_eq!(host.client_state.as_ref().unwrap().latest_height, header.height());
+        let stored = &host.consensus_states[&header.height()].consensus_state;
+        prop_assert_eq!(
+            borsh::to_vec(&stored.header).unwrap(),
+            borsh::to_vec(&header).unwrap()
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Replay of the test vectors recorded in the `test-vectors` folder.
+
+use borsh::BorshDeserialize;
+use near_light_client::test_vectors::{TestCase, TestVector, TEST_VECTOR_FILE_EXTENSION};
+use std::path::PathBuf;
+
+fn test_vectors() -> Vec<(PathBuf, TestVector)> {
+    let folder = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-vectors");
+    let mut vectors = std::fs::read_dir(&folder)
+        .expect("Should not fail based on the folder in the repository.")
+        .map(|entry| entry.expect("Invalid entry of test vectors folder.").path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == TEST_VECTOR_FILE_EXTENSION)
+        })
+        .map(|path| {
+            let bytes = std::fs::read(&path).expect("Failed to read test vector file.");
+            let vector = TestVector::try_from_slice(&bytes)
+                .unwrap_or_else(|_| panic!("Invalid test vector file {}.", path.display()));
+            (path, vector)
+        })
+        .collect::<Vec<_>>();
+    vectors.sort_by(|(path1, _), (path2, _)| path1.cmp(path2));
+    vectors
+}
+
+#[test]
+fn recorded_vectors_have_expected_results() {
+    for (path, vector) in test_vectors() {
+        assert!(
+            vector.check(),
+            "Unexpected verification result of {}, expected accepted: {}, actual: {:?}.",
+            path.display(),
+            vector.accepted,
+            vector.case.verify()
+        );
+    }
+}
+
+#[test]
+fn recorded_vectors_are_named_by_content() {
+    for (path, vector) in test_vectors() {
+        assert_eq!(
+            path.file_name().and_then(|name| name.to_str()),
+            Some(vector.file_name().as_str())
+        );
+    }
+}
+
+#[test]
+fn tampered_headers_of_recorded_vectors_are_rejected() {
+    for (path, vector) in test_vectors() {
+        if let TestCase::Header {
+            trusted,
+            mut header,
+        } = vector.case
+        {
+            if !vector.accepted {
+                continue;
+            }
+            header.light_client_block.inner_lite.timestamp += 1;
+            assert!(
+                TestCase::Header { trusted, header }.verify().is_err(),
+                "Tampered header of {} is accepted.",
+                path.display()
+            );
+        }
+    }
+}